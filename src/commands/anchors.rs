@@ -0,0 +1,50 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use tracing::info;
+
+use super::link_check::{LinkCheckArgs, LinkChecker};
+
+#[derive(Subcommand)]
+pub(crate) enum AnchorsCommand {
+    /// Crawl the site and export a JSON map of each page to its anchors
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct ExportArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// File to write the JSON map to (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub(crate) async fn run(command: AnchorsCommand) -> Result<()> {
+    match command {
+        AnchorsCommand::Export(args) => export(args).await,
+    }
+}
+
+async fn export(args: ExportArgs) -> Result<()> {
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    let anchor_map = checker.anchor_map();
+    info!("Exporting anchors for {} pages", anchor_map.len());
+    match args.output {
+        Some(path) => {
+            let mut file = File::create(&path)?;
+            serde_json::to_writer_pretty(&mut file, &anchor_map)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &anchor_map)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
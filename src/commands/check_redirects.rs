@@ -0,0 +1,372 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use futures::{stream, StreamExt};
+use reqwest::{header::LOCATION, redirect::Policy};
+use serde_json::Value;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use super::link_check::normalize_url;
+
+#[derive(Args)]
+pub(crate) struct CheckRedirectsArgs {
+    /// Redirects file: Netlify `_redirects`, Vercel `vercel.json`, or a JSON
+    /// export of the redirects in `next.config.js`
+    #[arg(long, value_name = "FILE")]
+    file: PathBuf,
+
+    /// The base URL of the website the redirects are served from
+    #[arg(short, long = "base")]
+    base_url: Url,
+
+    /// Maximum number of redirects checked at once
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    concurrency: usize,
+}
+
+/// A single redirect rule from the file
+#[derive(Debug, PartialEq)]
+struct Rule {
+    source: String,
+    destination: String,
+    /// Query parameters the source must have, as `(name, value)`, where a
+    /// `:placeholder` value matches any and can be used in the destination
+    query: Vec<(String, String)>,
+    /// Conditions on the request other than its URL (e.g., `Country=us` or
+    /// `Role=admin`), which can't be met when checking
+    conditions: Vec<String>,
+}
+
+impl Rule {
+    fn new(source: &str, destination: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            query: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+}
+
+pub(crate) async fn run(args: CheckRedirectsArgs) -> Result<()> {
+    let rules = read_rules(&args.file).inspect_err(|e| error!("{e:#}"))?;
+    info!("Found {} redirects in {}", rules.len(), args.file.display());
+
+    // Rules with placeholders or wildcards match many paths, so there's no
+    // single URL to check them with
+    let (patterns, rules): (Vec<Rule>, Vec<Rule>) = rules.into_iter().partition(|rule| {
+        rule.source.contains(['*', '('])
+            || rule
+                .source
+                .split('/')
+                .any(|segment| segment.starts_with(':'))
+    });
+    if !patterns.is_empty() {
+        warn!(
+            "Skipping {} redirects with placeholders or wildcards",
+            patterns.len()
+        );
+    }
+    let (conditional, rules): (Vec<Rule>, Vec<Rule>) = rules
+        .into_iter()
+        .partition(|rule| !rule.conditions.is_empty());
+    if !conditional.is_empty() {
+        warn!(
+            "Skipping {} redirects with conditions (e.g., Country or Role)",
+            conditional.len()
+        );
+    }
+
+    // The source is requested without following redirects, so the first hop
+    // can be checked on its own
+    let no_redirect_client = reqwest::Client::builder()
+        .user_agent("docs-tools")
+        .redirect(Policy::none())
+        .build()?;
+    let client = reqwest::Client::builder()
+        .user_agent("docs-tools")
+        .build()?;
+
+    let failures: Vec<String> = stream::iter(&rules)
+        .map(|rule| check_rule(&no_redirect_client, &client, &args.base_url, rule))
+        .buffer_unordered(args.concurrency.max(1))
+        .filter_map(|result| async move { result.err() })
+        .collect()
+        .await;
+    for failure in &failures {
+        error!("{failure}");
+    }
+
+    info!(
+        "{} of {} redirects verified",
+        rules.len() - failures.len(),
+        rules.len()
+    );
+    if !failures.is_empty() {
+        error!("Some redirects are broken");
+        anyhow::bail!("Some redirects are broken");
+    }
+    Ok(())
+}
+
+/// Check that a rule's source redirects to its destination, and that the
+/// destination loads without redirecting again.
+///
+/// ## Returns
+/// Returns why the rule is broken, if it is.
+async fn check_rule(
+    no_redirect_client: &reqwest::Client,
+    client: &reqwest::Client,
+    base_url: &Url,
+    rule: &Rule,
+) -> Result<(), String> {
+    let (source, destination) = rule_urls(base_url, rule)?;
+
+    let response = no_redirect_client
+        .get(source.as_str())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {e}", source.as_str()))?;
+    if !response.status().is_redirection() {
+        return Err(format!(
+            "{} doesn't redirect, returned {}",
+            source.as_str(),
+            response.status()
+        ));
+    }
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|location| source.join(location).ok())
+        .ok_or_else(|| format!("{} redirects without a Location", source.as_str()))?;
+    if comparable(&location) != comparable(&destination) {
+        return Err(format!(
+            "{} redirects to {}, expected {}",
+            source.as_str(),
+            location.as_str(),
+            destination.as_str()
+        ));
+    }
+
+    let response = client
+        .get(destination.as_str())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch destination {}: {e}", destination.as_str()))?;
+    if comparable(response.url()) != comparable(&destination) {
+        return Err(format!(
+            "Destination of {} redirects again, to {}",
+            source.as_str(),
+            response.url().as_str()
+        ));
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Destination {} of {} returned {}",
+            destination.as_str(),
+            source.as_str(),
+            response.status()
+        ));
+    }
+    debug!("{} redirects to {}", source.as_str(), destination.as_str());
+    Ok(())
+}
+
+/// The URL to request to check a rule, with the query it must have, and the
+/// URL it should redirect to. Query placeholders are given their own name as
+/// a value (`id=:id` is requested as `id=id`), which is substituted into the
+/// destination too.
+fn rule_urls(base_url: &Url, rule: &Rule) -> Result<(Url, Url), String> {
+    let mut source = base_url
+        .join(&rule.source)
+        .map_err(|e| format!("Invalid source {}: {e}", rule.source))?;
+    let mut destination = rule.destination.clone();
+    if !rule.query.is_empty() {
+        let mut query = source.query_pairs_mut();
+        for (name, value) in &rule.query {
+            match value.strip_prefix(':') {
+                Some(placeholder) => {
+                    query.append_pair(name, placeholder);
+                    destination = destination.replace(value, placeholder);
+                }
+                None => {
+                    query.append_pair(name, value);
+                }
+            }
+        }
+    }
+    let destination = base_url
+        .join(&destination)
+        .map_err(|e| format!("Invalid destination {}: {e}", rule.destination))?;
+    Ok((source, destination))
+}
+
+/// A URL as compared with a rule's destination: normalized, but keeping its
+/// query, since redirects can be told apart only by their query.
+fn comparable(url: &Url) -> Url {
+    let mut comparable = normalize_url(url);
+    comparable.set_query(url.query());
+    comparable
+}
+
+fn read_rules(path: &Path) -> Result<Vec<Rule>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let json: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        parse_json_rules(&json).with_context(|| format!("No redirects found in {}", path.display()))
+    } else {
+        Ok(parse_netlify_rules(&content))
+    }
+}
+
+/// Parse the redirects in a `vercel.json` or exported `next.config.js`, which
+/// are either a top-level array or under `redirects`. Rewrites, which don't
+/// redirect, aren't included.
+fn parse_json_rules(json: &Value) -> Option<Vec<Rule>> {
+    let redirects = json
+        .as_array()
+        .or_else(|| json.get("redirects")?.as_array())?;
+    Some(
+        redirects
+            .iter()
+            .filter_map(|redirect| {
+                Some(Rule::new(
+                    redirect.get("source")?.as_str()?,
+                    redirect.get("destination")?.as_str()?,
+                ))
+            })
+            .collect(),
+    )
+}
+
+/// Parse a Netlify `_redirects` file of
+/// `FROM [QUERY...] TO [STATUS] [CONDITION...]` lines, where each query
+/// field is `name=value` and each condition `Name=value` (e.g.,
+/// `Country=us,ca`). Rules with a non-redirect status (e.g., `200` rewrites
+/// or custom `404` pages) are skipped.
+fn parse_netlify_rules(content: &str) -> Vec<Rule> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let mut query = Vec::new();
+            let destination = loop {
+                let field = fields.next()?;
+                match field.split_once('=') {
+                    Some((name, value)) if !field.starts_with('/') && !field.contains("://") => {
+                        query.push((name.to_string(), value.to_string()));
+                    }
+                    _ => break field,
+                }
+            };
+            let mut status = "301";
+            let mut conditions = Vec::new();
+            for field in fields {
+                if field.starts_with(|c: char| c.is_ascii_digit()) {
+                    status = field.trim_end_matches('!');
+                } else if field.contains('=') {
+                    conditions.push(field.to_string());
+                }
+            }
+            if !status.starts_with('3') {
+                debug!("Skipping non-redirect rule {line}");
+                return None;
+            }
+            Some(Rule {
+                query,
+                conditions,
+                ..Rule::new(source, destination)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_netlify_query_and_condition_fields() {
+        let rules = parse_netlify_rules(
+            "# Comment
+/old /new
+/store id=:id /blog/:id 301
+/search q=docs lang=en /docs/search 302!
+/ /anz 302! Country=au,nz
+/admin/* /admin/login 302 Role=admin
+/app/* /index.html 200
+",
+        );
+        assert_eq!(
+            rules,
+            [
+                Rule::new("/old", "/new"),
+                Rule {
+                    query: vec![("id".to_string(), ":id".to_string())],
+                    ..Rule::new("/store", "/blog/:id")
+                },
+                Rule {
+                    query: vec![
+                        ("q".to_string(), "docs".to_string()),
+                        ("lang".to_string(), "en".to_string()),
+                    ],
+                    ..Rule::new("/search", "/docs/search")
+                },
+                Rule {
+                    conditions: vec!["Country=au,nz".to_string()],
+                    ..Rule::new("/", "/anz")
+                },
+                Rule {
+                    conditions: vec!["Role=admin".to_string()],
+                    ..Rule::new("/admin/*", "/admin/login")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn requests_and_compares_queries() {
+        let base = Url::parse("https://docs.example.com/").unwrap();
+        let rule = Rule {
+            query: vec![
+                ("id".to_string(), ":id".to_string()),
+                ("tab".to_string(), "api".to_string()),
+            ],
+            ..Rule::new("/store", "/blog/:id?view=full")
+        };
+        let (source, destination) = rule_urls(&base, &rule).unwrap();
+        assert_eq!(
+            source.as_str(),
+            "https://docs.example.com/store?id=id&tab=api"
+        );
+        assert_eq!(
+            destination.as_str(),
+            "https://docs.example.com/blog/id?view=full"
+        );
+
+        let url = |url: &str| comparable(&Url::parse(url).unwrap());
+        assert_eq!(
+            url("https://docs.example.com/blog/?view=full#top"),
+            url("https://docs.example.com/blog?view=full")
+        );
+        assert_ne!(
+            url("https://docs.example.com/blog?view=full"),
+            url("https://docs.example.com/blog?view=summary")
+        );
+        assert_ne!(
+            url("https://docs.example.com/blog?view=full"),
+            url("https://docs.example.com/blog")
+        );
+    }
+}
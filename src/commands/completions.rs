@@ -0,0 +1,16 @@
+use anyhow::Result;
+use clap::{Args, Command};
+use clap_complete::Shell;
+
+#[derive(Args)]
+pub(crate) struct CompletionsArgs {
+    /// Shell to generate completions for
+    shell: Shell,
+}
+
+/// Write a completion script for the CLI to stdout.
+pub(crate) fn run(args: CompletionsArgs, mut command: Command) -> Result<()> {
+    let name = command.get_name().to_string();
+    clap_complete::generate(args.shell, &mut command, name, &mut std::io::stdout());
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+
+use super::link_check::config_schema;
+
+#[derive(Args)]
+pub(crate) struct ConfigSchemaArgs {
+    /// File to write the schema to (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub(crate) fn run(args: ConfigSchemaArgs) -> Result<()> {
+    let schema = config_schema();
+    match args.output {
+        Some(path) => {
+            let mut file = File::create(&path)?;
+            serde_json::to_writer_pretty(&mut file, &schema)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &schema)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use lychee_lib::{extract::Extractor, FileType, InputContent};
+use scraper::{Html, Selector};
+use serde_json::Value;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use super::link_check::normalize_url;
+
+#[derive(Args)]
+pub(crate) struct DocsearchCheckArgs {
+    /// Path to a DocSearch (docsearch-scraper) JSON config
+    #[arg(long)]
+    config: PathBuf,
+
+    /// Maximum number of pages to sample, starting from the config's start URLs
+    #[arg(long, default_value_t = 20)]
+    sample: usize,
+}
+
+/// Levels a DocSearch record is built from, in hierarchy order
+const LEVELS: &[&str] = &[
+    "lvl0", "lvl1", "lvl2", "lvl3", "lvl4", "lvl5", "lvl6", "text",
+];
+
+/// Levels every page needs at least one match for to produce any records
+const REQUIRED_LEVELS: &[&str] = &["lvl1", "text"];
+
+/// A single selector from the config
+struct LevelSelector {
+    level: String,
+    css: String,
+    /// Whether the config provides a fallback when nothing matches
+    has_default: bool,
+}
+
+pub(crate) async fn run(args: DocsearchCheckArgs) -> Result<()> {
+    let config: Value = serde_json::from_str(
+        &fs::read_to_string(&args.config)
+            .with_context(|| format!("Failed to read {}", args.config.display()))?,
+    )?;
+
+    let start_urls = start_urls(&config)?;
+    let selectors = level_selectors(&config)?;
+    if selectors.is_empty() {
+        anyhow::bail!("No selectors found in {}", args.config.display());
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent("docs-tools")
+        .build()?;
+    let pages = sample_pages(&client, &start_urls, args.sample).await;
+    info!("Sampled {} pages", pages.len());
+    if pages.is_empty() {
+        anyhow::bail!("Failed to fetch any pages from the config's start URLs");
+    }
+
+    // Number of sampled pages each selector matched on, keyed by level
+    let mut matched_pages: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut failures = 0;
+    for (url, body) in &pages {
+        let document = Html::parse_document(body);
+        for selector in &selectors {
+            let Ok(parsed) = Selector::parse(&selector.css) else {
+                continue;
+            };
+            let matches = document.select(&parsed).count();
+            debug!("{} matched {matches} elements on {url}", selector.css);
+            if matches > 0 {
+                *matched_pages.entry(&selector.level).or_default() += 1;
+            }
+        }
+        for level in REQUIRED_LEVELS {
+            let page_matches = selectors.iter().any(|selector| {
+                selector.level == *level
+                    && Selector::parse(&selector.css)
+                        .is_ok_and(|parsed| document.select(&parsed).next().is_some())
+            });
+            if !page_matches && selectors.iter().any(|s| s.level == *level) {
+                warn!("No {level} selector matches on {url}, so it produces no records");
+            }
+        }
+    }
+
+    for selector in &selectors {
+        if Selector::parse(&selector.css).is_err() {
+            error!(
+                "Invalid {} selector `{}` in {}",
+                selector.level,
+                selector.css,
+                args.config.display()
+            );
+            failures += 1;
+            continue;
+        }
+        let count = matched_pages
+            .get(selector.level.as_str())
+            .copied()
+            .unwrap_or(0);
+        info!(
+            "{} selector `{}` matched on {}/{} sampled pages",
+            selector.level,
+            selector.css,
+            count,
+            pages.len()
+        );
+        if count == 0 {
+            if selector.has_default {
+                warn!(
+                    "{} selector `{}` matched no sampled pages, falling back to its default value",
+                    selector.level, selector.css
+                );
+            } else {
+                error!(
+                    "{} selector `{}` matched no sampled pages",
+                    selector.level, selector.css
+                );
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        error!("Some DocSearch selectors no longer match the site");
+        anyhow::bail!("Some DocSearch selectors no longer match the site");
+    }
+    Ok(())
+}
+
+fn start_urls(config: &Value) -> Result<Vec<Url>> {
+    let urls = config
+        .get("start_urls")
+        .and_then(Value::as_array)
+        .context("Config has no `start_urls`")?;
+    urls.iter()
+        .filter_map(|entry| match entry {
+            Value::String(url) => Some(url.as_str()),
+            Value::Object(obj) => obj.get("url").and_then(Value::as_str),
+            _ => None,
+        })
+        .map(|url| Url::parse(url).with_context(|| format!("Invalid start URL {url}")))
+        .collect()
+}
+
+/// Flatten the config's selectors, which are either a single set of levels or
+/// several sets keyed by `selectors_key`.
+fn level_selectors(config: &Value) -> Result<Vec<LevelSelector>> {
+    let selectors = config
+        .get("selectors")
+        .and_then(Value::as_object)
+        .context("Config has no `selectors`")?;
+
+    let is_keyed = !selectors.keys().any(|key| LEVELS.contains(&key.as_str()));
+    let sets: Vec<&serde_json::Map<String, Value>> = if is_keyed {
+        selectors.values().filter_map(Value::as_object).collect()
+    } else {
+        vec![selectors]
+    };
+
+    let mut result = Vec::new();
+    for set in sets {
+        for level in LEVELS {
+            let Some(value) = set.get(*level) else {
+                continue;
+            };
+            let (css, has_default) = match value {
+                Value::String(css) => (css.clone(), false),
+                Value::Object(obj) => {
+                    if obj.get("type").and_then(Value::as_str) == Some("xpath") {
+                        warn!("Skipping XPath selector for {level}, only CSS is supported");
+                        continue;
+                    }
+                    let Some(css) = obj.get("selector").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    (css.to_string(), obj.contains_key("default_value"))
+                }
+                _ => continue,
+            };
+            result.push(LevelSelector {
+                level: level.to_string(),
+                css,
+                has_default,
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// Fetch the start URLs, then pages they link to under the same prefix, until
+/// the sample size is reached.
+async fn sample_pages(
+    client: &reqwest::Client,
+    start_urls: &[Url],
+    sample: usize,
+) -> Vec<(Url, String)> {
+    let extractor = Extractor::default();
+    let mut queue: Vec<Url> = start_urls.to_vec();
+    let mut seen = HashSet::new();
+    let mut pages = Vec::new();
+
+    let mut i = 0;
+    while i < queue.len() && pages.len() < sample {
+        let url = queue[i].clone();
+        i += 1;
+        if !seen.insert(normalize_url(&url)) {
+            continue;
+        }
+
+        let body = match client.get(url.as_str()).send().await {
+            Ok(response) if response.status().is_success() => {
+                let is_html = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|h| h.to_str().ok())
+                    .is_some_and(|content_type| content_type.contains("text/html"));
+                if is_html {
+                    response.text().await.ok()
+                } else {
+                    debug!("Not sampling non-HTML page {url}");
+                    None
+                }
+            }
+            Ok(response) => {
+                warn!("Failed to fetch {url}: {}", response.status());
+                None
+            }
+            Err(e) => {
+                warn!("Failed to fetch {url}: {e}");
+                None
+            }
+        };
+        let Some(body) = body else {
+            continue;
+        };
+
+        let mut links: Vec<Url> = extractor
+            .extract(&InputContent::from_string(&body, FileType::Html))
+            .into_iter()
+            .filter_map(|raw_uri| url.join(&raw_uri.text).ok())
+            .map(|mut link| {
+                link.set_fragment(None);
+                link
+            })
+            .filter(|link| {
+                start_urls.iter().any(|start| {
+                    link.origin() == start.origin() && link.path().starts_with(start.path())
+                })
+            })
+            .collect();
+        links.sort();
+        queue.extend(links);
+        pages.push((url, body));
+    }
+    pages
+}
@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::Args;
+use rusqlite::{params, Connection};
+use tracing::{error, info};
+
+use super::{
+    link_check::{normalize_url, LinkCheckArgs, LinkChecker},
+    xml,
+};
+
+/// File marking a bundle as written by this command, which only ever
+/// replaces bundles it wrote
+const MARKER_FILE: &str = "Contents/.docs-tools-docset";
+
+#[derive(Args)]
+pub(crate) struct DocsetArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// Name of the docset, as shown in Dash/Zeal. Also names the bundle, so
+    /// can't contain path separators or `..`.
+    #[arg(long, value_parser = parse_name)]
+    name: String,
+
+    /// Directory to write the `<name>.docset` bundle to
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+}
+
+pub(crate) async fn run(mut args: DocsetArgs) -> Result<()> {
+    let bundle = args.output.join(format!("{}.docset", args.name));
+    let documents = bundle.join("Contents/Resources/Documents");
+    if fs::symlink_metadata(&bundle).is_ok() {
+        if !is_own_bundle(&bundle) {
+            error!(
+                "Not replacing {}, which wasn't written by docs-tools; move or delete it first",
+                bundle.display()
+            );
+            anyhow::bail!(
+                "Not replacing {}, which wasn't written by docs-tools; move or delete it first",
+                bundle.display()
+            );
+        }
+        fs::remove_dir_all(&bundle)?;
+    }
+    fs::create_dir_all(&documents)?;
+    fs::write(bundle.join(MARKER_FILE), "")?;
+
+    args.link_check.mirror = Some(documents);
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    let pages = checker.mirrored_pages();
+    let anchor_map = checker.anchor_map();
+    let Some(index_page) = pages
+        .get(normalize_url(checker.start_url()).as_str())
+        .or_else(|| pages.values().next())
+    else {
+        error!("No pages were crawled, not writing a docset");
+        anyhow::bail!("No pages were crawled, not writing a docset");
+    };
+
+    fs::write(
+        bundle.join("Contents/Info.plist"),
+        info_plist(&args.name, &index_page.path.to_string_lossy()),
+    )?;
+
+    let conn = Connection::open(bundle.join("Contents/Resources/docSet.dsidx"))?;
+    conn.execute_batch(
+        "CREATE TABLE searchIndex(id INTEGER PRIMARY KEY, name TEXT, type TEXT, path TEXT);
+         CREATE UNIQUE INDEX anchor ON searchIndex (name, type, path);",
+    )?;
+    let mut insert =
+        conn.prepare("INSERT OR IGNORE INTO searchIndex(name, type, path) VALUES (?1, ?2, ?3)")?;
+    let mut entries = 0;
+    for (url, page) in &pages {
+        let path = page.path.to_string_lossy();
+        let name = page.title.clone().unwrap_or_else(|| url.clone());
+        entries += insert.execute(params![name, "Guide", path])?;
+        for anchor in anchor_map.get(url).into_iter().flatten() {
+            entries += insert.execute(params![anchor, "Section", format!("{path}#{anchor}")])?;
+        }
+    }
+
+    info!(
+        "Wrote docset {} with {} pages and {} index entries",
+        bundle.display(),
+        pages.len(),
+        entries
+    );
+    Ok(())
+}
+
+fn parse_name(s: &str) -> Result<String, String> {
+    if s.trim().is_empty() {
+        return Err("the name can't be empty".to_string());
+    }
+    if s.contains(['/', '\\', '\0']) || s.contains("..") {
+        return Err(format!(
+            "`{s}` can't be used as a bundle name, as it contains a path separator or `..`"
+        ));
+    }
+    Ok(s.to_string())
+}
+
+/// Whether a path is a docset bundle directory written by this command,
+/// rather than a symlink or anything else that shouldn't be deleted.
+fn is_own_bundle(bundle: &Path) -> bool {
+    fs::symlink_metadata(bundle).is_ok_and(|metadata| metadata.is_dir())
+        && bundle.join(MARKER_FILE).is_file()
+}
+
+fn info_plist(name: &str, index_path: &str) -> String {
+    let identifier: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>{identifier}</string>
+    <key>CFBundleName</key>
+    <string>{name}</string>
+    <key>DocSetPlatformFamily</key>
+    <string>{identifier}</string>
+    <key>isDashDocset</key>
+    <true/>
+    <key>dashIndexFilePath</key>
+    <string>{index_path}</string>
+</dict>
+</plist>
+"#,
+        name = xml::escape(name),
+        index_path = xml::escape(index_path),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_cant_escape_the_output_directory() {
+        assert_eq!(parse_name("Rust Docs"), Ok("Rust Docs".to_string()));
+        assert!(parse_name("../outside").is_err());
+        assert!(parse_name("..").is_err());
+        assert!(parse_name("nested/name").is_err());
+        assert!(parse_name("C:\\name").is_err());
+        assert!(parse_name("/tmp/name").is_err());
+        assert!(parse_name(" ").is_err());
+    }
+
+    #[test]
+    fn only_replaces_its_own_bundles() {
+        let dir = std::env::temp_dir().join(format!("docs-tools-docset-{}", std::process::id()));
+        let own = dir.join("Own.docset");
+        let other = dir.join("Other.docset");
+        fs::create_dir_all(own.join("Contents")).unwrap();
+        fs::write(own.join(MARKER_FILE), "").unwrap();
+        fs::create_dir_all(other.join("Contents")).unwrap();
+
+        assert!(is_own_bundle(&own));
+        assert!(!is_own_bundle(&other));
+        assert!(!is_own_bundle(&dir.join("Missing.docset")));
+        #[cfg(unix)]
+        {
+            let link = dir.join("Link.docset");
+            std::os::unix::fs::symlink(&own, &link).unwrap();
+            assert!(!is_own_bundle(&link));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
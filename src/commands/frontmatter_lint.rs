@@ -0,0 +1,400 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use clap::Args;
+use serde::Deserialize;
+use tracing::{debug, error, info};
+
+use super::link_check_src::collect_source_files;
+
+/// Date format used for fields of type `date` without a `date-format`,
+/// besides RFC 3339 timestamps
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Args)]
+pub(crate) struct FrontmatterLintArgs {
+    /// Directory of Markdown and MDX source files (e.g., ./docs)
+    #[arg(long, value_name = "DIR")]
+    path: PathBuf,
+
+    /// TOML file describing the frontmatter fields, with a `[fields.<name>]`
+    /// table for each field setting `required`, `type` (`string`, `number`,
+    /// `boolean`, `date` or `list`), `enum`, `date-format` and `unique`
+    #[arg(long, value_name = "FILE")]
+    schema: PathBuf,
+}
+
+/// Rules for the frontmatter of every source file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Schema {
+    fields: BTreeMap<String, FieldRule>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct FieldRule {
+    /// Whether every file must set the field
+    required: bool,
+    #[serde(rename = "type")]
+    kind: Option<FieldType>,
+    /// Values the field may take. For lists, each item must be one of them.
+    #[serde(rename = "enum")]
+    allowed: Vec<String>,
+    /// `strftime`-style format of a `date` field (e.g., `%d/%m/%Y`)
+    /// [default: %Y-%m-%d or RFC 3339]
+    date_format: Option<String>,
+    /// Whether no two files may set the field to the same value (e.g., for
+    /// `slug`)
+    unique: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum FieldType {
+    String,
+    Number,
+    Boolean,
+    Date,
+    List,
+}
+
+/// A frontmatter value, within the subset of YAML frontmatter uses
+#[derive(Debug)]
+enum Value {
+    Scalar {
+        text: String,
+        quoted: bool,
+    },
+    List(Vec<String>),
+    /// A nested mapping, which is only checked for presence
+    Map,
+}
+
+pub(crate) fn run(args: FrontmatterLintArgs) -> Result<()> {
+    let schema = fs::read_to_string(&args.schema)
+        .with_context(|| format!("Failed to read schema {}", args.schema.display()))
+        .and_then(|schema| {
+            toml::from_str::<Schema>(&schema)
+                .with_context(|| format!("Invalid schema {}", args.schema.display()))
+        })
+        .inspect_err(|e| error!("{e:#}"))?;
+    let root = args
+        .path
+        .canonicalize()
+        .with_context(|| format!("Failed to read source directory {}", args.path.display()))
+        .inspect_err(|e| error!("{e:#}"))?;
+    let mut files = Vec::new();
+    collect_source_files(&root, &mut files)?;
+    files.sort();
+    info!("Found {} source files in {}", files.len(), root.display());
+
+    let mut problems = 0;
+    // Files setting each value of the unique fields
+    let mut seen: HashMap<(&str, String), Vec<PathBuf>> = HashMap::new();
+    for file in &files {
+        let relative = file.strip_prefix(&root).unwrap_or(file);
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))
+            .inspect_err(|e| error!("{e:#}"))?;
+        let frontmatter = match parse_frontmatter(&content) {
+            Ok(frontmatter) => frontmatter,
+            Err(e) => {
+                error!("Invalid frontmatter in {}: {e}", relative.display());
+                problems += 1;
+                continue;
+            }
+        };
+        debug!(
+            "Found {} frontmatter fields in {}",
+            frontmatter.len(),
+            relative.display()
+        );
+        for (name, rule) in &schema.fields {
+            let Some(value) = frontmatter.get(name) else {
+                if rule.required {
+                    error!(
+                        "Invalid frontmatter in {}: missing required field `{name}`",
+                        relative.display()
+                    );
+                    problems += 1;
+                }
+                continue;
+            };
+            for problem in rule.check(value) {
+                error!(
+                    "Invalid frontmatter in {}: field `{name}` {problem}",
+                    relative.display()
+                );
+                problems += 1;
+            }
+            if let (true, Value::Scalar { text, .. }) = (rule.unique, value) {
+                seen.entry((name.as_str(), text.clone()))
+                    .or_default()
+                    .push(relative.to_path_buf());
+            }
+        }
+    }
+
+    let mut duplicates: Vec<_> = seen
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .collect();
+    duplicates.sort();
+    for ((name, value), files) in duplicates {
+        let files: Vec<String> = files
+            .iter()
+            .map(|file| file.display().to_string())
+            .collect();
+        error!(
+            "Field `{name}` is `{value}` in {} files: {}",
+            files.len(),
+            files.join(", ")
+        );
+        problems += 1;
+    }
+
+    info!("\nFrontmatter Lint Summary:");
+    info!("Files checked: {}", files.len());
+    info!("Problems found: {}", problems);
+    if problems > 0 {
+        error!("Some frontmatter is invalid");
+        anyhow::bail!("Some frontmatter is invalid");
+    }
+    Ok(())
+}
+
+impl FieldRule {
+    /// Problems with a field's value, as phrases following the field name.
+    fn check(&self, value: &Value) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(kind) = self.kind {
+            if let Err(problem) = self.check_type(kind, value) {
+                problems.push(problem);
+            }
+        }
+        if !self.allowed.is_empty() {
+            let values = match value {
+                Value::Scalar { text, .. } => std::slice::from_ref(text),
+                Value::List(items) => items.as_slice(),
+                Value::Map => &[],
+            };
+            for value in values.iter().filter(|value| !self.allowed.contains(value)) {
+                problems.push(format!(
+                    "is `{value}`, expected one of {}",
+                    self.allowed.join(", ")
+                ));
+            }
+        }
+        problems
+    }
+
+    fn check_type(&self, kind: FieldType, value: &Value) -> Result<(), String> {
+        let (text, quoted) = match (kind, value) {
+            (FieldType::List, Value::List(_)) => return Ok(()),
+            (FieldType::List, _) => return Err("must be a list".to_string()),
+            (_, Value::Scalar { text, quoted }) => (text.as_str(), *quoted),
+            (_, _) => return Err("must be a single value".to_string()),
+        };
+        match kind {
+            FieldType::String if text.is_empty() => Err("must not be empty".to_string()),
+            FieldType::Number if quoted || text.parse::<f64>().is_err() => {
+                Err(format!("is `{text}`, expected a number"))
+            }
+            FieldType::Boolean if quoted || !matches!(text, "true" | "false") => {
+                Err(format!("is `{text}`, expected true or false"))
+            }
+            FieldType::Date if !self.is_date(text) => Err(format!(
+                "is `{text}`, expected a date formatted as {}",
+                self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_date(&self, text: &str) -> bool {
+        match &self.date_format {
+            Some(format) => {
+                NaiveDate::parse_from_str(text, format).is_ok()
+                    || NaiveDateTime::parse_from_str(text, format).is_ok()
+                    || DateTime::parse_from_str(text, format).is_ok()
+            }
+            None => {
+                NaiveDate::parse_from_str(text, DEFAULT_DATE_FORMAT).is_ok()
+                    || DateTime::parse_from_rfc3339(text).is_ok()
+            }
+        }
+    }
+}
+
+/// Parse the frontmatter between `---` lines at the start of a file, which
+/// is empty if there's none. Only the subset of YAML used for frontmatter is
+/// understood: top-level `key: value` pairs whose values are plain or quoted
+/// scalars, block scalars (`|` and `>`), flow (`[a, b]`) or block (`- a`)
+/// lists, or nested mappings.
+fn parse_frontmatter(content: &str) -> Result<BTreeMap<String, Value>, String> {
+    let mut lines = content.lines().enumerate();
+    if lines
+        .next()
+        .is_none_or(|(_, line)| line.trim_end() != "---")
+    {
+        return Ok(BTreeMap::new());
+    }
+    let mut body = Vec::new();
+    let mut closed = false;
+    for (number, line) in lines {
+        if matches!(line.trim_end(), "---" | "...") {
+            closed = true;
+            break;
+        }
+        body.push((number + 1, line));
+    }
+    if !closed {
+        return Err("frontmatter is never closed with `---`".to_string());
+    }
+
+    let mut fields = BTreeMap::new();
+    let mut index = 0;
+    while index < body.len() {
+        let (number, line) = body[index];
+        index += 1;
+        if is_blank(line) {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            return Err(format!("line {number}: unexpected indentation"));
+        }
+        let Some((key, rest)) = line.split_once(':') else {
+            return Err(format!("line {number}: expected `key: value`"));
+        };
+        let key = unquote(key.trim()).0;
+        let rest = strip_comment(rest).trim();
+        // Indented lines following the key belong to its value
+        let start = index;
+        while index < body.len()
+            && (is_blank(body[index].1) || body[index].1.starts_with([' ', '\t']))
+        {
+            index += 1;
+        }
+        let nested: Vec<&str> = body[start..index].iter().map(|(_, line)| *line).collect();
+        let value = parse_value(rest, &nested).map_err(|e| format!("line {number}: {e}"))?;
+        if fields.insert(key.clone(), value).is_some() {
+            return Err(format!("line {number}: duplicate field `{key}`"));
+        }
+    }
+    Ok(fields)
+}
+
+fn parse_value(rest: &str, nested: &[&str]) -> Result<Value, String> {
+    let nested_lines = || {
+        nested
+            .iter()
+            .filter(|line| !is_blank(line))
+            .map(|line| line.trim())
+    };
+    if rest.starts_with('|') || rest.starts_with('>') {
+        let separator = if rest.starts_with('|') { "\n" } else { " " };
+        let text = nested
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join(separator);
+        return Ok(Value::Scalar {
+            text: text.trim().to_string(),
+            quoted: true,
+        });
+    }
+    if rest.is_empty() {
+        if nested_lines().next().is_none() {
+            return Ok(Value::Scalar {
+                text: String::new(),
+                quoted: false,
+            });
+        }
+        if nested_lines().all(|line| line == "-" || line.starts_with("- ")) {
+            return Ok(Value::List(
+                nested_lines()
+                    .map(|line| unquote(strip_comment(&line[1..]).trim()).0)
+                    .collect(),
+            ));
+        }
+        return Ok(Value::Map);
+    }
+    if nested_lines().next().is_some() && !rest.starts_with(['"', '\'']) {
+        // A plain scalar continued on the following lines
+        let text = std::iter::once(rest)
+            .chain(nested_lines())
+            .collect::<Vec<_>>()
+            .join(" ");
+        return Ok(Value::Scalar {
+            text,
+            quoted: false,
+        });
+    }
+    if let Some(items) = rest.strip_prefix('[') {
+        let items = items
+            .strip_suffix(']')
+            .ok_or_else(|| "list is never closed with `]`".to_string())?;
+        return Ok(Value::List(
+            items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| unquote(item).0)
+                .collect(),
+        ));
+    }
+    if rest.starts_with('{') {
+        return Ok(Value::Map);
+    }
+    let (text, quoted) = unquote(rest);
+    Ok(Value::Scalar { text, quoted })
+}
+
+/// Remove the quotes around a scalar, if it's quoted.
+fn unquote(text: &str) -> (String, bool) {
+    for quote in ['"', '\''] {
+        if let Some(inner) = text
+            .strip_prefix(quote)
+            .and_then(|text| text.strip_suffix(quote))
+        {
+            let inner = match quote {
+                '"' => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+                _ => inner.replace("''", "'"),
+            };
+            return (inner, true);
+        }
+    }
+    (text.to_string(), false)
+}
+
+/// Drop a trailing `# comment`, which must follow whitespace and, for a
+/// quoted scalar, the closing quote.
+fn strip_comment(text: &str) -> &str {
+    let trimmed = text.trim_start();
+    let mut start = text.len() - trimmed.len();
+    if let Some(quote) = trimmed.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        match trimmed[1..].find(quote) {
+            Some(end) => start += end + 2,
+            None => return text,
+        }
+    }
+    let mut previous = ' ';
+    for (index, c) in text[start..].char_indices() {
+        if c == '#' && previous.is_whitespace() {
+            return &text[..start + index];
+        }
+        previous = c;
+    }
+    text
+}
+
+fn is_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
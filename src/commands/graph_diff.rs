@@ -0,0 +1,197 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::Write,
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use super::link_check::GraphSnapshot;
+
+#[derive(Args)]
+pub(crate) struct GraphDiffArgs {
+    /// Link graph of the earlier crawl, saved with `link-check --save-graph`
+    #[arg(value_name = "OLD")]
+    old: PathBuf,
+
+    /// Link graph of the later crawl
+    #[arg(value_name = "NEW")]
+    new: PathBuf,
+
+    /// Number of links to other pages that makes a page a hub
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    hub_links: usize,
+
+    /// File to write the differences to as JSON (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Structural changes between two crawls of a site
+#[derive(Serialize)]
+struct GraphDiff {
+    added_pages: Vec<String>,
+    removed_pages: Vec<String>,
+    /// Directories whose pages were all removed, without their
+    /// subdirectories
+    removed_sections: Vec<String>,
+    /// Pages still crawled that no other page links to any more
+    lost_inbound_links: Vec<LostInbound>,
+    /// Pages that now link to at least `--hub-links` other pages
+    new_hubs: Vec<Hub>,
+}
+
+#[derive(Serialize)]
+struct LostInbound {
+    page: String,
+    /// Pages that linked to it in the earlier crawl
+    previously_linked_from: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Hub {
+    page: String,
+    links: usize,
+    previous_links: usize,
+}
+
+pub(crate) fn run(args: GraphDiffArgs) -> Result<()> {
+    let old = GraphSnapshot::load(&args.old).inspect_err(|e| error!("{e:#}"))?;
+    let new = GraphSnapshot::load(&args.new).inspect_err(|e| error!("{e:#}"))?;
+    info!(
+        "Comparing {} pages of {} with {} pages of {}",
+        old.pages.len(),
+        old.base_url,
+        new.pages.len(),
+        new.base_url
+    );
+
+    let old_pages: BTreeSet<&String> = old.pages.keys().collect();
+    let new_pages: BTreeSet<&String> = new.pages.keys().collect();
+    let added_pages: Vec<String> = new_pages
+        .difference(&old_pages)
+        .map(|page| page.to_string())
+        .collect();
+    let removed_pages: Vec<String> = old_pages
+        .difference(&new_pages)
+        .map(|page| page.to_string())
+        .collect();
+
+    let old_inbound = inbound(&old);
+    let new_inbound = inbound(&new);
+    let mut lost_inbound_links = Vec::new();
+    for page in old_pages.intersection(&new_pages) {
+        let Some(linked_from) = old_inbound.get(page.as_str()) else {
+            continue;
+        };
+        if new_inbound.contains_key(page.as_str()) {
+            continue;
+        }
+        warn!("Page {page} is no longer linked from any page");
+        lost_inbound_links.push(LostInbound {
+            page: page.to_string(),
+            previously_linked_from: linked_from.iter().map(|page| page.to_string()).collect(),
+        });
+    }
+
+    let mut new_hubs = Vec::new();
+    for (page, targets) in &new.pages {
+        let links = targets.iter().filter(|target| *target != page).count();
+        let previous_links = old.pages.get(page).map_or(0, |targets| {
+            targets.iter().filter(|target| *target != page).count()
+        });
+        if links >= args.hub_links && previous_links < args.hub_links {
+            info!(
+                "Page {page} is a new hub, linking to {links} pages (previously {previous_links})"
+            );
+            new_hubs.push(Hub {
+                page: page.clone(),
+                links,
+                previous_links,
+            });
+        }
+    }
+
+    let removed_sections = removed_sections(&old_pages, &new_pages);
+    for section in &removed_sections {
+        warn!("Section {section} was removed");
+    }
+    info!(
+        "{} pages added, {} removed, {} sections removed, {} pages lost their inbound links, {} new hubs",
+        added_pages.len(),
+        removed_pages.len(),
+        removed_sections.len(),
+        lost_inbound_links.len(),
+        new_hubs.len()
+    );
+
+    let diff = GraphDiff {
+        added_pages,
+        removed_pages,
+        removed_sections,
+        lost_inbound_links,
+        new_hubs,
+    };
+    match args.output {
+        Some(path) => {
+            let mut file = File::create(&path)?;
+            serde_json::to_writer_pretty(&mut file, &diff)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &diff)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// The pages linking to each page, not counting links to itself.
+fn inbound(snapshot: &GraphSnapshot) -> BTreeMap<&str, BTreeSet<&str>> {
+    let mut inbound: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (page, targets) in &snapshot.pages {
+        for target in targets.iter().filter(|target| *target != page) {
+            inbound.entry(target).or_default().insert(page);
+        }
+    }
+    inbound
+}
+
+/// Directories with pages in the old crawl but none in the new one, leaving
+/// out those inside another removed directory.
+fn removed_sections(old_pages: &BTreeSet<&String>, new_pages: &BTreeSet<&String>) -> Vec<String> {
+    let sections = |pages: &BTreeSet<&String>| -> BTreeSet<String> {
+        pages
+            .iter()
+            .filter(|page| page.starts_with('/'))
+            .flat_map(|page| {
+                let segments: Vec<&str> = page.split('/').filter(|s| !s.is_empty()).collect();
+                (1..segments.len()).map(move |depth| format!("/{}/", segments[..depth].join("/")))
+            })
+            .collect()
+    };
+    let new_sections = sections(new_pages);
+    let removed: Vec<String> = sections(old_pages)
+        .into_iter()
+        .filter(|section| {
+            !new_sections.contains(section)
+                && !new_pages
+                    .iter()
+                    .any(|page| page.trim_end_matches('/') == section.trim_end_matches('/'))
+        })
+        .collect();
+    removed
+        .iter()
+        .filter(|section| {
+            !removed
+                .iter()
+                .any(|parent| parent != *section && section.starts_with(parent.as_str()))
+        })
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,172 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use tracing::{info, warn};
+use url::Url;
+
+use super::link_check::{AssetCheck, AssetKind, LinkCheckArgs, LinkChecker};
+
+#[derive(Args)]
+pub(crate) struct ImageAuditArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// Report images larger than this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = 200_000)]
+    max_image_bytes: u64,
+
+    /// File to write the audit to as JSON (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Formats with a smaller AVIF or WebP equivalent, by MIME type and file
+/// extension
+const LEGACY_FORMATS: &[(&str, &[&str])] = &[
+    ("image/png", &["png"]),
+    ("image/jpeg", &["jpg", "jpeg"]),
+    ("image/gif", &["gif"]),
+    ("image/bmp", &["bmp"]),
+    ("image/tiff", &["tif", "tiff"]),
+];
+
+#[derive(Serialize)]
+struct Audit {
+    /// Images shown by an `<img>` without an `alt` attribute
+    missing_alt: Vec<MissingAlt>,
+    /// Images larger than `--max-image-bytes`
+    oversized: Vec<Oversized>,
+    /// Images in a legacy format, with no AVIF or WebP alternative offered
+    /// through `<picture>`
+    legacy_format: Vec<LegacyFormat>,
+}
+
+#[derive(Serialize)]
+struct MissingAlt {
+    url: String,
+    pages: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Oversized {
+    url: String,
+    bytes: u64,
+    pages: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LegacyFormat {
+    url: String,
+    format: String,
+    pages: Vec<String>,
+}
+
+pub(crate) async fn run(mut args: ImageAuditArgs) -> Result<()> {
+    args.link_check.check_assets = true;
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    let images: Vec<AssetCheck> = checker
+        .asset_checks()
+        .into_iter()
+        .filter(|check| check.kind == AssetKind::Image)
+        .collect();
+    info!("Auditing {} images", images.len());
+
+    let mut audit = Audit {
+        missing_alt: Vec::new(),
+        oversized: Vec::new(),
+        legacy_format: Vec::new(),
+    };
+    for image in &images {
+        let pages = || image.pages.iter().map(Url::to_string).collect();
+        if !image.pages_missing_alt.is_empty() {
+            let missing: Vec<String> = image.pages_missing_alt.iter().map(Url::to_string).collect();
+            warn!(
+                "Image {} has no alt text on {}",
+                image.url.as_str(),
+                missing.join(", ")
+            );
+            audit.missing_alt.push(MissingAlt {
+                url: image.url.to_string(),
+                pages: missing,
+            });
+        }
+        // Broken images are already reported by the crawl
+        if !image.is_success() {
+            continue;
+        }
+        if let Some(bytes) = image.size.filter(|&bytes| bytes > args.max_image_bytes) {
+            warn!(
+                "Image {} is {bytes} bytes, over the {} byte limit",
+                image.url.as_str(),
+                args.max_image_bytes
+            );
+            audit.oversized.push(Oversized {
+                url: image.url.to_string(),
+                bytes,
+                pages: pages(),
+            });
+        }
+        if image.has_modern_variant {
+            continue;
+        }
+        if let Some(format) = legacy_format(image) {
+            warn!(
+                "Image {} is {format} with no AVIF or WebP alternative",
+                image.url.as_str()
+            );
+            audit.legacy_format.push(LegacyFormat {
+                url: image.url.to_string(),
+                format: format.to_string(),
+                pages: pages(),
+            });
+        }
+    }
+    info!(
+        "Found {} images missing alt text, {} oversized images and {} images in legacy formats",
+        audit.missing_alt.len(),
+        audit.oversized.len(),
+        audit.legacy_format.len()
+    );
+
+    match args.output {
+        Some(path) => {
+            let mut file = File::create(&path)?;
+            serde_json::to_writer_pretty(&mut file, &audit)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &audit)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// The MIME type of an image in a legacy format, from its Content-Type or,
+/// for local files, its extension.
+fn legacy_format(image: &AssetCheck) -> Option<&'static str> {
+    let mime = image
+        .content_type
+        .as_deref()
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_lowercase());
+    let extension = image
+        .url
+        .path()
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase());
+    LEGACY_FORMATS
+        .iter()
+        .find(|(format, extensions)| match &mime {
+            Some(mime) => mime == format,
+            None => extension
+                .as_deref()
+                .is_some_and(|extension| extensions.contains(&extension)),
+        })
+        .map(|(format, _)| *format)
+}
@@ -0,0 +1,63 @@
+use std::{fmt, net::SocketAddr, time::Duration};
+
+use tokio::net::{lookup_host, TcpStream};
+use url::Url;
+
+/// Time allowed for each probe connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Addresses of each family tried before deciding the family fails
+const ADDRESSES_TRIED: usize = 2;
+
+/// A host with both IPv4 and IPv6 addresses that only accepts connections
+/// over one of them. CI runners often lack a working IPv6 route, so links to
+/// such hosts fail there with generic timeouts despite working elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FamilyFailure {
+    /// IPv6 connections fail while IPv4 ones succeed
+    Ipv6Only,
+    /// IPv4 connections fail while IPv6 ones succeed
+    Ipv4Only,
+}
+
+impl fmt::Display for FamilyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (failing, working) = match self {
+            Self::Ipv6Only => ("IPv6", "IPv4"),
+            Self::Ipv4Only => ("IPv4", "IPv6"),
+        };
+        write!(
+            f,
+            "{failing}-only failure: connections to the host's {failing} addresses fail while its {working} addresses accept them"
+        )
+    }
+}
+
+/// Connect to the URL's host over each address family, if it has both, to
+/// tell whether only one of them fails.
+pub(super) async fn diagnose(url: &Url) -> Option<FamilyFailure> {
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    let addresses: Vec<SocketAddr> = lookup_host((host, port)).await.ok()?.collect();
+    let (ipv4, ipv6): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addresses.into_iter().partition(SocketAddr::is_ipv4);
+    if ipv4.is_empty() || ipv6.is_empty() {
+        return None;
+    }
+    match tokio::join!(connects(&ipv4), connects(&ipv6)) {
+        (true, false) => Some(FamilyFailure::Ipv6Only),
+        (false, true) => Some(FamilyFailure::Ipv4Only),
+        _ => None,
+    }
+}
+
+/// Whether any of the first few addresses accepts a TCP connection.
+async fn connects(addresses: &[SocketAddr]) -> bool {
+    for address in addresses.iter().take(ADDRESSES_TRIED) {
+        if let Ok(Ok(_)) = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address)).await
+        {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,101 @@
+/// Query parameters used for affiliate and referral tracking. A trailing `*`
+/// matches any parameter starting with the rest.
+const DEFAULT_PARAMS: &[&str] = &[
+    "aff",
+    "aff_*",
+    "affid",
+    "affiliate",
+    "affiliate_*",
+    "affiliateid",
+    "ascsubtag",
+    "clickid",
+    "irclickid",
+    "partner",
+    "partner_id",
+    "partnerid",
+    "ref",
+    "ref_",
+    "ref_id",
+    "refid",
+    "referral",
+    "referral_*",
+];
+
+/// Which query parameters mark a link as an affiliate or referral link,
+/// which documentation shouldn't carry
+#[derive(Debug, Clone)]
+pub(crate) struct AffiliatePolicy {
+    params: Vec<String>,
+}
+
+impl AffiliatePolicy {
+    /// The default parameters plus any extra ones, matched
+    /// case-insensitively.
+    pub(crate) fn new(extra: &[String]) -> Self {
+        Self {
+            params: DEFAULT_PARAMS
+                .iter()
+                .map(|param| param.to_string())
+                .chain(extra.iter().map(|param| param.to_lowercase()))
+                .collect(),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.params
+            .iter()
+            .any(|param| match param.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == *param,
+            })
+    }
+
+    /// The names of the affiliate parameters a link carries.
+    pub(crate) fn violations(&self, link: &str) -> Vec<String> {
+        let (_, query, _) = split_query(link);
+        query
+            .unwrap_or_default()
+            .split('&')
+            .map(|pair| pair.split('=').next().unwrap_or_default())
+            .filter(|name| !name.is_empty() && self.matches(name))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// The link without its affiliate parameters, leaving the rest of it as
+    /// written so it can be replaced in source files.
+    pub(crate) fn strip(&self, link: &str) -> String {
+        let (base, query, fragment) = split_query(link);
+        let kept: Vec<&str> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or_default();
+                !name.is_empty() && !self.matches(name)
+            })
+            .collect();
+        let mut stripped = base.to_string();
+        if !kept.is_empty() {
+            stripped.push('?');
+            stripped.push_str(&kept.join("&"));
+        }
+        if let Some(fragment) = fragment {
+            stripped.push('#');
+            stripped.push_str(fragment);
+        }
+        stripped
+    }
+}
+
+/// Split a link into the part before its query, its query and its fragment.
+fn split_query(link: &str) -> (&str, Option<&str>, Option<&str>) {
+    let (rest, fragment) = match link.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (link, None),
+    };
+    match rest.split_once('?') {
+        Some((base, query)) => (base, Some(query), fragment),
+        None => (rest, None, fragment),
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use url::Url;
+
+use super::utils::normalize_url;
+
+/// Per-page index of the anchors (element `id`s and `<a name>`s) found on
+/// internal HTML pages, so each page is only parsed for anchors once.
+#[derive(Debug, Default)]
+pub(super) struct AnchorIndex {
+    pages: Mutex<HashMap<Url, BTreeSet<String>>>,
+    /// Pages that redirected elsewhere, mapped to their final URL
+    aliases: Mutex<HashMap<Url, Url>>,
+}
+
+impl AnchorIndex {
+    pub(super) fn insert(&self, page: &Url, anchors: BTreeSet<String>) {
+        self.pages
+            .lock()
+            .unwrap()
+            .insert(normalize_url(page), anchors);
+    }
+
+    /// Record that a page redirected, so anchors on the final page are also
+    /// found when looking up the original URL.
+    pub(super) fn add_alias(&self, page: &Url, final_url: &Url) {
+        self.aliases
+            .lock()
+            .unwrap()
+            .insert(normalize_url(page), normalize_url(final_url));
+    }
+
+    /// Whether the anchor exists on the page.
+    ///
+    /// ## Returns
+    /// Returns `None` if the page was never parsed for anchors.
+    pub(super) fn contains(&self, page: &Url, anchor: &str) -> Option<bool> {
+        let mut page = normalize_url(page);
+        if let Some(final_url) = self.aliases.lock().unwrap().get(&page) {
+            page = final_url.clone();
+        }
+        let pages = self.pages.lock().unwrap();
+        let anchors = pages.get(&page)?;
+        let decoded = percent_encoding::percent_decode_str(anchor).decode_utf8_lossy();
+        // Browsers scroll to the top of the page for `#top` even without a
+        // matching element
+        Some(anchors.contains(anchor) || anchors.contains(decoded.as_ref()) || anchor == "top")
+    }
+
+    /// A sorted map of page URL to the anchors found on it.
+    pub(super) fn to_map(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.pages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(page, anchors)| (page.to_string(), anchors.clone()))
+            .collect()
+    }
+}
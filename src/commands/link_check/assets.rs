@@ -0,0 +1,171 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use url::Url;
+
+/// What a page uses a referenced asset as, which determines the content types
+/// it may be served with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AssetKind {
+    Image,
+    Script,
+    Stylesheet,
+    Font,
+    /// Video or audio
+    Media,
+}
+
+impl AssetKind {
+    /// Whether an asset of this kind may be served with a Content-Type.
+    pub(super) fn accepts(self, content_type: &str) -> bool {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        match self {
+            AssetKind::Image => mime.starts_with("image/"),
+            AssetKind::Script => mime.contains("javascript") || mime.contains("ecmascript"),
+            AssetKind::Stylesheet => mime == "text/css",
+            AssetKind::Font => {
+                mime.starts_with("font/")
+                    || mime.starts_with("application/font")
+                    || mime.starts_with("application/x-font")
+                    || mime == "application/vnd.ms-fontobject"
+                    || mime == "application/octet-stream"
+            }
+            AssetKind::Media => {
+                mime.starts_with("video/")
+                    || mime.starts_with("audio/")
+                    || mime == "application/ogg"
+                    || mime == "application/vnd.apple.mpegurl"
+                    || mime == "application/octet-stream"
+            }
+        }
+    }
+
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            AssetKind::Image => "image",
+            AssetKind::Script => "script",
+            AssetKind::Stylesheet => "stylesheet",
+            AssetKind::Font => "font",
+            AssetKind::Media => "media",
+        }
+    }
+
+    /// The kind of asset, as used in a sentence.
+    fn name(self) -> &'static str {
+        match self {
+            AssetKind::Image => "an image",
+            AssetKind::Script => "a script",
+            AssetKind::Stylesheet => "a stylesheet",
+            AssetKind::Font => "a font",
+            AssetKind::Media => "video or audio",
+        }
+    }
+
+    /// The kind of asset a `<link rel=preload as=...>` loads.
+    pub(super) fn from_preload(destination: &str) -> Option<Self> {
+        match destination.to_lowercase().as_str() {
+            "image" => Some(AssetKind::Image),
+            "script" => Some(AssetKind::Script),
+            "style" => Some(AssetKind::Stylesheet),
+            "font" => Some(AssetKind::Font),
+            "video" | "audio" => Some(AssetKind::Media),
+            _ => None,
+        }
+    }
+}
+
+/// An asset reference found on a page
+#[derive(Debug, Clone)]
+pub(super) struct ExtractedAsset {
+    pub(super) url: String,
+    pub(super) kind: AssetKind,
+    /// Whether the asset is an `<img>` without an `alt` attribute
+    pub(super) missing_alt: bool,
+    /// Whether the asset is an image in a `<picture>` that also offers an
+    /// AVIF or WebP source
+    pub(super) has_modern_variant: bool,
+}
+
+/// An asset referenced by crawled pages
+#[derive(Debug, Clone)]
+pub(super) struct AssetRef {
+    pub(super) kind: AssetKind,
+    /// Pages referencing the asset
+    pub(super) pages: BTreeSet<Url>,
+    /// Pages showing the image without an `alt` attribute
+    pub(super) pages_missing_alt: BTreeSet<Url>,
+    /// Whether any page offers an AVIF or WebP alternative to the image
+    pub(super) has_modern_variant: bool,
+}
+
+impl AssetRef {
+    pub(super) fn new(kind: AssetKind) -> Self {
+        Self {
+            kind,
+            pages: BTreeSet::new(),
+            pages_missing_alt: BTreeSet::new(),
+            has_modern_variant: false,
+        }
+    }
+}
+
+/// The outcome of checking an asset
+#[derive(Debug, Clone)]
+pub(crate) struct AssetCheck {
+    pub(crate) url: Url,
+    pub(crate) kind: AssetKind,
+    pub(crate) pages: BTreeSet<Url>,
+    pub(crate) status: Option<u16>,
+    pub(crate) content_type: Option<String>,
+    /// Size of the response body in bytes, if known
+    pub(crate) size: Option<u64>,
+    /// Why the check failed, or `None` if it succeeded
+    pub(crate) error: Option<String>,
+    /// Pages showing the image without an `alt` attribute
+    pub(crate) pages_missing_alt: BTreeSet<Url>,
+    /// Whether any page offers an AVIF or WebP alternative to the image
+    pub(crate) has_modern_variant: bool,
+}
+
+impl AssetCheck {
+    /// Check a response to a request for an asset: it must succeed and have a
+    /// Content-Type matching how the asset is used.
+    pub(super) fn new(
+        url: Url,
+        asset: AssetRef,
+        status: Option<u16>,
+        content_type: Option<String>,
+        error: Option<String>,
+    ) -> Self {
+        let error = error.or_else(|| match (status, content_type.as_deref()) {
+            (Some(status), _) if !(200..300).contains(&status) => Some(format!("status {status}")),
+            (_, Some(content_type)) if !asset.kind.accepts(content_type) => Some(format!(
+                "unexpected content type {content_type} for {}",
+                asset.kind.name()
+            )),
+            (Some(_), None) => Some(format!("no content type for {}", asset.kind.name())),
+            _ => None,
+        });
+        Self {
+            url,
+            kind: asset.kind,
+            pages: asset.pages,
+            status,
+            content_type,
+            size: None,
+            error,
+            pages_missing_alt: asset.pages_missing_alt,
+            has_modern_variant: asset.has_modern_variant,
+        }
+    }
+
+    pub(crate) fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
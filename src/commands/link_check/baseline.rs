@@ -0,0 +1,80 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// A failure accepted as known, so it doesn't fail the check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KnownFailure {
+    url: String,
+    /// Why the link was failing when the baseline was written, for reference
+    #[serde(default)]
+    reason: String,
+}
+
+/// Failures known to exist when the baseline was written, so a site with
+/// existing breakage can be checked for new failures only
+#[derive(Debug, Default)]
+pub(super) struct Baseline {
+    /// Reason each known failing URL was failing, keyed by URL
+    known: BTreeMap<String, String>,
+}
+
+impl Baseline {
+    pub(super) fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let failures: Vec<KnownFailure> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse baseline {}", path.display()))?;
+        Ok(Self {
+            known: failures
+                .into_iter()
+                .map(|failure| (failure.url, failure.reason))
+                .collect(),
+        })
+    }
+
+    /// Write the given failures as the new baseline, sorted by URL so the
+    /// file diffs cleanly.
+    pub(super) fn write<'a>(
+        path: &Path,
+        failures: impl IntoIterator<Item = (&'a Url, &'a str)>,
+    ) -> Result<usize> {
+        let failures: BTreeMap<&str, &str> = failures
+            .into_iter()
+            .map(|(url, reason)| (url.as_str(), reason))
+            .collect();
+        let failures: Vec<KnownFailure> = failures
+            .into_iter()
+            .map(|(url, reason)| KnownFailure {
+                url: url.to_string(),
+                reason: reason.to_string(),
+            })
+            .collect();
+        let mut content = serde_json::to_string_pretty(&failures)?;
+        content.push('\n');
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write baseline {}", path.display()))?;
+        Ok(failures.len())
+    }
+
+    pub(super) fn contains(&self, url: &Url) -> bool {
+        self.known.contains_key(url.as_str())
+    }
+
+    /// Known failures that no longer fail, so can be removed from the
+    /// baseline.
+    pub(super) fn stale(&self, failing: &[Url]) -> Vec<&str> {
+        let failing: BTreeSet<&str> = failing.iter().map(Url::as_str).collect();
+        self.known
+            .keys()
+            .map(String::as_str)
+            .filter(|url| !failing.contains(url))
+            .collect()
+    }
+}
@@ -0,0 +1,55 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use url::Url;
+
+/// Time that may be spent checking external links to each host, after which
+/// its remaining links are skipped so a slow host can't drag out the whole
+/// run. Shared by all concurrent workers, so checks already in flight when a
+/// budget runs out still finish.
+#[derive(Debug, Default)]
+pub(super) struct DomainBudget {
+    /// Budget for every host, or `None` for no limit
+    budget: Option<Duration>,
+    /// Budgets for specific hosts, from the config file
+    host_budgets: HashMap<String, Duration>,
+    /// Time spent on checks of each host so far
+    spent: Mutex<HashMap<String, Duration>>,
+}
+
+impl DomainBudget {
+    pub(super) fn new(budget: Option<Duration>, host_budgets: HashMap<String, Duration>) -> Self {
+        Self {
+            budget,
+            host_budgets,
+            spent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn budget_of(&self, host: &str) -> Option<Duration> {
+        self.host_budgets.get(host).copied().or(self.budget)
+    }
+
+    /// Count the time a check of the URL took against its host's budget.
+    pub(super) fn spend(&self, url: &Url, elapsed: Duration) {
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        if self.budget_of(host).is_none() {
+            return;
+        }
+        *self
+            .spent
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_default() += elapsed;
+    }
+
+    /// The budget of the URL's host, if it has been used up.
+    pub(super) fn exceeded(&self, url: &Url) -> Option<Duration> {
+        let host = url.host_str()?;
+        let budget = self.budget_of(host)?;
+        let spent = self.spent.lock().unwrap().get(host).copied()?;
+        (spent >= budget).then_some(budget)
+    }
+}
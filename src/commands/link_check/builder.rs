@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use regex::Regex;
+use url::Url;
+
+use super::{args::LinkCheckArgs, config::Config, CheckRecord, LinkChecker, ResultCallback};
+
+/// Builds a [`LinkChecker`] for embedding in other tools. Unlike the
+/// `link-check` command, no config file is read: every setting not given here
+/// uses its default.
+pub struct LinkCheckerBuilder {
+    args: LinkCheckArgs,
+    on_result: Option<ResultCallback>,
+}
+
+impl LinkChecker {
+    /// Start building a checker for the site at `base_url`.
+    pub fn builder(base_url: Url) -> LinkCheckerBuilder {
+        LinkCheckerBuilder {
+            args: LinkCheckArgs {
+                base_url: Some(base_url.to_string()),
+                ..LinkCheckArgs::default()
+            },
+            on_result: None,
+        }
+    }
+}
+
+impl LinkCheckerBuilder {
+    /// URL to start the crawl from (defaults to the base URL)
+    pub fn start_url(mut self, start_url: Url) -> Self {
+        self.args.start_url = Some(start_url.to_string());
+        self
+    }
+
+    /// Only check links under the base URL
+    pub fn internal_only(mut self, internal_only: bool) -> Self {
+        self.args.internal_only = Some(internal_only);
+        self
+    }
+
+    /// Only follow links on pages at most `max_depth` links away from the
+    /// start URL
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.args.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Stop following links after `max_pages` pages have been parsed
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.args.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Maximum number of links checked at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.args.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Maximum number of requests per second sent to any single host
+    pub fn max_requests_per_second_per_host(mut self, rate: f64) -> Self {
+        self.args.max_requests_per_second_per_host = Some(rate);
+        self
+    }
+
+    /// Maximum number of redirects to follow before treating a link as broken
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.args.max_redirects = Some(max_redirects);
+        self
+    }
+
+    /// Skip URLs matching a pattern, neither checking them nor following
+    /// their links. Can be called more than once.
+    pub fn exclude(mut self, pattern: Regex) -> Self {
+        self.args.excludes.push(pattern);
+        self
+    }
+
+    /// Check URLs matching a pattern even if they're excluded. Can be called
+    /// more than once.
+    pub fn include(mut self, pattern: Regex) -> Self {
+        self.args.includes.push(pattern);
+        self
+    }
+
+    /// Send a header with requests to the base URL's origin
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.args
+            .headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Skip URLs disallowed by each host's `robots.txt`
+    pub fn respect_robots(mut self, respect_robots: bool) -> Self {
+        self.args.respect_robots = respect_robots;
+        self
+    }
+
+    /// Call `callback` with the outcome of every check as it's made, from
+    /// whichever task made it
+    pub fn on_result(mut self, callback: impl Fn(&CheckRecord) + Send + Sync + 'static) -> Self {
+        self.on_result = Some(Arc::new(callback));
+        self
+    }
+
+    /// Create the checker, failing if a URL or pattern is invalid
+    pub fn build(self) -> Result<LinkChecker> {
+        let mut config = Config::default();
+        config.add_headers(&self.args.headers, &self.args.cookies);
+        let mut checker = LinkChecker::with_config(self.args, &config)?;
+        checker.on_result = self.on_result;
+        Ok(checker)
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+/// File in the cache directory that results are stored in
+const CACHE_FILE: &str = "results.json";
+
+/// A successful check of an external URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Seconds since the Unix epoch
+    checked_at: u64,
+    status: Option<u16>,
+}
+
+/// Successful external link checks persisted between runs, so links verified
+/// recently aren't checked again.
+#[derive(Debug)]
+pub(super) struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl Cache {
+    /// Load the cache from a directory, dropping expired entries. A missing
+    /// cache is treated as empty.
+    pub(super) fn load(dir: PathBuf, ttl: Duration) -> Result<Self> {
+        let path = dir.join(CACHE_FILE);
+        let mut entries: HashMap<String, CacheEntry> = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse cache file {}", path.display()))?,
+            Err(_) => HashMap::new(),
+        };
+        let now = now();
+        entries.retain(|_, entry| now.saturating_sub(entry.checked_at) < ttl.as_secs());
+        debug!(
+            "Loaded {} cached results from {}",
+            entries.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            dir,
+            ttl,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// The status of a URL checked successfully within the TTL.
+    ///
+    /// ## Returns
+    /// Returns `None` if the URL needs to be checked.
+    pub(super) fn get(&self, url: &Url) -> Option<Option<u16>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(&key(url))?;
+        (now().saturating_sub(entry.checked_at) < self.ttl.as_secs()).then_some(entry.status)
+    }
+
+    pub(super) fn insert(&self, url: &Url, status: Option<u16>) {
+        self.entries.lock().unwrap().insert(
+            key(url),
+            CacheEntry {
+                checked_at: now(),
+                status,
+            },
+        );
+    }
+
+    pub(super) fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create cache directory {}", self.dir.display()))?;
+        let path = self.dir.join(CACHE_FILE);
+        let entries = self.entries.lock().unwrap();
+        fs::write(&path, serde_json::to_string(&*entries)?)
+            .with_context(|| format!("Failed to write cache file {}", path.display()))
+    }
+}
+
+/// Fragments don't affect whether an external page loads, so they share a
+/// cache entry.
+fn key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
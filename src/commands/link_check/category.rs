@@ -0,0 +1,90 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Kind of problem found with a link, used to decide which problems fail the
+/// check
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// The link doesn't resolve (e.g., a 404, a connection error or a missing
+    /// anchor)
+    Broken,
+    /// The link redirects elsewhere
+    Redirect,
+    /// The request timed out
+    Timeout,
+    /// The link was skipped by an `--exclude` pattern or its domain
+    Excluded,
+    /// The host responded with 429 Too Many Requests
+    RateLimited,
+    /// The connection failed due to a TLS or certificate error
+    Insecure,
+    /// The link goes through a URL shortener
+    Shortened,
+    /// The link carries affiliate or referral query parameters
+    Affiliate,
+    /// The link was skipped after its host used up its time budget
+    Skipped,
+    /// An HTTPS page links to or loads an asset over plain HTTP, with
+    /// `--https-only` or `--check-mixed-content`
+    PlainHttp,
+}
+
+/// Categories that fail the check unless `--fail-on` is given, matching what
+/// counted as a failure before categories existed
+pub(super) const DEFAULT_FAIL_ON: &[Category] = &[
+    Category::Broken,
+    Category::Timeout,
+    Category::RateLimited,
+    Category::Insecure,
+];
+
+impl Category {
+    /// The category of a failed check, from its status and reason.
+    pub(super) fn of_failure(status: Option<u16>, reason: &str) -> Self {
+        if status == Some(429) {
+            return Self::RateLimited;
+        }
+        if reason.starts_with("redirects to") {
+            return Self::Redirect;
+        }
+        if reason.starts_with("shortened URL") {
+            return Self::Shortened;
+        }
+        if reason.starts_with("affiliate parameters") {
+            return Self::Affiliate;
+        }
+        if reason.starts_with("skipped:") {
+            return Self::Skipped;
+        }
+        if reason.starts_with("plain HTTP") || reason.starts_with("mixed content") {
+            return Self::PlainHttp;
+        }
+        // Only the cause is considered, not any URL in the message
+        let reason = reason
+            .rsplit_once("): ")
+            .map_or(reason, |(_, cause)| cause)
+            .to_lowercase();
+        if reason.contains("timed out") || reason.contains("timeout") {
+            Self::Timeout
+        } else if ["certificate", "tls", "ssl"]
+            .iter()
+            .any(|term| reason.contains(term))
+        {
+            Self::Insecure
+        } else {
+            Self::Broken
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.write_str(value.get_name())
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+use super::{category::Category, redirects::Redirect, CheckRecord, UrlWithReferrer};
+
+/// How often crawl progress is saved
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedUrl {
+    url: String,
+    referrer: Option<String>,
+    #[serde(default)]
+    depth: usize,
+}
+
+impl SavedUrl {
+    fn new(url_with_referrer: &UrlWithReferrer) -> Self {
+        Self {
+            url: url_with_referrer.url.to_string(),
+            referrer: url_with_referrer.referrer.as_ref().map(Url::to_string),
+            depth: url_with_referrer.depth,
+        }
+    }
+
+    fn restore(&self) -> Option<UrlWithReferrer> {
+        Some(UrlWithReferrer {
+            url: Url::parse(&self.url).ok()?,
+            referrer: self
+                .referrer
+                .as_deref()
+                .and_then(|url| Url::parse(url).ok()),
+            depth: self.depth,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedRecord {
+    url: String,
+    referrer: Option<String>,
+    status: Option<u16>,
+    elapsed_ms: u64,
+    error: Option<String>,
+    category: Option<Category>,
+}
+
+impl SavedRecord {
+    fn new(record: &CheckRecord) -> Self {
+        Self {
+            url: record.url.to_string(),
+            referrer: record.referrer.as_ref().map(Url::to_string),
+            status: record.status,
+            elapsed_ms: record.elapsed.as_millis() as u64,
+            error: record.error.clone(),
+            category: record.category,
+        }
+    }
+
+    fn restore(&self) -> Option<CheckRecord> {
+        Some(CheckRecord {
+            url: Url::parse(&self.url).ok()?,
+            referrer: self
+                .referrer
+                .as_deref()
+                .and_then(|url| Url::parse(url).ok()),
+            status: self.status,
+            elapsed: Duration::from_millis(self.elapsed_ms),
+            error: self.error.clone(),
+            category: self.category,
+            last_alive: None,
+            network_diagnosis: None,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedRedirect {
+    status: u16,
+    location: String,
+}
+
+/// Progress of a crawl, saved periodically so an interrupted crawl can be
+/// resumed with `--resume`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct Checkpoint {
+    /// Base URL of the crawl, so a checkpoint isn't resumed against another
+    /// site
+    base_url: String,
+    queue: Vec<SavedUrl>,
+    visited: Vec<String>,
+    records: Vec<SavedRecord>,
+    redirects: BTreeMap<String, Vec<SavedRedirect>>,
+    excluded: Vec<String>,
+    fragment_links: Vec<SavedUrl>,
+    /// Anchors on the pages parsed so far, so links to them can still be
+    /// validated after resuming
+    anchors: BTreeMap<String, BTreeSet<String>>,
+    /// Links found on the pages parsed so far
+    links: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Checkpoint {
+    pub(super) fn new(base_url: &Url) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub(super) fn load(path: &Path, base_url: &Url) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read checkpoint {}", path.display()))?;
+        let checkpoint: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse checkpoint {}", path.display()))?;
+        if checkpoint.base_url != base_url.as_str() {
+            anyhow::bail!(
+                "Checkpoint {} is for a crawl of {}, not {}",
+                path.display(),
+                checkpoint.base_url,
+                base_url.as_str()
+            );
+        }
+        Ok(checkpoint)
+    }
+
+    pub(super) fn with_queue<'a>(
+        mut self,
+        queue: impl IntoIterator<Item = &'a UrlWithReferrer>,
+    ) -> Self {
+        self.queue = queue.into_iter().map(SavedUrl::new).collect();
+        self
+    }
+
+    pub(super) fn with_visited(mut self, visited: impl IntoIterator<Item = Url>) -> Self {
+        self.visited = visited.into_iter().map(String::from).collect();
+        self.visited.sort();
+        self
+    }
+
+    pub(super) fn with_records(mut self, records: &[CheckRecord]) -> Self {
+        self.records = records.iter().map(SavedRecord::new).collect();
+        self
+    }
+
+    pub(super) fn with_redirects(
+        mut self,
+        redirects: impl IntoIterator<Item = (Url, Vec<Redirect>)>,
+    ) -> Self {
+        self.redirects = redirects
+            .into_iter()
+            .map(|(url, redirects)| {
+                let redirects = redirects
+                    .iter()
+                    .map(|redirect| SavedRedirect {
+                        status: redirect.status,
+                        location: redirect.location.to_string(),
+                    })
+                    .collect();
+                (url.to_string(), redirects)
+            })
+            .collect();
+        self
+    }
+
+    pub(super) fn with_excluded(mut self, excluded: impl IntoIterator<Item = Url>) -> Self {
+        self.excluded = excluded.into_iter().map(String::from).collect();
+        self
+    }
+
+    pub(super) fn with_fragment_links(mut self, fragment_links: &[UrlWithReferrer]) -> Self {
+        self.fragment_links = fragment_links.iter().map(SavedUrl::new).collect();
+        self
+    }
+
+    pub(super) fn with_anchors(mut self, anchors: BTreeMap<String, BTreeSet<String>>) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    pub(super) fn with_links(
+        mut self,
+        links: impl IntoIterator<Item = (Url, BTreeSet<Url>)>,
+    ) -> Self {
+        self.links = links
+            .into_iter()
+            .map(|(page, targets)| {
+                let targets = targets.into_iter().map(String::from).collect();
+                (page.to_string(), targets)
+            })
+            .collect();
+        self
+    }
+
+    pub(super) fn queue(&self) -> Vec<UrlWithReferrer> {
+        self.queue.iter().filter_map(SavedUrl::restore).collect()
+    }
+
+    pub(super) fn visited(&self) -> impl Iterator<Item = Url> + '_ {
+        self.visited.iter().filter_map(|url| Url::parse(url).ok())
+    }
+
+    pub(super) fn records(&self) -> impl Iterator<Item = CheckRecord> + '_ {
+        self.records.iter().filter_map(SavedRecord::restore)
+    }
+
+    pub(super) fn redirects(&self) -> impl Iterator<Item = (Url, Vec<Redirect>)> + '_ {
+        self.redirects.iter().filter_map(|(url, redirects)| {
+            let redirects = redirects
+                .iter()
+                .filter_map(|redirect| {
+                    Some(Redirect {
+                        status: redirect.status,
+                        location: Url::parse(&redirect.location).ok()?,
+                    })
+                })
+                .collect();
+            Some((Url::parse(url).ok()?, redirects))
+        })
+    }
+
+    pub(super) fn excluded(&self) -> impl Iterator<Item = Url> + '_ {
+        self.excluded.iter().filter_map(|url| Url::parse(url).ok())
+    }
+
+    pub(super) fn fragment_links(&self) -> Vec<UrlWithReferrer> {
+        self.fragment_links
+            .iter()
+            .filter_map(SavedUrl::restore)
+            .collect()
+    }
+
+    pub(super) fn anchors(&self) -> impl Iterator<Item = (Url, BTreeSet<String>)> + '_ {
+        self.anchors
+            .iter()
+            .filter_map(|(page, anchors)| Some((Url::parse(page).ok()?, anchors.clone())))
+    }
+
+    pub(super) fn links(&self) -> impl Iterator<Item = (Url, Vec<Url>)> + '_ {
+        self.links.iter().filter_map(|(page, targets)| {
+            let targets = targets
+                .iter()
+                .filter_map(|target| Url::parse(target).ok())
+                .collect();
+            Some((Url::parse(page).ok()?, targets))
+        })
+    }
+}
+
+/// Writes checkpoints to a file at most every [`CHECKPOINT_INTERVAL`]
+#[derive(Debug)]
+pub(super) struct Checkpointer {
+    path: PathBuf,
+    last_saved: Mutex<Instant>,
+}
+
+impl Checkpointer {
+    pub(super) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_saved: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub(super) fn is_due(&self) -> bool {
+        self.last_saved.lock().unwrap().elapsed() >= CHECKPOINT_INTERVAL
+    }
+
+    /// Save a checkpoint, replacing the previous one atomically so a crawl
+    /// killed while saving can still be resumed.
+    pub(super) fn save(&self, checkpoint: &Checkpoint) -> Result<()> {
+        let partial = self.path.with_extension("partial");
+        fs::write(&partial, serde_json::to_string(checkpoint)?)
+            .with_context(|| format!("Failed to write checkpoint {}", partial.display()))?;
+        fs::rename(&partial, &self.path)
+            .with_context(|| format!("Failed to write checkpoint {}", self.path.display()))?;
+        *self.last_saved.lock().unwrap() = Instant::now();
+        debug!(
+            "Saved checkpoint with {} queued URLs to {}",
+            checkpoint.queue.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+
+    /// Remove the checkpoint once the crawl finishes, so a later run doesn't
+    /// resume a finished crawl.
+    pub(super) fn remove(&self) {
+        if fs::remove_file(&self.path).is_ok() {
+            debug!("Removed checkpoint {}", self.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn saved_progress_loads_back() {
+        let dir =
+            std::env::temp_dir().join(format!("docs-tools-checkpoint-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("crawl.json");
+        let base_url = url("https://example.com/docs/");
+        let queued = UrlWithReferrer {
+            url: url("https://example.com/docs/next"),
+            referrer: Some(url("https://example.com/docs/")),
+            depth: 2,
+        };
+        let record = CheckRecord {
+            url: url("https://example.com/docs/missing"),
+            referrer: Some(url("https://example.com/docs/")),
+            status: Some(404),
+            elapsed: Duration::from_millis(120),
+            error: Some("404 Not Found".to_string()),
+            category: None,
+            last_alive: None,
+            network_diagnosis: None,
+        };
+        let redirect = Redirect {
+            status: 301,
+            location: url("https://example.com/docs/new"),
+        };
+
+        let checkpoint = Checkpoint::new(&base_url)
+            .with_queue([&queued])
+            .with_visited([
+                url("https://example.com/docs/b"),
+                url("https://example.com/docs/a"),
+            ])
+            .with_records(std::slice::from_ref(&record))
+            .with_redirects([(url("https://example.com/docs/old"), vec![redirect])])
+            .with_excluded([url("https://example.com/private")])
+            .with_links([(
+                url("https://example.com/docs/"),
+                BTreeSet::from([url("https://example.com/docs/next")]),
+            )]);
+        Checkpointer::new(path.clone()).save(&checkpoint).unwrap();
+        let loaded = Checkpoint::load(&path, &base_url).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let queue = loaded.queue();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].url, queued.url);
+        assert_eq!(queue[0].referrer, queued.referrer);
+        assert_eq!(queue[0].depth, 2);
+        assert_eq!(
+            loaded.visited().collect::<Vec<_>>(),
+            [
+                url("https://example.com/docs/a"),
+                url("https://example.com/docs/b")
+            ]
+        );
+        let records: Vec<_> = loaded.records().collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].url, record.url);
+        assert_eq!(records[0].status, Some(404));
+        assert_eq!(records[0].elapsed, record.elapsed);
+        assert_eq!(records[0].error, record.error);
+        let redirects: Vec<_> = loaded.redirects().collect();
+        assert_eq!(redirects.len(), 1);
+        assert_eq!(redirects[0].0, url("https://example.com/docs/old"));
+        assert_eq!(redirects[0].1[0].status, 301);
+        assert_eq!(
+            redirects[0].1[0].location,
+            url("https://example.com/docs/new")
+        );
+        assert_eq!(
+            loaded.excluded().collect::<Vec<_>>(),
+            [url("https://example.com/private")]
+        );
+        assert_eq!(
+            loaded.links().collect::<Vec<_>>(),
+            [(
+                url("https://example.com/docs/"),
+                vec![url("https://example.com/docs/next")]
+            )]
+        );
+    }
+
+    #[test]
+    fn checkpoints_for_another_site_are_rejected() {
+        let dir = std::env::temp_dir().join(format!(
+            "docs-tools-checkpoint-mismatch-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("crawl.json");
+        Checkpointer::new(path.clone())
+            .save(&Checkpoint::new(&url("https://example.com/docs/")))
+            .unwrap();
+
+        let error = Checkpoint::load(&path, &url("https://example.org/docs/")).unwrap_err();
+        let loaded_elsewhere = Checkpoint::load(&path, &url("https://example.com/blog/")).is_ok();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.to_string().contains("https://example.org/docs/"));
+        assert!(!loaded_elsewhere);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{collections::HashMap, str::FromStr};
+
+use url::Url;
+
+/// What kind of resource a URL points to, which determines whether its
+/// contents are parsed for further links
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ResourceKind {
+    Html,
+    /// Plain text documents, from which bare URLs are extracted
+    Text,
+    /// XML documents such as RSS/Atom feeds, OPML outlines or sitemaps
+    Xml,
+    Asset,
+}
+
+impl ResourceKind {
+    /// Whether links can be extracted from resources of this kind.
+    pub(super) fn is_parseable(self) -> bool {
+        self != ResourceKind::Asset
+    }
+}
+
+impl FromStr for ResourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "html" => Ok(ResourceKind::Html),
+            "asset" => Ok(ResourceKind::Asset),
+            _ => Err(format!(
+                "Unknown resource kind `{s}`, expected `html` or `asset`"
+            )),
+        }
+    }
+}
+
+/// An override for the classification of a file extension, given as
+/// `EXT=KIND` (e.g., `xml=asset` or `php=html`)
+#[derive(Debug, Clone)]
+pub(crate) struct ExtensionOverride {
+    extension: String,
+    kind: ResourceKind,
+}
+
+impl FromStr for ExtensionOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (extension, kind) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected `EXT=KIND`, got `{s}`"))?;
+        let extension = extension.trim().trim_start_matches('.').to_lowercase();
+        if extension.is_empty() {
+            return Err(format!("Missing extension in `{s}`"));
+        }
+        Ok(Self {
+            extension,
+            kind: kind.trim().parse()?,
+        })
+    }
+}
+
+const DEFAULT_ASSET_EXTENSIONS: &[&str] = &[
+    "svg", "png", "jpg", "jpeg", "gif", "webp", "avif", "ico", "css", "js", "json", "pdf", "woff",
+    "woff2", "ttf", "eot",
+];
+const DEFAULT_TEXT_EXTENSIONS: &[&str] = &["txt"];
+const DEFAULT_XML_EXTENSIONS: &[&str] = &["xml", "rss", "atom", "opml"];
+
+const HTML_CONTENT_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
+const TEXT_CONTENT_TYPES: &[&str] = &["text/plain"];
+const XML_CONTENT_TYPES: &[&str] = &[
+    "application/xml",
+    "text/xml",
+    "application/rss+xml",
+    "application/atom+xml",
+    "text/x-opml",
+    "text/x-opml+xml",
+];
+
+/// Classifies URLs as HTML pages or assets, primarily by Content-Type and
+/// falling back to a table of file extensions.
+#[derive(Debug, Clone)]
+pub(super) struct Classifier {
+    extensions: HashMap<String, ResourceKind>,
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        let table = [
+            (DEFAULT_ASSET_EXTENSIONS, ResourceKind::Asset),
+            (DEFAULT_TEXT_EXTENSIONS, ResourceKind::Text),
+            (DEFAULT_XML_EXTENSIONS, ResourceKind::Xml),
+        ];
+        Self {
+            extensions: table
+                .iter()
+                .flat_map(|(exts, kind)| exts.iter().map(|ext| (ext.to_string(), *kind)))
+                .collect(),
+        }
+    }
+}
+
+impl Classifier {
+    pub(super) fn with_overrides(overrides: &[ExtensionOverride]) -> Self {
+        let mut classifier = Self::default();
+        for ext_override in overrides {
+            classifier
+                .extensions
+                .insert(ext_override.extension.clone(), ext_override.kind);
+        }
+        classifier
+    }
+
+    pub(super) fn classify(&self, url: &Url, content_type: Option<&str>) -> ResourceKind {
+        if let Some(content_type) = content_type {
+            let mime = content_type
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase();
+            let mime = mime.as_str();
+            return if HTML_CONTENT_TYPES.contains(&mime) {
+                ResourceKind::Html
+            } else if TEXT_CONTENT_TYPES.contains(&mime) {
+                ResourceKind::Text
+            } else if XML_CONTENT_TYPES.contains(&mime) {
+                ResourceKind::Xml
+            } else {
+                ResourceKind::Asset
+            };
+        }
+
+        // If no content type is available, fall back to the extension, and
+        // assume anything unrecognized might be HTML
+        url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .and_then(|file_name| file_name.rsplit_once('.'))
+            .and_then(|(_, ext)| self.extensions.get(&ext.to_lowercase()))
+            .copied()
+            .unwrap_or(ResourceKind::Html)
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::BTreeSet;
+
+use url::Url;
+
+/// The outcome of checking a link found in HTML comments, with
+/// `--check-comment-links`
+#[derive(Debug, Clone)]
+pub(super) struct CommentLinkCheck {
+    pub(super) url: Url,
+    /// Pages with the link in a comment
+    pub(super) pages: BTreeSet<Url>,
+    pub(super) status: Option<u16>,
+    /// Why the check failed, or `None` if it succeeded
+    pub(super) error: Option<String>,
+}
+
+impl CommentLinkCheck {
+    pub(super) fn new(
+        url: Url,
+        pages: BTreeSet<Url>,
+        status: Option<u16>,
+        error: Option<String>,
+    ) -> Self {
+        let error = error.or_else(|| match status {
+            Some(status) if !(200..300).contains(&status) => Some(format!("status {status}")),
+            _ => None,
+        });
+        Self {
+            url,
+            pages,
+            status,
+            error,
+        }
+    }
+
+    pub(super) fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
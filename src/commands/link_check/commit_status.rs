@@ -0,0 +1,109 @@
+use std::{env, str::FromStr};
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use tracing::info;
+use url::Url;
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+/// Context of the status unless `--status-context` is given, telling it apart
+/// from other checks on the commit
+pub(super) const DEFAULT_CONTEXT: &str = "docs-tools/link-check";
+/// Longest description GitHub accepts
+const MAX_DESCRIPTION_CHARS: usize = 140;
+
+/// Commit to post the result of a check to as a GitHub commit status, as
+/// `OWNER/REPO@SHA`
+#[derive(Debug, Clone)]
+pub(crate) struct CommitStatusTarget {
+    repo: String,
+    sha: String,
+}
+
+impl FromStr for CommitStatusTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('@') {
+            Some((repo, sha))
+                if repo.split('/').filter(|s| !s.is_empty()).count() == 2
+                    && !sha.is_empty()
+                    && sha.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Ok(Self {
+                    repo: repo.to_string(),
+                    sha: sha.to_string(),
+                })
+            }
+            _ => Err(format!("expected OWNER/REPO@SHA, got {s}")),
+        }
+    }
+}
+
+/// State of a commit status
+#[derive(Debug, Clone, Copy)]
+pub(super) enum StatusState {
+    Success,
+    Failure,
+    /// The check couldn't finish, such as when stopped at `--max-duration`
+    Error,
+}
+
+impl StatusState {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl CommitStatusTarget {
+    /// Post a status to the commit, authenticated with `GITHUB_TOKEN`,
+    /// linking to `target_url` if given.
+    pub(super) async fn post(
+        &self,
+        state: StatusState,
+        description: &str,
+        target_url: Option<&Url>,
+        context: &str,
+    ) -> Result<()> {
+        let token =
+            env::var("GITHUB_TOKEN").context("GITHUB_TOKEN must be set to post a commit status")?;
+        // Set by GitHub Actions, including on GitHub Enterprise Server
+        let api_url = env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        let description: String = description.chars().take(MAX_DESCRIPTION_CHARS).collect();
+        let mut body = json!({
+            "state": state.as_str(),
+            "description": description,
+            "context": context,
+        });
+        if let Some(target_url) = target_url {
+            body["target_url"] = json!(target_url.as_str());
+        }
+        reqwest::Client::builder()
+            .user_agent("docs-tools")
+            .build()?
+            .post(format!(
+                "{}/repos/{}/statuses/{}",
+                api_url.trim_end_matches('/'),
+                self.repo,
+                self.sha
+            ))
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json")
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to post a commit status to {}", self.repo))?;
+        info!(
+            "Posted {} commit status to {}@{}",
+            state.as_str(),
+            self.repo,
+            self.sha
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,788 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use toml::{Table, Value};
+use tracing::debug;
+use url::Url;
+
+use clap::ValueEnum;
+
+use super::{
+    args::{parse_method, LinkCheckArgs},
+    category::Category,
+    expect::{ExpectConfig, Expectation},
+    methods::{MethodConfig, MethodOverride},
+    path_depth::{PathDepthConfig, PathDepthLimit},
+    rewrite::{RewriteConfig, RewriteRule},
+    scope::ScopeMatcher,
+    signing::{RequestSigner, SigningConfig},
+    slugs::{SlugConfig, SlugRules},
+    strategy::Strategy,
+};
+
+/// Config file read from the working directory when `--config` isn't given
+const DEFAULT_CONFIG_FILE: &str = "docs-tools.toml";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Contents of a `docs-tools.toml` file.
+///
+/// Settings that can also be passed as flags use the flag's long name, and
+/// are only used when the flag isn't passed. Named profiles under
+/// `[profiles.<name>]` can override any setting, and are selected with
+/// `--profile`. String values may reference environment variables as
+/// `${NAME}`, so secrets such as tokens don't need to be committed.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct Config {
+    /// Version requirement for docs-tools itself (e.g., `>=0.4`), so that CI
+    /// fails fast when running an older binary than the config expects
+    require_version: Option<String>,
+    /// Base URL of the website, as `--base`
+    base: Option<String>,
+    /// Local static site directory to check, as `--path`
+    path: Option<PathBuf>,
+    /// URL to start the crawl from, as `--start`
+    start: Option<String>,
+    /// Only check internal links, as `--internal-only`
+    internal_only: bool,
+    /// Use a browser-like User-Agent, as `--human-agent`
+    human_agent: bool,
+    /// Check links in a stable order, as `--deterministic`
+    deterministic: bool,
+    /// Order in which queued links are checked, as `--strategy`
+    strategy: Option<String>,
+    /// Look up broken external links in the Wayback Machine, as `--wayback`
+    wayback: bool,
+    /// Treat links that redirect as failures, as `--fail-on-redirect`
+    fail_on_redirect: bool,
+    /// Treat links through URL shorteners as failures, as
+    /// `--fail-on-shortener`
+    fail_on_shortener: bool,
+    /// Additional URL shortener hosts, as `--shortener`
+    shortener: Vec<String>,
+    /// Fail on links with affiliate parameters, as
+    /// `--check-affiliate-params`
+    check_affiliate_params: bool,
+    /// Additional affiliate query parameters, as `--affiliate-param`
+    affiliate_param: Vec<String>,
+    /// Fail on plain HTTP links on HTTPS pages, as `--https-only`
+    https_only: bool,
+    /// Fail on assets loaded over plain HTTP by HTTPS pages, as
+    /// `--check-mixed-content`
+    check_mixed_content: bool,
+    /// Suggest the HTTPS equivalents of plain HTTP links, as
+    /// `--suggest-https`
+    suggest_https: bool,
+    /// JSON file of known failures, as `--baseline`
+    baseline: Option<PathBuf>,
+    /// File to save the link graph to, as `--save-graph`
+    save_graph: Option<PathBuf>,
+    /// DOT or GraphML file to export the link graph to, as `--export-graph`
+    export_graph: Option<PathBuf>,
+    /// OpenTelemetry collector to export metrics and spans to, as
+    /// `--otlp-endpoint`
+    otlp_endpoint: Option<Url>,
+    /// Categories of problems that fail the check, as `--fail-on`
+    fail_on: Option<Vec<String>>,
+    /// Maximum number of redirects to follow for a link, as `--max-redirects`
+    max_redirects: Option<usize>,
+    /// Additional URL prefixes or `*.HOST` wildcards to crawl, as `--scope`
+    scope: Vec<String>,
+    /// Additional URL prefixes or wildcards to check without crawling, as
+    /// `--scope-no-recurse`
+    scope_no_recurse: Vec<String>,
+    /// Paths, URL prefixes or wildcards whose links aren't followed, as
+    /// `--no-recurse`
+    no_recurse: Vec<String>,
+    /// `EXT=KIND` overrides for classifying resources, as `--classify`
+    classify: Vec<String>,
+    /// Sphinx `objects.inv` URLs, as `--inventory`
+    inventory: Vec<Url>,
+    /// Directory to save crawled pages to, as `--mirror`
+    mirror: Option<PathBuf>,
+    /// Directory to cache external link checks in, as `--cache`
+    cache: Option<PathBuf>,
+    /// Hours cached checks stay valid, as `--cache-ttl`
+    cache_ttl: Option<u64>,
+    /// Locale path prefixes to crawl and report separately, as `--locale`
+    locale: Vec<String>,
+    /// Languages to re-request key pages with, as `--accept-language`
+    accept_language: Vec<String>,
+    /// Number of pages to compare on desktop and mobile, as `--compare-mobile`
+    compare_mobile: Option<usize>,
+    /// OWNERS-style file mapping path prefixes to owners, as `--owners`
+    owners: Option<PathBuf>,
+    /// Slack-compatible incoming webhook for each owner in the owners file,
+    /// notified of the broken links on their pages
+    owner_webhooks: HashMap<String, Url>,
+    /// File recording which broken links owners were notified of, as
+    /// `--notify-state`
+    notify_state: Option<PathBuf>,
+    /// Sitemap URL to seed the crawl from, as `--sitemap`
+    sitemap: Option<String>,
+    /// Regexes of URLs to skip, as `--exclude`
+    exclude: Vec<String>,
+    /// Regexes of URLs to check even if excluded, as `--include`
+    include: Vec<String>,
+    /// Don't check links with `rel="nofollow"`, as `--skip-nofollow`
+    skip_nofollow: bool,
+    /// Extra attributes to extract links from, as `--link-attribute`
+    link_attribute: Vec<String>,
+    /// Domains or globs whose links aren't checked, as `--skip-domain`
+    skip_domain: Vec<String>,
+    /// Domains or globs to check external links to, as `--only-domains`
+    only_domains: Vec<String>,
+    /// Regexes of GraphQL endpoints, as `--graphql`
+    graphql: Vec<String>,
+    /// Follow each host's `robots.txt`, as `--respect-robots`
+    respect_robots: bool,
+    /// Check the assets pages load, as `--check-assets`
+    check_assets: bool,
+    /// Check links in HTML comments, as `--check-comment-links`
+    check_comment_links: bool,
+    /// Check the endpoints forms submit to, as `--check-forms`
+    check_forms: bool,
+    /// Method to check form endpoints with, as `--form-method`
+    form_method: Option<String>,
+    /// Validate fragments of links into Markdown on github.com, as
+    /// `--check-github-anchors`
+    check_github_anchors: bool,
+    /// Check Stack Exchange and MDN links through their APIs, as
+    /// `--site-apis`
+    site_apis: bool,
+    /// Depth from the start URL beyond which links aren't followed, as
+    /// `--max-depth`
+    max_depth: Option<usize>,
+    /// Number of pages to parse before no longer following links, as
+    /// `--max-pages`
+    max_pages: Option<usize>,
+    /// Number of path segments links may have before they're skipped, as
+    /// `--max-path-depth`
+    max_path_depth: Option<usize>,
+    /// Number of sitemaps loaded by following sitemap indexes, as
+    /// `--max-sitemaps`
+    max_sitemaps: Option<usize>,
+    /// Number of URLs to size a Bloom filter of visited URLs for, as
+    /// `--visited-bloom`
+    visited_bloom: Option<usize>,
+    /// Maximum number of links checked at once, as `--concurrency`
+    concurrency: Option<usize>,
+    /// Maximum number of connections open at once, as `--max-connections`
+    max_connections: Option<usize>,
+    /// Maximum number of DNS lookups at once, as `--max-dns-lookups`
+    max_dns_lookups: Option<usize>,
+    /// Number of internal links a page may have, as `--max-internal-links`
+    max_internal_links: Option<usize>,
+    /// Number of external links a page may have, as `--max-external-links`
+    max_external_links: Option<usize>,
+    /// Check external links with HEAD first, as `--head-first`
+    head_first: bool,
+    /// Check internal non-page resources with HEAD, as `--no-body`
+    no_body: bool,
+    /// Number of slowest checks listed in the summary, as `--slowest`
+    slowest: Option<usize>,
+    /// Per-host request rate limit, as `--max-requests-per-second-per-host`
+    max_requests_per_second_per_host: Option<f64>,
+    /// Seconds that may be spent checking links to each host, as
+    /// `--domain-budget`
+    domain_budget: Option<u64>,
+    /// Request timeout in seconds, as `--timeout`
+    timeout: Option<u64>,
+    /// Minutes the run may take, as `--max-duration`
+    max_duration: Option<u64>,
+    /// Headers sent with requests to the base URL's origin and to hosts in
+    /// `hosts`, added to by `--header`
+    headers: HashMap<String, String>,
+    /// Cookies sent where `headers` are, as `--cookie`
+    cookie: Vec<String>,
+    /// Proxy to send requests through, as `--proxy`
+    proxy: Option<Url>,
+    /// Proxy auto-config file, as `--pac-file`
+    pac_file: Option<PathBuf>,
+    /// Hosts to reach without the proxy, as `--no-proxy`
+    no_proxy: Vec<String>,
+    /// PEM file of extra root certificates, as `--ca-cert`
+    ca_cert: Option<PathBuf>,
+    /// Settings for requests to specific hosts, keyed by hostname
+    hosts: HashMap<String, HostConfig>,
+    /// Custom success criteria for links matching a pattern, such as a
+    /// status page that must contain some text or an endpoint that must
+    /// return 401. The first matching entry applies.
+    expect: Vec<ExpectConfig>,
+    /// Methods other than GET to check links matching a pattern with, such
+    /// as API endpoints that only accept POST. The first matching entry
+    /// applies.
+    method: Vec<MethodConfig>,
+    /// Signing for requests to links matching a pattern, such as API
+    /// gateways requiring AWS SigV4 or an HMAC header. The first matching
+    /// entry applies.
+    sign: Vec<SigningConfig>,
+    /// How heading anchors are generated on a locale's pages, so links to
+    /// headings with accented or CJK text are matched as the site's
+    /// generator would slug them
+    slug: Vec<SlugConfig>,
+    /// Rewrites of the links found on pages before they're checked, such as
+    /// pointing absolute production URLs at a preview deployment or removing
+    /// tracking parameters. Every matching entry applies, in order.
+    rewrite: Vec<RewriteConfig>,
+    /// Path depth limits for links matching a pattern, overriding
+    /// `max-path-depth` for sections nested deeper than the rest of the
+    /// site. The first matching entry applies.
+    path_depth: Vec<PathDepthConfig>,
+    /// Named sets of overrides for any of the settings above, selected with
+    /// `--profile`
+    // Profiles are applied to the raw file in `load`, so this is only ever
+    // empty and is declared for the schema
+    profiles: HashMap<String, Config>,
+}
+
+/// Overrides for requests to a single host
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+struct HostConfig {
+    /// Request timeout in seconds
+    timeout: Option<u64>,
+    /// Headers sent in addition to (or replacing) the global headers
+    headers: HashMap<String, String>,
+    /// Seconds that may be spent checking links to the host, overriding
+    /// `--domain-budget`
+    budget: Option<u64>,
+}
+
+/// Request settings resolved for a single host
+#[derive(Debug, Clone)]
+pub(super) struct HostOverride {
+    pub(super) timeout: Duration,
+    pub(super) headers: HeaderMap,
+}
+
+impl Config {
+    /// Load the config file at `path`, or `docs-tools.toml` in the working
+    /// directory if it exists, with the given profile applied.
+    pub(super) fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => Path::new(DEFAULT_CONFIG_FILE),
+            None => match profile {
+                Some(profile) => {
+                    anyhow::bail!("Profile {profile} requested, but no config file found")
+                }
+                None => return Ok(Self::default()),
+            },
+        };
+        debug!("Loading config from {}", path.display());
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut table: Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+        let profiles = table.remove("profiles");
+        if let Some(profile) = profile {
+            let overrides = profiles
+                .as_ref()
+                .and_then(|profiles| profiles.get(profile))
+                .and_then(Value::as_table)
+                .with_context(|| format!("No profile {profile} in {}", path.display()))?;
+            debug!("Applying profile {profile}");
+            merge_tables(&mut table, overrides.clone());
+        }
+
+        // Interpolate after applying the profile, so variables only used by
+        // other profiles don't need to be set
+        let mut table = Value::Table(table);
+        interpolate_env(&mut table)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+        let config = Config::deserialize(table)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+        config.check_version()?;
+        Ok(config)
+    }
+
+    fn check_version(&self) -> Result<()> {
+        let Some(requirement) = &self.require_version else {
+            return Ok(());
+        };
+        let requirement = VersionReq::parse(requirement)
+            .with_context(|| format!("Invalid require-version {requirement} in config"))?;
+        let version = Version::parse(env!("CARGO_PKG_VERSION"))?;
+        if !requirement.matches(&version) {
+            anyhow::bail!(
+                "Config requires docs-tools {requirement}, but this is version {version}, run `docs-tools self-update` to update"
+            );
+        }
+        Ok(())
+    }
+
+    /// Fill in any arguments not passed on the command line from the config.
+    pub(super) fn merge_into(&self, args: &mut LinkCheckArgs) -> Result<()> {
+        if args.base_url.is_none() {
+            args.base_url = self.base.clone();
+        }
+        if args.path.is_none() {
+            args.path = self.path.clone();
+        }
+        if args.start_url.is_none() {
+            args.start_url = self.start.clone();
+        }
+        // Unlike other switches, these can be turned off from the command
+        // line, so the file is only used when they're left out
+        if args.internal_only.is_none() {
+            args.internal_only = Some(self.internal_only);
+        }
+        if args.human_agent.is_none() {
+            args.human_agent = Some(self.human_agent);
+        }
+        if args.deterministic.is_none() {
+            args.deterministic = Some(self.deterministic);
+        }
+        args.wayback |= self.wayback;
+        args.fail_on_redirect |= self.fail_on_redirect;
+        args.fail_on_shortener |= self.fail_on_shortener;
+        if args.shorteners.is_empty() {
+            args.shorteners = self.shortener.clone();
+        }
+        args.check_affiliate_params |= self.check_affiliate_params;
+        if args.affiliate_params.is_empty() {
+            args.affiliate_params = self.affiliate_param.clone();
+        }
+        args.https_only |= self.https_only;
+        args.check_mixed_content |= self.check_mixed_content;
+        args.suggest_https |= self.suggest_https;
+        args.respect_robots |= self.respect_robots;
+        args.check_assets |= self.check_assets;
+        args.check_comment_links |= self.check_comment_links;
+        args.check_forms |= self.check_forms;
+        if args.form_method.is_none() {
+            if let Some(method) = &self.form_method {
+                args.form_method = Some(
+                    parse_method(method)
+                        .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))?,
+                );
+            }
+        }
+        args.check_github_anchors |= self.check_github_anchors;
+        args.site_apis |= self.site_apis;
+        if args.baseline.is_none() {
+            args.baseline = self.baseline.clone();
+        }
+        if args.save_graph.is_none() {
+            args.save_graph = self.save_graph.clone();
+        }
+        if args.export_graph.is_none() {
+            args.export_graph = self.export_graph.clone();
+        }
+        if args.otlp_endpoint.is_none() {
+            args.otlp_endpoint = self.otlp_endpoint.clone();
+        }
+        if args.fail_on.is_none() {
+            if let Some(fail_on) = &self.fail_on {
+                args.fail_on = Some(parse_all(fail_on, |category| {
+                    Category::from_str(category, true)
+                })?);
+            }
+        }
+        if args.strategy.is_none() {
+            if let Some(strategy) = &self.strategy {
+                args.strategy = Some(
+                    Strategy::from_str(strategy, true)
+                        .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))?,
+                );
+            }
+        }
+        if args.max_redirects.is_none() {
+            args.max_redirects = self.max_redirects;
+        }
+        if args.scopes.is_empty() {
+            args.scopes = parse_all(&self.scope, ScopeMatcher::from_str)?;
+        }
+        if args.scopes_no_recurse.is_empty() {
+            args.scopes_no_recurse = parse_all(&self.scope_no_recurse, ScopeMatcher::from_str)?;
+        }
+        if args.no_recurse.is_empty() {
+            args.no_recurse = parse_all(&self.no_recurse, ScopeMatcher::parse_no_recurse)?;
+        }
+        if args.classify.is_empty() {
+            args.classify = parse_all(&self.classify, str::parse)?;
+        }
+        if args.inventories.is_empty() {
+            args.inventories = self.inventory.clone();
+        }
+        if args.mirror.is_none() {
+            args.mirror = self.mirror.clone();
+        }
+        if args.excludes.is_empty() {
+            args.excludes = parse_all(&self.exclude, |pattern| {
+                Regex::new(pattern).map_err(|e| e.to_string())
+            })?;
+        }
+        if args.includes.is_empty() {
+            args.includes = parse_all(&self.include, |pattern| {
+                Regex::new(pattern).map_err(|e| e.to_string())
+            })?;
+        }
+        args.skip_nofollow |= self.skip_nofollow;
+        if args.link_attributes.is_empty() {
+            args.link_attributes = self.link_attribute.clone();
+        }
+        if args.skip_domains.is_empty() {
+            args.skip_domains = parse_all(&self.skip_domain, str::parse)?;
+        }
+        if args.only_domains.is_empty() {
+            args.only_domains = parse_all(&self.only_domains, str::parse)?;
+        }
+        if args.graphql.is_empty() {
+            args.graphql = parse_all(&self.graphql, |pattern| {
+                Regex::new(pattern).map_err(|e| e.to_string())
+            })?;
+        }
+        if args.cache.is_none() {
+            args.cache = self.cache.clone();
+        }
+        if args.cache_ttl.is_none() {
+            args.cache_ttl = self.cache_ttl;
+        }
+        if args.max_depth.is_none() {
+            args.max_depth = self.max_depth;
+        }
+        if args.max_pages.is_none() {
+            args.max_pages = self.max_pages;
+        }
+        if args.max_path_depth.is_none() {
+            args.max_path_depth = self.max_path_depth;
+        }
+        if args.max_sitemaps.is_none() {
+            args.max_sitemaps = self.max_sitemaps;
+        }
+        if args.visited_bloom.is_none() {
+            args.visited_bloom = self.visited_bloom;
+        }
+        if args.max_duration.is_none() {
+            args.max_duration = self.max_duration;
+        }
+        if args.concurrency.is_none() {
+            args.concurrency = self.concurrency;
+        }
+        if args.max_connections.is_none() {
+            args.max_connections = self.max_connections;
+        }
+        if args.max_dns_lookups.is_none() {
+            args.max_dns_lookups = self.max_dns_lookups;
+        }
+        if args.max_internal_links.is_none() {
+            args.max_internal_links = self.max_internal_links;
+        }
+        if args.max_external_links.is_none() {
+            args.max_external_links = self.max_external_links;
+        }
+        args.head_first |= self.head_first;
+        args.no_body |= self.no_body;
+        if args.slowest.is_none() {
+            args.slowest = self.slowest;
+        }
+        if args.max_requests_per_second_per_host.is_none() {
+            args.max_requests_per_second_per_host = self.max_requests_per_second_per_host;
+        }
+        if args.domain_budget.is_none() {
+            args.domain_budget = self.domain_budget;
+        }
+        if args.locales.is_empty() {
+            args.locales = self.locale.clone();
+        }
+        if args.accept_language.is_empty() {
+            args.accept_language = self.accept_language.clone();
+        }
+        if args.compare_mobile.is_none() {
+            args.compare_mobile = self.compare_mobile;
+        }
+        if args.owners.is_none() {
+            args.owners = self.owners.clone();
+        }
+        if args.notify_state.is_none() {
+            args.notify_state = self.notify_state.clone();
+        }
+        if args.sitemap.is_none() {
+            args.sitemap = self.sitemap.clone().map(Some);
+        }
+        // A proxy and PAC file given on the command line replace either from
+        // the config file
+        if args.proxy.is_none() && args.pac_file.is_none() {
+            args.proxy = self.proxy.clone();
+            args.pac_file = self.pac_file.clone();
+        }
+        if args.no_proxy.is_empty() {
+            args.no_proxy = self.no_proxy.clone();
+        }
+        if args.ca_cert.is_none() {
+            args.ca_cert = self.ca_cert.clone();
+        }
+        Ok(())
+    }
+
+    /// Add headers and cookies passed on the command line, replacing any
+    /// from the config with the same name.
+    pub(super) fn add_headers(&mut self, headers: &[(String, String)], cookies: &[String]) {
+        let cookies = if cookies.is_empty() {
+            &self.cookie
+        } else {
+            cookies
+        };
+        let cookie = (!cookies.is_empty()).then(|| ("Cookie".to_string(), cookies.join("; ")));
+        for (name, value) in headers.iter().cloned().chain(cookie) {
+            self.headers
+                .retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+            self.headers.insert(name, value);
+        }
+    }
+
+    /// Use the timeout passed on the command line, if any, instead of the
+    /// config's, including for hosts without their own.
+    pub(super) fn set_timeout(&mut self, timeout: Option<u64>) {
+        if timeout.is_some() {
+            self.timeout = timeout;
+        }
+    }
+
+    pub(super) fn owner_webhooks(&self) -> HashMap<String, Url> {
+        self.owner_webhooks.clone()
+    }
+
+    pub(super) fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+
+    pub(super) fn headers(&self) -> Result<HeaderMap> {
+        to_header_map(&self.headers)
+    }
+
+    /// Resolved settings for each host with overrides, layered over the
+    /// global timeout and headers.
+    pub(super) fn host_overrides(&self) -> Result<HashMap<String, HostOverride>> {
+        self.hosts
+            .iter()
+            .map(|(host, host_config)| {
+                let mut headers = self.headers()?;
+                headers.extend(to_header_map(&host_config.headers)?);
+                let timeout = host_config
+                    .timeout
+                    .map(Duration::from_secs)
+                    .unwrap_or_else(|| self.timeout());
+                Ok((host.clone(), HostOverride { timeout, headers }))
+            })
+            .collect()
+    }
+
+    /// Time budgets of the hosts that set their own.
+    pub(super) fn domain_budgets(&self) -> HashMap<String, Duration> {
+        self.hosts
+            .iter()
+            .filter_map(|(host, host_config)| {
+                Some((host.clone(), Duration::from_secs(host_config.budget?)))
+            })
+            .collect()
+    }
+
+    pub(super) fn expectations(&self) -> Result<Vec<Expectation>> {
+        self.expect
+            .iter()
+            .map(|expect| {
+                Expectation::new(expect)
+                    .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))
+            })
+            .collect()
+    }
+
+    pub(super) fn method_overrides(&self) -> Result<Vec<MethodOverride>> {
+        self.method
+            .iter()
+            .map(|method| {
+                MethodOverride::new(method)
+                    .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))
+            })
+            .collect()
+    }
+
+    pub(super) fn request_signers(&self) -> Result<Vec<RequestSigner>> {
+        self.sign
+            .iter()
+            .map(|sign| {
+                RequestSigner::new(sign)
+                    .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))
+            })
+            .collect()
+    }
+
+    pub(super) fn path_depth_limits(&self) -> Result<Vec<PathDepthLimit>> {
+        self.path_depth
+            .iter()
+            .map(|path_depth| {
+                PathDepthLimit::new(path_depth)
+                    .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))
+            })
+            .collect()
+    }
+
+    pub(super) fn rewrite_rules(&self) -> Result<Vec<RewriteRule>> {
+        self.rewrite
+            .iter()
+            .map(|rewrite| {
+                RewriteRule::new(rewrite)
+                    .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))
+            })
+            .collect()
+    }
+
+    pub(super) fn slug_rules(&self, base_url: &Url) -> Result<Vec<SlugRules>> {
+        self.slug
+            .iter()
+            .map(|slug| {
+                SlugRules::new(base_url, slug)
+                    .map_err(|e| anyhow::anyhow!("Invalid value in config: {e}"))
+            })
+            .collect()
+    }
+}
+
+fn parse_all<T>(values: &[String], parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>> {
+    values
+        .iter()
+        .map(|value| parse(value).map_err(|e| anyhow::anyhow!("Invalid value in config: {e}")))
+        .collect()
+}
+
+fn to_header_map(headers: &HashMap<String, String>) -> Result<HeaderMap> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = HeaderName::from_str(name)
+                .with_context(|| format!("Invalid header name in config: {name}"))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for header {name} in config"))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// Overlay `overrides` onto `base`, merging nested tables (e.g., `headers`)
+/// key by key and replacing any other value.
+fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(override_table)) => {
+                merge_tables(base_table, override_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Replace `${NAME}` in every string value with the environment variable
+/// `NAME`, failing if it isn't set.
+fn interpolate_env(value: &mut Value) -> Result<()> {
+    static ENV_VAR: OnceLock<Regex> = OnceLock::new();
+    let env_var = ENV_VAR.get_or_init(|| {
+        Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("Env var regex is valid")
+    });
+
+    match value {
+        Value::String(s) => {
+            let mut missing = None;
+            let interpolated = env_var.replace_all(s, |captures: &Captures| {
+                std::env::var(&captures[1]).unwrap_or_else(|_| {
+                    missing.get_or_insert_with(|| captures[1].to_string());
+                    String::new()
+                })
+            });
+            if let Some(name) = missing {
+                anyhow::bail!("Environment variable {name} is not set");
+            }
+            *s = interpolated.into_owned();
+        }
+        Value::Array(values) => {
+            for value in values {
+                interpolate_env(value)?;
+            }
+        }
+        Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_env(value)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// JSON Schema for the config file, for editor validation and completion.
+pub(crate) fn schema() -> RootSchema {
+    schema_for!(Config)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: LinkCheckArgs,
+    }
+
+    fn merge(config: &str, flags: &[&str]) -> LinkCheckArgs {
+        let config: Config = toml::from_str(config).unwrap();
+        let mut args =
+            Cli::parse_from(std::iter::once("link-check").chain(flags.iter().copied())).args;
+        config.merge_into(&mut args).unwrap();
+        args
+    }
+
+    #[test]
+    fn switches_on_in_the_file_can_be_turned_off() {
+        let config = "internal-only = true\nhuman-agent = true\ndeterministic = true";
+
+        let args = merge(config, &[]);
+        assert_eq!(args.internal_only, Some(true));
+        assert_eq!(args.human_agent, Some(true));
+        assert_eq!(args.deterministic, Some(true));
+
+        let args = merge(
+            config,
+            &[
+                "--internal-only=false",
+                "--human-agent=false",
+                "--deterministic=false",
+            ],
+        );
+        assert_eq!(args.internal_only, Some(false));
+        assert_eq!(args.human_agent, Some(false));
+        assert_eq!(args.deterministic, Some(false));
+    }
+
+    #[test]
+    fn switches_off_in_the_file_can_be_turned_on() {
+        let args = merge(
+            "internal-only = false",
+            &["--internal-only", "--deterministic"],
+        );
+        assert_eq!(args.internal_only, Some(true));
+        assert_eq!(args.deterministic, Some(true));
+        assert_eq!(args.human_agent, Some(false));
+    }
+}
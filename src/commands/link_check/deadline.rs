@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Error ending a run that `--max-duration` stopped before every queued URL
+/// was checked, or before a later phase finished, once its partial results
+/// have been reported. The binary exits with [`DeadlineReached::EXIT_CODE`]
+/// for it, even if links failed, so CI can tell a partial report apart from
+/// broken links.
+#[derive(Debug)]
+pub struct DeadlineReached {
+    /// Number of queued URLs left unchecked
+    pub unchecked: usize,
+    /// Phases after the crawl that were stopped or never started, such as
+    /// `"checking assets"`
+    pub cut_short: Vec<&'static str>,
+}
+
+impl DeadlineReached {
+    /// Exit code of a partial run, distinct from failing links (1) and usage
+    /// errors (2)
+    pub const EXIT_CODE: u8 = 3;
+}
+
+impl fmt::Display for DeadlineReached {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Stopped at --max-duration with {} URLs unchecked",
+            self.unchecked
+        )?;
+        if !self.cut_short.is_empty() {
+            write!(f, ", cutting short {}", self.cut_short.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeadlineReached {}
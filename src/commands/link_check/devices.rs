@@ -0,0 +1,106 @@
+use url::Url;
+
+/// Safari on an iPhone, which sites serving mobile pages recognize
+pub(super) const MOBILE_USER_AGENT: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1";
+
+/// How a page responded to a request with a desktop or mobile `User-Agent`
+#[derive(Debug, Clone, Default)]
+pub(super) struct DeviceResponse {
+    pub(super) status: Option<u16>,
+    /// Where each redirect followed went
+    pub(super) redirects: Vec<Url>,
+    /// The page's `<link rel=canonical>`, resolved against the final URL
+    pub(super) canonical: Option<Url>,
+    /// Why the request failed, such as a redirect loop
+    pub(super) error: Option<String>,
+}
+
+/// How a page's response on mobile differs from its response on desktop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum DeviceProblem {
+    /// The page fails on mobile only, failing the check
+    Fails(String),
+    /// The page loads on mobile, but not as it does on desktop, which is
+    /// reported
+    Differs(String),
+}
+
+impl DeviceResponse {
+    /// What's wrong with the mobile response compared with the `desktop`
+    /// response, if anything, listing every difference in status, redirects
+    /// and, for the same page, canonical URL.
+    pub(super) fn problem(&self, desktop: &DeviceResponse) -> Option<DeviceProblem> {
+        if desktop.error.is_some() {
+            return None;
+        }
+        if let Some(error) = &self.error {
+            return Some(DeviceProblem::Fails(error.clone()));
+        }
+        let loads = |status: Option<u16>| status.is_some_and(|status| (200..300).contains(&status));
+        let describe_status = |status: Option<u16>| {
+            status.map_or("no response".to_string(), |status| status.to_string())
+        };
+        if loads(desktop.status) && !loads(self.status) {
+            return Some(DeviceProblem::Fails(format!(
+                "{} where the page loads on desktop",
+                describe_status(self.status)
+            )));
+        }
+
+        let mut differences = Vec::new();
+        if self.status != desktop.status {
+            differences.push(format!(
+                "status {} differs from {} on desktop",
+                describe_status(self.status),
+                describe_status(desktop.status)
+            ));
+        }
+        if self.redirects != desktop.redirects {
+            differences.push(format!(
+                "{} where desktop {}",
+                describe_redirects(&self.redirects),
+                describe_redirects(&desktop.redirects)
+            ));
+        } else if self.canonical != desktop.canonical {
+            let describe = |canonical: &Option<Url>| {
+                canonical
+                    .as_ref()
+                    .map_or("none".to_string(), Url::to_string)
+            };
+            differences.push(format!(
+                "canonical {} differs from {} on desktop",
+                describe(&self.canonical),
+                describe(&desktop.canonical)
+            ));
+        }
+        (!differences.is_empty()).then(|| DeviceProblem::Differs(differences.join("; ")))
+    }
+}
+
+fn describe_redirects(redirects: &[Url]) -> String {
+    if redirects.is_empty() {
+        return "doesn't redirect".to_string();
+    }
+    let chain: Vec<&str> = redirects.iter().map(Url::as_str).collect();
+    format!("redirects to {}", chain.join(" -> "))
+}
+
+/// A page's responses on desktop and mobile
+#[derive(Debug, Clone)]
+pub(super) struct DeviceCheck {
+    pub(super) page: Url,
+    pub(super) desktop: DeviceResponse,
+    pub(super) mobile: DeviceResponse,
+    pub(super) problem: Option<DeviceProblem>,
+}
+
+/// Up to `count` pages spread evenly across the sorted pages, so every part
+/// of the site is sampled.
+pub(super) fn sample(pages: &[Url], count: usize) -> Vec<Url> {
+    if pages.len() <= count {
+        return pages.to_vec();
+    }
+    (0..count)
+        .map(|i| pages[i * pages.len() / count].clone())
+        .collect()
+}
@@ -0,0 +1,59 @@
+use std::str::FromStr;
+
+use regex::Regex;
+use url::Url;
+
+/// A domain given to `--skip-domain` or `--only-domains`: either a domain
+/// (e.g., `linkedin.com`), matching it and its subdomains, or a glob where
+/// `*` matches any characters (e.g., `cdn*.example.com`)
+#[derive(Debug, Clone)]
+pub(crate) struct DomainPattern(Regex);
+
+impl FromStr for DomainPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let domain = s.trim().trim_end_matches('.').to_lowercase();
+        if domain.is_empty() || domain.contains(['/', ':']) {
+            return Err(format!(
+                "Expected a domain (e.g., `example.com` or `*.example.com`), got `{s}`"
+            ));
+        }
+        let glob = regex::escape(&domain).replace(r"\*", ".*");
+        let pattern = match domain.contains('*') {
+            true => format!("^{glob}$"),
+            false => format!(r"^(.*\.)?{glob}$"),
+        };
+        Regex::new(&pattern).map(Self).map_err(|e| e.to_string())
+    }
+}
+
+impl DomainPattern {
+    fn matches(&self, host: &str) -> bool {
+        self.0.is_match(&host.to_lowercase())
+    }
+}
+
+/// Which external hosts are checked, so links to hosts that block bots
+/// (e.g., `linkedin.com`) can be left out
+#[derive(Debug, Default)]
+pub(super) struct DomainFilter {
+    skip: Vec<DomainPattern>,
+    /// Hosts checked when not empty, unless skipped
+    only: Vec<DomainPattern>,
+}
+
+impl DomainFilter {
+    pub(super) fn new(skip: Vec<DomainPattern>, only: Vec<DomainPattern>) -> Self {
+        Self { skip, only }
+    }
+
+    /// Whether an external link should be checked.
+    pub(super) fn allows(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return true;
+        };
+        let only = self.only.is_empty() || self.only.iter().any(|domain| domain.matches(host));
+        only && !self.skip.iter().any(|domain| domain.matches(host))
+    }
+}
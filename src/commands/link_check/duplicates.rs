@@ -0,0 +1,75 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Mutex,
+};
+
+use url::Url;
+
+use super::utils::normalize_url;
+
+/// Tracks hashes of internal page bodies to find distinct URLs serving
+/// identical content.
+#[derive(Debug, Default)]
+pub(super) struct ContentHashes {
+    hashes: Mutex<HashMap<u64, Vec<Url>>>,
+}
+
+impl ContentHashes {
+    /// Record the body served at a URL.
+    pub(super) fn record(&self, url: &Url, body: &str) {
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let url = normalize_url(url);
+        let mut hashes = self.hashes.lock().unwrap();
+        let urls = hashes.entry(hash).or_default();
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    /// Groups of URLs that served identical content, sorted for stable output.
+    pub(super) fn duplicate_groups(&self) -> Vec<Vec<Url>> {
+        let hashes = self.hashes.lock().unwrap();
+        let mut groups: Vec<Vec<Url>> = hashes
+            .values()
+            .filter(|urls| urls.len() > 1)
+            .map(|urls| {
+                let mut urls = urls.clone();
+                urls.sort();
+                urls
+            })
+            .collect();
+        groups.sort();
+        groups
+    }
+}
+
+/// Groups of pages sharing the same text (e.g., a title), ignoring case and
+/// whitespace, sorted for stable output.
+pub(super) fn group_by_text<'a>(
+    pages: impl IntoIterator<Item = (Url, &'a str)>,
+) -> Vec<(String, Vec<Url>)> {
+    let mut groups: BTreeMap<String, (String, Vec<Url>)> = BTreeMap::new();
+    for (url, text) in pages {
+        let key = text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        let (_, urls) = groups
+            .entry(key)
+            .or_insert_with(|| (text.to_string(), Vec::new()));
+        urls.push(url);
+    }
+    groups
+        .into_values()
+        .filter(|(_, urls)| urls.len() > 1)
+        .map(|(text, mut urls)| {
+            urls.sort();
+            (text, urls)
+        })
+        .collect()
+}
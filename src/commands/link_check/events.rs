@@ -0,0 +1,164 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use tracing::debug;
+
+use super::{category::Category, report::Summary, skips::SkipReason, CheckRecord};
+
+/// Something that happened during a crawl, streamed as a line of JSON with
+/// `--format ndjson`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub(super) enum Event<'a> {
+    /// A URL was found and queued to be checked
+    Queued {
+        url: &'a str,
+        /// The page it was found on, if any
+        from: Option<&'a str>,
+        /// Number of links from the start URL
+        depth: usize,
+    },
+    /// A URL was found but won't be checked
+    Skipped {
+        url: &'a str,
+        referrer: Option<&'a str>,
+        reason: SkipReason,
+        /// More about why, such as the limit the URL went beyond
+        detail: Option<&'a str>,
+    },
+    /// A URL started being checked
+    Started { url: &'a str },
+    /// A URL was checked and works
+    Succeeded {
+        url: &'a str,
+        referrer: Option<&'a str>,
+        status: Option<u16>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        elapsed_ms: Option<u64>,
+    },
+    /// A URL was checked and has a problem
+    Failed {
+        url: &'a str,
+        referrer: Option<&'a str>,
+        status: Option<u16>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        elapsed_ms: Option<u64>,
+        error: &'a str,
+        category: Option<Category>,
+    },
+    /// The crawl finished, with the totals of the report
+    Finished { summary: &'a Summary },
+}
+
+impl<'a> Event<'a> {
+    /// The event for the outcome of a check, with the time it took if
+    /// `timed`.
+    pub(super) fn checked(record: &'a CheckRecord, timed: bool) -> Self {
+        let url = record.url.as_str();
+        let referrer = record.referrer.as_ref().map(|referrer| referrer.as_str());
+        let elapsed_ms = timed.then_some(record.elapsed.as_millis() as u64);
+        match &record.error {
+            None => Event::Succeeded {
+                url,
+                referrer,
+                status: record.status,
+                elapsed_ms,
+            },
+            Some(error) => Event::Failed {
+                url,
+                referrer,
+                status: record.status,
+                elapsed_ms,
+                error,
+                category: record.category,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    /// When the event happened, in RFC 3339, unless left out with
+    /// `--deterministic`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<String>,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Writes events as they happen, one JSON object per line, so that wrappers
+/// can follow long crawls rather than wait for the report
+pub(super) struct EventStream {
+    writer: Mutex<Box<dyn Write + Send>>,
+    /// Whether events say when they happened
+    timed: bool,
+}
+
+impl EventStream {
+    /// Stream events to a file, or to stdout if none is given, saying when
+    /// each happened if `timed`.
+    pub(super) fn open(path: Option<&Path>, timed: bool) -> Result<Self> {
+        let writer: Box<dyn Write + Send> =
+            match path {
+                Some(path) => Box::new(BufWriter::new(File::create(path).with_context(|| {
+                    format!("Failed to create report file {}", path.display())
+                })?)),
+                None => Box::new(io::stdout()),
+            };
+        Ok(Self {
+            writer: Mutex::new(writer),
+            timed,
+        })
+    }
+
+    /// Write an event, flushing it so it's seen straight away.
+    pub(super) fn emit(&self, event: &Event) {
+        let line = Line {
+            time: self
+                .timed
+                .then(|| Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+            event,
+        };
+        let mut writer = self.writer.lock().unwrap();
+        let written = serde_json::to_writer(&mut *writer, &line)
+            .map_err(io::Error::from)
+            .and_then(|()| writeln!(writer))
+            .and_then(|()| writer.flush());
+        // A reader that went away shouldn't stop the crawl
+        if let Err(e) = written {
+            debug!("Failed to write event: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untimed_checks_leave_out_elapsed_time() {
+        let record = CheckRecord {
+            url: "https://example.com/".parse().unwrap(),
+            referrer: None,
+            status: Some(200),
+            elapsed: std::time::Duration::from_millis(42),
+            error: None,
+            category: None,
+            last_alive: None,
+            network_diagnosis: None,
+        };
+        let timed = serde_json::to_value(Event::checked(&record, true)).unwrap();
+        assert_eq!(timed["elapsed_ms"], 42);
+
+        let untimed = serde_json::to_value(Event::checked(&record, false)).unwrap();
+        assert!(untimed.get("elapsed_ms").is_none());
+        assert_eq!(untimed["status"], 200);
+    }
+}
@@ -0,0 +1,82 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use url::Url;
+
+/// Success criteria for links matching a pattern, as configured under
+/// `[[expect]]`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct ExpectConfig {
+    /// Regex of URLs the criteria apply to
+    url: String,
+    /// Status the link must return (e.g., `401` for an endpoint requiring
+    /// authentication), instead of any 2xx status
+    status: Option<u16>,
+    /// Text the response body must contain (e.g., `operational` for a
+    /// status page)
+    body_contains: Option<String>,
+}
+
+/// Custom success criteria for links matching a pattern, so special cases
+/// can be checked rather than excluded
+#[derive(Debug)]
+pub(super) struct Expectation {
+    pattern: Regex,
+    status: Option<u16>,
+    body_contains: Option<String>,
+}
+
+impl Expectation {
+    pub(super) fn new(config: &ExpectConfig) -> Result<Self, String> {
+        Ok(Self {
+            pattern: Regex::new(&config.url).map_err(|e| e.to_string())?,
+            status: config.status,
+            body_contains: config.body_contains.clone(),
+        })
+    }
+
+    /// Whether the criteria apply to a URL. Fragments don't affect the
+    /// response, so are ignored.
+    /// A GraphQL endpoint must answer the query sent by
+    /// [`MethodOverride::graphql`](super::methods::MethodOverride::graphql)
+    /// with data, not only errors.
+    pub(super) fn graphql(pattern: Regex) -> Self {
+        Self {
+            pattern,
+            status: None,
+            body_contains: Some(r#""__typename""#.to_string()),
+        }
+    }
+
+    pub(super) fn matches(&self, url: &Url) -> bool {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        self.pattern.is_match(url.as_str())
+    }
+
+    /// Whether the body needs to be read to check the response
+    pub(super) fn needs_body(&self) -> bool {
+        self.body_contains.is_some()
+    }
+
+    /// Check a response against the criteria, returning why it doesn't meet
+    /// them.
+    pub(super) fn check(&self, status: u16, body: &str) -> Result<(), String> {
+        match self.status {
+            Some(expected) if status != expected => {
+                return Err(format!("expected status {expected}, got {status}"));
+            }
+            None if !(200..300).contains(&status) => {
+                return Err(format!("expected a successful status, got {status}"));
+            }
+            _ => {}
+        }
+        if let Some(text) = self.body_contains.as_deref() {
+            if !body.contains(text) {
+                return Err(format!("response body doesn't contain `{text}`"));
+            }
+        }
+        Ok(())
+    }
+}
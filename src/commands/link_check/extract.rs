@@ -0,0 +1,505 @@
+use std::collections::{BTreeSet, HashMap};
+
+use html5gum::{Token, Tokenizer};
+
+use super::assets::{AssetKind, ExtractedAsset};
+
+/// Attributes that carry URLs in common XML formats (Atom `link`, OPML
+/// `outline`, media enclosures, ...)
+const XML_URL_ATTRIBUTES: &[&str] = &["href", "src", "url", "xmlurl", "htmlurl"];
+
+/// Elements whose text content is a URL in common XML formats (RSS `link`,
+/// sitemap `loc`, ...)
+const XML_URL_ELEMENTS: &[&str] = &["link", "loc", "url", "guid", "comments"];
+
+/// Extract links from an XML document such as an RSS or Atom feed, an OPML
+/// outline or a sitemap.
+pub(super) fn extract_xml_links(s: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut current_element: Option<String> = None;
+
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) => {
+                let name = String::from_utf8_lossy(&tag.name).into_owned();
+                for (attr, value) in &tag.attributes {
+                    let attr = String::from_utf8_lossy(attr);
+                    if XML_URL_ATTRIBUTES.contains(&attr.as_ref()) {
+                        links.push(String::from_utf8_lossy(value).trim().to_string());
+                    }
+                }
+                current_element = (!tag.self_closing).then_some(name);
+            }
+            Token::EndTag(_) => current_element = None,
+            Token::String(text) => {
+                let Some(element) = &current_element else {
+                    continue;
+                };
+                if !XML_URL_ELEMENTS.contains(&element.as_str()) {
+                    continue;
+                }
+                let text = String::from_utf8_lossy(&text);
+                let text = text.trim();
+                if text.contains("://") || text.starts_with('/') {
+                    links.push(text.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    links.retain(|link| !link.is_empty());
+    links
+}
+
+/// Attribute content authors set on a link, or on an element around links
+/// (e.g., a code sample), to opt them out of checking
+pub(super) const SKIP_ATTRIBUTE: &str = "data-docs-tools-skip";
+
+/// Elements that never have an end tag, so can't contain skipped links
+const VOID_ELEMENTS: &[&[u8]] = &[
+    b"area", b"base", b"br", b"col", b"embed", b"hr", b"img", b"input", b"link", b"meta",
+    b"source", b"track", b"wbr",
+];
+
+/// Extract the values of extra attributes that carry links in some
+/// frameworks' rendered output (e.g., `data-href` or `to`), on any element.
+/// Attribute names are lowercase, as they're parsed.
+pub(super) fn extract_attribute_links(s: &str, attributes: &[String]) -> Vec<String> {
+    let mut links = Vec::new();
+
+    for Ok(token) in Tokenizer::new(s) {
+        let Token::StartTag(tag) = token else {
+            continue;
+        };
+        for name in attributes {
+            if let Some(link) = tag.attributes.get(name.as_bytes()) {
+                links.push(String::from_utf8_lossy(link).trim().to_string());
+            }
+        }
+    }
+
+    links.retain(|link| !link.is_empty());
+    links
+}
+
+/// Extract the links authors opted out of checking: those on or inside
+/// elements with the skip attribute and, if `nofollow` is set, those with
+/// `rel="nofollow"`. Links are read from `href`, `src` and `attributes`. Each
+/// link is counted as many times as it's skipped, so that the same link
+/// elsewhere on the page is still checked.
+pub(super) fn extract_skipped_links(
+    s: &str,
+    nofollow: bool,
+    attributes: &[String],
+) -> HashMap<String, usize> {
+    let mut skipped: HashMap<String, usize> = HashMap::new();
+    // The skipped element being read inside of, and how many elements of
+    // its name are open, so its end tag is found despite nesting
+    let mut inside: Option<(Vec<u8>, usize)> = None;
+
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) => {
+                let attribute = |name: &str| tag.attributes.get(name.as_bytes());
+                let is_nofollow = nofollow
+                    && attribute("rel").is_some_and(|rel| {
+                        String::from_utf8_lossy(rel)
+                            .split_ascii_whitespace()
+                            .any(|value| value.eq_ignore_ascii_case("nofollow"))
+                    });
+                if inside.is_some() || attribute(SKIP_ATTRIBUTE).is_some() || is_nofollow {
+                    let names = ["href", "src"]
+                        .into_iter()
+                        .chain(attributes.iter().map(String::as_str));
+                    for name in names {
+                        if let Some(link) = attribute(name) {
+                            let link = String::from_utf8_lossy(link).trim().to_string();
+                            *skipped.entry(link).or_default() += 1;
+                        }
+                    }
+                }
+                let opens = !tag.self_closing && !VOID_ELEMENTS.contains(&tag.name.as_slice());
+                match &mut inside {
+                    Some((name, open)) if *name == *tag.name && opens => *open += 1,
+                    None if opens && attribute(SKIP_ATTRIBUTE).is_some() => {
+                        inside = Some((tag.name.to_vec(), 1));
+                    }
+                    _ => {}
+                }
+            }
+            Token::EndTag(tag) => {
+                if let Some((name, open)) = &mut inside {
+                    if *name == *tag.name {
+                        *open -= 1;
+                        if *open == 0 {
+                            inside = None;
+                        }
+                    }
+                }
+            }
+            // Bare URLs in the text of a skipped code sample
+            Token::String(text) if inside.is_some() => {
+                for word in String::from_utf8_lossy(&text).split_whitespace() {
+                    if word.starts_with("http://") || word.starts_with("https://") {
+                        *skipped.entry(word.to_string()).or_default() += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    skipped
+}
+
+/// Extract the links in an HTML document's comments, such as commented-out
+/// navigation: the `href` and `src` of markup in each comment, and bare
+/// http(s) URLs in its text.
+pub(super) fn extract_comment_links(s: &str) -> Vec<String> {
+    let mut links = Vec::new();
+
+    for Ok(token) in Tokenizer::new(s) {
+        let Token::Comment(comment) = token else {
+            continue;
+        };
+        let comment = String::from_utf8_lossy(&comment);
+        for Ok(token) in Tokenizer::new(comment.as_ref()) {
+            match token {
+                Token::StartTag(tag) => {
+                    for name in ["href", "src"] {
+                        if let Some(link) = tag.attributes.get(name.as_bytes()) {
+                            links.push(String::from_utf8_lossy(link).trim().to_string());
+                        }
+                    }
+                }
+                Token::String(text) => {
+                    for word in String::from_utf8_lossy(&text).split_whitespace() {
+                        if word.starts_with("http://") || word.starts_with("https://") {
+                            links.push(word.to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    links.retain(|link| !link.is_empty());
+    links
+}
+
+/// Extract where each form in an HTML document submits to, with its method
+/// in lowercase: its `action` and the `formaction` of each of its buttons,
+/// which may override the method with `formmethod`. Forms without an action
+/// submit to their own page, so are left out, as are dialog forms.
+pub(super) fn extract_form_actions(s: &str) -> Vec<(String, String)> {
+    let mut actions = Vec::new();
+    // The method of the form being read inside of
+    let mut form_method: Option<String> = None;
+
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) => {
+                let attribute = |name: &str| {
+                    tag.attributes
+                        .get(name.as_bytes())
+                        .map(|value| String::from_utf8_lossy(value).trim().to_string())
+                };
+                let method = |name: &str, default: &str| {
+                    attribute(name)
+                        .filter(|method| !method.is_empty())
+                        .map_or(default.to_string(), |method| method.to_ascii_lowercase())
+                };
+                match tag.name.as_slice() {
+                    b"form" => {
+                        let method = method("method", "get");
+                        if let Some(action) = attribute("action").filter(|a| !a.is_empty()) {
+                            if method != "dialog" {
+                                actions.push((action, method.clone()));
+                            }
+                        }
+                        form_method = Some(method);
+                    }
+                    b"button" | b"input" => {
+                        let Some(action) = attribute("formaction").filter(|a| !a.is_empty()) else {
+                            continue;
+                        };
+                        let method = method("formmethod", form_method.as_deref().unwrap_or("get"));
+                        if method != "dialog" {
+                            actions.push((action, method));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Token::EndTag(tag) if tag.name == b"form" => form_method = None,
+            _ => {}
+        }
+    }
+
+    actions
+}
+
+/// Extract every anchor that a fragment can point to in an HTML document: the
+/// `id` of any element and the `name` of `<a>` elements.
+pub(crate) fn extract_anchors(s: &str) -> BTreeSet<String> {
+    let mut anchors = BTreeSet::new();
+
+    for Ok(token) in Tokenizer::new(s) {
+        let Token::StartTag(tag) = token else {
+            continue;
+        };
+        if let Some(id) = tag.attributes.get(b"id".as_slice()) {
+            anchors.insert(String::from_utf8_lossy(id).into_owned());
+        }
+        if tag.name == b"a" {
+            if let Some(name) = tag.attributes.get(b"name".as_slice()) {
+                anchors.insert(String::from_utf8_lossy(name).into_owned());
+            }
+        }
+    }
+
+    anchors.remove("");
+    anchors
+}
+
+/// Extract the text of an HTML document's `<title>`.
+pub(super) fn extract_title(s: &str) -> Option<String> {
+    let mut in_title = false;
+    let mut title = String::new();
+
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) if tag.name == b"title" => in_title = true,
+            Token::EndTag(tag) if tag.name == b"title" => break,
+            Token::String(text) if in_title => title.push_str(&String::from_utf8_lossy(&text)),
+            _ => {}
+        }
+    }
+
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// The tags of an HTML page that search engines index it by
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PageMeta {
+    /// The `href` of every `<link rel=canonical>`
+    pub(crate) canonicals: Vec<String>,
+    pub(crate) title: Option<String>,
+    /// The content of every `<meta name=description>`
+    pub(crate) descriptions: Vec<String>,
+    /// Whether a `<meta name=robots>`, or one for a specific crawler (e.g.,
+    /// `googlebot`), blocks indexing
+    pub(crate) noindex: bool,
+}
+
+/// Extract the canonical links, title, descriptions and robots directives of
+/// an HTML document.
+pub(super) fn extract_meta(s: &str) -> PageMeta {
+    let mut meta = PageMeta {
+        title: extract_title(s),
+        ..PageMeta::default()
+    };
+    let attribute = |tag: &html5gum::StartTag, name: &[u8]| {
+        tag.attributes
+            .get(name)
+            .map(|value| String::from_utf8_lossy(value).trim().to_string())
+    };
+
+    for Ok(token) in Tokenizer::new(s) {
+        let Token::StartTag(tag) = token else {
+            continue;
+        };
+        match tag.name.as_slice() {
+            b"link" => {
+                let is_canonical = attribute(&tag, b"rel").is_some_and(|rel| {
+                    rel.split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("canonical"))
+                });
+                if let Some(href) = attribute(&tag, b"href").filter(|_| is_canonical) {
+                    meta.canonicals.push(href);
+                }
+            }
+            b"meta" => {
+                let name = attribute(&tag, b"name")
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                let content = attribute(&tag, b"content").unwrap_or_default();
+                if name == "description" {
+                    meta.descriptions.push(content);
+                } else if name == "robots" || name.ends_with("bot") {
+                    meta.noindex |= content.split(',').any(|directive| {
+                        let directive = directive.trim().to_ascii_lowercase();
+                        directive == "noindex" || directive == "none"
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    meta
+}
+
+/// The `<title>` and headings of an HTML page
+#[derive(Debug, Clone, Default)]
+pub(super) struct PageHeadings {
+    pub(super) title: Option<String>,
+    /// The first `<h1>`
+    pub(super) h1: Option<String>,
+    /// Every `<h1>`-`<h6>` heading, in document order
+    pub(super) headings: Vec<String>,
+}
+
+impl PageHeadings {
+    /// The title followed by every heading.
+    pub(super) fn all(&self) -> Vec<String> {
+        self.title
+            .iter()
+            .chain(self.headings.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+/// Extract the text of an HTML document's `<title>` and `<h1>`-`<h6>`
+/// headings.
+pub(super) fn extract_headings(s: &str) -> PageHeadings {
+    const HEADINGS: &[&[u8]] = &[b"title", b"h1", b"h2", b"h3", b"h4", b"h5", b"h6"];
+    let mut page = PageHeadings::default();
+    let mut current: Option<String> = None;
+
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) if HEADINGS.contains(&tag.name.as_slice()) => {
+                current = Some(String::new())
+            }
+            Token::EndTag(tag) if HEADINGS.contains(&tag.name.as_slice()) => {
+                if let Some(heading) = current.take() {
+                    let heading = heading.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if heading.is_empty() {
+                        continue;
+                    }
+                    match tag.name.as_slice() {
+                        b"title" => {
+                            page.title.get_or_insert(heading);
+                        }
+                        b"h1" => {
+                            page.h1.get_or_insert_with(|| heading.clone());
+                            page.headings.push(heading);
+                        }
+                        _ => page.headings.push(heading),
+                    }
+                }
+            }
+            Token::String(text) => {
+                if let Some(heading) = current.as_mut() {
+                    heading.push_str(&String::from_utf8_lossy(&text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    page
+}
+
+/// Extract the assets an HTML document loads: images (including `srcset`
+/// candidates and video posters), scripts, stylesheets, preloaded resources,
+/// and video and audio sources.
+pub(super) fn extract_assets(s: &str) -> Vec<ExtractedAsset> {
+    let mut assets = Vec::new();
+    // `<source src>` is video or audio inside `<video>` and `<audio>`, and an
+    // image inside `<picture>`
+    let mut in_media = false;
+    // Whether the current `<picture>` offers an AVIF or WebP source
+    let mut has_modern_variant = false;
+
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) => {
+                let attr = |name: &str| {
+                    tag.attributes
+                        .get(name.as_bytes())
+                        .map(|value| String::from_utf8_lossy(value).trim().to_string())
+                };
+                let is_img = tag.name == b"img";
+                let missing_alt = is_img && !tag.attributes.contains_key(b"alt".as_slice());
+                let modern_variant = is_img && has_modern_variant;
+                let mut push = |url: Option<String>, kind: AssetKind| {
+                    assets.extend(url.map(|url| ExtractedAsset {
+                        url,
+                        kind,
+                        missing_alt,
+                        has_modern_variant: modern_variant,
+                    }));
+                };
+                match tag.name.as_slice() {
+                    b"img" => {
+                        push(attr("src"), AssetKind::Image);
+                        for url in srcset_urls(attr("srcset")) {
+                            push(Some(url), AssetKind::Image);
+                        }
+                    }
+                    b"picture" => has_modern_variant = false,
+                    b"source" => {
+                        has_modern_variant |=
+                            attr("type").is_some_and(|t| t == "image/avif" || t == "image/webp");
+                        let kind = match in_media {
+                            true => AssetKind::Media,
+                            false => AssetKind::Image,
+                        };
+                        push(attr("src"), kind);
+                        for url in srcset_urls(attr("srcset")) {
+                            push(Some(url), AssetKind::Image);
+                        }
+                    }
+                    b"video" | b"audio" => {
+                        in_media = !tag.self_closing;
+                        push(attr("src"), AssetKind::Media);
+                        push(attr("poster"), AssetKind::Image);
+                    }
+                    b"script" => push(attr("src"), AssetKind::Script),
+                    b"link" => {
+                        let rel = attr("rel").unwrap_or_default().to_lowercase();
+                        let rels: Vec<&str> = rel.split_whitespace().collect();
+                        let kind = if rels.contains(&"stylesheet") {
+                            Some(AssetKind::Stylesheet)
+                        } else if rels.contains(&"icon") || rels.contains(&"apple-touch-icon") {
+                            Some(AssetKind::Image)
+                        } else if rels.contains(&"modulepreload") {
+                            Some(AssetKind::Script)
+                        } else if rels.contains(&"preload") {
+                            attr("as").as_deref().and_then(AssetKind::from_preload)
+                        } else {
+                            None
+                        };
+                        if let Some(kind) = kind {
+                            push(attr("href"), kind);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Token::EndTag(tag) if tag.name == b"video" || tag.name == b"audio" => in_media = false,
+            Token::EndTag(tag) if tag.name == b"picture" => has_modern_variant = false,
+            _ => {}
+        }
+    }
+
+    // Inline data needs no request
+    assets.retain(|asset| !asset.url.is_empty() && !asset.url.starts_with("data:"));
+    assets
+}
+
+/// The URLs of the candidates in a `srcset` (e.g., `a.png 1x, b.png 2x`).
+fn srcset_urls(srcset: Option<String>) -> Vec<String> {
+    srcset
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|candidate| candidate.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
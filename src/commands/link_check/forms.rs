@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+
+use reqwest::Method;
+use url::Url;
+
+/// Request method forms that don't submit with GET are checked with, which
+/// finds the endpoint without submitting anything
+pub(super) const DEFAULT_FORM_METHOD: Method = Method::OPTIONS;
+
+/// Where a form submits to: a `<form action>`, or the `formaction` of one of
+/// its buttons, with the method it submits with
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct FormTarget {
+    pub(super) url: Url,
+    /// The form's method, lowercase (e.g., `get` or `post`)
+    pub(super) method: String,
+}
+
+/// The outcome of checking that a form's endpoint exists, with
+/// `--check-forms`
+#[derive(Debug, Clone)]
+pub(super) struct FormCheck {
+    pub(super) target: FormTarget,
+    /// Pages with a form submitting to the endpoint
+    pub(super) pages: BTreeSet<Url>,
+    pub(super) status: Option<u16>,
+    /// Why the check failed, or `None` if it succeeded
+    pub(super) error: Option<String>,
+}
+
+impl FormCheck {
+    /// Check a response from a form's endpoint. Only a missing endpoint or a
+    /// server error fails it, as endpoints reject requests that aren't a
+    /// real submission in many other ways (e.g., 405 Method Not Allowed or
+    /// 422 Unprocessable Entity).
+    pub(super) fn new(
+        target: FormTarget,
+        pages: BTreeSet<Url>,
+        status: Option<u16>,
+        error: Option<String>,
+    ) -> Self {
+        let error = error.or_else(|| match status {
+            Some(status @ (404 | 410)) => Some(format!("status {status}")),
+            Some(status) if status >= 500 && status != 501 => Some(format!("status {status}")),
+            _ => None,
+        });
+        Self {
+            target,
+            pages,
+            status,
+            error,
+        }
+    }
+
+    pub(super) fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    env,
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use url::Url;
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// A Markdown document on github.com whose headings can be linked to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct GithubDoc {
+    owner: String,
+    repo: String,
+    /// Git ref and path of a Markdown file, or `None` for the README shown on
+    /// the repo's front page
+    file: Option<(String, String)>,
+}
+
+impl GithubDoc {
+    /// The document a link points into, for links to a repo's front page
+    /// (`github.com/OWNER/REPO`) or a Markdown file
+    /// (`github.com/OWNER/REPO/blob/REF/PATH.md`).
+    pub(super) fn from_url(url: &Url) -> Option<Self> {
+        if url.host_str() != Some("github.com") {
+            return None;
+        }
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        match segments.as_slice() {
+            [owner, repo] => Some(Self {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                file: None,
+            }),
+            [owner, repo, "blob", git_ref, path @ ..]
+                if path
+                    .last()
+                    .is_some_and(|name| name.to_lowercase().ends_with(".md")) =>
+            {
+                Some(Self {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    file: Some((git_ref.to_string(), path.join("/"))),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Validates fragments of links into Markdown on github.com, which GitHub
+/// renders client-side so can't be checked against the fetched page
+pub(super) struct GithubAnchors {
+    client: reqwest::Client,
+    api_url: String,
+    token: Option<String>,
+    /// Anchors in each document fetched so far, or `None` if it couldn't be
+    /// fetched
+    docs: HashMap<GithubDoc, Option<BTreeSet<String>>>,
+}
+
+impl GithubAnchors {
+    pub(super) fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            // Set by GitHub Actions
+            api_url: env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string()),
+            token: env::var("GITHUB_TOKEN").ok(),
+            docs: HashMap::new(),
+        }
+    }
+
+    /// The anchors GitHub generates for a document, fetching it the first
+    /// time.
+    ///
+    /// ## Returns
+    /// Returns `Err` if the document couldn't be fetched.
+    pub(super) async fn anchors(&mut self, doc: &GithubDoc) -> Result<&BTreeSet<String>> {
+        if !self.docs.contains_key(doc) {
+            let anchors = self.fetch(doc).await.map(|markdown| anchors(&markdown));
+            let failed = anchors.as_ref().err().map(|e| format!("{e:#}"));
+            self.docs.insert(doc.clone(), anchors.ok());
+            if let Some(e) = failed {
+                anyhow::bail!(e);
+            }
+        }
+        self.docs[doc]
+            .as_ref()
+            .context("Failed to fetch the document earlier")
+    }
+
+    /// Fetch the raw Markdown of a document through the GitHub API.
+    async fn fetch(&self, doc: &GithubDoc) -> Result<String> {
+        let repo_url = format!(
+            "{}/repos/{}/{}",
+            self.api_url.trim_end_matches('/'),
+            doc.owner,
+            doc.repo
+        );
+        let mut request = match &doc.file {
+            Some((git_ref, path)) => self
+                .client
+                .get(format!("{repo_url}/contents/{path}"))
+                .query(&[("ref", git_ref)]),
+            None => self.client.get(format!("{repo_url}/readme")),
+        };
+        request = request.header(ACCEPT, "application/vnd.github.raw");
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let response = request.send().await?.error_for_status()?;
+        Ok(response.text().await?)
+    }
+}
+
+/// The anchors GitHub generates for a Markdown document's headings, plus any
+/// explicit `id` or `name` attributes in inline HTML.
+fn anchors(markdown: &str) -> BTreeSet<String> {
+    static HTML_ANCHOR: OnceLock<Regex> = OnceLock::new();
+    let html_anchor = HTML_ANCHOR.get_or_init(|| {
+        Regex::new(r#"\b(?:id|name)\s*=\s*["']([^"']+)["']"#).expect("HTML anchor regex is valid")
+    });
+
+    let mut anchors = BTreeSet::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut in_code_block = false;
+    let mut previous: Option<&str> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            previous = None;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        anchors.extend(
+            html_anchor
+                .captures_iter(line)
+                .map(|captures| captures[1].to_string()),
+        );
+
+        let heading = if let Some(text) = atx_heading(trimmed) {
+            Some(text)
+        } else if is_setext_underline(trimmed) {
+            previous.filter(|text| !text.is_empty())
+        } else {
+            None
+        };
+        if let Some(text) = heading {
+            // Repeated slugs are numbered, skipping numbers taken by other
+            // headings (`Intro`, `Intro`, `Intro 1` is `intro`, `intro-1`,
+            // `intro-1-1`)
+            let slug = slug(text);
+            let mut anchor = slug.clone();
+            while counts.contains_key(&anchor) {
+                let count = counts.get_mut(&slug).expect("slug was counted");
+                *count += 1;
+                anchor = format!("{slug}-{count}");
+            }
+            counts.insert(anchor.clone(), 0);
+            anchors.insert(anchor);
+            previous = None;
+        } else {
+            previous = Some(trimmed);
+        }
+    }
+    anchors
+}
+
+/// The text of an ATX heading (`## Text ##`).
+fn atx_heading(line: &str) -> Option<&str> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let rest = &line[hashes..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some(rest.trim().trim_end_matches('#').trim_end())
+}
+
+fn is_setext_underline(line: &str) -> bool {
+    !line.is_empty() && (line.chars().all(|c| c == '=') || line.chars().all(|c| c == '-'))
+}
+
+/// GitHub's slug for a heading: the rendered text lowercased, with
+/// punctuation other than `-` and `_` removed and spaces replaced by `-`.
+fn slug(heading: &str) -> String {
+    static MARKUP: OnceLock<Regex> = OnceLock::new();
+    let markup = MARKUP.get_or_init(|| {
+        // Links and images render as their text, and tags render as nothing
+        Regex::new(r"!?\[([^\]]*)\]\([^)]*\)|<[^>]+>").expect("Markup regex is valid")
+    });
+    let text = markup.replace_all(heading, "$1");
+    text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_match_github() {
+        // As rendered on github.com
+        for (heading, expected) in [
+            ("Getting Started", "getting-started"),
+            ("What's new?", "whats-new"),
+            ("C++ & Rust", "c--rust"),
+            ("API: `fetch()`", "api-fetch"),
+            ("Version 1.2.3", "version-123"),
+            ("snake_case and kebab-case", "snake_case-and-kebab-case"),
+            ("**Bold** and _emphasis_", "bold-and-_emphasis_"),
+            ("[Linked](https://example.com) heading", "linked-heading"),
+            ("<code>tags</code> removed", "tags-removed"),
+            ("Über Café", "über-café"),
+            ("日本語の見出し", "日本語の見出し"),
+            ("Привет мир", "привет-мир"),
+            ("🚀 Quick start", "-quick-start"),
+            ("Done ✅", "done-"),
+        ] {
+            assert_eq!(slug(heading), expected, "{heading}");
+        }
+    }
+
+    #[test]
+    fn anchors_match_github() {
+        let markdown = r#"# Intro
+## Intro
+### Intro
+## Intro 1
+Setup
+-----
+## Setup ##
+<a name="custom-anchor"></a>
+<h2 id="html-heading">HTML</h2>
+```sh
+# Not a heading
+```
+"#;
+        assert_eq!(
+            anchors(markdown),
+            BTreeSet::from(
+                [
+                    "intro",
+                    "intro-1",
+                    "intro-2",
+                    "intro-1-1",
+                    "setup",
+                    "setup-1",
+                    "custom-anchor",
+                    "html-heading",
+                ]
+                .map(String::from)
+            )
+        );
+    }
+}
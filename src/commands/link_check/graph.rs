@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{super::xml, utils::normalize_url};
+
+/// The links between pages found during a crawl, keyed by normalized URL, for
+/// reports that need more than per-link results.
+#[derive(Debug, Default)]
+pub(super) struct LinkGraph {
+    /// Every URL linked from each crawled page
+    links: Mutex<BTreeMap<Url, BTreeSet<Url>>>,
+}
+
+impl LinkGraph {
+    /// Record the links found on a page.
+    pub(super) fn add_links<'a>(&self, page: &Url, targets: impl IntoIterator<Item = &'a Url>) {
+        let page = normalize_url(page);
+        let mut links = self.links.lock().unwrap();
+        let page_links = links.entry(page).or_default();
+        page_links.extend(targets.into_iter().map(normalize_url));
+    }
+
+    /// Record that a URL redirected, so the final page counts as linked from
+    /// wherever the original URL was.
+    pub(super) fn add_redirect(&self, url: &Url, final_url: &Url) {
+        self.add_links(url, [final_url]);
+    }
+
+    /// Every crawled page with the URLs linked from it.
+    pub(super) fn pages(&self) -> Vec<(Url, BTreeSet<Url>)> {
+        self.links
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(page, targets)| (page.clone(), targets.clone()))
+            .collect()
+    }
+
+    /// Every page linking to a URL.
+    pub(super) fn linking_to(&self, url: &Url) -> Vec<Url> {
+        let url = normalize_url(url);
+        self.links
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(page, targets)| **page != url && targets.contains(&url))
+            .map(|(page, _)| page.clone())
+            .collect()
+    }
+
+    /// Of the given pages, those that no other page links to.
+    pub(super) fn unlinked<'a>(&self, pages: impl IntoIterator<Item = &'a Url>) -> Vec<Url> {
+        let links = self.links.lock().unwrap();
+        let linked: BTreeSet<&Url> = links
+            .iter()
+            .flat_map(|(page, targets)| targets.iter().filter(move |target| *target != page))
+            .collect();
+        pages
+            .into_iter()
+            .map(normalize_url)
+            .filter(|page| !linked.contains(page))
+            .collect()
+    }
+}
+
+/// The internal link graph of a crawl, saved with `--save-graph` so that
+/// `graph-diff` can compare the structure of the site between runs
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GraphSnapshot {
+    /// Base URL of the crawl. Pages are keyed by their path under it, so
+    /// crawls of different deployments (e.g., staging and production) can be
+    /// compared.
+    pub(crate) base_url: String,
+    /// Paths of the pages linked from each crawled page
+    pub(crate) pages: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl GraphSnapshot {
+    /// Snapshot the links between the pages of a crawl, keeping only links
+    /// for which `is_internal` holds.
+    pub(super) fn new(
+        base_url: &Url,
+        graph: &LinkGraph,
+        is_internal: impl Fn(&Url) -> bool,
+    ) -> Self {
+        let pages = graph
+            .pages()
+            .into_iter()
+            .filter(|(page, _)| is_internal(page))
+            .map(|(page, targets)| {
+                let targets = targets
+                    .iter()
+                    .filter(|target| is_internal(target))
+                    .map(|target| relative_path(base_url, target))
+                    .collect();
+                (relative_path(base_url, &page), targets)
+            })
+            .collect();
+        Self {
+            base_url: base_url.to_string(),
+            pages,
+        }
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read link graph {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse link graph {}", path.display()))
+    }
+
+    pub(super) fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content + "\n")
+            .with_context(|| format!("Failed to write link graph {}", path.display()))
+    }
+}
+
+/// File formats the link graph can be exported to with `--export-graph`
+#[derive(Debug, Clone, Copy)]
+pub(super) enum GraphFormat {
+    Dot,
+    Graphml,
+}
+
+impl GraphFormat {
+    /// The format of an export file, by its extension.
+    pub(super) fn of_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("dot" | "gv") => Ok(Self::Dot),
+            Some("graphml") => Ok(Self::Graphml),
+            _ => anyhow::bail!(
+                "Can't tell the format of {} from its extension, use .dot, .gv or .graphml",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl GraphSnapshot {
+    /// Export the graph for tools such as Graphviz, Gephi or NetworkX, with
+    /// each page's number of inbound links from other pages as an attribute
+    /// so that pages no other page links to stand out.
+    pub(super) fn export(&self, path: &Path, format: GraphFormat) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create graph file {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            GraphFormat::Dot => self.write_dot(&mut writer)?,
+            GraphFormat::Graphml => self.write_graphml(&mut writer)?,
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write graph file {}", path.display()))
+    }
+
+    /// Every page, crawled or only linked to, with its number of inbound
+    /// links from other pages.
+    fn nodes(&self) -> BTreeMap<&str, usize> {
+        let mut nodes: BTreeMap<&str, usize> =
+            self.pages.keys().map(|page| (page.as_str(), 0)).collect();
+        for (page, targets) in &self.pages {
+            for target in targets {
+                let inbound = nodes.entry(target).or_default();
+                if target != page {
+                    *inbound += 1;
+                }
+            }
+        }
+        nodes
+    }
+
+    fn write_dot(&self, writer: &mut impl Write) -> Result<()> {
+        writeln!(writer, "digraph site {{")?;
+        writeln!(writer, "  // {}", self.base_url)?;
+        for (page, inbound) in self.nodes() {
+            let crawled = self.pages.contains_key(page);
+            writeln!(
+                writer,
+                "  {} [inbound={inbound}, crawled={crawled}];",
+                dot_id(page)
+            )?;
+        }
+        for (page, targets) in &self.pages {
+            for target in targets.iter().filter(|target| *target != page) {
+                writeln!(writer, "  {} -> {};", dot_id(page), dot_id(target))?;
+            }
+        }
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+
+    fn write_graphml(&self, writer: &mut impl Write) -> Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <key id="inbound" for="node" attr.name="inbound" attr.type="int"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <key id="crawled" for="node" attr.name="crawled" attr.type="boolean"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <graph id="{}" edgedefault="directed">"#,
+            xml::escape(&self.base_url)
+        )?;
+        for (page, inbound) in self.nodes() {
+            let crawled = self.pages.contains_key(page);
+            writeln!(writer, r#"    <node id="{}">"#, xml::escape(page))?;
+            writeln!(writer, r#"      <data key="inbound">{inbound}</data>"#)?;
+            writeln!(writer, r#"      <data key="crawled">{crawled}</data>"#)?;
+            writeln!(writer, "    </node>")?;
+        }
+        for (page, targets) in &self.pages {
+            for target in targets.iter().filter(|target| *target != page) {
+                writeln!(
+                    writer,
+                    r#"    <edge source="{}" target="{}"/>"#,
+                    xml::escape(page),
+                    xml::escape(target)
+                )?;
+            }
+        }
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</graphml>")?;
+        Ok(())
+    }
+}
+
+/// A quoted DOT identifier.
+fn dot_id(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The path of a URL under a base URL, or the whole URL if it isn't under it.
+fn relative_path(base_url: &Url, url: &Url) -> String {
+    let base_path = base_url.path().trim_end_matches('/');
+    match url.origin() == base_url.origin() {
+        true => {
+            let path = url.path().strip_prefix(base_path).unwrap_or(url.path());
+            format!("/{}", path.trim_start_matches('/'))
+        }
+        false => url.to_string(),
+    }
+}
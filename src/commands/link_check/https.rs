@@ -0,0 +1,16 @@
+use url::Url;
+
+/// Whether a link or asset on a page downgrades from HTTPS to plain HTTP.
+pub(super) fn is_downgrade(page: &Url, url: &Url) -> bool {
+    page.scheme() == "https" && url.scheme() == "http"
+}
+
+/// The URL over HTTPS, dropping an explicit port 80.
+pub(super) fn https_equivalent(url: &Url) -> Option<Url> {
+    let mut https = url.clone();
+    if https.port() == Some(80) {
+        https.set_port(None).ok()?;
+    }
+    https.set_scheme("https").ok()?;
+    Some(https)
+}
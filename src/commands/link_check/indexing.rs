@@ -0,0 +1,25 @@
+use reqwest::header::HeaderMap;
+
+const X_ROBOTS_TAG: &str = "x-robots-tag";
+
+/// Whether a response's `X-Robots-Tag` headers block it from being indexed,
+/// by any crawler.
+///
+/// Directives may be scoped to a crawler (e.g., `googlebot: noindex`), and
+/// `none` is equivalent to `noindex, nofollow`.
+pub(super) fn is_noindex(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(X_ROBOTS_TAG)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| {
+            directive
+                .rsplit(':')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        })
+        .any(|directive| directive == "noindex" || directive == "none")
+}
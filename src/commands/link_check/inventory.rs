@@ -0,0 +1,105 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+};
+
+use anyhow::{Context, Result};
+use flate2::read::ZlibDecoder;
+use regex::Regex;
+use tracing::debug;
+use url::Url;
+
+use super::utils::{normalize_url, StartsWith as _};
+
+/// A Sphinx `objects.inv` inventory, listing the pages and anchors of an
+/// external documentation site (as used by intersphinx)
+#[derive(Debug)]
+pub(super) struct Inventory {
+    /// Root of the documentation site the inventory describes
+    base: Url,
+    /// Anchors known to exist on each page
+    pages: HashMap<Url, HashSet<String>>,
+}
+
+/// The outcome of looking up a URL in an inventory
+pub(super) enum InventoryLookup {
+    /// The page (and anchor, if any) is listed in the inventory
+    Found,
+    /// The page is listed in the inventory, but the anchor isn't
+    MissingAnchor,
+}
+
+impl Inventory {
+    pub(super) async fn fetch(client: &reqwest::Client, url: &Url) -> Result<Self> {
+        let bytes = client
+            .get(url.as_str())
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let base = url.join(".")?;
+        Self::parse(base, &bytes).with_context(|| format!("Invalid inventory at {url}"))
+    }
+
+    fn parse(base: Url, bytes: &[u8]) -> Result<Self> {
+        // The format is four plain-text header lines followed by a
+        // zlib-compressed body
+        let mut header_end = 0;
+        for _ in 0..4 {
+            let line_end = bytes[header_end..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .context("Truncated inventory header")?;
+            header_end += line_end + 1;
+        }
+        let header = String::from_utf8_lossy(&bytes[..header_end]);
+        if !header.starts_with("# Sphinx inventory version 2") {
+            anyhow::bail!("Unsupported inventory version");
+        }
+
+        let mut body = String::new();
+        ZlibDecoder::new(&bytes[header_end..]).read_to_string(&mut body)?;
+
+        // `name domain:role priority uri dispname`, where the name and display
+        // name may contain spaces
+        let line_re = Regex::new(r"^(.+?)\s+(\S+:\S+)\s+(-?\d+)\s+(\S*)\s+(.*)$")?;
+        let mut pages: HashMap<Url, HashSet<String>> = HashMap::new();
+        for line in body.lines() {
+            let Some(captures) = line_re.captures(line) else {
+                continue;
+            };
+            let name = &captures[1];
+            let mut uri = captures[4].to_string();
+            if let Some(stripped) = uri.strip_suffix('$') {
+                uri = format!("{stripped}{name}");
+            }
+            let Ok(target) = base.join(&uri) else {
+                continue;
+            };
+            let anchors = pages.entry(normalize_url(&target)).or_default();
+            if let Some(fragment) = target.fragment() {
+                anchors.insert(fragment.to_string());
+            }
+        }
+        debug!("Loaded inventory for {} with {} pages", base, pages.len());
+
+        Ok(Self { base, pages })
+    }
+
+    /// Look up a URL, returning `None` if the inventory can't vouch for it
+    /// either way (it is outside the documentation site, or the page has no
+    /// objects listed).
+    pub(super) fn lookup(&self, url: &Url) -> Option<InventoryLookup> {
+        if !url.starts_with(&self.base) {
+            return None;
+        }
+        let anchors = self.pages.get(&normalize_url(url))?;
+        match url.fragment() {
+            Some(fragment) if !fragment.is_empty() && !anchors.contains(fragment) => {
+                Some(InventoryLookup::MissingAnchor)
+            }
+            _ => Some(InventoryLookup::Found),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, env};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use url::Url;
+
+use super::{issue_body, BrokenLink};
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+/// Label applied to every filed issue, used to find them again
+const LABEL: &str = "broken-link";
+const PER_PAGE: usize = 100;
+const MARKER_PREFIX: &str = "<!-- docs-tools:broken-link ";
+const MARKER_SUFFIX: &str = " -->";
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    body: Option<String>,
+}
+
+struct Client {
+    client: reqwest::Client,
+    repo_url: String,
+    token: String,
+}
+
+/// Hidden marker identifying the issue for a URL, so issues are found again
+/// even if their title is edited.
+fn marker(url: &str) -> String {
+    format!("{MARKER_PREFIX}{url}{MARKER_SUFFIX}")
+}
+
+pub(super) async fn sync(
+    repo: &str,
+    broken: &[BrokenLink],
+    recovered: impl Fn(&Url) -> bool,
+) -> Result<()> {
+    let token =
+        env::var("GITHUB_TOKEN").context("GITHUB_TOKEN must be set to file GitHub issues")?;
+    // Set by GitHub Actions, including on GitHub Enterprise Server
+    let api_url = env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+    let client = Client {
+        client: reqwest::Client::builder()
+            .user_agent("docs-tools")
+            .build()?,
+        repo_url: format!("{}/repos/{repo}", api_url.trim_end_matches('/')),
+        token,
+    };
+
+    // Open issues keyed by the URL in their marker
+    let mut open: HashMap<String, Issue> = HashMap::new();
+    for issue in client.open_issues().await? {
+        let url = issue.body.as_deref().and_then(|body| {
+            body.lines()
+                .next()?
+                .strip_prefix(MARKER_PREFIX)?
+                .strip_suffix(MARKER_SUFFIX)
+                .map(str::to_string)
+        });
+        if let Some(url) = url {
+            open.insert(url, issue);
+        }
+    }
+
+    for link in broken {
+        let body = format!("{}\n{}", marker(link.url.as_str()), issue_body(link));
+        match open.get(link.url.as_str()) {
+            Some(issue) if issue.body.as_deref() == Some(body.as_str()) => {}
+            Some(issue) => {
+                client.patch(issue.number, json!({ "body": body })).await?;
+                info!("Updated issue #{} for {}", issue.number, link.url.as_str());
+            }
+            None => {
+                let number = client
+                    .create(json!({
+                        "title": format!("Broken link: {}", link.url.as_str()),
+                        "body": body,
+                        "labels": [LABEL],
+                    }))
+                    .await?;
+                info!("Opened issue #{number} for {}", link.url.as_str());
+            }
+        }
+    }
+
+    for (url, issue) in &open {
+        if !Url::parse(url).is_ok_and(|url| recovered(&url)) {
+            continue;
+        }
+        client
+            .comment(issue.number, "This link is working again, closing.")
+            .await?;
+        client
+            .patch(issue.number, json!({ "state": "closed" }))
+            .await?;
+        info!("Closed issue #{} for {url}", issue.number);
+    }
+    Ok(())
+}
+
+impl Client {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{path}", self.repo_url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+    }
+
+    async fn open_issues(&self) -> Result<Vec<Issue>> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let batch: Vec<Issue> = self
+                .request(
+                    reqwest::Method::GET,
+                    &format!("/issues?state=open&labels={LABEL}&per_page={PER_PAGE}&page={page}"),
+                )
+                .send()
+                .await?
+                .error_for_status()
+                .context("Failed to list GitHub issues")?
+                .json()
+                .await?;
+            let done = batch.len() < PER_PAGE;
+            issues.extend(batch);
+            if done {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    async fn create(&self, issue: serde_json::Value) -> Result<u64> {
+        let created: Issue = self
+            .request(reqwest::Method::POST, "/issues")
+            .json(&issue)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to open GitHub issue")?
+            .json()
+            .await?;
+        Ok(created.number)
+    }
+
+    async fn patch(&self, number: u64, update: serde_json::Value) -> Result<()> {
+        self.request(reqwest::Method::PATCH, &format!("/issues/{number}"))
+            .json(&update)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to update GitHub issue #{number}"))?;
+        Ok(())
+    }
+
+    async fn comment(&self, number: u64, body: &str) -> Result<()> {
+        self.request(reqwest::Method::POST, &format!("/issues/{number}/comments"))
+            .json(&json!({ "body": body }))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to comment on GitHub issue #{number}"))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::HashMap, env};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use url::Url;
+
+use super::{issue_body, BrokenLink};
+
+/// Label applied to every filed ticket, used to find them again
+const LABEL: &str = "broken-link";
+/// Prefix of the label for each site section a broken link is in
+const SECTION_LABEL_PREFIX: &str = "docs-";
+const ISSUE_TYPE: &str = "Bug";
+const MAX_RESULTS: usize = 100;
+const SUMMARY_PREFIX: &str = "Broken link: ";
+
+#[derive(Deserialize)]
+struct SearchResults {
+    issues: Vec<Ticket>,
+    total: usize,
+}
+
+#[derive(Deserialize)]
+struct Ticket {
+    key: String,
+    fields: TicketFields,
+}
+
+#[derive(Deserialize)]
+struct TicketFields {
+    summary: String,
+    description: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Transitions {
+    transitions: Vec<Transition>,
+}
+
+#[derive(Deserialize)]
+struct Transition {
+    id: String,
+    to: Value,
+}
+
+struct Client {
+    client: reqwest::Client,
+    api_url: String,
+    email: String,
+    token: String,
+}
+
+pub(super) async fn sync(
+    project: &str,
+    broken: &[BrokenLink],
+    recovered: impl Fn(&Url) -> bool,
+) -> Result<()> {
+    let var = |name: &str| {
+        env::var(name).with_context(|| format!("{name} must be set to file JIRA tickets"))
+    };
+    let client = Client {
+        client: reqwest::Client::builder()
+            .user_agent("docs-tools")
+            .build()?,
+        api_url: format!("{}/rest/api/2", var("JIRA_URL")?.trim_end_matches('/')),
+        email: var("JIRA_EMAIL")?,
+        token: var("JIRA_API_TOKEN")?,
+    };
+
+    // Open tickets keyed by the URL in their summary
+    let open: HashMap<String, Ticket> = client
+        .open_tickets(project)
+        .await?
+        .into_iter()
+        .filter_map(|ticket| {
+            let url = ticket
+                .fields
+                .summary
+                .strip_prefix(SUMMARY_PREFIX)?
+                .to_string();
+            Some((url, ticket))
+        })
+        .collect();
+
+    for link in broken {
+        let description = issue_body(link);
+        let mut labels = vec![LABEL.to_string()];
+        labels.extend(
+            link.sections
+                .iter()
+                .map(|section| format!("{SECTION_LABEL_PREFIX}{section}")),
+        );
+        match open.get(link.url.as_str()) {
+            Some(ticket)
+                if ticket.fields.description.as_deref() == Some(description.as_str())
+                    && ticket.fields.labels == labels => {}
+            Some(ticket) => {
+                client
+                    .update(
+                        &ticket.key,
+                        json!({ "fields": { "description": description, "labels": labels } }),
+                    )
+                    .await?;
+                info!("Updated {} for {}", ticket.key, link.url.as_str());
+            }
+            None => {
+                let key = client
+                    .create(json!({
+                        "fields": {
+                            "project": { "key": project },
+                            "issuetype": { "name": ISSUE_TYPE },
+                            "summary": format!("{SUMMARY_PREFIX}{}", link.url.as_str()),
+                            "description": description,
+                            "labels": labels,
+                        }
+                    }))
+                    .await?;
+                info!("Opened {key} for {}", link.url.as_str());
+            }
+        }
+    }
+
+    for (url, ticket) in &open {
+        if !Url::parse(url).is_ok_and(|url| recovered(&url)) {
+            continue;
+        }
+        client
+            .comment(&ticket.key, "This link is working again, closing.")
+            .await?;
+        if client.close(&ticket.key).await? {
+            info!("Closed {} for {url}", ticket.key);
+        } else {
+            warn!(
+                "{} for {url} has recovered, but has no transition to a done status",
+                ticket.key
+            );
+        }
+    }
+    Ok(())
+}
+
+impl Client {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{path}", self.api_url))
+            .basic_auth(&self.email, Some(&self.token))
+    }
+
+    async fn open_tickets(&self, project: &str) -> Result<Vec<Ticket>> {
+        let jql =
+            format!("project = \"{project}\" AND labels = \"{LABEL}\" AND statusCategory != Done");
+        let mut tickets = Vec::new();
+        loop {
+            let results: SearchResults = self
+                .request(reqwest::Method::POST, "/search")
+                .json(&json!({
+                    "jql": jql,
+                    "startAt": tickets.len(),
+                    "maxResults": MAX_RESULTS,
+                    "fields": ["summary", "description", "labels"],
+                }))
+                .send()
+                .await?
+                .error_for_status()
+                .context("Failed to search JIRA tickets")?
+                .json()
+                .await?;
+            let done = results.issues.is_empty();
+            tickets.extend(results.issues);
+            if done || tickets.len() >= results.total {
+                break;
+            }
+        }
+        Ok(tickets)
+    }
+
+    async fn create(&self, ticket: Value) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Created {
+            key: String,
+        }
+
+        let created: Created = self
+            .request(reqwest::Method::POST, "/issue")
+            .json(&ticket)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed to create JIRA ticket")?
+            .json()
+            .await?;
+        Ok(created.key)
+    }
+
+    async fn update(&self, key: &str, update: Value) -> Result<()> {
+        self.request(reqwest::Method::PUT, &format!("/issue/{key}"))
+            .json(&update)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to update JIRA ticket {key}"))?;
+        Ok(())
+    }
+
+    async fn comment(&self, key: &str, body: &str) -> Result<()> {
+        self.request(reqwest::Method::POST, &format!("/issue/{key}/comment"))
+            .json(&json!({ "body": body }))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to comment on JIRA ticket {key}"))?;
+        Ok(())
+    }
+
+    /// Move a ticket to a done status, using whichever transition the
+    /// project's workflow has for it.
+    ///
+    /// ## Returns
+    /// Returns `false` if the workflow has no transition to a done status.
+    async fn close(&self, key: &str) -> Result<bool> {
+        let transitions: Transitions = self
+            .request(reqwest::Method::GET, &format!("/issue/{key}/transitions"))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to list transitions for JIRA ticket {key}"))?
+            .json()
+            .await?;
+        let Some(transition) = transitions
+            .transitions
+            .iter()
+            .find(|transition| transition.to["statusCategory"]["key"].as_str() == Some("done"))
+        else {
+            return Ok(false);
+        };
+        self.request(reqwest::Method::POST, &format!("/issue/{key}/transitions"))
+            .json(&json!({ "transition": { "id": transition.id } }))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to close JIRA ticket {key}"))?;
+        Ok(true)
+    }
+}
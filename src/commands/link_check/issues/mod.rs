@@ -0,0 +1,77 @@
+use std::{collections::BTreeSet, str::FromStr};
+
+use anyhow::Result;
+use url::Url;
+
+mod github;
+mod jira;
+
+/// Where to file issues for broken links, as `KIND:TARGET`
+#[derive(Debug, Clone)]
+pub(crate) enum IssueTracker {
+    /// GitHub issues in an `owner/repo` repository
+    Github { repo: String },
+    /// JIRA tickets in a project, given by its key
+    Jira { project: String },
+}
+
+impl FromStr for IssueTracker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("github", repo)) if repo.split('/').filter(|s| !s.is_empty()).count() == 2 => {
+                Ok(Self::Github {
+                    repo: repo.to_string(),
+                })
+            }
+            Some(("github", _)) => Err(format!("expected github:OWNER/REPO, got {s}")),
+            Some(("jira", project)) if !project.is_empty() => Ok(Self::Jira {
+                project: project.to_string(),
+            }),
+            _ => Err(format!(
+                "unknown issue tracker {s}, expected github:OWNER/REPO or jira:PROJECT"
+            )),
+        }
+    }
+}
+
+/// A broken link to file an issue for
+#[derive(Debug)]
+pub(super) struct BrokenLink {
+    pub(super) url: Url,
+    pub(super) reason: String,
+    /// Every page linking to the URL
+    pub(super) referrers: Vec<Url>,
+    /// Top-level sections of the site (e.g., `guides`) the referrers are in
+    pub(super) sections: BTreeSet<String>,
+}
+
+impl IssueTracker {
+    /// Open an issue for each broken link without one, update the referrers
+    /// listed on existing ones, and close those for links that `recovered`
+    /// returns true for.
+    pub(super) async fn sync(
+        &self,
+        broken: &[BrokenLink],
+        recovered: impl Fn(&Url) -> bool,
+    ) -> Result<()> {
+        match self {
+            Self::Github { repo } => github::sync(repo, broken, recovered).await,
+            Self::Jira { project } => jira::sync(project, broken, recovered).await,
+        }
+    }
+}
+
+/// The issue body for a broken link, listing the pages linking to it.
+fn issue_body(link: &BrokenLink) -> String {
+    let mut body = format!(
+        "{} is broken: {}\n\nLinked from:\n",
+        link.url.as_str(),
+        link.reason
+    );
+    for referrer in &link.referrers {
+        body.push_str(&format!("- {}\n", referrer.as_str()));
+    }
+    body
+}
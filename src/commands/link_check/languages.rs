@@ -0,0 +1,71 @@
+use url::Url;
+
+/// How a page responded to a request with an `Accept-Language` header, or
+/// without one
+#[derive(Debug, Clone, Default)]
+pub(super) struct LanguageResponse {
+    pub(super) status: Option<u16>,
+    /// Where the request ended up after redirects
+    pub(super) final_url: Option<Url>,
+    /// The page's `<link rel=canonical>`, resolved against the final URL
+    pub(super) canonical: Option<Url>,
+    /// Why the request failed, such as a redirect loop
+    pub(super) error: Option<String>,
+}
+
+/// How a page's response to a language differs from its response without
+/// one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum LanguageProblem {
+    /// The page fails for readers of the language only, failing the check
+    Fails(String),
+    /// The page loads, but not as it does otherwise, which is reported
+    Differs(String),
+}
+
+impl LanguageResponse {
+    /// What's wrong with the response compared with the `default` response
+    /// without an `Accept-Language` header, if anything. Redirects to a
+    /// localized page are expected, but failures aren't, nor is the same
+    /// page naming a different canonical URL.
+    pub(super) fn problem(&self, default: &LanguageResponse) -> Option<LanguageProblem> {
+        if default.error.is_some() {
+            return None;
+        }
+        if let Some(error) = &self.error {
+            return Some(LanguageProblem::Fails(error.clone()));
+        }
+        let loads = |status: Option<u16>| status.is_some_and(|status| (200..300).contains(&status));
+        if loads(default.status) && !loads(self.status) {
+            return Some(LanguageProblem::Fails(format!(
+                "{} where the page loads without it",
+                self.status
+                    .map_or("no response".to_string(), |status| status.to_string())
+            )));
+        }
+        if self.final_url == default.final_url && self.canonical != default.canonical {
+            let describe = |canonical: &Option<Url>| {
+                canonical
+                    .as_ref()
+                    .map_or("none".to_string(), Url::to_string)
+            };
+            return Some(LanguageProblem::Differs(format!(
+                "canonical {} differs from {} without it",
+                describe(&self.canonical),
+                describe(&default.canonical)
+            )));
+        }
+        None
+    }
+}
+
+/// A key page's response to one language
+#[derive(Debug, Clone)]
+pub(super) struct LanguageCheck {
+    pub(super) page: Url,
+    /// The `Accept-Language` requested with, or `None` for the request
+    /// without one the others are compared with
+    pub(super) language: Option<String>,
+    pub(super) response: LanguageResponse,
+    pub(super) problem: Option<LanguageProblem>,
+}
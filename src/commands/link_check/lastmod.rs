@@ -0,0 +1,58 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate};
+use url::Url;
+
+/// How far a sitemap `lastmod` may be from when the page was actually last
+/// modified before it is reported
+const TOLERANCE_DAYS: i64 = 30;
+
+/// A page whose sitemap `lastmod` doesn't match when it was last modified
+pub(super) struct LastmodMismatch {
+    pub(super) url: Url,
+    pub(super) lastmod: NaiveDate,
+    /// From the page's `Last-Modified` header, or the file's modification
+    /// time when checking a local directory
+    pub(super) last_modified: NaiveDate,
+}
+
+/// Parse a W3C datetime `lastmod` (e.g., `2024-05-01` or
+/// `2024-05-01T12:00:00+00:00`) to its date.
+pub(super) fn parse_lastmod(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Parse an HTTP date (e.g., `Wed, 21 Oct 2015 07:28:00 GMT`) to its date.
+pub(super) fn parse_http_date(s: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc2822(s)
+        .ok()
+        .map(|date| date.date_naive())
+}
+
+/// Pages whose sitemap `lastmod` is further than the tolerance from when they
+/// were last modified, sorted by URL.
+pub(super) fn find_mismatches(
+    sitemap_pages: &BTreeMap<Url, Option<NaiveDate>>,
+    last_modified: &HashMap<Url, NaiveDate>,
+) -> Vec<LastmodMismatch> {
+    sitemap_pages
+        .iter()
+        .filter_map(|(url, lastmod)| {
+            let lastmod = (*lastmod)?;
+            let last_modified = *last_modified.get(url)?;
+            ((lastmod - last_modified).num_days().abs() > TOLERANCE_DAYS).then(|| LastmodMismatch {
+                url: url.clone(),
+                lastmod,
+                last_modified,
+            })
+        })
+        .collect()
+}
+
+/// Whether every `lastmod` in the sitemap is the given day, which suggests the
+/// sitemap generator sets it to the build time rather than when each page
+/// changed.
+pub(super) fn is_always(sitemap_pages: &BTreeMap<Url, Option<NaiveDate>>, day: NaiveDate) -> bool {
+    let lastmods: Vec<NaiveDate> = sitemap_pages.values().flatten().copied().collect();
+    lastmods.len() > 1 && lastmods.iter().all(|lastmod| *lastmod == day)
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use super::super::paths::normalize_path;
+
+/// Find the file in a static site directory that would be served for a URL
+/// under the base URL, the way static hosts resolve paths: a directory serves
+/// its `index.html`, and an extensionless path may be an `.html` file.
+///
+/// ## Returns
+/// Returns the path of the file, or the path that was expected if no file
+/// exists or the URL's decoded path leaves `root` (e.g., `..%2F..%2Fetc`).
+pub(super) fn resolve(root: &Path, base: &Url, url: &Url) -> Result<PathBuf, PathBuf> {
+    // Only a whole base path is stripped, so `/docs-old` isn't read as
+    // `-old` under a base of `/docs`
+    let relative = url
+        .path()
+        .strip_prefix(base.path().trim_end_matches('/'))
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .unwrap_or(url.path());
+    let relative = percent_encoding::percent_decode_str(relative).decode_utf8_lossy();
+    let path = normalize_path(&root.join(relative.trim_start_matches('/')));
+    if !path.starts_with(normalize_path(root)) {
+        return Err(path);
+    }
+
+    if path.is_file() {
+        return Ok(path);
+    }
+    let index = path.join("index.html");
+    if index.is_file() {
+        return Ok(index);
+    }
+    if path.extension().is_none() {
+        let html = path.with_extension("html");
+        if html.is_file() {
+            return Ok(html);
+        }
+    }
+    Err(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn resolves_like_a_static_host() {
+        let root = std::env::temp_dir().join(format!("docs-tools-local-{}", std::process::id()));
+        for file in [
+            "index.html",
+            "guide.html",
+            "about/index.html",
+            "app.js",
+            "café.html",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let base = Url::parse("https://example.com/docs/").unwrap();
+        let resolve = |path: &str| {
+            let url = Url::parse(&format!("https://example.com{path}")).unwrap();
+            resolve(&root, &base, &url)
+        };
+
+        assert_eq!(resolve("/docs/"), Ok(root.join("index.html")));
+        assert_eq!(resolve("/docs"), Ok(root.join("index.html")));
+        assert_eq!(resolve("/docs/about/"), Ok(root.join("about/index.html")));
+        assert_eq!(resolve("/docs/about"), Ok(root.join("about/index.html")));
+        assert_eq!(resolve("/docs/guide"), Ok(root.join("guide.html")));
+        assert_eq!(resolve("/docs/guide.html"), Ok(root.join("guide.html")));
+        assert_eq!(resolve("/docs/app.js"), Ok(root.join("app.js")));
+        assert_eq!(resolve("/docs/caf%C3%A9"), Ok(root.join("café.html")));
+        assert_eq!(resolve("/docs/missing"), Err(root.join("missing")));
+        // `.html` is only tried for extensionless paths
+        assert_eq!(resolve("/docs/app.css"), Err(root.join("app.css")));
+        // Not under the base path, despite sharing its start
+        assert_eq!(resolve("/docs-old/guide"), Err(root.join("docs-old/guide")));
+        // Encoded separators can't climb out of the site directory
+        let outside = resolve("/docs/..%2F..%2Fetc%2Fpasswd").unwrap_err();
+        assert!(!outside.starts_with(&root), "{}", outside.display());
+        assert_eq!(
+            resolve("/docs/about%2F..%2Fguide.html"),
+            Ok(root.join("guide.html"))
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use url::Url;
+
+use super::utils::{normalize_url, StartsWith};
+
+/// Locale sections of a site (e.g., `/en/` and `/fr/`), crawled together so
+/// that external links shared between locales are only checked once, but
+/// reported separately.
+#[derive(Debug, Default)]
+pub(super) struct Locales {
+    /// Each locale's name and root URL
+    roots: Vec<(String, Url)>,
+    /// Locales of the pages linking to each URL
+    linked_from: Mutex<HashMap<Url, BTreeSet<String>>>,
+}
+
+impl Locales {
+    /// Resolve locale prefixes (e.g., `fr` or `/fr/`) against the base URL.
+    pub(super) fn new(base_url: &Url, prefixes: &[String]) -> Result<Self> {
+        let roots = prefixes
+            .iter()
+            .map(|prefix| {
+                let name = prefix.trim_matches('/');
+                let root = base_url
+                    .join(&format!("{name}/"))
+                    .with_context(|| format!("Invalid locale prefix {prefix}"))?;
+                Ok((name.to_string(), root))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            roots,
+            linked_from: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Root URL of every locale, to start crawling from.
+    pub(super) fn roots(&self) -> impl Iterator<Item = &Url> {
+        self.roots.iter().map(|(_, root)| root)
+    }
+
+    pub(super) fn names(&self) -> impl Iterator<Item = &str> {
+        self.roots.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The locale a URL is under, if any.
+    fn locale_of(&self, url: &Url) -> Option<&str> {
+        self.roots
+            .iter()
+            .find(|(_, root)| url.starts_with(root))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Record that `referrer` links to `url`, so `url` is reported under the
+    /// referrer's locale even if it was checked when linked from elsewhere.
+    pub(super) fn record_link(&self, url: &Url, referrer: Option<&Url>) {
+        let Some(locale) = referrer.and_then(|referrer| self.locale_of(referrer)) else {
+            return;
+        };
+        self.linked_from
+            .lock()
+            .unwrap()
+            .entry(normalize_url(url))
+            .or_default()
+            .insert(locale.to_string());
+    }
+
+    /// Whether a URL belongs in a locale's report, either because it's under
+    /// the locale or because a page in the locale links to it.
+    pub(super) fn includes(&self, locale: &str, url: &Url) -> bool {
+        self.locale_of(url) == Some(locale)
+            || self
+                .linked_from
+                .lock()
+                .unwrap()
+                .get(&normalize_url(url))
+                .is_some_and(|locales| locales.contains(locale))
+    }
+}
@@ -0,0 +1,76 @@
+use regex::Regex;
+use reqwest::{header::CONTENT_TYPE, Method, RequestBuilder};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use url::Url;
+
+/// Request method for links matching a pattern, as configured under
+/// `[[method]]`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct MethodConfig {
+    /// Regex of URLs checked with this method
+    url: String,
+    /// HTTP method to check them with (e.g., `POST` or `OPTIONS`)
+    method: String,
+    /// Request body to send
+    body: Option<String>,
+    /// Content-Type of the body (e.g., `application/json`)
+    content_type: Option<String>,
+}
+
+/// A method other than GET to check links matching a pattern with, for API
+/// endpoints that reject GET
+#[derive(Debug)]
+pub(super) struct MethodOverride {
+    pattern: Regex,
+    method: Method,
+    body: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Query sent to GraphQL endpoints, which any schema can answer
+const GRAPHQL_QUERY: &str = r#"{"query":"{ __typename }"}"#;
+
+impl MethodOverride {
+    pub(super) fn new(config: &MethodConfig) -> Result<Self, String> {
+        Ok(Self {
+            pattern: Regex::new(&config.url).map_err(|e| e.to_string())?,
+            method: Method::from_bytes(config.method.to_ascii_uppercase().as_bytes())
+                .map_err(|_| format!("invalid HTTP method `{}`", config.method))?,
+            body: config.body.clone(),
+            content_type: config.content_type.clone(),
+        })
+    }
+
+    /// Check a GraphQL endpoint with a trivial query, as a GET typically
+    /// fails.
+    pub(super) fn graphql(pattern: Regex) -> Self {
+        Self {
+            pattern,
+            method: Method::POST,
+            body: Some(GRAPHQL_QUERY.to_string()),
+            content_type: Some("application/json".to_string()),
+        }
+    }
+
+    /// Whether the override applies to a URL. Fragments aren't sent, so are
+    /// ignored.
+    pub(super) fn matches(&self, url: &Url) -> bool {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        self.pattern.is_match(url.as_str())
+    }
+
+    /// Build the request for a URL with `client`.
+    pub(super) fn request(&self, client: &reqwest::Client, url: &Url) -> RequestBuilder {
+        let mut request = client.request(self.method.clone(), url.as_str());
+        if let Some(content_type) = &self.content_type {
+            request = request.header(CONTENT_TYPE, content_type);
+        }
+        match &self.body {
+            Some(body) => request.body(body.clone()),
+            None => request,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use tracing::warn;
+use url::Url;
+
+use super::{
+    super::paths::{is_plain_segment, normalize_path, percent_decode},
+    extract::extract_title,
+    utils::normalize_url,
+};
+
+/// A crawled page saved to the mirror directory
+#[derive(Debug, Clone)]
+pub(crate) struct MirroredPage {
+    /// Path of the saved file, relative to the mirror directory
+    pub(crate) path: PathBuf,
+    /// Contents of the page's `<title>`, if any
+    pub(crate) title: Option<String>,
+}
+
+/// Saves crawled HTML pages to a local directory, laid out as
+/// `<host>/<path>`, so the site can be browsed or packaged offline.
+#[derive(Debug)]
+pub(super) struct Mirror {
+    root: PathBuf,
+    pages: Mutex<BTreeMap<String, MirroredPage>>,
+}
+
+impl Mirror {
+    pub(super) fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            pages: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Save a page, unless its URL maps to a path outside the mirror
+    /// directory, which is logged and skipped.
+    pub(super) fn save(&self, url: &Url, body: &str) -> Result<()> {
+        let Some(relative_path) = mirror_path(url) else {
+            warn!("Not mirroring {url}, as its path would leave the mirror directory");
+            return Ok(());
+        };
+        let path = self.root.join(&relative_path);
+        if !normalize_path(&path).starts_with(normalize_path(&self.root)) {
+            warn!("Not mirroring {url}, as its path would leave the mirror directory");
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, rewrite_root_relative_links(body, &relative_path))?;
+
+        self.pages.lock().unwrap().insert(
+            normalize_url(url).to_string(),
+            MirroredPage {
+                path: relative_path,
+                title: extract_title(body),
+            },
+        );
+        Ok(())
+    }
+
+    /// Every saved page, keyed by its normalized URL.
+    pub(super) fn pages(&self) -> BTreeMap<String, MirroredPage> {
+        self.pages.lock().unwrap().clone()
+    }
+}
+
+/// Map a URL to a file path, using `index.html` for directory-style URLs and
+/// for extensionless paths.
+///
+/// ## Returns
+/// Returns `None` if a decoded segment isn't a plain file name, such as `..`
+/// or `%2Fetc`, so the path could leave the mirror directory.
+fn mirror_path(url: &Url) -> Option<PathBuf> {
+    let host = url.host_str().unwrap_or("localhost");
+    if !is_plain_segment(host) {
+        return None;
+    }
+    let mut path = PathBuf::from(host);
+    let segments: Vec<String> = url
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|s| !s.is_empty())
+                .map(percent_decode)
+                .collect()
+        })
+        .unwrap_or_default();
+    for segment in &segments {
+        if !is_plain_segment(segment) {
+            return None;
+        }
+        path.push(segment);
+    }
+    let has_extension = segments.last().is_some_and(|last| last.contains('.'));
+    if !has_extension {
+        path.push("index.html");
+    }
+    Some(path)
+}
+
+/// Point root-relative links (`/guides/...`) at the host directory, so they
+/// resolve within the mirror when opened from disk.
+fn rewrite_root_relative_links(body: &str, relative_path: &Path) -> String {
+    // One level for each directory between the host directory and the file
+    let depth = relative_path.components().count().saturating_sub(2);
+    let prefix = if depth == 0 {
+        "./".to_string()
+    } else {
+        "../".repeat(depth)
+    };
+    let mut rewritten = body.to_string();
+    for attr in ["href", "src"] {
+        for quote in ['"', '\''] {
+            rewritten = rewritten.replace(
+                &format!("{attr}={quote}/"),
+                &format!("{attr}={quote}{prefix}"),
+            );
+        }
+    }
+    // Undo the rewrite for protocol-relative URLs (`//cdn.example.com`)
+    for attr in ["href", "src"] {
+        for quote in ['"', '\''] {
+            rewritten = rewritten.replace(
+                &format!("{attr}={quote}{prefix}/"),
+                &format!("{attr}={quote}//"),
+            );
+        }
+    }
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_for(url: &str) -> Option<PathBuf> {
+        mirror_path(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn maps_urls_under_the_host_directory() {
+        assert_eq!(
+            path_for("https://example.com/"),
+            Some(PathBuf::from("example.com/index.html"))
+        );
+        assert_eq!(
+            path_for("https://example.com/guides/intro"),
+            Some(PathBuf::from("example.com/guides/intro/index.html"))
+        );
+        assert_eq!(
+            path_for("https://example.com/caf%C3%A9/app.js"),
+            Some(PathBuf::from("example.com/café/app.js"))
+        );
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_mirror_directory() {
+        for url in [
+            "https://example.com/%2Fetc%2Fcron.d%2Fx",
+            "https://example.com/..%2F..%2F.bashrc",
+            "https://example.com/docs/..%2f..%2fx",
+            "https://example.com/a%5C..%5C..%5Cx",
+            "https://example.com/C:%5Cx",
+            "https://example.com/a%00b",
+        ] {
+            assert_eq!(path_for(url), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn skips_pages_leaving_the_mirror_directory() {
+        let root = std::env::temp_dir().join(format!("docs-tools-mirror-{}", std::process::id()));
+        let mirror = Mirror::new(root.join("site"));
+        let url = Url::parse("https://example.com/..%2F..%2Fescaped.html").unwrap();
+        mirror.save(&url, "<title>Escaped</title>").unwrap();
+        let escaped = root.join("escaped.html").exists();
+        let nothing_saved = mirror.pages().is_empty();
+        let _ = fs::remove_dir_all(&root);
+
+        assert!(!escaped);
+        assert!(nothing_saved);
+    }
+}
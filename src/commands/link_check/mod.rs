@@ -1,53 +1,433 @@
 use std::{
-    collections::VecDeque,
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    future::Future,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
 use futures::{stream, StreamExt};
-use log::{debug, error, info};
 use lychee_lib::{extract::Extractor, FileType, InputContent};
-use url::{ParseError, Url};
+use regex::{Regex, RegexSet};
+use reqwest::{header::HeaderMap, Method};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use url::{ParseError, Position, Url};
 
+mod address_family;
+mod affiliate;
+mod anchors;
+mod args;
+mod assets;
+mod baseline;
+mod budget;
+mod builder;
+mod cache;
+mod category;
+mod checkpoint;
+mod classify;
+mod comment_links;
+mod commit_status;
+mod config;
+mod deadline;
+mod devices;
+mod domains;
+mod duplicates;
+mod events;
+mod expect;
+mod extract;
+mod forms;
+mod github_anchors;
+mod graph;
+mod https;
+mod indexing;
+mod inventory;
+mod issues;
+mod languages;
+mod lastmod;
+mod local;
+mod locales;
+mod methods;
+mod mirror;
+mod notify;
+mod notify_state;
+mod otlp;
+mod owners;
+mod pac;
+mod path_depth;
+mod pause;
 mod progress;
+mod proxy;
+mod rate_limit;
+mod redirects;
+mod report;
+mod resources;
+mod results;
+mod rewrite;
+mod robots;
+mod scope;
+mod sections;
+mod shorteners;
+mod signing;
+mod site_apis;
+mod sitemap;
+mod skips;
+mod slugs;
+mod stats;
+mod strategy;
+mod upload;
 mod utils;
 mod visited;
+mod wayback;
 
+pub(crate) use affiliate::AffiliatePolicy;
+use anchors::AnchorIndex;
+pub(crate) use args::LinkCheckArgs;
+use assets::AssetRef;
+pub(crate) use assets::{AssetCheck, AssetKind};
+use baseline::Baseline;
+use budget::DomainBudget;
+pub use builder::LinkCheckerBuilder;
+use cache::Cache;
+pub use category::Category;
+use category::DEFAULT_FAIL_ON;
+use checkpoint::{Checkpoint, Checkpointer};
+use classify::{Classifier, ResourceKind};
+use comment_links::CommentLinkCheck;
+use commit_status::{CommitStatusTarget, StatusState};
+pub(crate) use config::schema as config_schema;
+use config::{Config, HostOverride};
+pub use deadline::DeadlineReached;
+use devices::{DeviceCheck, DeviceProblem, DeviceResponse, MOBILE_USER_AGENT};
+use domains::DomainFilter;
+use duplicates::{group_by_text, ContentHashes};
+use events::{Event, EventStream};
+use expect::Expectation;
+pub(crate) use extract::{extract_anchors, PageMeta};
+use extract::{
+    extract_assets, extract_attribute_links, extract_comment_links, extract_form_actions,
+    extract_headings, extract_meta, extract_skipped_links, extract_xml_links, PageHeadings,
+    SKIP_ATTRIBUTE,
+};
+use forms::{FormCheck, FormTarget, DEFAULT_FORM_METHOD};
+use github_anchors::{GithubAnchors, GithubDoc};
+pub(crate) use graph::GraphSnapshot;
+use graph::{GraphFormat, LinkGraph};
+use inventory::{Inventory, InventoryLookup};
+use issues::{BrokenLink, IssueTracker};
+use languages::{LanguageCheck, LanguageProblem, LanguageResponse};
+use lastmod::LastmodMismatch;
+use locales::Locales;
+use methods::MethodOverride;
+use mirror::Mirror;
+pub(crate) use mirror::MirroredPage;
+pub(crate) use notify_state::NotifyState;
+use otlp::{CrawlCounters, OtlpExporter};
+use owners::Owners;
+use path_depth::{path_depth, PathDepthLimit};
+use pause::PauseControl;
 use progress::ProgressBar;
-use utils::{get_origin, is_html, StartsWith as _};
+use proxy::ProxySettings;
+use rate_limit::RateLimiter;
+use redirects::Redirect;
+pub(crate) use report::ReportArgs;
+use report::{
+    AssetEntry, BrokenLinkEntry, CommentLinkEntry, DeviceEntry, DeviceResponseEntry, DuplicateText,
+    FormEntry, LanguageEntry, LastmodEntry, LinkBudgetEntry, LocaleSection, OutputFormat,
+    OwnerSection, RedirectEntry, Report, ReportEntry, SkippedEntry, Summary,
+};
+use resources::ResourceLimits;
+pub use results::CheckRecord;
+use results::Results;
+use rewrite::RewriteRule;
+use robots::Robots;
+use scope::{Scope, ScopeRule};
+use shorteners::Shorteners;
+use signing::RequestSigner;
+use site_apis::{SiteCheck, SiteLookup};
+use sitemap::Sitemap;
+use skips::{SkipReason, Skips};
+use slugs::SlugRules;
+use stats::Stats;
+use strategy::{CrawlQueue, Strategy};
+use upload::UploadTarget;
+pub(crate) use utils::normalize_url;
+use utils::{get_origin, StartsWith};
 use visited::Visited;
 
+/// Crawls a site from a start URL, checking every link found along the way
 #[derive(Clone)]
-pub(crate) struct LinkChecker {
-    /// The base URL used to determine whether a link is internal (should be
-    /// recursively checked) or external
+pub struct LinkChecker {
+    /// The base URL the crawl must start within
     base_url: Url,
+    /// The URL the crawl starts from
+    start_url: Url,
+    /// Rules used to determine whether a link is internal (should be
+    /// recursively checked) or external
+    scope: Scope,
     /// Client for the link checker library
     lychee_client: Arc<lychee_lib::Client>,
     /// Client for raw HTTP requests
     reqwest_client: reqwest::Client,
+    /// Client for checking links, which doesn't follow redirects itself so
+    /// that they can be tracked
+    check_client: reqwest::Client,
+    /// Whether requests go through a proxy or trust extra certificates, in
+    /// which case links are checked with `check_client` rather than the
+    /// lychee client
+    proxied: bool,
+    /// Proxy for requests, with any PAC file choosing it
+    proxy_settings: Arc<ProxySettings>,
     /// Extractor to extract HTML links from HTML documents
     extractor: Extractor,
+    /// Classifier deciding which resources are pages to be parsed for links
+    classifier: Arc<Classifier>,
     /// Links that have already been visited
     visited: Arc<Visited>,
+    /// Hashes of internal page bodies, used to detect duplicate-content aliases
+    content_hashes: Arc<ContentHashes>,
+    /// Anchors found on each internal HTML page
+    anchors: Arc<AnchorIndex>,
+    /// How heading anchors are generated on each locale's pages, if
+    /// configured
+    slug_rules: Arc<Vec<SlugRules>>,
+    /// Rewrites of the links found on pages, applied before they're queued
+    rewrite_rules: Arc<Vec<RewriteRule>>,
+    /// Number of path segments links may have before they're skipped as a
+    /// crawler trap, unless a per-pattern limit applies
+    default_max_path_depth: usize,
+    /// Path depth limits for links matching a pattern
+    path_depth_limits: Arc<Vec<PathDepthLimit>>,
+    /// Number of sitemaps loaded by following sitemap indexes
+    max_sitemaps: usize,
+    /// URLs found but not checked, with why
+    skips: Arc<Skips>,
+    /// Title and headings of each internal HTML page, keyed by normalized URL
+    headings: Arc<Mutex<BTreeMap<String, PageHeadings>>>,
+    /// Whether to keep the canonical links and meta tags of each page
+    collect_meta: bool,
+    /// Canonical links and meta tags of each internal HTML page, keyed by
+    /// normalized URL
+    page_meta: Arc<Mutex<BTreeMap<String, PageMeta>>>,
+    /// Links between crawled pages
+    graph: Arc<LinkGraph>,
+    /// File to save the internal link graph to once the crawl finishes
+    save_graph: Option<PathBuf>,
+    /// File to export the internal link graph to once the crawl finishes,
+    /// with its format
+    export_graph: Option<(PathBuf, GraphFormat)>,
+    /// URLs of Sphinx inventories to validate external links against
+    inventory_urls: Vec<Url>,
+    /// Inventories loaded at the start of the crawl
+    inventories: Arc<OnceLock<Vec<Inventory>>>,
+    /// URLs matching any of these patterns are skipped entirely
+    excludes: RegexSet,
+    /// URLs matching any of these patterns are checked even if excluded
+    includes: RegexSet,
+    /// External hosts whose links are checked, the others being excluded
+    domain_filter: Arc<DomainFilter>,
+    /// Maximum number of links checked at once
+    concurrency: usize,
+    /// Ceilings on the connections and DNS lookups open at once, shared by
+    /// every check
+    resource_limits: Arc<ResourceLimits>,
+    /// Number of distinct internal links a page may have
+    max_internal_links: Option<usize>,
+    /// Number of distinct external links a page may have
+    max_external_links: Option<usize>,
+    /// Pages over either link budget, with their internal and external link
+    /// counts
+    over_link_budget: Arc<Mutex<BTreeMap<Url, (usize, usize)>>>,
+    /// Whether to check external links with HEAD before falling back to GET
+    head_first: bool,
+    /// Whether to check internal resources other than pages with HEAD
+    no_body: bool,
+    /// Number of slowest checks listed in the summary
+    slowest: usize,
+    /// Bytes of response bodies read so far
+    bytes_downloaded: Arc<AtomicU64>,
+    max_redirects: usize,
+    fail_on_redirect: bool,
+    fail_on_shortener: bool,
+    /// URL shorteners, whose links are expanded to check their destination
+    shorteners: Arc<Shorteners>,
+    /// Affiliate parameters external links must not carry, with
+    /// `--check-affiliate-params`
+    affiliate_policy: Option<Arc<AffiliatePolicy>>,
+    /// Categories of problems that fail the check
+    fail_on: Vec<Category>,
+    /// File of known failures that don't fail the check
+    baseline_path: Option<PathBuf>,
+    /// Known failures loaded from the baseline file
+    baseline: Arc<Baseline>,
+    /// Whether to replace the baseline with this run's failures
+    update_baseline: bool,
+    /// Limits the rate of requests to each host
+    rate_limiter: Arc<RateLimiter>,
+    /// Time spent on external links to each host, against its budget
+    domain_budget: Arc<DomainBudget>,
+    /// `robots.txt` rules for each origin, if they're respected
+    robots: Option<Arc<Robots>>,
+    /// Headers and cookies from `--header` and `--cookie`, sent only to the
+    /// base URL's origin
+    headers: Arc<HeaderMap>,
+    /// Request settings for specific hosts, keyed by hostname
+    host_overrides: Arc<HashMap<String, HostOverride>>,
+    /// Custom success criteria for links matching a pattern
+    expectations: Arc<Vec<Expectation>>,
+    /// Methods other than GET to check links matching a pattern with
+    method_overrides: Arc<Vec<MethodOverride>>,
+    /// Signing for requests to links matching a pattern
+    request_signers: Arc<Vec<RequestSigner>>,
+    /// Directory of a built static site to read pages under the base URL
+    /// from, instead of fetching them over HTTP
+    local_root: Option<PathBuf>,
+    /// Recent successful external link checks, if caching is enabled
+    cache: Option<Arc<Cache>>,
+    /// Local copy of crawled HTML pages, if mirroring is enabled
+    mirror: Option<Arc<Mirror>>,
+    /// Checkpoint file to resume the crawl from
+    resume: Option<PathBuf>,
+    /// Saves crawl progress periodically, if checkpointing is enabled
+    checkpointer: Option<Arc<Checkpointer>>,
     /// Number of successfully checked links
     successful_checks: Arc<AtomicUsize>,
     /// Number of link check failures
     failed_checks: Arc<AtomicUsize>,
+    /// Outcome of every check, for the report
+    results: Arc<Results>,
+    /// Internal links with fragments, validated once every page is parsed
+    fragment_links: Arc<Mutex<Vec<UrlWithReferrer>>>,
+    /// Number of internal links whose fragment matches no anchor
+    broken_anchors: Arc<AtomicUsize>,
+    /// Whether to fail on plain HTTP links on HTTPS pages
+    https_only: bool,
+    /// Whether to fail on assets HTTPS pages load over plain HTTP
+    check_mixed_content: bool,
+    /// Whether to suggest the HTTPS equivalents of plain HTTP links
+    suggest_https: bool,
+    /// Assets loaded over plain HTTP by HTTPS pages, with their kind and the
+    /// first page loading them, reported once the crawl finishes
+    mixed_content: Arc<Mutex<BTreeMap<Url, (AssetKind, Url)>>>,
+    /// Whether to check the images, scripts and other assets pages load
+    /// separately from their links
+    check_assets: bool,
+    /// Assets loaded by crawled pages, checked once the crawl finishes. An
+    /// asset used as more than one kind is checked as each.
+    assets: Arc<Mutex<BTreeMap<(Url, AssetKind), AssetRef>>>,
+    /// Outcome of checking each asset, sorted by URL
+    asset_checks: Arc<OnceLock<Vec<AssetCheck>>>,
+    /// Whether to check links in HTML comments separately from the links
+    /// readers can follow
+    check_comment_links: bool,
+    /// Links in HTML comments, with the pages they're on, checked once the
+    /// crawl finishes
+    comment_links: Arc<Mutex<BTreeMap<Url, BTreeSet<Url>>>>,
+    /// Outcome of checking each link in HTML comments, sorted by URL
+    comment_link_checks: Arc<OnceLock<Vec<CommentLinkCheck>>>,
+    /// Whether to check the endpoints forms on internal pages submit to
+    check_forms: bool,
+    /// Method to check endpoints of forms not submitting with GET with
+    form_method: Method,
+    /// Where forms submit to, with the pages they're on, checked once the
+    /// crawl finishes
+    forms: Arc<Mutex<BTreeMap<FormTarget, BTreeSet<Url>>>>,
+    /// Outcome of checking each form endpoint, sorted by URL
+    form_checks: Arc<OnceLock<Vec<FormCheck>>>,
+    /// Whether to validate the fragments of links into Markdown on
+    /// github.com
+    check_github_anchors: bool,
+    /// Links into Markdown on github.com with fragments, validated once the
+    /// crawl finishes
+    github_fragment_links: Arc<Mutex<Vec<UrlWithReferrer>>>,
+    /// Whether to check Stack Exchange and MDN links through their APIs
+    site_apis: bool,
+    /// Sitemap to seed the crawl from, in addition to the start URL
+    sitemap_url: Option<Url>,
+    /// Locale sections crawled together and reported separately
+    locales: Arc<Locales>,
+    /// Languages to re-request key pages with as `Accept-Language`
+    accept_languages: Vec<String>,
+    /// Key pages' responses to each language, once checked
+    language_checks: Arc<OnceLock<Vec<LanguageCheck>>>,
+    /// Number of pages to compare with desktop and mobile `User-Agent`s
+    compare_mobile: Option<usize>,
+    /// Sampled pages' responses on desktop and mobile, once checked
+    device_checks: Arc<OnceLock<Vec<DeviceCheck>>>,
+    /// Owners of sections of the site, to split the report by
+    owners: Arc<Owners>,
+    /// Webhook to notify each owner of their broken links on
+    owner_webhooks: Arc<HashMap<String, Url>>,
+    /// File recording which broken links owners were notified of or muted
+    notify_state: Option<PathBuf>,
+    /// Sitemap URLs that the crawl from the start URL never linked to
+    orphaned_pages: Arc<OnceLock<Vec<Url>>>,
+    /// Every page URL listed in the sitemap, normalized, with its `lastmod`
+    sitemap_pages: Arc<OnceLock<BTreeMap<Url, Option<NaiveDate>>>>,
+    /// When each internal page was last modified, keyed by normalized URL
+    last_modified: Arc<Mutex<HashMap<Url, NaiveDate>>>,
+    /// Internal pages whose `X-Robots-Tag` header blocks indexing
+    noindex_pages: Arc<Mutex<BTreeSet<Url>>>,
+    /// Whether to look up when broken external links were last archived
+    /// working by the Wayback Machine
+    wayback: bool,
     /// Whether to only check links that are internal
     internal_only: bool,
+    /// Whether to skip links with `rel="nofollow"`
+    skip_nofollow: bool,
+    /// Attributes to extract links from besides those lychee reads, in
+    /// lowercase
+    link_attributes: Arc<Vec<String>>,
+    /// Number of links from the start URL beyond which pages aren't parsed
+    max_depth: Option<usize>,
+    /// Number of pages parsed for links before the crawl stops following
+    /// links
+    max_pages: Option<usize>,
+    /// Number of pages parsed for links so far
+    pages_parsed: Arc<AtomicUsize>,
+    /// How long the run may take, from the start of the crawl
+    max_duration: Option<Duration>,
+    /// Request timeout, within which of the deadline the crawl stops
+    /// checking new URLs so that checks in flight finish in time
+    request_timeout: Duration,
+    /// When the run must end, set once the crawl starts
+    deadline: Arc<OnceLock<Instant>>,
+    /// Number of queued URLs left unchecked at the deadline, if reached
+    unchecked_at_deadline: Arc<OnceLock<usize>>,
+    /// Phases after the crawl that the deadline stopped or kept from
+    /// starting
+    cut_short: Arc<Mutex<Vec<&'static str>>>,
+    /// Whether to sort extracted links and preserve queue insertion order so
+    /// that repeated runs over identical content behave identically
+    deterministic: bool,
+    /// Order in which queued URLs are checked
+    strategy: Strategy,
     /// Progress bar for CLI display
     progress_bar: Arc<Mutex<Option<ProgressBar>>>,
+    /// Pauses and resumes sending requests while the crawl runs
+    pause: Arc<PauseControl>,
+    /// Called with the outcome of every check as it's made
+    on_result: Option<ResultCallback>,
+    /// Exports metrics and a span for each check to an OpenTelemetry
+    /// collector, with `--otlp-endpoint`
+    otlp: Option<Arc<OtlpExporter>>,
+    /// Where events are streamed as they happen with `--format ndjson`, set
+    /// once the check starts
+    events: Arc<OnceLock<EventStream>>,
 }
 
 /// A URL to check along with information about where it came from
+#[derive(Clone)]
 struct UrlWithReferrer {
     url: Url,
     referrer: Option<Url>,
+    /// Number of links followed from the start URL (or the sitemap) to reach
+    /// this URL
+    depth: usize,
 }
 
 enum CheckResult {
@@ -57,6 +437,8 @@ enum CheckResult {
 
 type NextTargets = Vec<UrlWithReferrer>;
 
+type ResultCallback = Arc<dyn Fn(&CheckRecord) + Send + Sync>;
+
 struct MaxConcurrency(usize);
 
 impl std::ops::Deref for MaxConcurrency {
@@ -75,329 +457,3748 @@ impl std::ops::DerefMut for MaxConcurrency {
 
 const HUMAN_USER_AGENT: &str =  "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/134.0.0 Safari/537.36";
 const DEFAULT_USER_AGENT: &str = "docs-tools";
+const DEFAULT_CONCURRENCY: usize = 10;
+const DEFAULT_SLOWEST: usize = 5;
+const DEFAULT_CACHE_TTL_HOURS: u64 = 24;
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+const DEFAULT_MAX_PATH_DEPTH: usize = 20;
+const DEFAULT_MAX_SITEMAPS: usize = 1000;
+const DEFAULT_MAX_DNS_LOOKUPS: usize = 64;
+/// Base URL used for a local site directory when none is given
+const LOCAL_BASE_URL: &str = "http://localhost/";
 
 impl LinkChecker {
-    pub(crate) fn new(
-        base_url: impl AsRef<str>,
-        internal_only: bool,
-        human_agent: bool,
-    ) -> Result<Self> {
-        debug!("Creating LinkChecker with base: {}", base_url.as_ref());
-        let base_url = Url::parse(base_url.as_ref())?;
-
-        let user_agent = if human_agent {
+    pub(crate) fn new(args: &LinkCheckArgs) -> Result<Self> {
+        let mut config = Config::load(args.config.as_deref(), args.profile.as_deref())
+            .inspect_err(|e| error!("{e:#}"))?;
+        config.add_headers(&args.headers, &args.cookies);
+        config.set_timeout(args.timeout);
+        let mut args = args.clone();
+        config
+            .merge_into(&mut args)
+            .inspect_err(|e| error!("{e:#}"))?;
+        Self::with_config(args, &config)
+    }
+
+    /// Create a checker from arguments that already have the config merged
+    /// into them.
+    fn with_config(mut args: LinkCheckArgs, config: &Config) -> Result<Self> {
+        // Links in a local site only need a base URL to resolve against if
+        // they're all relative
+        if args.path.is_some() && args.base_url.is_none() {
+            args.base_url = Some(LOCAL_BASE_URL.to_string());
+        }
+        let Some(base) = &args.base_url else {
+            error!("A base URL is required, either with --base or in the config file");
+            anyhow::bail!("A base URL is required, either with --base or in the config file");
+        };
+        debug!("Creating LinkChecker with base: {}", base);
+        let base_url = Url::parse(base)?;
+        let start_url = match &args.start_url {
+            Some(start) => Url::parse(start)?,
+            None => base_url.clone(),
+        };
+        let sitemap_url = match &args.sitemap {
+            Some(Some(sitemap)) => Some(Url::parse(sitemap)?),
+            Some(None) => Some(base_url.join("sitemap.xml")?),
+            None => None,
+        };
+        let locales = Locales::new(&base_url, &args.locales)?;
+        let slug_rules = config.slug_rules(&base_url)?;
+        let owners = match &args.owners {
+            Some(path) => Owners::load(path, &base_url).inspect_err(|e| error!("{e:#}"))?,
+            None => Owners::default(),
+        };
+
+        let mut scope = Scope::new(base_url.clone());
+        for prefix in &args.scopes {
+            scope.add_rule(ScopeRule::new(prefix.clone(), true));
+        }
+        for prefix in &args.scopes_no_recurse {
+            scope.add_rule(ScopeRule::new(prefix.clone(), false));
+        }
+        for matcher in &args.no_recurse {
+            scope.add_no_recurse(matcher.clone());
+        }
+
+        let user_agent = if args.human_agent.unwrap_or_default() {
             HUMAN_USER_AGENT
         } else {
             DEFAULT_USER_AGENT
         };
+        let cache = match &args.cache {
+            Some(dir) => {
+                let ttl = Duration::from_secs(
+                    args.cache_ttl.unwrap_or(DEFAULT_CACHE_TTL_HOURS) * 60 * 60,
+                );
+                Some(Arc::new(Cache::load(dir.clone(), ttl)?))
+            }
+            None => None,
+        };
+
+        let mut fail_on = args
+            .fail_on
+            .clone()
+            .unwrap_or_else(|| DEFAULT_FAIL_ON.to_vec());
+        if args.fail_on_redirect && !fail_on.contains(&Category::Redirect) {
+            fail_on.push(Category::Redirect);
+        }
+        if args.fail_on_shortener && !fail_on.contains(&Category::Shortened) {
+            fail_on.push(Category::Shortened);
+        }
+        if args.check_affiliate_params && !fail_on.contains(&Category::Affiliate) {
+            fail_on.push(Category::Affiliate);
+        }
+        if (args.https_only || args.check_mixed_content) && !fail_on.contains(&Category::PlainHttp)
+        {
+            fail_on.push(Category::PlainHttp);
+        }
+
+        let export_graph = match &args.export_graph {
+            Some(path) => Some((
+                path.clone(),
+                GraphFormat::of_path(path).inspect_err(|e| error!("{e:#}"))?,
+            )),
+            None => None,
+        };
+
+        let baseline = match &args.baseline {
+            Some(path) if !args.update_baseline => {
+                Baseline::load(path).inspect_err(|e| error!("{e:#}"))?
+            }
+            Some(_) => Baseline::default(),
+            None if args.update_baseline => {
+                error!("--update-baseline requires a baseline file, either with --baseline or in the config file");
+                anyhow::bail!("--update-baseline requires a baseline file");
+            }
+            None => Baseline::default(),
+        };
+
+        let mut expectations = config.expectations()?;
+        expectations.extend(args.graphql.iter().cloned().map(Expectation::graphql));
+        let mut method_overrides = config.method_overrides()?;
+        method_overrides.extend(args.graphql.iter().cloned().map(MethodOverride::graphql));
+
+        let headers = config.headers()?;
+        let max_redirects = args.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS);
+        // User headers and cookies are only sent to the base URL's origin and
+        // hosts with overrides, which are checked directly, so no client
+        // sends them by default
+        // The lychee client's connections can't be limited, so it closes
+        // each one once its request is answered rather than keep it idle
         let lychee_client = lychee_lib::ClientBuilder::builder()
             .user_agent(user_agent)
+            .custom_headers(http::HeaderMap::from_iter([(
+                http::header::CONNECTION,
+                http::HeaderValue::from_static("close"),
+            )]))
+            .timeout(config.timeout())
+            .max_redirects(max_redirects)
             .build()
             .client()?;
-        let reqwest_client = reqwest::Client::builder()
+        let resource_limits = Arc::new(ResourceLimits::new(
+            args.max_connections,
+            Some(args.max_dns_lookups.unwrap_or(DEFAULT_MAX_DNS_LOOKUPS)),
+            &base_url,
+        ));
+        let proxy_settings = ProxySettings::new(
+            args.proxy.as_ref(),
+            args.pac_file.as_deref(),
+            &args.no_proxy,
+            args.ca_cert.as_deref(),
+            &resource_limits,
+        )
+        .inspect_err(|e| error!("{e:#}"))?;
+        // Services and robots.txt files are requested without
+        // `ResourceLimits::limit_idle`, from many hosts, so this client
+        // keeps no connections alive
+        let reqwest_client = resource_limits
+            .apply(proxy_settings.apply(reqwest::Client::builder()))
+            .pool_max_idle_per_host(0)
+            .user_agent(user_agent)
+            .timeout(config.timeout())
+            .build()?;
+        let check_client = resource_limits
+            .apply(proxy_settings.apply(reqwest::Client::builder()))
             .user_agent(user_agent)
-            .pool_idle_timeout(Some(Duration::from_secs(30)))
-            .timeout(Duration::from_secs(30))
+            .timeout(config.timeout())
+            .redirect(reqwest::redirect::Policy::none())
             .build()?;
 
         let extractor = Extractor::default();
-        let visited = Arc::new(Visited::default());
+        let classifier = Arc::new(Classifier::with_overrides(&args.classify));
+        // Checkpoints list every visited URL, so only then are they kept
+        let visited = Arc::new(Visited::new(args.visited_bloom, args.checkpoint.is_some()));
+        let content_hashes = Arc::new(ContentHashes::default());
+        let anchors = Arc::new(AnchorIndex::default());
         let successful_checks = Arc::new(AtomicUsize::new(0));
         let failed_checks = Arc::new(AtomicUsize::new(0));
+        let results = Arc::new(Results::default());
+        let fragment_links = Arc::new(Mutex::new(Vec::new()));
+        let broken_anchors = Arc::new(AtomicUsize::new(0));
         let progress_bar = Arc::new(Mutex::new(None));
+        let pages_parsed = Arc::new(AtomicUsize::new(0));
+        let otlp = match &args.otlp_endpoint {
+            Some(endpoint) => Some(Arc::new(OtlpExporter::new(
+                reqwest_client.clone(),
+                endpoint,
+                &base_url,
+                CrawlCounters {
+                    successful_checks: successful_checks.clone(),
+                    failed_checks: failed_checks.clone(),
+                    pages_parsed: pages_parsed.clone(),
+                },
+            )?)),
+            None => None,
+        };
 
         Ok(Self {
             base_url,
+            start_url,
+            scope,
             lychee_client: Arc::new(lychee_client),
             reqwest_client,
+            check_client,
+            proxied: proxy_settings.is_set(),
+            proxy_settings: Arc::new(proxy_settings),
             extractor,
+            classifier,
             visited,
+            content_hashes,
+            anchors,
+            slug_rules: Arc::new(slug_rules),
+            rewrite_rules: Arc::new(config.rewrite_rules()?),
+            default_max_path_depth: args.max_path_depth.unwrap_or(DEFAULT_MAX_PATH_DEPTH),
+            path_depth_limits: Arc::new(config.path_depth_limits()?),
+            max_sitemaps: args.max_sitemaps.unwrap_or(DEFAULT_MAX_SITEMAPS),
+            skips: Arc::new(Skips::default()),
+            headings: Arc::new(Mutex::new(BTreeMap::new())),
+            collect_meta: args.collect_meta,
+            page_meta: Arc::new(Mutex::new(BTreeMap::new())),
+            graph: Arc::new(LinkGraph::default()),
+            save_graph: args.save_graph.clone(),
+            export_graph,
+            inventory_urls: args.inventories.clone(),
+            inventories: Arc::new(OnceLock::new()),
+            excludes: RegexSet::new(args.excludes.iter().map(Regex::as_str))?,
+            includes: RegexSet::new(args.includes.iter().map(Regex::as_str))?,
+            domain_filter: Arc::new(DomainFilter::new(
+                args.skip_domains.clone(),
+                args.only_domains.clone(),
+            )),
+            concurrency: args.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1),
+            resource_limits,
+            max_internal_links: args.max_internal_links,
+            max_external_links: args.max_external_links,
+            over_link_budget: Arc::new(Mutex::new(BTreeMap::new())),
+            head_first: args.head_first,
+            no_body: args.no_body,
+            slowest: args.slowest.unwrap_or(DEFAULT_SLOWEST),
+            bytes_downloaded: Arc::new(AtomicU64::new(0)),
+            max_redirects,
+            fail_on_redirect: args.fail_on_redirect,
+            fail_on_shortener: args.fail_on_shortener,
+            shorteners: Arc::new(Shorteners::new(&args.shorteners)),
+            affiliate_policy: args
+                .check_affiliate_params
+                .then(|| Arc::new(AffiliatePolicy::new(&args.affiliate_params))),
+            fail_on,
+            baseline_path: args.baseline.clone(),
+            baseline: Arc::new(baseline),
+            update_baseline: args.update_baseline,
+            rate_limiter: Arc::new(RateLimiter::new(args.max_requests_per_second_per_host)),
+            domain_budget: Arc::new(DomainBudget::new(
+                args.domain_budget.map(Duration::from_secs),
+                config.domain_budgets(),
+            )),
+            robots: args
+                .respect_robots
+                .then(|| Arc::new(Robots::new(user_agent))),
+            headers: Arc::new(headers),
+            host_overrides: Arc::new(config.host_overrides()?),
+            expectations: Arc::new(expectations),
+            method_overrides: Arc::new(method_overrides),
+            request_signers: Arc::new(config.request_signers()?),
+            local_root: args.path.clone(),
+            cache,
+            mirror: args.mirror.clone().map(|dir| Arc::new(Mirror::new(dir))),
+            resume: args.resume.clone(),
+            checkpointer: args
+                .checkpoint
+                .clone()
+                .or_else(|| args.resume.clone())
+                .map(|path| Arc::new(Checkpointer::new(path))),
             successful_checks,
             failed_checks,
-            internal_only,
+            results,
+            fragment_links,
+            broken_anchors,
+            https_only: args.https_only,
+            check_mixed_content: args.https_only || args.check_mixed_content,
+            suggest_https: args.suggest_https,
+            mixed_content: Arc::new(Mutex::new(BTreeMap::new())),
+            check_assets: args.check_assets,
+            assets: Arc::new(Mutex::new(BTreeMap::new())),
+            asset_checks: Arc::new(OnceLock::new()),
+            check_comment_links: args.check_comment_links,
+            comment_links: Arc::new(Mutex::new(BTreeMap::new())),
+            comment_link_checks: Arc::new(OnceLock::new()),
+            check_forms: args.check_forms,
+            form_method: args.form_method.clone().unwrap_or(DEFAULT_FORM_METHOD),
+            forms: Arc::new(Mutex::new(BTreeMap::new())),
+            form_checks: Arc::new(OnceLock::new()),
+            check_github_anchors: args.check_github_anchors,
+            github_fragment_links: Arc::new(Mutex::new(Vec::new())),
+            site_apis: args.site_apis,
+            sitemap_url,
+            locales: Arc::new(locales),
+            accept_languages: args.accept_language.clone(),
+            language_checks: Arc::new(OnceLock::new()),
+            compare_mobile: args.compare_mobile,
+            device_checks: Arc::new(OnceLock::new()),
+            owners: Arc::new(owners),
+            owner_webhooks: Arc::new(config.owner_webhooks()),
+            notify_state: args.notify_state.clone(),
+            orphaned_pages: Arc::new(OnceLock::new()),
+            sitemap_pages: Arc::new(OnceLock::new()),
+            last_modified: Arc::new(Mutex::new(HashMap::new())),
+            noindex_pages: Arc::new(Mutex::new(BTreeSet::new())),
+            wayback: args.wayback,
+            internal_only: args.internal_only.unwrap_or_default(),
+            skip_nofollow: args.skip_nofollow,
+            link_attributes: Arc::new(
+                args.link_attributes
+                    .iter()
+                    .map(|name| name.to_ascii_lowercase())
+                    .collect(),
+            ),
+            max_depth: args.max_depth,
+            max_pages: args.max_pages,
+            pages_parsed,
+            max_duration: args.max_duration.map(|mins| Duration::from_secs(mins * 60)),
+            request_timeout: config.timeout(),
+            deadline: Arc::new(OnceLock::new()),
+            unchecked_at_deadline: Arc::new(OnceLock::new()),
+            cut_short: Arc::new(Mutex::new(Vec::new())),
+            deterministic: args.deterministic.unwrap_or_default(),
+            strategy: args.strategy.unwrap_or_default(),
             progress_bar,
+            pause: Arc::new(PauseControl::default()),
+            on_result: None,
+            otlp,
+            events: Arc::new(OnceLock::new()),
         })
     }
 
-    pub(crate) async fn check(&self, start_url: impl AsRef<str>) -> Result<()> {
-        let start_url = Url::parse(start_url.as_ref())?;
-        if !start_url.origin().eq(&self.base_url.origin()) {
-            error!("Start URL must be within the base URL domain");
-            anyhow::bail!("Start URL must be within the base URL domain");
+    pub(crate) async fn check(&self, report_args: &ReportArgs) -> Result<()> {
+        let archived = report_args.upload.is_some() || report_args.report_dir.is_some();
+        if archived && report_args.format == OutputFormat::Text {
+            error!("--upload and --report-dir need a report --format other than text");
+            anyhow::bail!("--upload and --report-dir need a report --format other than text");
         }
-
+        if report_args.keep_reports == Some(0) {
+            error!("--keep-reports must keep at least one report");
+            anyhow::bail!("--keep-reports must keep at least one report");
+        }
+        if report_args.format == OutputFormat::Ndjson {
+            let events = EventStream::open(report_args.output.as_deref(), !self.deterministic)
+                .inspect_err(|e| error!("{e:#}"))?;
+            let _ = self.events.set(events);
+        }
+        // Only the command takes over the process's signals and draws
+        // progress, so embedders' crawls leave both alone
+        #[cfg(unix)]
+        self.pause.listen_for_signals()?;
         let mut pb = ProgressBar::new();
         pb.init();
-        {
-            let mut pb_lock = self.progress_bar.lock().unwrap();
-            *pb_lock = Some(pb);
+        *self.progress_bar.lock().unwrap() = Some(pb);
+        let crawled = self.crawl().await;
+        if let Some(mut pb) = self.progress_bar.lock().unwrap().take() {
+            pb.finish();
+        }
+        crawled?;
+        self.within_deadline("diagnosing failed hosts", self.diagnose_address_families())
+            .await;
+        self.within_deadline("checking languages", self.check_accept_languages())
+            .await;
+        self.within_deadline("comparing mobile pages", self.compare_mobile())
+            .await;
+        if self.wayback {
+            self.within_deadline("looking up the Wayback Machine", self.look_up_wayback())
+                .await;
         }
 
-        let queue = Arc::new(Mutex::new(VecDeque::new()));
-        queue.lock().unwrap().push_back(UrlWithReferrer {
-            url: start_url,
-            referrer: None,
-        });
-        self.run_queue(queue, MaxConcurrency(10)).await?;
+        if self.update_baseline {
+            self.write_baseline()?;
+        }
 
-        {
-            let mut pb_lock = self.progress_bar.lock().unwrap();
-            if let Some(mut pb) = pb_lock.take() {
-                pb.finish();
+        self.display_summary(report_args.max_referrers_shown);
+        let report = self.report(report_args.max_referrers_shown);
+        if let Some(events) = self.events.get() {
+            events.emit(&Event::Finished {
+                summary: &report.summary,
+            });
+        }
+        match &report_args.report_dir {
+            Some(dir) => {
+                report
+                    .save_run(report_args.format, dir, report_args.keep_reports)
+                    .inspect_err(|e| error!("{e:#}"))?;
             }
+            None => report.write(report_args)?,
         }
-
-        self.display_summary();
-        self.fail_on_error()
-    }
-
-    async fn run_queue(
-        &self,
-        queue: Arc<Mutex<VecDeque<UrlWithReferrer>>>,
-        max_concurrent: MaxConcurrency,
-    ) -> Result<()> {
-        loop {
-            let batch: Vec<UrlWithReferrer> = {
-                let mut queue_lock = queue.lock().unwrap();
-                let mut batch = Vec::with_capacity(*max_concurrent);
-                while let Some(url_with_referrer) = queue_lock.pop_front() {
-                    batch.push(url_with_referrer);
-                    if batch.len() >= *max_concurrent {
-                        break;
-                    }
-                }
-                batch
+        let uploaded = match &report_args.upload {
+            Some(target) => self
+                .within_deadline(
+                    "uploading the report",
+                    self.upload_report(&report, target, report_args),
+                )
+                .await
+                .transpose()
+                .inspect_err(|e| error!("{e:#}"))?,
+            None => None,
+        };
+        let report_url = uploaded.as_ref().or(report_args.report_url.as_ref());
+        self.within_deadline("notifying owners", self.notify_owners(report_url))
+            .await;
+        if let Some(tracker) = &report_args.file_issues {
+            self.within_deadline("filing issues", self.file_issues(tracker))
+                .await
+                .transpose()
+                .inspect_err(|e| error!("{e:#}"))?;
+        }
+        if let Some(target) = &report_args.commit_status {
+            self.within_deadline(
+                "posting the commit status",
+                self.post_commit_status(target, report_url, report_args),
+            )
+            .await
+            .transpose()
+            .inspect_err(|e| error!("{e:#}"))?;
+        }
+        // A run stopped at the deadline exits with its own code even if links
+        // failed, so CI can tell its results are incomplete
+        let cut_short = self.cut_short.lock().unwrap().clone();
+        if self.unchecked_at_deadline.get().is_some() || !cut_short.is_empty() {
+            let e = DeadlineReached {
+                unchecked: self
+                    .unchecked_at_deadline
+                    .get()
+                    .copied()
+                    .unwrap_or_default(),
+                cut_short,
             };
-            if batch.is_empty() {
-                break;
-            }
-
-            let results = stream::iter(batch)
-                .map(|url_with_referrer| {
-                    let checker = self.clone();
-                    let queue_clone = Arc::clone(&queue);
-                    async move {
-                        checker
-                            .process_url_parallel(&url_with_referrer, queue_clone)
-                            .await
-                    }
-                })
-                .buffer_unordered(*max_concurrent)
-                .collect::<Vec<Result<()>>>()
-                .await;
-            for result in results {
-                result?;
-            }
+            warn!("{e}");
+            return Err(e.into());
         }
+        self.fail_on_error()?;
         Ok(())
     }
 
-    async fn process_url_parallel(
-        &self,
-        url_with_referrer: &UrlWithReferrer,
-        queue: Arc<Mutex<VecDeque<UrlWithReferrer>>>,
-    ) -> Result<()> {
-        let url = &url_with_referrer.url;
-        let referrer = &url_with_referrer.referrer;
+    /// Crawl the site from the start URL, checking every link found, without
+    /// reporting on the results, showing progress or listening for signals
+    /// to pause.
+    pub async fn crawl(&self) -> Result<()> {
+        let start_url = self.start_url.clone();
+        if !start_url.origin().eq(&self.base_url.origin()) {
+            error!("Start URL must be within the base URL domain");
+            anyhow::bail!("Start URL must be within the base URL domain");
+        }
 
-        {
-            let mut pb_lock = self.progress_bar.lock().unwrap();
-            if let Some(pb) = pb_lock.as_mut() {
-                pb.curr_checking(url)
-            }
+        if let Some(max_duration) = self.max_duration {
+            let _ = self.deadline.set(Instant::now() + max_duration);
         }
 
-        if !url.scheme().starts_with("http") {
-            debug!("Skipping non-http(s) URL: {}", url.as_str());
-            return Ok(());
+        let mut inventories = Vec::with_capacity(self.inventory_urls.len());
+        for inventory_url in &self.inventory_urls {
+            inventories.push(Inventory::fetch(&self.reqwest_client, inventory_url).await?);
         }
+        let _ = self.inventories.set(inventories);
+        let otlp_export = self.otlp.as_ref().map(|otlp| otlp.spawn_periodic());
 
-        if self.visited.mark_visited(url) {
-            debug!("Skipping URL {} as already checked", url.as_str());
-            return Ok(());
+        let mut queued = vec![UrlWithReferrer {
+            url: start_url,
+            referrer: None,
+            depth: 0,
+        }];
+        if let Some(path) = &self.resume {
+            let checkpoint =
+                Checkpoint::load(path, &self.base_url).inspect_err(|e| error!("{e:#}"))?;
+            queued.extend(self.restore(&checkpoint));
         }
+        // Every locale is queued up front so they're crawled concurrently
+        queued.extend(self.locales.roots().map(|root| UrlWithReferrer {
+            url: root.clone(),
+            referrer: None,
+            depth: 0,
+        }));
+        self.emit_queued(&queued);
+        let queue = Arc::new(Mutex::new(CrawlQueue::new(self.strategy)));
+        queue
+            .lock()
+            .unwrap()
+            .extend(queued, |url| self.scope.is_internal(url));
+        self.run_queue(queue.clone(), MaxConcurrency(self.concurrency))
+            .await?;
 
-        // If internal_only is true, skip non-internal URLs
-        if self.internal_only && !url.starts_with(&self.base_url) {
-            debug!(
-                "Skipping external URL due to --internal-only flag: {}",
-                url.as_str()
+        // Pages in the sitemap not reached from the start URL are queued
+        // after the crawl, so they can be reported as orphaned
+        let sitemap_pages = match &self.sitemap_url {
+            Some(sitemap_url) => self
+                .within_deadline("loading the sitemap", self.load_sitemap(sitemap_url))
+                .await
+                .transpose()?,
+            None => None,
+        };
+        if let Some(sitemap_pages) = sitemap_pages {
+            info!("Found {} pages in the sitemap", sitemap_pages.len());
+            let orphaned_pages: Vec<UrlWithReferrer> = sitemap_pages
+                .iter()
+                .map(|(page, _)| page)
+                .filter(|page| !self.visited.contains(&page.url))
+                .cloned()
+                .collect();
+            self.emit_queued(&orphaned_pages);
+            queue
+                .lock()
+                .unwrap()
+                .extend(orphaned_pages.clone(), |url| self.scope.is_internal(url));
+            self.run_queue(queue, MaxConcurrency(self.concurrency))
+                .await?;
+            let _ = self
+                .orphaned_pages
+                .set(orphaned_pages.into_iter().map(|page| page.url).collect());
+            let _ = self.sitemap_pages.set(
+                sitemap_pages
+                    .into_iter()
+                    .map(|(page, lastmod)| (normalize_url(&page.url), lastmod))
+                    .collect(),
             );
-            return Ok(());
         }
 
-        match url.starts_with(&self.base_url) && is_html(url, None) {
-            true => {
-                let result = self
-                    .check_response_internal_maybe_html(url, referrer.as_ref())
-                    .await?;
-                if let CheckResult::Success(Some(next)) = result {
-                    let mut queue_lock = queue.lock().unwrap();
-                    for next_url in next {
-                        queue_lock.push_back(next_url);
-                    }
-                }
+        self.check_fragments();
+        self.within_deadline("checking GitHub anchors", self.check_github_fragments())
+            .await;
+        if self.check_assets {
+            self.within_deadline("checking assets", self.check_all_assets())
+                .await;
+        }
+        if self.check_comment_links {
+            self.within_deadline("checking links in comments", self.check_all_comment_links())
+                .await;
+        }
+        if self.check_forms {
+            self.within_deadline("checking forms", self.check_all_forms())
+                .await;
+        }
+        self.within_deadline("checking mixed content", self.report_mixed_content())
+            .await;
+
+        if let Some(cache) = &self.cache {
+            cache.save()?;
+        }
+        if self.save_graph.is_some() || self.export_graph.is_some() {
+            let snapshot = GraphSnapshot::new(&self.base_url, &self.graph, |url| {
+                self.scope.is_internal(url)
+            });
+            if let Some(path) = &self.save_graph {
+                snapshot.write(path).inspect_err(|e| error!("{e:#}"))?;
+                info!("Saved the link graph to {}", path.display());
+            }
+            if let Some((path, format)) = &self.export_graph {
+                snapshot
+                    .export(path, *format)
+                    .inspect_err(|e| error!("{e:#}"))?;
+                info!("Exported the link graph to {}", path.display());
             }
-            false => self.check_non_internal_html(url, referrer.as_ref()).await,
+        }
+        // A crawl stopped at the deadline keeps its checkpoint to be resumed
+        if let Some(checkpointer) = self
+            .checkpointer
+            .as_ref()
+            .filter(|_| self.unchecked_at_deadline.get().is_none())
+        {
+            checkpointer.remove();
+        }
+
+        if let Some(otlp) = &self.otlp {
+            otlp_export.into_iter().for_each(|export| export.abort());
+            self.within_deadline("exporting metrics", otlp.finish())
+                .await;
         }
 
         Ok(())
     }
 
-    async fn check_response_internal_maybe_html(
+    /// Run a phase after the crawl, stopping it at the deadline, if any.
+    /// Phases stopped or started after the deadline are recorded, so the run
+    /// ends as stopped at it.
+    ///
+    /// ## Returns
+    /// Returns `None` if the deadline stopped the phase.
+    async fn within_deadline<T>(
         &self,
-        url: &Url,
-        referrer: Option<&Url>,
-    ) -> Result<CheckResult> {
-        let response = match self.reqwest_client.get(url.as_str()).send().await {
-            Ok(response) => response,
-            Err(e) => {
-                if let Some(ref_url) = referrer {
-                    error!(
-                        "Failed to fetch {} (referrer: {}): {}",
-                        url.as_str(),
-                        ref_url.as_str(),
-                        e
+        phase: &'static str,
+        work: impl Future<Output = T>,
+    ) -> Option<T> {
+        let Some(&deadline) = self.deadline.get() else {
+            return Some(work.await);
+        };
+        match tokio::time::timeout_at(deadline.into(), work).await {
+            Ok(output) => Some(output),
+            Err(_) => {
+                warn!("Stopped {phase} at --max-duration");
+                self.cut_short.lock().unwrap().push(phase);
+                None
+            }
+        }
+    }
+
+    /// Load every page URL from a sitemap, following sitemap indexes, along
+    /// with its `lastmod`. Each page's referrer is the sitemap listing it.
+    async fn load_sitemap(
+        &self,
+        sitemap_url: &Url,
+    ) -> Result<Vec<(UrlWithReferrer, Option<NaiveDate>)>> {
+        let mut pages = Vec::new();
+        let mut sitemaps = VecDeque::from([(sitemap_url.clone(), None)]);
+        let mut loaded = 0;
+        while let Some((url, index)) = sitemaps.pop_front() {
+            loaded += 1;
+            if loaded > self.max_sitemaps {
+                warn!("Not loading more than {} sitemaps", self.max_sitemaps);
+                let detail = format!("over the limit of {} sitemaps", self.max_sitemaps);
+                for (url, index) in std::iter::once((url, index)).chain(sitemaps) {
+                    self.skip_with_detail(
+                        &url,
+                        index.as_ref(),
+                        SkipReason::SitemapLimit,
+                        Some(detail.clone()),
                     );
-                } else {
-                    error!("Failed to fetch {}: {}", url.as_str(), e);
                 }
-                self.failed_checks.fetch_add(1, Ordering::Relaxed);
-                return Ok(CheckResult::Failure);
+                break;
             }
-        };
-        if !response.status().is_success() {
-            if let Some(ref_url) = referrer {
-                error!(
-                    "Failed to fetch {} (referrer: {}): {}",
-                    url.as_str(),
-                    ref_url.as_str(),
-                    response.status()
-                );
-            } else {
-                error!("Failed to fetch {}: {}", url.as_str(), response.status());
+            let content = self.fetch_text(&url).await.inspect_err(|e| {
+                error!("Failed to load sitemap {}: {}", url.as_str(), e);
+            })?;
+            let sitemap = Sitemap::parse(&content).inspect_err(|e| {
+                error!("Failed to parse sitemap {}: {e:#}", url.as_str());
+            })?;
+            match sitemap {
+                Sitemap::Index(children) => {
+                    sitemaps.extend(children.into_iter().map(|child| (child, Some(url.clone()))))
+                }
+                Sitemap::UrlSet(entries) => pages.extend(entries.into_iter().map(|entry| {
+                    let page = UrlWithReferrer {
+                        url: entry.loc,
+                        referrer: Some(url.clone()),
+                        depth: 0,
+                    };
+                    (page, entry.lastmod)
+                })),
             }
-            self.failed_checks.fetch_add(1, Ordering::Relaxed);
-            return Ok(CheckResult::Failure);
         }
-        info!("Successfully checked internal HTML link: {}", url.as_str());
-        self.successful_checks.fetch_add(1, Ordering::Relaxed);
+        Ok(pages)
+    }
 
-        let content_type = response
-            .headers()
-            .get(reqwest::header::CONTENT_TYPE)
-            .and_then(|h| h.to_str().ok());
-        if !is_html(url, content_type) {
-            return Ok(CheckResult::Success(None));
+    /// Read a resource under the base URL, from the local site directory if
+    /// one is being checked.
+    async fn fetch_text(&self, url: &Url) -> Result<String> {
+        if let Some(root) = self
+            .local_root
+            .as_deref()
+            .filter(|_| url.starts_with(&self.base_url))
+        {
+            let path = local::resolve(root, &self.base_url, url)
+                .map_err(|expected| anyhow::anyhow!("no file at {}", expected.display()))?;
+            return Ok(tokio::fs::read_to_string(path).await?);
         }
+        let (response, _) = self.send(url).await?;
+        Ok(response.error_for_status()?.text().await?)
+    }
 
-        let response_text = response.text().await;
-        let Ok(response_text) = response_text else {
-            let err_mess = format!("Failed to read response text from url: {}", url.as_str());
-            error!("{err_mess}");
-            anyhow::bail!("{err_mess}")
+    /// Crawled HTML pages under the base URL that the sitemap doesn't list,
+    /// other than those blocked from indexing.
+    fn pages_missing_from_sitemap(&self) -> Vec<Url> {
+        let Some(sitemap_pages) = self.sitemap_pages.get() else {
+            return Vec::new();
         };
-        Ok(CheckResult::Success(Some(
-            self.extract_links(url, &response_text),
-        )))
+        let noindex_pages = self.noindex_pages.lock().unwrap();
+        self.headings
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|page| Url::parse(page).ok())
+            .filter(|page| {
+                page.starts_with(&self.base_url)
+                    && !sitemap_pages.contains_key(page)
+                    && !noindex_pages.contains(&normalize_url(page))
+            })
+            .collect()
     }
 
-    fn extract_links(&self, curr_base: &Url, s: &str) -> NextTargets {
-        let input = InputContent::from_string(s, FileType::Html);
+    /// Crawled pages that no other page links to, so are only reachable via
+    /// the sitemap or by direct URL. The start URL and locale roots are
+    /// expected to be entry points, so aren't included.
+    fn unlinked_pages(&self) -> Vec<Url> {
+        let entry_points: BTreeSet<Url> = std::iter::once(&self.start_url)
+            .chain(self.locales.roots())
+            .map(normalize_url)
+            .collect();
+        let pages: Vec<Url> = self
+            .headings
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|page| Url::parse(page).ok())
+            .filter(|page| !entry_points.contains(page))
+            .collect();
+        self.graph.unlinked(&pages)
+    }
 
-        self.extractor
-            .extract(&input)
+    /// Sitemap pages whose `X-Robots-Tag` header blocks indexing.
+    fn noindex_pages_in_sitemap(&self) -> Vec<Url> {
+        let Some(sitemap_pages) = self.sitemap_pages.get() else {
+            return Vec::new();
+        };
+        self.noindex_pages
+            .lock()
+            .unwrap()
             .iter()
-            .filter_map(|raw_uri| {
-                let link_str = &raw_uri.text;
-                let parsed_url = match Url::parse(link_str) {
-                    Ok(url) => Some(url),
-                    Err(ParseError::RelativeUrlWithoutBase) => {
-                        if let Some(stripped) = link_str.strip_prefix('/') {
-                            let origin_url = get_origin(curr_base)?;
-                            origin_url.join(stripped).ok()
-                        } else {
-                            curr_base.join(link_str).ok()
-                        }
-                    }
-                    Err(_) => {
-                        error!("Error parsing URL from raw URI: {}", link_str);
-                        None
-                    }
-                };
-                parsed_url.map(|url| UrlWithReferrer {
-                    url,
-                    referrer: Some(curr_base.clone()),
-                })
-            })
-            // Cap path depth to avoid infinite recursion from self-referring pages
-            .filter(|url_with_referrer| {
-                const MAX_PATH_DEPTH: usize = 20;
-                let path_segments: Vec<_> = url_with_referrer
-                    .url
-                    .path()
-                    .split('/')
-                    .filter(|s| !s.is_empty())
-                    .collect();
-                if path_segments.len() > MAX_PATH_DEPTH {
-                    error!(
-                        "Path exceeded depth filter: {}",
-                        url_with_referrer.url.path()
-                    );
-                    false
-                } else {
-                    true
-                }
-            })
-            // If internal_only is true, only include URLs that start with the base URL
-            .filter(|url_with_referrer| {
-                !self.internal_only || url_with_referrer.url.starts_with(&self.base_url)
-            })
+            .filter(|page| sitemap_pages.contains_key(*page))
+            .cloned()
             .collect()
     }
 
-    async fn check_non_internal_html(&self, url: &Url, referrer: Option<&Url>) {
-        match self.lychee_client.check(url.as_str()).await {
-            Ok(response) => {
-                if !response.status().is_success() {
-                    if let Some(ref_url) = referrer {
-                        error!(
-                            "Link check failed for {} (referrer: {}): {}",
-                            url.as_str(),
-                            ref_url.as_str(),
-                            response.status()
-                        );
-                    } else {
-                        error!(
-                            "Link check failed for {}: {}",
-                            url.as_str(),
-                            response.status()
-                        );
-                    }
-                    self.failed_checks.fetch_add(1, Ordering::Relaxed);
-                } else {
-                    self.successful_checks.fetch_add(1, Ordering::Relaxed);
-                    info!("Successfully checked link: {}", url.as_str());
-                }
-            }
-            Err(e) => {
-                if let Some(ref_url) = referrer {
-                    error!(
-                        "Failed to check link {} (referrer: {}): {}",
-                        url.as_str(),
-                        ref_url.as_str(),
-                        e
-                    );
-                } else {
-                    error!("Failed to check link {}: {}", url.as_str(), e);
-                }
-                self.failed_checks.fetch_add(1, Ordering::Relaxed);
-            }
-        }
+    /// Sitemap pages whose `lastmod` is far from when they were last
+    /// modified.
+    fn lastmod_mismatches(&self) -> Vec<LastmodMismatch> {
+        let Some(sitemap_pages) = self.sitemap_pages.get() else {
+            return Vec::new();
+        };
+        lastmod::find_mismatches(sitemap_pages, &self.last_modified.lock().unwrap())
     }
 
-    fn display_summary(&self) {
-        let successful_checks = self.successful_checks.load(Ordering::Relaxed);
+    /// Whether every sitemap `lastmod` is today.
+    fn lastmod_always_today(&self) -> bool {
+        self.sitemap_pages
+            .get()
+            .is_some_and(|sitemap_pages| lastmod::is_always(sitemap_pages, Utc::now().date_naive()))
+    }
+
+    /// Groups of distinct pages sharing the same title or `<h1>`, as chosen by
+    /// `select`. Pages serving identical content are already reported as
+    /// duplicates, so only the first of each is considered.
+    fn duplicate_headings(
+        &self,
+        select: impl Fn(&PageHeadings) -> Option<&str>,
+    ) -> Vec<(String, Vec<Url>)> {
+        let aliases: BTreeSet<Url> = self
+            .content_hashes
+            .duplicate_groups()
+            .into_iter()
+            .flat_map(|group| group.into_iter().skip(1))
+            .collect();
+        let headings = self.headings.lock().unwrap();
+        group_by_text(headings.iter().filter_map(|(page, headings)| {
+            let page = Url::parse(page)
+                .ok()
+                .filter(|page| !aliases.contains(page))?;
+            Some((page, select(headings)?))
+        }))
+    }
+
+    /// The outcome of every check made so far, sorted by URL.
+    pub fn results(&self) -> Vec<CheckRecord> {
+        self.results.records()
+    }
+
+    pub(crate) fn start_url(&self) -> &Url {
+        &self.start_url
+    }
+
+    /// Every page saved to the mirror directory, keyed by normalized URL.
+    pub(crate) fn mirrored_pages(&self) -> BTreeMap<String, MirroredPage> {
+        self.mirror
+            .as_ref()
+            .map(|mirror| mirror.pages())
+            .unwrap_or_default()
+    }
+
+    /// A sorted map of every crawled internal HTML page to its title and
+    /// headings.
+    pub(crate) fn page_headings(&self) -> BTreeMap<String, Vec<String>> {
+        self.headings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(url, headings)| (url.clone(), headings.all()))
+            .collect()
+    }
+
+    /// A sorted map of every crawled internal HTML page to its canonical
+    /// links and meta tags. Empty unless collected for `meta-check`.
+    pub(crate) fn page_meta(&self) -> BTreeMap<String, PageMeta> {
+        self.page_meta.lock().unwrap().clone()
+    }
+
+    /// Pages listed in the sitemap, if one was loaded.
+    pub(crate) fn sitemap_pages(&self) -> BTreeSet<Url> {
+        self.sitemap_pages
+            .get()
+            .map(|pages| pages.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Crawled pages whose `X-Robots-Tag` header blocks indexing.
+    pub(crate) fn noindex_pages(&self) -> BTreeSet<Url> {
+        self.noindex_pages.lock().unwrap().clone()
+    }
+
+    /// The date each crawled page that sent a `Last-Modified` header was last
+    /// modified.
+    pub(crate) fn last_modified(&self) -> BTreeMap<Url, NaiveDate> {
+        self.last_modified
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(page, &date)| (page.clone(), date))
+            .collect()
+    }
+
+    /// Check that a URL loads, from the local site directory if it's under
+    /// the base URL of one.
+    pub(crate) async fn resolves(&self, url: &Url) -> Result<()> {
+        self.fetch_text(url).await.map(|_| ())
+    }
+
+    /// The outcome of checking each asset loaded by the crawled pages, sorted
+    /// by URL. Empty unless assets were checked.
+    pub(crate) fn asset_checks(&self) -> Vec<AssetCheck> {
+        self.asset_checks.get().cloned().unwrap_or_default()
+    }
+
+    /// A sorted map of every crawled internal HTML page to its anchors.
+    pub(crate) fn anchor_map(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.anchors.to_map()
+    }
+
+    /// Tell failures caused by one address family not working, such as a CI
+    /// runner without an IPv6 route, apart from broken links, by connecting
+    /// to each host whose links failed without a response over IPv4 and
+    /// IPv6.
+    async fn diagnose_address_families(&self) {
+        let mut hosts: BTreeMap<(String, u16), Vec<Url>> = BTreeMap::new();
+        for record in self.results.records() {
+            let unreachable = record.status.is_none()
+                && matches!(record.category, Some(Category::Broken | Category::Timeout));
+            if !unreachable || self.scope.is_internal(&record.url) {
+                continue;
+            }
+            let (Some(host), Some(port)) =
+                (record.url.host_str(), record.url.port_or_known_default())
+            else {
+                continue;
+            };
+            let urls = hosts.entry((host.to_string(), port)).or_default();
+            if !urls.contains(&record.url) {
+                urls.push(record.url);
+            }
+        }
+        if hosts.is_empty() {
+            return;
+        }
+        debug!(
+            "Diagnosing connections to {} unreachable hosts",
+            hosts.len()
+        );
+
+        let diagnoses = stream::iter(hosts)
+            .map(|((host, _), urls)| {
+                self.resource_limits.with_connection(async move {
+                    let failure = address_family::diagnose(&urls[0]).await;
+                    (host, urls, failure)
+                })
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        for (host, urls, failure) in diagnoses {
+            let Some(failure) = failure else {
+                continue;
+            };
+            warn!(
+                "{} links to {host} failed to connect: {failure}. They likely work from other networks.",
+                urls.len()
+            );
+            for url in &urls {
+                self.results
+                    .set_network_diagnosis(url, &failure.to_string());
+            }
+        }
+    }
+
+    /// Re-request the start page, locale roots and pages the start page
+    /// links to with each `--accept-language`, failing those that fail only
+    /// for readers of a language.
+    async fn check_accept_languages(&self) {
+        if self.accept_languages.is_empty() {
+            return;
+        }
+        if self.local_root.is_some() {
+            warn!("Not checking --accept-language against a local directory");
+            return;
+        }
+        let start = normalize_url(&self.start_url);
+        let mut pages: BTreeSet<Url> = std::iter::once(start.clone())
+            .chain(self.locales.roots().map(normalize_url))
+            .collect();
+        if let Some((_, targets)) = self
+            .graph
+            .pages()
+            .into_iter()
+            .find(|(page, _)| *page == start)
+        {
+            let headings = self.headings.lock().unwrap();
+            pages.extend(targets.into_iter().filter(|target| {
+                self.scope.is_internal(target) && headings.contains_key(target.as_str())
+            }));
+        }
+        info!(
+            "Checking {} pages with {} languages",
+            pages.len(),
+            self.accept_languages.len()
+        );
+
+        let mut checks: Vec<LanguageCheck> = stream::iter(pages)
+            .map(|page| {
+                self.resource_limits.with_connection(async move {
+                    let default = self.language_response(&page, None).await;
+                    let mut checks = Vec::with_capacity(self.accept_languages.len() + 1);
+                    for language in &self.accept_languages {
+                        let response = self.language_response(&page, Some(language)).await;
+                        checks.push(LanguageCheck {
+                            page: page.clone(),
+                            language: Some(language.clone()),
+                            problem: response.problem(&default),
+                            response,
+                        });
+                    }
+                    checks.insert(
+                        0,
+                        LanguageCheck {
+                            page,
+                            language: None,
+                            response: default,
+                            problem: None,
+                        },
+                    );
+                    checks
+                })
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        checks.sort_by(|a, b| (&a.page, &a.language).cmp(&(&b.page, &b.language)));
+
+        for check in &checks {
+            let (Some(problem), Some(language)) = (&check.problem, &check.language) else {
+                continue;
+            };
+            match problem {
+                LanguageProblem::Fails(reason) => {
+                    let reason = format!("with Accept-Language {language}: {reason}");
+                    error!("Link check failed for {}: {reason}", check.page.as_str());
+                    self.record_failure(
+                        &check.page,
+                        None,
+                        check.response.status,
+                        reason,
+                        Instant::now(),
+                    );
+                }
+                LanguageProblem::Differs(reason) => {
+                    warn!(
+                        "{} with Accept-Language {language}: {reason}",
+                        check.page.as_str()
+                    );
+                }
+            }
+        }
+        let _ = self.language_checks.set(checks);
+    }
+
+    /// Request a page with an `Accept-Language`, or without one, following
+    /// redirects to find where it ends up and the canonical URL it names.
+    async fn language_response(&self, page: &Url, language: Option<&str>) -> LanguageResponse {
+        let result = redirects::follow(
+            page,
+            self.max_redirects,
+            &self.rate_limiter,
+            &self.proxy_settings,
+            &self.resource_limits,
+            |url, credentials| {
+                let request = self.request(url, credentials);
+                match language {
+                    Some(language) => request.header(reqwest::header::ACCEPT_LANGUAGE, language),
+                    None => request,
+                }
+            },
+        )
+        .await;
+        let response = match result {
+            Ok((response, _)) => response,
+            Err(e) => {
+                return LanguageResponse {
+                    error: Some(e.to_string()),
+                    ..LanguageResponse::default()
+                }
+            }
+        };
+        let final_url = response.url().clone();
+        let status = response.status().as_u16();
+        let canonical = match response.text().await {
+            Ok(content) => extract_meta(&content)
+                .canonicals
+                .first()
+                .and_then(|href| final_url.join(href).ok()),
+            Err(_) => None,
+        };
+        LanguageResponse {
+            status: Some(status),
+            final_url: Some(final_url),
+            canonical,
+            error: None,
+        }
+    }
+
+    /// Re-request a sample of the crawled pages with a desktop and a mobile
+    /// `User-Agent`, failing those that fail only on mobile and reporting
+    /// other differences in status, redirects or canonical URL.
+    async fn compare_mobile(&self) {
+        let Some(count) = self.compare_mobile else {
+            return;
+        };
+        if self.local_root.is_some() {
+            warn!("Not checking --compare-mobile against a local directory");
+            return;
+        }
+        let mut pages: Vec<Url> = self
+            .headings
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|page| Url::parse(page).ok())
+            .filter(|page| self.scope.is_internal(page))
+            .collect();
+        pages.sort();
+        let pages = devices::sample(&pages, count);
+        info!("Comparing {} pages on desktop and mobile", pages.len());
+
+        let mut checks: Vec<DeviceCheck> = stream::iter(pages)
+            .map(|page| {
+                self.resource_limits.with_connection(async move {
+                    let desktop = self.device_response(&page, HUMAN_USER_AGENT).await;
+                    let mobile = self.device_response(&page, MOBILE_USER_AGENT).await;
+                    DeviceCheck {
+                        problem: mobile.problem(&desktop),
+                        page,
+                        desktop,
+                        mobile,
+                    }
+                })
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        checks.sort_by(|a, b| a.page.cmp(&b.page));
+
+        for check in &checks {
+            match &check.problem {
+                Some(DeviceProblem::Fails(reason)) => {
+                    let reason = format!("with a mobile User-Agent: {reason}");
+                    error!("Link check failed for {}: {reason}", check.page.as_str());
+                    self.record_failure(
+                        &check.page,
+                        None,
+                        check.mobile.status,
+                        reason,
+                        Instant::now(),
+                    );
+                }
+                Some(DeviceProblem::Differs(reason)) => {
+                    warn!("{} on mobile: {reason}", check.page.as_str());
+                }
+                None => {}
+            }
+        }
+        let _ = self.device_checks.set(checks);
+    }
+
+    /// Request a page with a `User-Agent`, following redirects to find where
+    /// it ends up and the canonical URL it names.
+    async fn device_response(&self, page: &Url, user_agent: &str) -> DeviceResponse {
+        let result = redirects::follow(
+            page,
+            self.max_redirects,
+            &self.rate_limiter,
+            &self.proxy_settings,
+            &self.resource_limits,
+            |url, credentials| {
+                self.request(url, credentials)
+                    .header(reqwest::header::USER_AGENT, user_agent)
+            },
+        )
+        .await;
+        let (response, redirects) = match result {
+            Ok(followed) => followed,
+            Err(e) => {
+                return DeviceResponse {
+                    error: Some(e.to_string()),
+                    ..DeviceResponse::default()
+                }
+            }
+        };
+        let final_url = response.url().clone();
+        let status = response.status().as_u16();
+        let canonical = match response.text().await {
+            Ok(content) => extract_meta(&content)
+                .canonicals
+                .first()
+                .and_then(|href| final_url.join(href).ok()),
+            Err(_) => None,
+        };
+        DeviceResponse {
+            status: Some(status),
+            redirects: redirects
+                .into_iter()
+                .map(|redirect| redirect.location)
+                .collect(),
+            canonical,
+            error: None,
+        }
+    }
+
+    /// Estimate when each broken external link stopped working from its
+    /// captures in the Wayback Machine.
+    async fn look_up_wayback(&self) {
+        let failed: Vec<Url> = self
+            .results
+            .failed_urls()
+            .into_iter()
+            .filter(|url| !self.scope.is_internal(url))
+            .collect();
+        if failed.is_empty() {
+            return;
+        }
+        info!(
+            "Looking up {} broken links in the Wayback Machine",
+            failed.len()
+        );
+
+        let lookups = stream::iter(failed)
+            .map(|url| {
+                self.resource_limits.with_connection(async move {
+                    let last_alive =
+                        wayback::last_alive(&self.reqwest_client, &self.rate_limiter, &url).await;
+                    (url, last_alive)
+                })
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        for (url, last_alive) in lookups {
+            match last_alive {
+                Ok(Some(date)) => {
+                    warn!(
+                        "Broken link {} was last archived working on {}",
+                        url.as_str(),
+                        date
+                    );
+                    self.results.set_last_alive(&url, &date);
+                }
+                Ok(None) => warn!("Broken link {} was never archived working", url.as_str()),
+                Err(e) => error!(
+                    "Failed to look up {} in the Wayback Machine: {}",
+                    url.as_str(),
+                    e
+                ),
+            }
+        }
+    }
+
+    async fn run_queue(
+        &self,
+        queue: Arc<Mutex<CrawlQueue>>,
+        max_concurrent: MaxConcurrency,
+    ) -> Result<()> {
+        loop {
+            if self.deadline_reached(&queue.lock().unwrap())? {
+                break;
+            }
+            let batch: Vec<UrlWithReferrer> = {
+                let mut queue_lock = queue.lock().unwrap();
+                let mut batch = Vec::with_capacity(*max_concurrent);
+                while let Some(url_with_referrer) = queue_lock.pop() {
+                    let url = &url_with_referrer.url;
+                    if url.fragment().is_some_and(|fragment| !fragment.is_empty())
+                        && self.scope.is_internal(url)
+                    {
+                        self.fragment_links
+                            .lock()
+                            .unwrap()
+                            .push(url_with_referrer.clone());
+                    } else if self.is_github_fragment_link(url) {
+                        self.github_fragment_links
+                            .lock()
+                            .unwrap()
+                            .push(url_with_referrer.clone());
+                    }
+                    if !self.locales.is_empty() {
+                        self.locales
+                            .record_link(url, url_with_referrer.referrer.as_ref());
+                    }
+                    // Mark URLs as visited while building the batch (rather than
+                    // inside the concurrent workers) so the first referrer in
+                    // queue order always wins
+                    if self.visited.mark_visited(&url_with_referrer.url) {
+                        debug!(
+                            "Skipping URL {} as already checked",
+                            url_with_referrer.url.as_str()
+                        );
+                        // The visited set ignores fragments, but links to other
+                        // anchors on the same external page can still be
+                        // validated against an inventory
+                        if url_with_referrer.url.fragment().is_some() {
+                            self.check_via_inventory(
+                                &url_with_referrer.url,
+                                url_with_referrer.referrer.as_ref(),
+                            );
+                        }
+                        continue;
+                    }
+                    batch.push(url_with_referrer);
+                    if batch.len() >= *max_concurrent {
+                        break;
+                    }
+                }
+                batch
+            };
+            if batch.is_empty() {
+                break;
+            }
+
+            let tasks = stream::iter(batch).map(|url_with_referrer| {
+                let checker = self.clone();
+                // Logged with everything about the link, so failures can be
+                // traced back along the crawl path that reached it
+                let span = info_span!(
+                    "link",
+                    url = url_with_referrer.url.as_str(),
+                    referrer = url_with_referrer.referrer.as_ref().map(Url::as_str),
+                    depth = url_with_referrer.depth,
+                );
+                async move {
+                    let depth = url_with_referrer.depth + 1;
+                    let next = checker
+                        .resource_limits
+                        .with_connection(checker.process_url_parallel(&url_with_referrer))
+                        .await?;
+                    Ok(next
+                        .into_iter()
+                        .map(|target| UrlWithReferrer { depth, ..target })
+                        .collect())
+                }
+                .instrument(span)
+            });
+            // In deterministic mode, results are collected in batch order so
+            // that the next targets are queued in the same order on every run
+            let results = if self.deterministic {
+                tasks
+                    .buffered(*max_concurrent)
+                    .collect::<Vec<Result<NextTargets>>>()
+                    .await
+            } else {
+                tasks
+                    .buffer_unordered(*max_concurrent)
+                    .collect::<Vec<Result<NextTargets>>>()
+                    .await
+            };
+
+            let mut queue_lock = queue.lock().unwrap();
+            for result in results {
+                let next = result?;
+                self.emit_queued(&next);
+                queue_lock.extend(next, |url| self.scope.is_internal(url));
+            }
+            if let Some(otlp) = &self.otlp {
+                otlp.set_queue_depth(queue_lock.len());
+            }
+            if let Some(checkpointer) = self.checkpointer.as_ref().filter(|c| c.is_due()) {
+                checkpointer.save(&self.checkpoint(&queue_lock))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the crawl has run out of time, stopping with the URLs still in
+    /// `queue` unchecked. The first time, the progress so far is saved to
+    /// any checkpoint so the crawl can be resumed.
+    fn deadline_reached(&self, queue: &CrawlQueue) -> Result<bool> {
+        if self
+            .deadline
+            .get()
+            .is_none_or(|deadline| Instant::now() + self.request_timeout < *deadline)
+        {
+            return Ok(false);
+        }
+        if queue.is_empty() || self.unchecked_at_deadline.get().is_some() {
+            return Ok(!queue.is_empty());
+        }
+        warn!(
+            "Reached --max-duration, not checking the {} URLs still queued",
+            queue.len()
+        );
+        let _ = self.unchecked_at_deadline.set(queue.len());
+        if let Some(checkpointer) = &self.checkpointer {
+            checkpointer.save(&self.checkpoint(queue))?;
+        }
+        Ok(true)
+    }
+
+    /// The crawl's progress between batches, with `queue` still to check.
+    fn checkpoint(&self, queue: &CrawlQueue) -> Checkpoint {
+        Checkpoint::new(&self.base_url)
+            .with_queue(queue.iter())
+            .with_visited(self.visited.urls())
+            .with_records(&self.results.records())
+            .with_redirects(self.results.all_redirects())
+            .with_excluded(self.results.excluded())
+            .with_fragment_links(&self.fragment_links.lock().unwrap())
+            .with_anchors(self.anchors.to_map())
+            .with_links(self.graph.pages())
+    }
+
+    /// Restore the progress of an interrupted crawl, returning the URLs it
+    /// still had to check.
+    fn restore(&self, checkpoint: &Checkpoint) -> Vec<UrlWithReferrer> {
+        for url in checkpoint.visited() {
+            self.visited.mark_visited(&url);
+        }
+        for record in checkpoint.records() {
+            let counter = match record.is_success() {
+                true => &self.successful_checks,
+                false => &self.failed_checks,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+            self.results.record(record);
+        }
+        for (url, redirects) in checkpoint.redirects() {
+            self.results.add_redirects(&url, redirects);
+        }
+        for url in checkpoint.excluded() {
+            self.results.add_excluded(&url);
+        }
+        self.fragment_links
+            .lock()
+            .unwrap()
+            .extend(checkpoint.fragment_links());
+        for (page, anchors) in checkpoint.anchors() {
+            self.anchors.insert(&page, anchors);
+        }
+        for (page, targets) in checkpoint.links() {
+            self.graph.add_links(&page, &targets);
+        }
+        let queue = checkpoint.queue();
+        warn!(
+            "Resuming crawl with {} links already checked and {} queued",
+            self.successful_checks.load(Ordering::Relaxed)
+                + self.failed_checks.load(Ordering::Relaxed),
+            queue.len()
+        );
+        queue
+    }
+
+    async fn process_url_parallel(
+        &self,
+        url_with_referrer: &UrlWithReferrer,
+    ) -> Result<NextTargets> {
+        let url = &url_with_referrer.url;
+        let referrer = &url_with_referrer.referrer;
+
+        self.pause.wait().await;
+        {
+            let mut pb_lock = self.progress_bar.lock().unwrap();
+            if let Some(pb) = pb_lock.as_mut() {
+                pb.curr_checking(url)
+            }
+        }
+
+        if !url.scheme().starts_with("http") {
+            debug!("Skipping non-http(s) URL: {}", url.as_str());
+            self.skip(url, referrer.as_ref(), SkipReason::Scheme);
+            return Ok(Vec::new());
+        }
+
+        if let Some(reason) = self.exclusion(url) {
+            debug!("Skipping excluded URL: {}", url.as_str());
+            self.results.add_excluded(url);
+            self.skip(url, referrer.as_ref(), reason);
+            return Ok(Vec::new());
+        }
+
+        let is_internal = self.scope.is_internal(url);
+
+        // If internal_only is true, skip non-internal URLs
+        if self.internal_only && !is_internal {
+            debug!(
+                "Skipping external URL due to --internal-only flag: {}",
+                url.as_str()
+            );
+            self.skip(url, referrer.as_ref(), SkipReason::External);
+            return Ok(Vec::new());
+        }
+        if let Some(events) = self.events.get() {
+            events.emit(&Event::Started { url: url.as_str() });
+        }
+
+        if self.check_plain_http(url, referrer.as_ref()).await {
+            return Ok(Vec::new());
+        }
+
+        if let Some(root) = self
+            .local_root
+            .as_deref()
+            .filter(|_| url.starts_with(&self.base_url))
+        {
+            let result = self
+                .check_local_file(
+                    root,
+                    url,
+                    referrer.as_ref(),
+                    self.should_recurse(url_with_referrer),
+                )
+                .await?;
+            if let CheckResult::Success(Some(next)) = result {
+                return Ok(next);
+            }
+            return Ok(Vec::new());
+        }
+
+        if let Some(robots) = &self.robots {
+            if !robots
+                .allows(&self.reqwest_client, &self.rate_limiter, url)
+                .await
+            {
+                info!("Skipping URL disallowed by robots.txt: {}", url.as_str());
+                self.skip(url, referrer.as_ref(), SkipReason::Robots);
+                return Ok(Vec::new());
+            }
+        }
+
+        if let Some(expectation) = self
+            .expectations
+            .iter()
+            .find(|expectation| expectation.matches(url))
+        {
+            self.check_expectation(url, referrer.as_ref(), expectation)
+                .await;
+            return Ok(Vec::new());
+        }
+
+        // Responses to other methods aren't pages, so aren't parsed
+        let is_page = is_internal
+            && self.classifier.classify(url, None).is_parseable()
+            && self.method_override(url).is_none();
+        match is_page {
+            true => {
+                let result = self
+                    .check_response_internal_maybe_html(
+                        url,
+                        referrer.as_ref(),
+                        self.should_recurse(url_with_referrer),
+                    )
+                    .instrument(info_span!("page"))
+                    .await?;
+                if let CheckResult::Success(Some(next)) = result {
+                    return Ok(next);
+                }
+            }
+            false => self.check_non_internal_html(url, referrer.as_ref()).await,
+        }
+
+        Ok(Vec::new())
+    }
+
+    async fn check_response_internal_maybe_html(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        recurse: bool,
+    ) -> Result<CheckResult> {
+        let started = Instant::now();
+        let (response, redirects) = match self.send(url).await {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(ref_url) = referrer {
+                    error!(
+                        "Failed to fetch {} (referrer: {}): {}",
+                        url.as_str(),
+                        ref_url.as_str(),
+                        e
+                    );
+                } else {
+                    error!("Failed to fetch {}: {}", url.as_str(), e);
+                }
+                self.record_failure(url, referrer, None, e.to_string(), started);
+                return Ok(CheckResult::Failure);
+            }
+        };
+        if !response.status().is_success() {
+            if let Some(ref_url) = referrer {
+                error!(
+                    "Failed to fetch {} (referrer: {}): {}",
+                    url.as_str(),
+                    ref_url.as_str(),
+                    response.status()
+                );
+            } else {
+                error!("Failed to fetch {}: {}", url.as_str(), response.status());
+            }
+            self.record_failure(
+                url,
+                referrer,
+                Some(response.status().as_u16()),
+                response.status().to_string(),
+                started,
+            );
+            return Ok(CheckResult::Failure);
+        }
+        if let Some(last_modified) = response
+            .headers()
+            .get(reqwest::header::LAST_MODIFIED)
+            .and_then(|h| h.to_str().ok())
+            .and_then(lastmod::parse_http_date)
+        {
+            self.last_modified
+                .lock()
+                .unwrap()
+                .insert(normalize_url(response.url()), last_modified);
+        }
+        if indexing::is_noindex(response.headers()) {
+            debug!("{} is blocked from indexing", response.url().as_str());
+            self.noindex_pages
+                .lock()
+                .unwrap()
+                .insert(normalize_url(response.url()));
+        }
+        info!("Successfully checked internal HTML link: {}", url.as_str());
+        self.record_response(
+            url,
+            referrer,
+            response.status().as_u16(),
+            redirects,
+            started,
+        );
+
+        if !recurse {
+            debug!("Not following links on {} per scope rules", url.as_str());
+            return Ok(CheckResult::Success(None));
+        }
+
+        // Mark the post-redirect URL as visited too, so a page reachable via
+        // several redirecting aliases is only parsed once
+        let final_url = response.url().clone();
+        if normalize_url(&final_url) != normalize_url(url) {
+            self.anchors.add_alias(url, &final_url);
+            self.graph.add_redirect(url, &final_url);
+            self.results.add_referrer(&final_url, url);
+            if self.visited.mark_visited(&final_url) {
+                debug!(
+                    "Skipping parsing {} as redirect target {} was already checked",
+                    url.as_str(),
+                    final_url.as_str()
+                );
+                return Ok(CheckResult::Success(None));
+            }
+            if !self.scope.is_internal(&final_url) {
+                debug!(
+                    "Not following links on {} as it redirected out of scope to {}",
+                    url.as_str(),
+                    final_url.as_str()
+                );
+                return Ok(CheckResult::Success(None));
+            }
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok());
+        let kind = self.classifier.classify(url, content_type);
+        if !kind.is_parseable() {
+            return Ok(CheckResult::Success(None));
+        }
+
+        let response_text = response.text().await;
+        let Ok(response_text) = response_text else {
+            let err_mess = format!("Failed to read response text from url: {}", url.as_str());
+            error!("{err_mess}");
+            anyhow::bail!("{err_mess}")
+        };
+        self.add_downloaded(response_text.len());
+        Ok(CheckResult::Success(Some(self.process_page(
+            url,
+            &final_url,
+            &response_text,
+            kind,
+        )?)))
+    }
+
+    /// Whether to follow the links on a page, per the scope rules and
+    /// `--max-depth`.
+    fn should_recurse(&self, url_with_referrer: &UrlWithReferrer) -> bool {
+        self.scope.should_recurse(&url_with_referrer.url)
+            && self
+                .max_depth
+                .is_none_or(|max_depth| url_with_referrer.depth < max_depth)
+    }
+
+    /// Whether another page can be parsed under `--max-pages`, counting it
+    /// either way.
+    fn take_page(&self) -> bool {
+        let parsed = self.pages_parsed.fetch_add(1, Ordering::Relaxed);
+        let Some(max_pages) = self.max_pages else {
+            return true;
+        };
+        if parsed == max_pages {
+            warn!("Reached --max-pages {max_pages}, not following links on any more pages");
+        }
+        parsed < max_pages
+    }
+
+    /// Whether a URL matches an exclude pattern and no include pattern.
+    fn is_excluded(&self, url: &Url) -> bool {
+        self.exclusion(url).is_some()
+    }
+
+    /// Why a URL is excluded, if it matches an exclude pattern or is on a
+    /// skipped domain, and no include pattern.
+    fn exclusion(&self, url: &Url) -> Option<SkipReason> {
+        if self.includes.is_match(url.as_str()) {
+            return None;
+        }
+        if !self.scope.is_internal(url) && !self.domain_filter.allows(url) {
+            Some(SkipReason::Domain)
+        } else if self.excludes.is_match(url.as_str()) {
+            Some(SkipReason::Pattern)
+        } else {
+            None
+        }
+    }
+
+    /// Record a URL as skipped rather than checked, for the report.
+    fn skip(&self, url: &Url, referrer: Option<&Url>, reason: SkipReason) {
+        self.skip_with_detail(url, referrer, reason, None);
+    }
+
+    fn skip_with_detail(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        reason: SkipReason,
+        detail: Option<String>,
+    ) {
+        if !self.skips.add(url, referrer, reason, detail.clone()) {
+            return;
+        }
+        if let Some(events) = self.events.get() {
+            events.emit(&Event::Skipped {
+                url: url.as_str(),
+                referrer: referrer.map(Url::as_str),
+                reason,
+                detail: detail.as_deref(),
+            });
+        }
+    }
+
+    /// Number of path segments a link may have before it's taken for a
+    /// crawler trap and skipped.
+    fn max_path_depth(&self, url: &Url) -> usize {
+        self.path_depth_limits
+            .iter()
+            .find_map(|limit| limit.max_for(url))
+            .unwrap_or(self.default_max_path_depth)
+    }
+
+    /// Check that an internal URL under the base URL exists in the local
+    /// site directory, reading it from disk instead of over HTTP.
+    async fn check_local_file(
+        &self,
+        root: &Path,
+        url: &Url,
+        referrer: Option<&Url>,
+        recurse: bool,
+    ) -> Result<CheckResult> {
+        let started = Instant::now();
+        let path = match local::resolve(root, &self.base_url, url) {
+            Ok(path) => path,
+            Err(expected) => {
+                let reason = format!("no file at {}", expected.display());
+                return Ok(self.local_file_failure(url, referrer, reason, started));
+            }
+        };
+        let kind = self.classifier.classify(url, None);
+        // Read before the check is recorded, so a file that can't be read is
+        // a broken link rather than ending the crawl
+        let content = match recurse && kind.is_parseable() {
+            true => match tokio::fs::read(&path).await {
+                Ok(content) => Some(content),
+                Err(e) => {
+                    let reason = format!("failed to read {}: {e}", path.display());
+                    return Ok(self.local_file_failure(url, referrer, reason, started));
+                }
+            },
+            false => None,
+        };
+        if let Ok(modified) = tokio::fs::metadata(&path).await.and_then(|m| m.modified()) {
+            self.last_modified.lock().unwrap().insert(
+                normalize_url(url),
+                DateTime::<Utc>::from(modified).date_naive(),
+            );
+        }
+        info!("Successfully checked local file: {}", path.display());
+        self.record_success(url, referrer, None, started);
+
+        if !recurse {
+            debug!("Not following links on {} per scope rules", url.as_str());
+            return Ok(CheckResult::Success(None));
+        }
+        let Some(content) = content else {
+            return Ok(CheckResult::Success(None));
+        };
+        let content = String::from_utf8_lossy(&content);
+        Ok(CheckResult::Success(Some(
+            self.process_page(url, url, &content, kind)?,
+        )))
+    }
+
+    /// Log and record a link to a local file that couldn't be found or read.
+    fn local_file_failure(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        reason: String,
+        started: Instant,
+    ) -> CheckResult {
+        if let Some(ref_url) = referrer {
+            error!(
+                "Failed to find {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            error!("Failed to find {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, None, reason, started);
+        CheckResult::Failure
+    }
+
+    /// Index a fetched internal page and extract the links to check next.
+    fn process_page(
+        &self,
+        url: &Url,
+        final_url: &Url,
+        content: &str,
+        kind: ResourceKind,
+    ) -> Result<NextTargets> {
+        if !self.take_page() {
+            debug!("Not following links on {} per --max-pages", url.as_str());
+            return Ok(Vec::new());
+        }
+        // Redirecting aliases are already deduplicated via the visited set, so
+        // only pages served directly are hashed
+        if normalize_url(final_url) == normalize_url(url) {
+            self.content_hashes.record(url, content);
+        }
+        if kind == ResourceKind::Html {
+            let mut anchors = extract_anchors(content);
+            let headings = extract_headings(content);
+            // Sites generating heading anchors in the browser have no ids to
+            // find, so the anchors are slugged from the headings instead
+            if let Some(rules) = self.slug_rules_for(final_url) {
+                anchors.extend(
+                    headings
+                        .headings
+                        .iter()
+                        .map(|heading| rules.slug(heading))
+                        .filter(|slug| !slug.is_empty()),
+                );
+            }
+            self.anchors.insert(final_url, anchors);
+            self.headings
+                .lock()
+                .unwrap()
+                .insert(normalize_url(final_url).to_string(), headings);
+            if self.collect_meta {
+                self.page_meta
+                    .lock()
+                    .unwrap()
+                    .insert(normalize_url(final_url).to_string(), extract_meta(content));
+            }
+            if let Some(mirror) = &self.mirror {
+                mirror.save(final_url, content)?;
+            }
+        }
+        let mut next = self.extract_links(final_url, content, kind);
+        if kind == ResourceKind::Html && self.check_assets {
+            self.add_assets(final_url, content, &mut next);
+        }
+        if kind == ResourceKind::Html && self.check_comment_links {
+            self.add_comment_links(final_url, content);
+        }
+        if kind == ResourceKind::Html && self.check_forms {
+            self.add_forms(final_url, content);
+        }
+        if kind == ResourceKind::Html && self.check_mixed_content {
+            self.add_mixed_content(final_url, content, &mut next);
+        }
+        // Only links from pages count towards the graph, not those listed in
+        // sitemaps or feeds
+        if kind == ResourceKind::Html {
+            self.graph
+                .add_links(final_url, next.iter().map(|target| &target.url));
+            self.check_link_budget(final_url, &next);
+        }
+        Ok(next)
+    }
+
+    /// Warn about a page with more links than `--max-internal-links` or
+    /// `--max-external-links`.
+    fn check_link_budget(&self, page: &Url, next: &NextTargets) {
+        if self.max_internal_links.is_none() && self.max_external_links.is_none() {
+            return;
+        }
+        let targets: BTreeSet<Url> = next
+            .iter()
+            .map(|target| normalize_url(&target.url))
+            .filter(|url| *url != normalize_url(page))
+            .collect();
+        let internal = targets
+            .iter()
+            .filter(|url| self.scope.is_internal(url))
+            .count();
+        let external = targets.len() - internal;
+        let mut over = Vec::new();
+        if let Some(max) = self.max_internal_links.filter(|&max| internal > max) {
+            over.push(format!("{internal} internal links (limit {max})"));
+        }
+        if let Some(max) = self.max_external_links.filter(|&max| external > max) {
+            over.push(format!("{external} external links (limit {max})"));
+        }
+        if over.is_empty() {
+            return;
+        }
+        warn!("Page {} has {}", page.as_str(), over.join(" and "));
+        self.over_link_budget
+            .lock()
+            .unwrap()
+            .insert(normalize_url(page), (internal, external));
+    }
+
+    /// Record the assets a page loads, removing them from the links to
+    /// check next so they're only checked as assets.
+    fn add_assets(&self, page: &Url, content: &str, next: &mut NextTargets) {
+        let mut assets = self.assets.lock().unwrap();
+        let mut found = BTreeSet::new();
+        for extracted in extract_assets(content) {
+            let kind = extracted.kind;
+            for UrlWithReferrer { mut url, .. } in
+                self.resolve_links(page, vec![extracted.url.clone()])
+            {
+                // Excluded assets are skipped along with the page's links
+                if !url.scheme().starts_with("http") || self.is_excluded(&url) {
+                    continue;
+                }
+                url.set_fragment(None);
+                found.insert(url.clone());
+                let asset = assets
+                    .entry((url, kind))
+                    .or_insert_with(|| AssetRef::new(kind));
+                asset.pages.insert(normalize_url(page));
+                if extracted.missing_alt {
+                    asset.pages_missing_alt.insert(normalize_url(page));
+                }
+                asset.has_modern_variant |= extracted.has_modern_variant;
+            }
+        }
+        next.retain(|target| {
+            let mut url = target.url.clone();
+            url.set_fragment(None);
+            !found.contains(&url)
+        });
+    }
+
+    /// Check every asset loaded by the crawled pages.
+    async fn check_all_assets(&self) {
+        let assets = std::mem::take(&mut *self.assets.lock().unwrap());
+        info!("Checking {} assets", assets.len());
+        let mut checks: Vec<AssetCheck> = stream::iter(assets)
+            .map(|((url, _), asset)| {
+                self.resource_limits
+                    .with_connection(self.check_asset(url, asset))
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        checks.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+        let _ = self.asset_checks.set(checks);
+    }
+
+    /// Check that an asset loads with a Content-Type matching how pages use
+    /// it. Assets under the base URL of a local site only need to exist.
+    async fn check_asset(&self, url: Url, asset: AssetRef) -> AssetCheck {
+        self.pause.wait().await;
+        let local_root = self
+            .local_root
+            .as_deref()
+            .filter(|_| url.starts_with(&self.base_url));
+        let check = match local_root {
+            Some(root) => match local::resolve(root, &self.base_url, &url) {
+                Ok(path) => {
+                    let size = std::fs::metadata(path).ok().map(|metadata| metadata.len());
+                    AssetCheck {
+                        size,
+                        ..AssetCheck::new(url, asset, None, None, None)
+                    }
+                }
+                Err(expected) => {
+                    let error = format!("no file at {}", expected.display());
+                    AssetCheck::new(url, asset, None, None, Some(error))
+                }
+            },
+            None => match self.send(&url).await {
+                Ok((response, _)) => {
+                    let content_type = response
+                        .headers()
+                        .get(reqwest::header::CONTENT_TYPE)
+                        .and_then(|h| h.to_str().ok())
+                        .map(str::to_string);
+                    let status = response.status().as_u16();
+                    let kind = asset.kind;
+                    let size = match response.content_length() {
+                        Some(length) => Some(length),
+                        // Image sizes are reported by `image-audit`, so
+                        // measure them when the server doesn't say
+                        None if kind == AssetKind::Image => {
+                            let body = response.bytes().await.ok();
+                            if let Some(body) = &body {
+                                self.add_downloaded(body.len());
+                            }
+                            body.map(|body| body.len() as u64)
+                        }
+                        None => None,
+                    };
+                    AssetCheck {
+                        size,
+                        ..AssetCheck::new(url, asset, Some(status), content_type, None)
+                    }
+                }
+                Err(e) => AssetCheck::new(url, asset, None, None, Some(e.to_string())),
+            },
+        };
+        match &check.error {
+            Some(error) => error!(
+                "Broken asset {} (loaded by {}): {}",
+                check.url.as_str(),
+                check
+                    .pages
+                    .iter()
+                    .map(Url::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                error
+            ),
+            None => info!("Successfully checked asset: {}", check.url.as_str()),
+        }
+        check
+    }
+
+    /// Record where a page's forms submit to, to check once the crawl
+    /// finishes.
+    fn add_forms(&self, page: &Url, content: &str) {
+        let mut forms = self.forms.lock().unwrap();
+        for (action, method) in extract_form_actions(content) {
+            let Ok(mut url) = page.join(&action) else {
+                continue;
+            };
+            if !url.scheme().starts_with("http") {
+                continue;
+            }
+            url.set_fragment(None);
+            debug!(
+                "Found a form submitting to {} on {}",
+                url.as_str(),
+                page.as_str()
+            );
+            forms
+                .entry(FormTarget { url, method })
+                .or_default()
+                .insert(normalize_url(page));
+        }
+    }
+
+    /// Check that the endpoint of every form found exists.
+    async fn check_all_forms(&self) {
+        let forms = std::mem::take(&mut *self.forms.lock().unwrap());
+        info!("Checking {} form endpoints", forms.len());
+        let mut checks: Vec<FormCheck> = stream::iter(forms)
+            .map(|(target, pages)| {
+                self.resource_limits
+                    .with_connection(self.check_form(target, pages))
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        checks.sort_by(|a, b| a.target.cmp(&b.target));
+        let _ = self.form_checks.set(checks);
+    }
+
+    /// Check that a form's endpoint exists, with GET for forms submitting
+    /// with GET and `--form-method` otherwise, unless the endpoint has a
+    /// method override. Endpoints under the base URL of a local site only
+    /// need to exist.
+    async fn check_form(&self, target: FormTarget, pages: BTreeSet<Url>) -> FormCheck {
+        self.pause.wait().await;
+        let url = target.url.clone();
+        let local_root = self
+            .local_root
+            .as_deref()
+            .filter(|_| url.starts_with(&self.base_url));
+        let check = match local_root {
+            Some(root) => match local::resolve(root, &self.base_url, &url) {
+                Ok(_) => FormCheck::new(target, pages, None, None),
+                Err(expected) => {
+                    let error = format!("no file at {}", expected.display());
+                    FormCheck::new(target, pages, None, Some(error))
+                }
+            },
+            None => {
+                let method = match target.method.as_str() {
+                    "get" => Method::GET,
+                    _ => self.form_method.clone(),
+                };
+                let response = redirects::follow(
+                    &url,
+                    self.max_redirects,
+                    &self.rate_limiter,
+                    &self.proxy_settings,
+                    &self.resource_limits,
+                    |url, credentials| {
+                        let request = match self.method_override(url) {
+                            Some(method_override) => {
+                                method_override.request(&self.check_client, url)
+                            }
+                            None => self.check_client.request(method.clone(), url.as_str()),
+                        };
+                        self.with_host_override(url, request, credentials)
+                    },
+                )
+                .await;
+                match response {
+                    Ok((response, _)) => {
+                        let status = response.status().as_u16();
+                        FormCheck::new(target, pages, Some(status), None)
+                    }
+                    Err(e) => FormCheck::new(target, pages, None, Some(e.to_string())),
+                }
+            }
+        };
+        match &check.error {
+            Some(error) => error!(
+                "Broken form endpoint {} (form on {}): {}",
+                check.target.url.as_str(),
+                check
+                    .pages
+                    .iter()
+                    .map(Url::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                error
+            ),
+            None => info!(
+                "Successfully checked form endpoint: {}",
+                check.target.url.as_str()
+            ),
+        }
+        check
+    }
+
+    /// Record the links in a page's HTML comments, to check once the crawl
+    /// finishes.
+    fn add_comment_links(&self, page: &Url, content: &str) {
+        let mut comment_links = self.comment_links.lock().unwrap();
+        for link in extract_comment_links(content) {
+            let Ok(url) = page.join(&link) else {
+                continue;
+            };
+            if !url.scheme().starts_with("http") {
+                continue;
+            }
+            let mut url = rewrite::rewrite(&self.rewrite_rules, url);
+            url.set_fragment(None);
+            debug!("Found {} in a comment on {}", url.as_str(), page.as_str());
+            comment_links
+                .entry(url)
+                .or_default()
+                .insert(normalize_url(page));
+        }
+    }
+
+    /// Check every link found in HTML comments, reusing the outcome of
+    /// checking the URL during the crawl if it was linked outside comments
+    /// too.
+    async fn check_all_comment_links(&self) {
+        let comment_links = std::mem::take(&mut *self.comment_links.lock().unwrap());
+        info!("Checking {} links in HTML comments", comment_links.len());
+        let records = self.results.records();
+        let mut checks: Vec<CommentLinkCheck> = stream::iter(comment_links)
+            .map(|(url, pages)| {
+                let record = records.iter().find(|record| record.url == url);
+                async move {
+                    let check = match record {
+                        Some(record) => {
+                            CommentLinkCheck::new(url, pages, record.status, record.error.clone())
+                        }
+                        None => {
+                            self.resource_limits
+                                .with_connection(self.check_comment_link(url, pages))
+                                .await
+                        }
+                    };
+                    match &check.error {
+                        Some(error) => warn!(
+                            "Broken link in an HTML comment {} (on {}): {}",
+                            check.url.as_str(),
+                            check
+                                .pages
+                                .iter()
+                                .map(Url::as_str)
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            error
+                        ),
+                        None => info!(
+                            "Successfully checked link in an HTML comment: {}",
+                            check.url.as_str()
+                        ),
+                    }
+                    check
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        checks.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+        let _ = self.comment_link_checks.set(checks);
+    }
+
+    /// Check a link found only in HTML comments. Links under the base URL of
+    /// a local site only need to exist.
+    async fn check_comment_link(&self, url: Url, pages: BTreeSet<Url>) -> CommentLinkCheck {
+        self.pause.wait().await;
+        let local_root = self
+            .local_root
+            .as_deref()
+            .filter(|_| url.starts_with(&self.base_url));
+        match local_root {
+            Some(root) => match local::resolve(root, &self.base_url, &url) {
+                Ok(_) => CommentLinkCheck::new(url, pages, None, None),
+                Err(expected) => {
+                    let error = format!("no file at {}", expected.display());
+                    CommentLinkCheck::new(url, pages, None, Some(error))
+                }
+            },
+            None => match self.send(&url).await {
+                Ok((response, _)) => {
+                    let status = response.status().as_u16();
+                    CommentLinkCheck::new(url, pages, Some(status), None)
+                }
+                Err(e) => CommentLinkCheck::new(url, pages, None, Some(e.to_string())),
+            },
+        }
+    }
+
+    fn extract_links(&self, curr_base: &Url, s: &str, kind: ResourceKind) -> NextTargets {
+        let file_type = match kind {
+            ResourceKind::Html => FileType::Html,
+            ResourceKind::Text => FileType::Plaintext,
+            ResourceKind::Xml => return self.resolve_links(curr_base, extract_xml_links(s)),
+            ResourceKind::Asset => return Vec::new(),
+        };
+        let mut raw_links: Vec<String> = self
+            .extractor
+            .extract(&InputContent::from_string(s, file_type))
+            .into_iter()
+            .map(|raw_uri| raw_uri.text)
+            .collect();
+        if kind == ResourceKind::Html && !self.link_attributes.is_empty() {
+            raw_links.extend(extract_attribute_links(s, &self.link_attributes));
+        }
+        if kind == ResourceKind::Html && (self.skip_nofollow || s.contains(SKIP_ATTRIBUTE)) {
+            let mut skipped = extract_skipped_links(s, self.skip_nofollow, &self.link_attributes);
+            raw_links.retain(|link| match skipped.get_mut(link.trim()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    debug!("Skipping {link} on {} as opted out", curr_base.as_str());
+                    if let Ok(url) = curr_base.join(link.trim()) {
+                        self.skip(&url, Some(curr_base), SkipReason::OptedOut);
+                    }
+                    false
+                }
+                _ => true,
+            });
+        }
+        self.resolve_links(curr_base, raw_links)
+    }
+
+    fn resolve_links(&self, curr_base: &Url, raw_links: Vec<String>) -> NextTargets {
+        let mut next: NextTargets = raw_links
+            .iter()
+            .filter_map(|link_str| {
+                let parsed_url = match Url::parse(link_str) {
+                    Ok(url) => Some(url),
+                    Err(ParseError::RelativeUrlWithoutBase) => {
+                        if let Some(stripped) = link_str.strip_prefix('/') {
+                            let origin_url = get_origin(curr_base)?;
+                            origin_url.join(stripped).ok()
+                        } else {
+                            curr_base.join(link_str).ok()
+                        }
+                    }
+                    Err(_) => {
+                        error!("Error parsing URL from raw URI: {}", link_str);
+                        None
+                    }
+                };
+                // The depth is set by `run_queue`, which knows the page's depth
+                parsed_url.map(|url| UrlWithReferrer {
+                    url: rewrite::rewrite(&self.rewrite_rules, url),
+                    referrer: Some(curr_base.clone()),
+                    depth: 0,
+                })
+            })
+            // Cap path depth to avoid infinite recursion from self-referring pages
+            .filter(|url_with_referrer| {
+                let url = &url_with_referrer.url;
+                let (depth, max_depth) = (path_depth(url), self.max_path_depth(url));
+                if depth <= max_depth {
+                    return true;
+                }
+                error!("Path exceeded depth filter: {}", url.path());
+                self.skip_with_detail(
+                    url,
+                    Some(curr_base),
+                    SkipReason::PathDepth,
+                    Some(format!(
+                        "{depth} path segments, over the limit of {max_depth}"
+                    )),
+                );
+                false
+            })
+            // If internal_only is true, only include URLs that are within scope
+            .filter(|url_with_referrer| {
+                let url = &url_with_referrer.url;
+                if !self.internal_only || self.scope.is_internal(url) {
+                    return true;
+                }
+                let reason = match url.scheme().starts_with("http") {
+                    true => SkipReason::External,
+                    false => SkipReason::Scheme,
+                };
+                self.skip(url, Some(curr_base), reason);
+                false
+            })
+            .collect();
+
+        if self.deterministic {
+            next.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+            next.dedup_by(|a, b| a.url == b.url);
+        }
+        next
+    }
+
+    async fn check_non_internal_html(&self, url: &Url, referrer: Option<&Url>) {
+        if self.check_affiliate_params(url, referrer) {
+            return;
+        }
+        if self.check_via_inventory(url, referrer) {
+            return;
+        }
+        if self.check_domain_budget(url, referrer) {
+            return;
+        }
+        // Checked before the cache, which would hide the shortener
+        if self.shorteners.contains(url) {
+            self.check_shortened(url, referrer).await;
+            return;
+        }
+        if let Some(status) = self.cache.as_ref().and_then(|cache| cache.get(url)) {
+            self.record_success(url, referrer, status, Instant::now());
+            info!("Skipping recently checked link: {}", url.as_str());
+            return;
+        }
+        if self.check_via_site_api(url, referrer).await {
+            return;
+        }
+        // A HEAD request can't stand in for another method
+        let is_internal = self.scope.is_internal(url);
+        let use_head = match is_internal {
+            true => self.no_body,
+            false => self.head_first,
+        };
+        if use_head
+            && self.method_override(url).is_none()
+            && self.check_with_head(url, referrer, !is_internal).await
+        {
+            return;
+        }
+        // The lychee client's settings can't be varied per request, nor can
+        // it be given a proxy, certificates or the limited resolver, so links
+        // sent user headers, links with a method override or signing and any
+        // link when proxied or checking more at once than DNS lookups are
+        // allowed are checked directly instead
+        if self.proxied
+            || !self
+                .resource_limits
+                .allow_unlimited_lookups(self.concurrency)
+            || self.user_headers(url).is_some()
+            || self.method_override(url).is_some()
+            || self.request_signer(url).is_some()
+        {
+            self.check_with_host_override(url, referrer).await;
+            return;
+        }
+
+        let started = Instant::now();
+        self.rate_limiter.wait(url).await;
+        let response = self.lychee_client.check(url.as_str()).await;
+        match response {
+            Ok(response) => {
+                let status = response.status().code().map(|code| code.as_u16());
+                if !response.status().is_success() {
+                    if let Some(ref_url) = referrer {
+                        error!(
+                            "Link check failed for {} (referrer: {}): {}",
+                            url.as_str(),
+                            ref_url.as_str(),
+                            response.status()
+                        );
+                    } else {
+                        error!(
+                            "Link check failed for {}: {}",
+                            url.as_str(),
+                            response.status()
+                        );
+                    }
+                    self.record_failure(
+                        url,
+                        referrer,
+                        status,
+                        response.status().to_string(),
+                        started,
+                    );
+                } else {
+                    self.record_success(url, referrer, status, started);
+                    self.cache_success(url, status);
+                    info!("Successfully checked link: {}", url.as_str());
+                }
+            }
+            Err(e) => {
+                if let Some(ref_url) = referrer {
+                    error!(
+                        "Failed to check link {} (referrer: {}): {}",
+                        url.as_str(),
+                        ref_url.as_str(),
+                        e
+                    );
+                } else {
+                    error!("Failed to check link {}: {}", url.as_str(), e);
+                }
+                self.record_failure(url, referrer, None, e.to_string(), started);
+            }
+        }
+    }
+
+    async fn check_with_host_override(&self, url: &Url, referrer: Option<&Url>) {
+        let started = Instant::now();
+        let (status, reason) = match self.send(url).await {
+            Ok((response, redirects)) if response.status().is_success() => {
+                let status = response.status().as_u16();
+                self.record_response(url, referrer, status, redirects, started);
+                self.cache_success(url, Some(status));
+                info!("Successfully checked link: {}", url.as_str());
+                return;
+            }
+            Ok((response, _)) => (
+                Some(response.status().as_u16()),
+                response.status().to_string(),
+            ),
+            Err(e) => (None, e.to_string()),
+        };
+        if let Some(ref_url) = referrer {
+            error!(
+                "Link check failed for {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            error!("Link check failed for {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, status, reason, started);
+    }
+
+    /// Check a link with a HEAD request, so no body is downloaded.
+    ///
+    /// ## Returns
+    /// Returns `false` if the server answered 403, 405 or 501, which some servers
+    /// send for HEAD requests only, or, with `require_length`, sent no
+    /// Content-Length, so the link needs checking with GET.
+    async fn check_with_head(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        require_length: bool,
+    ) -> bool {
+        let started = Instant::now();
+        let result = redirects::follow(
+            url,
+            self.max_redirects,
+            &self.rate_limiter,
+            &self.proxy_settings,
+            &self.resource_limits,
+            |url, credentials| {
+                self.with_host_override(url, self.check_client.head(url.as_str()), credentials)
+            },
+        )
+        .await;
+        let (status, reason) = match result {
+            Ok((response, redirects)) => {
+                let status = response.status();
+                // The body of a response to HEAD is always empty, so the
+                // length has to come from the header
+                let has_length = response
+                    .headers()
+                    .contains_key(reqwest::header::CONTENT_LENGTH);
+                if matches!(status.as_u16(), 403 | 405 | 501) || (require_length && !has_length) {
+                    debug!(
+                        "Falling back to GET for {} after HEAD returned {status}",
+                        url.as_str()
+                    );
+                    return false;
+                }
+                if status.is_success() {
+                    self.record_response(url, referrer, status.as_u16(), redirects, started);
+                    self.cache_success(url, Some(status.as_u16()));
+                    info!("Successfully checked link: {}", url.as_str());
+                    return true;
+                }
+                (Some(status.as_u16()), status.to_string())
+            }
+            Err(e) => (None, e.to_string()),
+        };
+        if let Some(ref_url) = referrer {
+            error!(
+                "Link check failed for {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            error!("Link check failed for {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, status, reason, started);
+        true
+    }
+
+    /// Check a link through a URL shortener by following it to its
+    /// destination, which is suggested as a replacement.
+    async fn check_shortened(&self, url: &Url, referrer: Option<&Url>) {
+        let started = Instant::now();
+        let (status, reason) = match self.send(url).await {
+            Ok((response, redirects)) if response.status().is_success() => {
+                let status = response.status().as_u16();
+                let Some(expanded) = redirects.last().map(|redirect| redirect.location.clone())
+                else {
+                    self.record_success(url, referrer, Some(status), started);
+                    info!("Successfully checked link: {}", url.as_str());
+                    return;
+                };
+                let message = match referrer {
+                    Some(ref_url) => format!(
+                        "Link uses a URL shortener (referrer: {}): {} expands to {}",
+                        ref_url.as_str(),
+                        url.as_str(),
+                        expanded.as_str()
+                    ),
+                    None => format!(
+                        "Link uses a URL shortener: {} expands to {}",
+                        url.as_str(),
+                        expanded.as_str()
+                    ),
+                };
+                if self.fail_on_shortener {
+                    error!("{message}");
+                    let reason = format!("shortened URL, replace with {}", expanded.as_str());
+                    self.results.add_redirects(url, redirects);
+                    self.record_failure(url, referrer, Some(status), reason, started);
+                } else if self.fail_on_redirect {
+                    self.record_response(url, referrer, status, redirects, started);
+                } else {
+                    warn!("{message}");
+                    self.results.add_redirects(url, redirects);
+                    self.record_success(url, referrer, Some(status), started);
+                }
+                return;
+            }
+            Ok((response, _)) => (
+                Some(response.status().as_u16()),
+                response.status().to_string(),
+            ),
+            Err(e) => (None, e.to_string()),
+        };
+        if let Some(ref_url) = referrer {
+            error!(
+                "Link check failed for {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            error!("Link check failed for {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, status, reason, started);
+    }
+
+    /// Check a link against custom success criteria from the config. Pages
+    /// checked this way aren't parsed for links.
+    async fn check_expectation(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        expectation: &Expectation,
+    ) {
+        let started = Instant::now();
+        let (status, result) = match self.send(url).await {
+            Ok((response, _)) => {
+                let status = response.status().as_u16();
+                let body = match expectation.needs_body() {
+                    true => response.text().await.unwrap_or_default(),
+                    false => String::new(),
+                };
+                self.add_downloaded(body.len());
+                (Some(status), expectation.check(status, &body))
+            }
+            Err(e) => (None, Err(e.to_string())),
+        };
+        let reason = match result {
+            Ok(()) => {
+                self.record_success(url, referrer, status, started);
+                info!("Link meets its expected criteria: {}", url.as_str());
+                return;
+            }
+            Err(reason) => reason,
+        };
+        if let Some(ref_url) = referrer {
+            error!(
+                "Link check failed for {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            error!("Link check failed for {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, status, reason, started);
+    }
+
+    /// Cache a successful check of an external link.
+    fn cache_success(&self, url: &Url, status: Option<u16>) {
+        if let Some(cache) = self.cache.as_ref().filter(|_| !self.scope.is_internal(url)) {
+            cache.insert(url, status);
+        }
+    }
+
+    fn method_override(&self, url: &Url) -> Option<&MethodOverride> {
+        self.method_overrides
+            .iter()
+            .find(|method_override| method_override.matches(url))
+    }
+
+    /// Send a request for a URL, following and returning any redirects.
+    async fn send(&self, url: &Url) -> Result<(reqwest::Response, Vec<Redirect>)> {
+        redirects::follow(
+            url,
+            self.max_redirects,
+            &self.rate_limiter,
+            &self.proxy_settings,
+            &self.resource_limits,
+            |url, credentials| self.request(url, credentials),
+        )
+        .await
+    }
+
+    /// A request for a URL, with any settings for its host applied, and the
+    /// user's headers and signing too with `credentials`. Uses GET unless the
+    /// URL has a method override.
+    fn request(&self, url: &Url, credentials: bool) -> reqwest::RequestBuilder {
+        let request = match self.method_override(url) {
+            Some(method_override) => method_override.request(&self.check_client, url),
+            None => self.check_client.get(url.as_str()),
+        };
+        self.with_host_override(url, request, credentials)
+    }
+
+    fn request_signer(&self, url: &Url) -> Option<&RequestSigner> {
+        self.request_signers
+            .iter()
+            .find(|request_signer| request_signer.matches(url))
+    }
+
+    /// The user's headers and cookies for a URL: those for its host if it
+    /// has overrides, or the global ones if it's on the base URL's origin.
+    /// Other hosts, including third-party services, get none.
+    fn user_headers(&self, url: &Url) -> Option<&HeaderMap> {
+        match url
+            .host_str()
+            .and_then(|host| self.host_overrides.get(host))
+        {
+            Some(host_override) => Some(&host_override.headers),
+            None => (url.origin() == self.base_url.origin() && !self.headers.is_empty())
+                .then_some(&*self.headers),
+        }
+    }
+
+    /// Apply any settings for a URL's host to a request for it. With
+    /// `credentials`, also add the user's headers for it and sign it if the
+    /// URL needs signing.
+    fn with_host_override(
+        &self,
+        url: &Url,
+        request: reqwest::RequestBuilder,
+        credentials: bool,
+    ) -> reqwest::RequestBuilder {
+        let request = match url
+            .host_str()
+            .and_then(|host| self.host_overrides.get(host))
+        {
+            Some(host_override) => request.timeout(host_override.timeout),
+            None => request,
+        };
+        if !credentials {
+            return request;
+        }
+        let request = match self.user_headers(url) {
+            Some(headers) => request.headers(headers.clone()),
+            None => request,
+        };
+        match self.request_signer(url) {
+            Some(request_signer) => request_signer.sign(request),
+            None => request,
+        }
+    }
+
+    /// Validate the fragments of internal links against the anchors found on
+    /// their target pages.
+    fn check_fragments(&self) {
+        let fragment_links = std::mem::take(&mut *self.fragment_links.lock().unwrap());
+        // Anchors linked from many pages are logged once, with every page
+        // listed in the summary
+        let mut logged = BTreeSet::new();
+        for UrlWithReferrer { url, referrer, .. } in fragment_links {
+            let fragment = url.fragment().unwrap_or_default();
+            match self.has_anchor(&url, fragment) {
+                Some(true) => debug!("Found anchor for {}", url.as_str()),
+                Some(false) => {
+                    if !logged.insert(url.clone()) {
+                        debug!("Broken anchor {} linked again", url.as_str());
+                    } else if let Some(ref_url) = &referrer {
+                        error!(
+                            "Broken anchor {} (referrer: {}): no element with id or name `{}`",
+                            url.as_str(),
+                            ref_url.as_str(),
+                            fragment
+                        );
+                    } else {
+                        error!(
+                            "Broken anchor {}: no element with id or name `{}`",
+                            url.as_str(),
+                            fragment
+                        );
+                    }
+                    self.broken_anchors.fetch_add(1, Ordering::Relaxed);
+                    self.record(CheckRecord {
+                        url: url.clone(),
+                        referrer,
+                        status: None,
+                        elapsed: Duration::ZERO,
+                        error: Some(format!("no element with id or name `{fragment}`")),
+                        category: Some(Category::Broken),
+                        last_alive: None,
+                        network_diagnosis: None,
+                    });
+                }
+                None => debug!(
+                    "Not checking anchor for {} as the page wasn't parsed",
+                    url.as_str()
+                ),
+            }
+        }
+    }
+
+    /// Whether a page has an anchor, also trying the fragment as the page's
+    /// locale would slug it, for links to a heading by its text.
+    ///
+    /// ## Returns
+    /// Returns `None` if the page was never parsed for anchors.
+    fn has_anchor(&self, page: &Url, fragment: &str) -> Option<bool> {
+        let found = self.anchors.contains(page, fragment)?;
+        if found {
+            return Some(true);
+        }
+        let Some(rules) = self.slug_rules_for(page) else {
+            return Some(false);
+        };
+        let decoded = percent_encoding::percent_decode_str(fragment).decode_utf8_lossy();
+        let slug = rules.slug(&decoded);
+        Some(!slug.is_empty() && self.anchors.contains(page, &slug) == Some(true))
+    }
+
+    /// The slug rules of the locale a page is in, if any.
+    fn slug_rules_for(&self, page: &Url) -> Option<&SlugRules> {
+        self.slug_rules.iter().find(|rules| rules.applies_to(page))
+    }
+
+    /// Whether a link's fragment should be validated against a Markdown
+    /// document on github.com.
+    fn is_github_fragment_link(&self, url: &Url) -> bool {
+        self.check_github_anchors
+            && !self.internal_only
+            && url.fragment().is_some_and(|fragment| !fragment.is_empty())
+            && !self.is_excluded(url)
+            && GithubDoc::from_url(url).is_some()
+    }
+
+    /// Validate the fragments of links into Markdown on github.com against
+    /// the headings GitHub generates anchors for.
+    async fn check_github_fragments(&self) {
+        let links = std::mem::take(&mut *self.github_fragment_links.lock().unwrap());
+        let mut github = GithubAnchors::new(self.reqwest_client.clone());
+        for UrlWithReferrer { url, referrer, .. } in links {
+            let Some(doc) = GithubDoc::from_url(&url) else {
+                continue;
+            };
+            let fragment = url.fragment().unwrap_or_default();
+            let decoded = percent_encoding::percent_decode_str(fragment).decode_utf8_lossy();
+            let anchor = decoded.strip_prefix("user-content-").unwrap_or(&decoded);
+            match github.anchors(&doc).await {
+                Ok(anchors) if anchors.contains(anchor) => {
+                    debug!("Found GitHub anchor for {}", url.as_str())
+                }
+                Ok(_) => {
+                    if let Some(ref_url) = &referrer {
+                        error!(
+                            "Broken anchor {} (referrer: {}): no heading or element with id `{}`",
+                            url.as_str(),
+                            ref_url.as_str(),
+                            fragment
+                        );
+                    } else {
+                        error!(
+                            "Broken anchor {}: no heading or element with id `{}`",
+                            url.as_str(),
+                            fragment
+                        );
+                    }
+                    self.broken_anchors.fetch_add(1, Ordering::Relaxed);
+                    self.record(CheckRecord {
+                        url: url.clone(),
+                        referrer,
+                        status: None,
+                        elapsed: Duration::ZERO,
+                        error: Some(format!("no heading or element with id `{fragment}`")),
+                        category: Some(Category::Broken),
+                        last_alive: None,
+                        network_diagnosis: None,
+                    });
+                }
+                Err(e) => warn!(
+                    "Not checking anchor for {} as the document couldn't be fetched from GitHub: {e}",
+                    url.as_str()
+                ),
+            }
+        }
+    }
+
+    /// Check a link against the loaded inventories, recording the result.
+    ///
+    /// ## Returns
+    /// Returns `true` if an inventory could determine the result, `false` if
+    /// the link still needs to be checked over HTTP.
+    fn check_via_inventory(&self, url: &Url, referrer: Option<&Url>) -> bool {
+        let started = Instant::now();
+        let inventory_lookup = self
+            .inventories
+            .get()
+            .and_then(|inventories| inventories.iter().find_map(|inv| inv.lookup(url)));
+        match inventory_lookup {
+            Some(InventoryLookup::Found) => {
+                self.record_success(url, referrer, None, started);
+                info!("Successfully checked link via inventory: {}", url.as_str());
+                true
+            }
+            Some(InventoryLookup::MissingAnchor) => {
+                if let Some(ref_url) = referrer {
+                    error!(
+                        "Link check failed for {} (referrer: {}): anchor not found in inventory",
+                        url.as_str(),
+                        ref_url.as_str()
+                    );
+                } else {
+                    error!(
+                        "Link check failed for {}: anchor not found in inventory",
+                        url.as_str()
+                    );
+                }
+                self.record_failure(
+                    url,
+                    referrer,
+                    None,
+                    "anchor not found in inventory".to_string(),
+                    started,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check a link to Stack Exchange or MDN through the site's API, with
+    /// `--site-apis`.
+    ///
+    /// ## Returns
+    /// Returns `false` if the link isn't to a supported site or the API
+    /// couldn't answer, so the link still needs checking.
+    async fn check_via_site_api(&self, url: &Url, referrer: Option<&Url>) -> bool {
+        let Some(lookup) = SiteLookup::from_url(url).filter(|_| self.site_apis) else {
+            return false;
+        };
+        let started = Instant::now();
+        let api_url = match Url::parse(&lookup.api_url()) {
+            Ok(api_url) => api_url,
+            Err(_) => return false,
+        };
+        let result = match self.send(&api_url).await {
+            Ok((response, _)) => {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                self.add_downloaded(body.len());
+                lookup.check(status, &body)
+            }
+            Err(e) => SiteCheck::Unavailable(e.to_string()),
+        };
+        match result {
+            SiteCheck::Found => {
+                self.record_success(url, referrer, None, started);
+                self.cache_success(url, None);
+                info!("Successfully checked link via site API: {}", url.as_str());
+            }
+            SiteCheck::Missing(reason) => {
+                if let Some(ref_url) = referrer {
+                    error!(
+                        "Link check failed for {} (referrer: {}): {}",
+                        url.as_str(),
+                        ref_url.as_str(),
+                        reason
+                    );
+                } else {
+                    error!("Link check failed for {}: {}", url.as_str(), reason);
+                }
+                self.record_failure(url, referrer, None, reason, started);
+            }
+            SiteCheck::Unavailable(reason) => {
+                debug!("Falling back to fetching {}: {}", url.as_str(), reason);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Fail a link carrying affiliate parameters, with
+    /// `--check-affiliate-params`. It isn't requested, so no click is
+    /// attributed.
+    ///
+    /// ## Returns
+    /// Returns `false` if the link has no affiliate parameters, so still
+    /// needs checking.
+    fn check_affiliate_params(&self, url: &Url, referrer: Option<&Url>) -> bool {
+        let Some(policy) = &self.affiliate_policy else {
+            return false;
+        };
+        let violations = policy.violations(url.as_str());
+        if violations.is_empty() {
+            return false;
+        }
+        let reason = format!(
+            "affiliate parameters {}, replace with {}",
+            violations.join(", "),
+            policy.strip(url.as_str())
+        );
+        if let Some(ref_url) = referrer {
+            error!(
+                "Link check failed for {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            error!("Link check failed for {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, None, reason, Instant::now());
+        true
+    }
+
+    /// Fail a plain HTTP link on an HTTPS page per `--https-only`, without
+    /// requesting it. Returns whether it was failed.
+    async fn check_plain_http(&self, url: &Url, referrer: Option<&Url>) -> bool {
+        let Some(page) = referrer.filter(|page| self.https_only && https::is_downgrade(page, url))
+        else {
+            return false;
+        };
+        let reason = match self.https_replacement(url).await {
+            Some(https) => format!("plain HTTP link on an HTTPS page, replace with {https}"),
+            None => "plain HTTP link on an HTTPS page".to_string(),
+        };
+        error!(
+            "Link check failed for {} (referrer: {}): {}",
+            url.as_str(),
+            page.as_str(),
+            reason
+        );
+        self.record_failure(url, referrer, None, reason, Instant::now());
+        true
+    }
+
+    /// Record the assets an HTTPS page loads over plain HTTP, removing them
+    /// from the links to check next so they're only reported as mixed
+    /// content.
+    fn add_mixed_content(&self, page: &Url, content: &str, next: &mut NextTargets) {
+        let mut mixed_content = self.mixed_content.lock().unwrap();
+        let mut found = BTreeSet::new();
+        for extracted in extract_assets(content) {
+            for UrlWithReferrer { mut url, .. } in self.resolve_links(page, vec![extracted.url]) {
+                if !https::is_downgrade(page, &url) || self.is_excluded(&url) {
+                    continue;
+                }
+                url.set_fragment(None);
+                found.insert(url.clone());
+                mixed_content
+                    .entry(url)
+                    .or_insert_with(|| (extracted.kind, page.clone()));
+            }
+        }
+        next.retain(|target| {
+            let mut url = target.url.clone();
+            url.set_fragment(None);
+            !found.contains(&url)
+        });
+    }
+
+    /// Fail each asset loaded over plain HTTP by an HTTPS page, per
+    /// `--check-mixed-content`.
+    async fn report_mixed_content(&self) {
+        let mixed_content = std::mem::take(&mut *self.mixed_content.lock().unwrap());
+        for (url, (kind, page)) in mixed_content {
+            let started = Instant::now();
+            let reason = match self.https_replacement(&url).await {
+                Some(https) => format!(
+                    "mixed content: {} loaded over plain HTTP, replace with {https}",
+                    kind.as_str()
+                ),
+                None => format!("mixed content: {} loaded over plain HTTP", kind.as_str()),
+            };
+            error!(
+                "Link check failed for {} (referrer: {}): {}",
+                url.as_str(),
+                page.as_str(),
+                reason
+            );
+            self.record_failure(&url, Some(&page), None, reason, started);
+        }
+    }
+
+    /// The HTTPS equivalent of a plain HTTP URL, if `--suggest-https` is
+    /// given and it loads.
+    async fn https_replacement(&self, url: &Url) -> Option<Url> {
+        if !self.suggest_https {
+            return None;
+        }
+        let https = https::https_equivalent(url)?;
+        match self.send(&https).await {
+            Ok((response, _)) if response.status().is_success() => Some(https),
+            Ok((response, _)) => {
+                debug!("Not suggesting {}: {}", https.as_str(), response.status());
+                None
+            }
+            Err(e) => {
+                debug!("Not suggesting {}: {e}", https.as_str());
+                None
+            }
+        }
+    }
+
+    /// Skip an external link if its host has used up its time budget,
+    /// recording it as skipped. Returns whether it was skipped.
+    fn check_domain_budget(&self, url: &Url, referrer: Option<&Url>) -> bool {
+        if self.scope.is_internal(url) {
+            return false;
+        }
+        let Some(budget) = self.domain_budget.exceeded(url) else {
+            return false;
+        };
+        let reason = format!(
+            "skipped: domain budget exceeded ({}s spent on {})",
+            budget.as_secs(),
+            url.host_str().unwrap_or_default()
+        );
+        if let Some(ref_url) = referrer {
+            warn!(
+                "Not checking {} (referrer: {}): {}",
+                url.as_str(),
+                ref_url.as_str(),
+                reason
+            );
+        } else {
+            warn!("Not checking {}: {}", url.as_str(), reason);
+        }
+        self.record_failure(url, referrer, None, reason, Instant::now());
+        true
+    }
+
+    /// Record a successful response, warning if it was redirected, or failing
+    /// with `--fail-on-redirect`.
+    fn record_response(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        status: u16,
+        redirects: Vec<Redirect>,
+        started: Instant,
+    ) {
+        let Some(last) = redirects.last() else {
+            self.record_success(url, referrer, Some(status), started);
+            return;
+        };
+        let chain = redirects::describe_chain(url, &redirects);
+        let message = match referrer {
+            Some(ref_url) => format!("Link redirects (referrer: {}): {chain}", ref_url.as_str()),
+            None => format!("Link redirects: {chain}"),
+        };
+        let reason = format!("redirects to {}", last.location.as_str());
+        let first_status = redirects[0].status;
+        self.results.add_redirects(url, redirects);
+        if self.fail_on_redirect {
+            error!("{message}");
+            self.record_failure(url, referrer, Some(first_status), reason, started);
+        } else {
+            warn!("{message}");
+            self.record_success(url, referrer, Some(status), started);
+        }
+    }
+
+    fn record_success(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        status: Option<u16>,
+        started: Instant,
+    ) {
+        self.successful_checks.fetch_add(1, Ordering::Relaxed);
+        self.record(CheckRecord {
+            url: url.clone(),
+            referrer: referrer.cloned(),
+            status,
+            elapsed: started.elapsed(),
+            error: None,
+            category: None,
+            last_alive: None,
+            network_diagnosis: None,
+        });
+    }
+
+    fn record_failure(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        status: Option<u16>,
+        reason: String,
+        started: Instant,
+    ) {
+        self.failed_checks.fetch_add(1, Ordering::Relaxed);
+        self.record(CheckRecord {
+            url: url.clone(),
+            referrer: referrer.cloned(),
+            status,
+            elapsed: started.elapsed(),
+            category: Some(Category::of_failure(status, &reason)),
+            error: Some(reason),
+            last_alive: None,
+            network_diagnosis: None,
+        });
+    }
+
+    /// Stream an event for each target queued that hasn't been checked yet.
+    fn emit_queued(&self, targets: &[UrlWithReferrer]) {
+        let Some(events) = self.events.get() else {
+            return;
+        };
+        for target in targets
+            .iter()
+            .filter(|target| !self.visited.contains(&target.url))
+        {
+            events.emit(&Event::Queued {
+                url: target.url.as_str(),
+                from: target.referrer.as_ref().map(Url::as_str),
+                depth: target.depth,
+            });
+        }
+    }
+
+    fn record(&self, record: CheckRecord) {
+        if !self.scope.is_internal(&record.url) {
+            self.domain_budget.spend(&record.url, record.elapsed);
+        }
+        if let Some(on_result) = &self.on_result {
+            on_result(&record);
+        }
+        if let Some(otlp) = &self.otlp {
+            otlp.add_check(&record);
+        }
+        if let Some(events) = self.events.get() {
+            events.emit(&Event::checked(&record, !self.deterministic));
+        }
+        self.results.record(record);
+    }
+
+    /// Build a report of every check made during the crawl.
+    fn report(&self, max_referrers_shown: Option<usize>) -> Report {
+        let successful = self.successful_checks.load(Ordering::Relaxed);
+        let failed = self.failed_checks.load(Ordering::Relaxed);
+        let records = self.results.records();
+        let locales = self
+            .locales
+            .names()
+            .map(|locale| {
+                let results: Vec<ReportEntry> = records
+                    .iter()
+                    .filter(|record| self.locales.includes(locale, &record.url))
+                    .map(|record| self.report_entry(record))
+                    .collect();
+                LocaleSection {
+                    locale: locale.to_string(),
+                    total: results.len(),
+                    failed: results.iter().filter(|entry| !entry.success).count(),
+                    results,
+                }
+            })
+            .collect();
+        let owners = self
+            .owners
+            .names()
+            .into_iter()
+            .map(|owner| {
+                let results: Vec<ReportEntry> = records
+                    .iter()
+                    .filter(|record| self.owner_of(record) == Some(owner))
+                    .map(|record| self.report_entry(record))
+                    .collect();
+                OwnerSection {
+                    owner: owner.to_string(),
+                    total: results.len(),
+                    failed: results.iter().filter(|entry| !entry.success).count(),
+                    results,
+                }
+            })
+            .collect();
+        let results = records
+            .iter()
+            .map(|record| self.report_entry(record))
+            .collect();
+        let noindex_pages = self
+            .noindex_pages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(Url::to_string)
+            .collect();
+        let stats = match self.deterministic {
+            true => self.stats().untimed(),
+            false => self.stats(),
+        };
+        Report {
+            stats,
+            sections: sections::by_section(&records, &self.base_url),
+            summary: Summary {
+                total: successful + failed,
+                successful,
+                failed,
+                broken_anchors: self.broken_anchors.load(Ordering::Relaxed),
+                redirected: self.results.redirected_count(),
+                categories: self.category_counts(|_| true),
+                known: records
+                    .iter()
+                    .filter(|record| self.is_known(record))
+                    .count(),
+                unchecked_at_deadline: self.unchecked_at_deadline.get().copied(),
+                cut_short: self.cut_short.lock().unwrap().clone(),
+                skipped: self.skips.counts(),
+                possible_false_skips: self.visited.possible_false_positives(),
+            },
+            duplicates: self
+                .content_hashes
+                .duplicate_groups()
+                .into_iter()
+                .map(|group| group.iter().map(Url::to_string).collect())
+                .collect(),
+            duplicate_titles: self
+                .duplicate_headings(|headings| headings.title.as_deref())
+                .into_iter()
+                .map(to_duplicate_text)
+                .collect(),
+            duplicate_h1s: self
+                .duplicate_headings(|headings| headings.h1.as_deref())
+                .into_iter()
+                .map(to_duplicate_text)
+                .collect(),
+            orphaned_pages: self
+                .orphaned_pages
+                .get()
+                .into_iter()
+                .flatten()
+                .map(Url::to_string)
+                .collect(),
+            unlinked_pages: self.unlinked_pages().iter().map(Url::to_string).collect(),
+            over_link_budget: self
+                .over_link_budget
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(page, &(internal, external))| LinkBudgetEntry {
+                    page: page.to_string(),
+                    internal,
+                    external,
+                })
+                .collect(),
+            missing_from_sitemap: self
+                .pages_missing_from_sitemap()
+                .iter()
+                .map(Url::to_string)
+                .collect(),
+            noindex_pages,
+            noindex_in_sitemap: self
+                .noindex_pages_in_sitemap()
+                .iter()
+                .map(Url::to_string)
+                .collect(),
+            lastmod_always_today: self.lastmod_always_today(),
+            lastmod_mismatches: self
+                .lastmod_mismatches()
+                .into_iter()
+                .map(|mismatch| LastmodEntry {
+                    url: mismatch.url.to_string(),
+                    lastmod: mismatch.lastmod.to_string(),
+                    last_modified: mismatch.last_modified.to_string(),
+                })
+                .collect(),
+            stale_baseline: self.stale_baseline(),
+            assets: self
+                .asset_checks
+                .get()
+                .into_iter()
+                .flatten()
+                .map(|check| AssetEntry {
+                    url: check.url.to_string(),
+                    kind: check.kind,
+                    success: check.is_success(),
+                    status: check.status,
+                    content_type: check.content_type.clone(),
+                    error: check.error.clone(),
+                    pages: check.pages.iter().map(Url::to_string).collect(),
+                })
+                .collect(),
+            forms: self
+                .form_checks
+                .get()
+                .into_iter()
+                .flatten()
+                .map(|check| FormEntry {
+                    url: check.target.url.to_string(),
+                    method: check.target.method.clone(),
+                    success: check.is_success(),
+                    status: check.status,
+                    error: check.error.clone(),
+                    pages: check.pages.iter().map(Url::to_string).collect(),
+                })
+                .collect(),
+            comment_links: self
+                .comment_link_checks
+                .get()
+                .into_iter()
+                .flatten()
+                .map(|check| CommentLinkEntry {
+                    url: check.url.to_string(),
+                    success: check.is_success(),
+                    status: check.status,
+                    error: check.error.clone(),
+                    pages: check.pages.iter().map(Url::to_string).collect(),
+                })
+                .collect(),
+            locales,
+            owners,
+            accept_language: self
+                .language_checks
+                .get()
+                .into_iter()
+                .flatten()
+                .map(|check| LanguageEntry {
+                    page: check.page.to_string(),
+                    language: check.language.clone(),
+                    status: check.response.status,
+                    final_url: check.response.final_url.as_ref().map(Url::to_string),
+                    canonical: check.response.canonical.as_ref().map(Url::to_string),
+                    error: check.response.error.clone(),
+                    problem: match &check.problem {
+                        Some(LanguageProblem::Fails(reason) | LanguageProblem::Differs(reason)) => {
+                            Some(reason.clone())
+                        }
+                        None => None,
+                    },
+                })
+                .collect(),
+            mobile: self
+                .device_checks
+                .get()
+                .into_iter()
+                .flatten()
+                .map(|check| DeviceEntry {
+                    page: check.page.to_string(),
+                    desktop: device_response_entry(&check.desktop),
+                    mobile: device_response_entry(&check.mobile),
+                    problem: match &check.problem {
+                        Some(DeviceProblem::Fails(reason) | DeviceProblem::Differs(reason)) => {
+                            Some(reason.clone())
+                        }
+                        None => None,
+                    },
+                })
+                .collect(),
+            skipped: self
+                .skips
+                .all()
+                .into_iter()
+                .map(|skip| SkippedEntry {
+                    url: skip.url.to_string(),
+                    reason: skip.reason,
+                    detail: skip.detail.clone(),
+                    referrer: skip.referrer.as_ref().map(Url::to_string),
+                })
+                .collect(),
+            broken_links: self
+                .broken_links()
+                .into_iter()
+                .map(|link| {
+                    let record = records
+                        .iter()
+                        .find(|record| !record.is_success() && record.url == link.url);
+                    BrokenLinkEntry {
+                        url: link.url.to_string(),
+                        status: record.and_then(|record| record.status),
+                        error: record.and_then(|record| record.error.clone()),
+                        category: record.and_then(|record| self.category(record)),
+                        known: record.is_some_and(|record| self.is_known(record)),
+                        referrer_file: link
+                            .referrers
+                            .first()
+                            .and_then(|referrer| self.source_file(referrer)),
+                        referrer_line: link
+                            .referrers
+                            .first()
+                            .and_then(|referrer| self.source_line(referrer, &link.url)),
+                        referrer_count: link.referrers.len(),
+                        referrers: link
+                            .referrers
+                            .iter()
+                            .take(max_referrers_shown.unwrap_or(usize::MAX))
+                            .map(Url::to_string)
+                            .collect(),
+                    }
+                })
+                .collect(),
+            results,
+        }
+    }
+
+    /// Whether a failure is in the baseline, so doesn't fail the check. When
+    /// updating the baseline, every failure is.
+    fn is_known(&self, record: &CheckRecord) -> bool {
+        !record.is_success() && (self.update_baseline || self.baseline.contains(&record.url))
+    }
+
+    /// Baseline entries that are no longer failing.
+    fn stale_baseline(&self) -> Vec<String> {
+        if self.update_baseline {
+            return Vec::new();
+        }
+        self.baseline
+            .stale(&self.results.failed_urls())
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Replace the baseline file with this run's failures.
+    fn write_baseline(&self) -> Result<()> {
+        let Some(path) = &self.baseline_path else {
+            return Ok(());
+        };
+        let records = self.results.records();
+        let count = Baseline::write(
+            path,
+            records
+                .iter()
+                .filter_map(|record| Some((&record.url, record.error.as_deref()?))),
+        )
+        .inspect_err(|e| error!("{e:#}"))?;
+        info!("Wrote {count} known failures to {}", path.display());
+        Ok(())
+    }
+
+    /// The owner of the page a link is on, or of the URL itself if it wasn't
+    /// linked from anywhere.
+    fn owner_of(&self, record: &CheckRecord) -> Option<&str> {
+        self.owners
+            .owner_of(record.referrer.as_ref().unwrap_or(&record.url))
+    }
+
+    /// Upload the report, named after the `--output` file if there is one,
+    /// returning the URL it can be read at.
+    async fn upload_report(
+        &self,
+        report: &Report,
+        target: &UploadTarget,
+        report_args: &ReportArgs,
+    ) -> Result<Url> {
+        let format = report_args.format;
+        let file_name = match report_args
+            .output
+            .as_deref()
+            .and_then(|path| path.file_name())
+        {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => format!("report.{}", format.extension()),
+        };
+        report
+            .upload_run(
+                format,
+                target,
+                &self.reqwest_client,
+                &file_name,
+                report_args.keep_reports,
+            )
+            .await
+    }
+
+    /// Post each owner's broken links to their webhook, if they have one,
+    /// linking to the full report if it was published.
+    async fn notify_owners(&self, report_url: Option<&Url>) {
+        const MAX_LISTED: usize = 20;
+
+        if self.owner_webhooks.is_empty() {
+            return;
+        }
+        let mut state = match self.notify_state.as_deref().map(NotifyState::load) {
+            Some(Ok(state)) => Some(state),
+            Some(Err(e)) => {
+                error!("{e:#}");
+                return;
+            }
+            None => None,
+        };
+        let records = self.results.records();
+        let mut notified = Vec::new();
+        for (owner, webhook) in self.owner_webhooks.iter() {
+            let failures: Vec<&CheckRecord> = records
+                .iter()
+                .filter(|record| !record.is_success() && self.owner_of(record) == Some(owner))
+                .filter(|record| {
+                    state
+                        .as_ref()
+                        .is_none_or(|state| state.should_notify(&record.url))
+                })
+                .collect();
+            if failures.is_empty() {
+                continue;
+            }
+            let mut text = format!("{} broken links on pages owned by {owner}:", failures.len());
+            for record in failures.iter().take(MAX_LISTED) {
+                text.push_str(&format!(
+                    "\n• {} ({})",
+                    record.url.as_str(),
+                    record.error.as_deref().unwrap_or_default()
+                ));
+                if let Some(referrer) = &record.referrer {
+                    text.push_str(&format!(" on {}", referrer.as_str()));
+                }
+            }
+            if failures.len() > MAX_LISTED {
+                text.push_str(&format!("\n…and {} more", failures.len() - MAX_LISTED));
+            }
+            if let Some(url) = report_url {
+                text.push_str(&format!("\nFull report: {url}"));
+            }
+            match notify::send_webhook(&self.reqwest_client, webhook, &text).await {
+                Ok(()) => {
+                    info!("Notified {owner} of {} broken links", failures.len());
+                    notified.extend(failures.iter().map(|record| record.url.clone()));
+                }
+                Err(e) => error!("Failed to notify {owner}: {e:#}"),
+            }
+        }
+
+        let Some(state) = &mut state else {
+            return;
+        };
+        for url in &notified {
+            state.mark_notified(url);
+        }
+        state.forget_recovered(&self.results.failed_urls().into_iter().collect());
+        if let Err(e) = state.save() {
+            error!("{e:#}");
+        }
+    }
+
+    /// The top-level section of the site a page is in (e.g., `guides` for
+    /// `/guides/auth`), if it's under the base URL and not at its root.
+    fn section_of(&self, page: &Url) -> Option<String> {
+        if !page.starts_with(&self.base_url) {
+            return None;
+        }
+        let path = page.path().strip_prefix(self.base_url.path())?;
+        let (section, _) = path.trim_start_matches('/').split_once('/')?;
+        (!section.is_empty()).then(|| section.to_string())
+    }
+
+    /// Each failing URL once, with every page linking to it, most linked
+    /// first.
+    fn broken_links(&self) -> Vec<BrokenLink> {
+        let records = self.results.records();
+        let mut broken: Vec<BrokenLink> = Vec::new();
+        for record in records.iter().filter(|record| !record.is_success()) {
+            if let Some(link) = broken.iter_mut().find(|link| link.url == record.url) {
+                link.referrers.extend(record.referrer.clone());
+                continue;
+            }
+            broken.push(BrokenLink {
+                url: record.url.clone(),
+                reason: record.error.clone().unwrap_or_default(),
+                referrers: record.referrer.iter().cloned().collect(),
+                sections: BTreeSet::new(),
+            });
+        }
+        for link in &mut broken {
+            // The graph doesn't record fragments, but each broken anchor is
+            // already recorded once per page linking to it
+            if link.url.fragment().is_none() {
+                link.referrers.extend(self.graph.linking_to(&link.url));
+            }
+            link.referrers.sort();
+            link.referrers.dedup();
+            link.sections = link
+                .referrers
+                .iter()
+                .filter_map(|referrer| self.section_of(referrer))
+                .collect();
+        }
+        broken.sort_by(|a, b| {
+            b.referrers
+                .len()
+                .cmp(&a.referrers.len())
+                .then_with(|| a.url.cmp(&b.url))
+        });
+        broken
+    }
+
+    /// Sync an issue for each broken link with the tracker, closing those for
+    /// links that work again.
+    async fn file_issues(&self, tracker: &IssueTracker) -> Result<()> {
+        let records = self.results.records();
+        let broken = self.broken_links();
+
+        // Links to anchors aren't recorded when they work, so a link is
+        // recovered if it no longer fails and its page loaded
+        let failed: BTreeSet<&Url> = broken.iter().map(|link| &link.url).collect();
+        let loaded: BTreeSet<Url> = records
+            .iter()
+            .filter(|record| record.is_success())
+            .map(|record| normalize_url(&record.url))
+            .collect();
+        let recovered = |url: &Url| !failed.contains(url) && loaded.contains(&normalize_url(url));
+        tracker.sync(&broken, recovered).await
+    }
+
+    fn report_entry(&self, record: &CheckRecord) -> ReportEntry {
+        ReportEntry {
+            url: record.url.to_string(),
+            success: record.is_success(),
+            status: record.status,
+            referrers: self
+                .results
+                .referrer_chain(record)
+                .iter()
+                .map(Url::to_string)
+                .collect(),
+            referrer_file: record
+                .referrer
+                .as_ref()
+                .and_then(|referrer| self.source_file(referrer)),
+            // Only failures and redirects are annotated with the line
+            referrer_line: record
+                .referrer
+                .as_ref()
+                .filter(|_| !record.is_success() || !self.results.redirects(&record.url).is_empty())
+                .and_then(|referrer| self.source_line(referrer, &record.url)),
+            redirects: self
+                .results
+                .redirects(&record.url)
+                .into_iter()
+                .map(|redirect| RedirectEntry {
+                    status: redirect.status,
+                    location: redirect.location.to_string(),
+                })
+                .collect(),
+            elapsed_ms: (!self.deterministic).then_some(record.elapsed.as_millis() as u64),
+            error: record.error.clone(),
+            category: self.category(record),
+            known: self.is_known(record),
+            last_alive: record.last_alive.clone(),
+            network_diagnosis: record.network_diagnosis.clone(),
+        }
+    }
+
+    /// The file a page under the base URL is read from when checking a local
+    /// directory, as a relative path with `/` separators.
+    fn source_file(&self, page: &Url) -> Option<String> {
+        let root = self
+            .local_root
+            .as_deref()
+            .filter(|_| page.starts_with(&self.base_url))?;
+        let path = local::resolve(root, &self.base_url, page).ok()?;
+        let path = path.to_string_lossy().replace('\\', "/");
+        Some(path.trim_start_matches("./").to_string())
+    }
+
+    /// The line of a page's source file that a link is on, if it's written
+    /// there in full or, when on the page's origin, from its path on.
+    fn source_line(&self, page: &Url, link: &Url) -> Option<usize> {
+        let source = std::fs::read_to_string(self.source_file(page)?).ok()?;
+        let mut written = vec![link.as_str()];
+        if link.origin() == page.origin() {
+            written.push(&link[Position::BeforePath..]);
+        }
+        source
+            .lines()
+            .position(|line| written.iter().any(|written| line.contains(written)))
+            .map(|index| index + 1)
+    }
+
+    /// The kind of problem with a checked link, counting links that
+    /// redirected but were otherwise successful as redirects.
+    fn category(&self, record: &CheckRecord) -> Option<Category> {
+        record.category.or_else(|| {
+            if self.results.redirects(&record.url).is_empty() {
+                None
+            } else if self.shorteners.contains(&record.url) {
+                Some(Category::Shortened)
+            } else {
+                Some(Category::Redirect)
+            }
+        })
+    }
+
+    /// Number of links with each kind of problem, including those skipped
+    /// by an exclude pattern, counting only records matching `include`.
+    fn category_counts(&self, include: impl Fn(&CheckRecord) -> bool) -> BTreeMap<Category, usize> {
+        let mut counts = BTreeMap::new();
+        for record in self
+            .results
+            .records()
+            .iter()
+            .filter(|record| include(record))
+        {
+            if let Some(category) = self.category(record) {
+                *counts.entry(category).or_default() += 1;
+            }
+        }
+        let excluded = self.results.excluded_count();
+        if excluded > 0 {
+            counts.insert(Category::Excluded, excluded);
+        }
+        let broken_assets = self.broken_asset_count() + self.broken_form_count();
+        if broken_assets > 0 {
+            *counts.entry(Category::Broken).or_default() += broken_assets;
+        }
+        counts
+    }
+
+    fn broken_asset_count(&self) -> usize {
+        self.asset_checks
+            .get()
+            .into_iter()
+            .flatten()
+            .filter(|check| !check.is_success())
+            .count()
+    }
+
+    fn broken_form_count(&self) -> usize {
+        self.form_checks
+            .get()
+            .into_iter()
+            .flatten()
+            .filter(|check| !check.is_success())
+            .count()
+    }
+
+    fn add_downloaded(&self, bytes: usize) {
+        self.bytes_downloaded
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> Stats {
+        Stats::new(
+            &self.results.records(),
+            |record| !self.scope.is_internal(&record.url),
+            self.slowest,
+            self.bytes_downloaded.load(Ordering::Relaxed),
+        )
+    }
+
+    fn display_summary(&self, max_referrers_shown: Option<usize>) {
+        let successful_checks = self.successful_checks.load(Ordering::Relaxed);
         let failed_checks = self.failed_checks.load(Ordering::Relaxed);
         let total_checks = successful_checks + failed_checks;
 
@@ -405,13 +4206,312 @@ impl LinkChecker {
         info!("Total links checked: {}", total_checks);
         info!("Successful checks: {}", successful_checks);
         info!("Failed checks: {}", failed_checks);
+        info!(
+            "Broken anchors: {}",
+            self.broken_anchors.load(Ordering::Relaxed)
+        );
+        info!("Redirected links: {}", self.results.redirected_count());
+        if let Some(unchecked) = self.unchecked_at_deadline.get() {
+            info!("URLs left unchecked at --max-duration: {unchecked}");
+        }
+        let cut_short = self.cut_short.lock().unwrap();
+        if !cut_short.is_empty() {
+            info!("Stopped at --max-duration: {}", cut_short.join(", "));
+        }
+        if let Some(skips) = self.visited.possible_false_positives() {
+            info!("New URLs --visited-bloom may have skipped as visited: about {skips}");
+        }
+        if let Some(asset_checks) = self.asset_checks.get() {
+            info!("Assets checked: {}", asset_checks.len());
+            info!("Broken assets: {}", self.broken_asset_count());
+        }
+        if let Some(form_checks) = self.form_checks.get() {
+            info!("Form endpoints checked: {}", form_checks.len());
+            info!("Broken form endpoints: {}", self.broken_form_count());
+        }
+        if let Some(comment_link_checks) = self.comment_link_checks.get() {
+            let broken = comment_link_checks
+                .iter()
+                .filter(|check| !check.is_success())
+                .count();
+            info!(
+                "Links in HTML comments checked: {}",
+                comment_link_checks.len()
+            );
+            info!("Broken links in HTML comments: {broken}");
+        }
+        if self.baseline_path.is_some() {
+            let known = self
+                .results
+                .records()
+                .iter()
+                .filter(|record| self.is_known(record))
+                .count();
+            info!("Known failures in the baseline: {known}");
+        }
+        for (category, count) in self.category_counts(|_| true) {
+            let blocking = if self.fail_on.contains(&category) {
+                "failing"
+            } else {
+                "warning only"
+            };
+            info!("Links in category {category}: {count} ({blocking})");
+        }
+        for (reason, count) in self.skips.counts() {
+            info!("Skipped URLs {reason}: {count}");
+        }
+        if !self.locales.is_empty() {
+            let records = self.results.records();
+            for locale in self.locales.names() {
+                let (total, failed) = records
+                    .iter()
+                    .filter(|record| self.locales.includes(locale, &record.url))
+                    .fold((0, 0), |(total, failed), record| {
+                        (total + 1, failed + usize::from(!record.is_success()))
+                    });
+                info!("Locale {locale}: {total} links checked, {failed} failed");
+            }
+        }
+        if !self.owners.is_empty() {
+            let records = self.results.records();
+            for owner in self.owners.names() {
+                let (total, failed) = records
+                    .iter()
+                    .filter(|record| self.owner_of(record) == Some(owner))
+                    .fold((0, 0), |(total, failed), record| {
+                        (total + 1, failed + usize::from(!record.is_success()))
+                    });
+                info!("Owner {owner}: {total} links checked, {failed} failed");
+            }
+        }
+        sections::log(&sections::by_section(
+            &self.results.records(),
+            &self.base_url,
+        ));
+        self.stats().log();
+
+        for url in self.stale_baseline() {
+            warn!("Baseline entry {url} is no longer failing, run with --update-baseline to remove it");
+        }
+        for page in self.orphaned_pages.get().into_iter().flatten() {
+            warn!(
+                "Page {} is in the sitemap but not reachable from the start URL",
+                page.as_str()
+            );
+        }
+        for page in self.pages_missing_from_sitemap() {
+            warn!("Page {} is missing from the sitemap", page.as_str());
+        }
+        for page in self.noindex_pages_in_sitemap() {
+            warn!(
+                "Page {} is in the sitemap but blocked from indexing by X-Robots-Tag",
+                page.as_str()
+            );
+        }
+        if self.lastmod_always_today() {
+            warn!("Every sitemap lastmod is today, it is likely set to the build time rather than when each page changed");
+        }
+        for mismatch in self.lastmod_mismatches() {
+            warn!(
+                "Sitemap lastmod for {} is {}, but the page was last modified on {}",
+                mismatch.url.as_str(),
+                mismatch.lastmod,
+                mismatch.last_modified
+            );
+        }
+
+        for group in self.content_hashes.duplicate_groups() {
+            let urls: Vec<&str> = group.iter().map(Url::as_str).collect();
+            warn!(
+                "Duplicate content served at {} URLs, consider redirecting to a canonical URL: {}",
+                urls.len(),
+                urls.join(", ")
+            );
+        }
+        self.display_duplicate_headings();
+        self.display_broken_links(max_referrers_shown);
+
+        let unlinked_pages = self.unlinked_pages();
+        if !unlinked_pages.is_empty() {
+            warn!("\nOrphan pages (not linked from any other page):");
+            for page in unlinked_pages {
+                warn!("  {}", page.as_str());
+            }
+        }
+    }
+
+    /// Log each broken link once, with the pages linking to it, so a link
+    /// broken across the site reads as one problem.
+    fn display_broken_links(&self, max_referrers_shown: Option<usize>) {
+        let broken = self.broken_links();
+        if broken.is_empty() {
+            return;
+        }
+        error!("\nBroken links:");
+        for link in broken {
+            match link.referrers.len() {
+                0 => error!("  {} ({})", link.url.as_str(), link.reason),
+                1 => error!(
+                    "  {} ({}), linked from 1 page:",
+                    link.url.as_str(),
+                    link.reason
+                ),
+                count => error!(
+                    "  {} ({}), linked from {count} pages:",
+                    link.url.as_str(),
+                    link.reason
+                ),
+            }
+            let shown = max_referrers_shown.unwrap_or(usize::MAX);
+            for referrer in link.referrers.iter().take(shown) {
+                error!("    {}", referrer.as_str());
+            }
+            if link.referrers.len() > shown {
+                error!("    and {} more", link.referrers.len() - shown);
+            }
+        }
+    }
+
+    fn display_duplicate_headings(&self) {
+        let titles = self.duplicate_headings(|headings| headings.title.as_deref());
+        let h1s = self.duplicate_headings(|headings| headings.h1.as_deref());
+        for (kind, groups) in [("title", titles), ("h1", h1s)] {
+            for (text, pages) in groups {
+                let urls: Vec<&str> = pages.iter().map(Url::as_str).collect();
+                warn!(
+                    "{} pages share the {kind} \"{text}\": {}",
+                    urls.len(),
+                    urls.join(", ")
+                );
+            }
+        }
+    }
+
+    /// Post whether the check passed to a GitHub commit, with the number of
+    /// links failing it, linking to the report if it was published.
+    async fn post_commit_status(
+        &self,
+        target: &CommitStatusTarget,
+        report_url: Option<&Url>,
+        report_args: &ReportArgs,
+    ) -> Result<()> {
+        let total = self.successful_checks.load(Ordering::Relaxed)
+            + self.failed_checks.load(Ordering::Relaxed);
+        let failing: usize = self
+            .category_counts(|record| !self.is_known(record))
+            .into_iter()
+            .filter(|(category, _)| self.fail_on.contains(category))
+            .map(|(_, count)| count)
+            .sum();
+        let stopped = self.unchecked_at_deadline.get().is_some()
+            || !self.cut_short.lock().unwrap().is_empty();
+        let (state, description) = match self.unchecked_at_deadline.get() {
+            Some(unchecked) => (
+                StatusState::Error,
+                format!(
+                    "{failing} of {total} links failing, stopped at --max-duration with {unchecked} URLs unchecked"
+                ),
+            ),
+            None if stopped => (
+                StatusState::Error,
+                format!("{failing} of {total} links failing, stopped at --max-duration"),
+            ),
+            None if failing > 0 => (
+                StatusState::Failure,
+                format!("{failing} of {total} links failing"),
+            ),
+            None => (StatusState::Success, format!("All {total} links passed")),
+        };
+        let context = report_args
+            .status_context
+            .as_deref()
+            .unwrap_or(commit_status::DEFAULT_CONTEXT);
+        target.post(state, &description, report_url, context).await
     }
 
+    /// Fail if any link not in the baseline has a problem in a category that
+    /// fails the check, warning about problems in the others.
     fn fail_on_error(&self) -> Result<()> {
-        if self.failed_checks.load(Ordering::Relaxed) > 0 {
+        let mut failing = false;
+        for (category, count) in self.category_counts(|record| !self.is_known(record)) {
+            if self.fail_on.contains(&category) {
+                failing = true;
+            } else if category != Category::Excluded && category != Category::Redirect {
+                warn!(
+                    "{count} links in category {category} are not failing the check per --fail-on"
+                );
+            }
+        }
+        if failing {
             error!("Some links failed to check");
             anyhow::bail!("Some links failed to check");
         }
         Ok(())
     }
 }
+
+fn device_response_entry(response: &DeviceResponse) -> DeviceResponseEntry {
+    DeviceResponseEntry {
+        status: response.status,
+        redirects: response.redirects.iter().map(Url::to_string).collect(),
+        canonical: response.canonical.as_ref().map(Url::to_string),
+        error: response.error.clone(),
+    }
+}
+
+fn to_duplicate_text((text, pages): (String, Vec<Url>)) -> DuplicateText {
+    DuplicateText {
+        text,
+        pages: pages.iter().map(Url::to_string).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_headers_only_go_to_the_base_origin() {
+        let checker = LinkChecker::builder(Url::parse("https://docs.example.com/guide/").unwrap())
+            .header("Authorization", "Bearer secret")
+            .build()
+            .unwrap();
+        let headers_for = |url: &str| {
+            checker
+                .user_headers(&Url::parse(url).unwrap())
+                .and_then(|headers| headers.get("authorization").cloned())
+        };
+
+        assert!(headers_for("https://docs.example.com/other/page").is_some());
+        assert!(headers_for("https://example.com/").is_none());
+        assert!(headers_for("http://docs.example.com/guide/").is_none());
+        assert!(headers_for("https://docs.example.com:8443/guide/").is_none());
+        assert!(headers_for("https://web.archive.org/wait/").is_none());
+    }
+
+    #[tokio::test]
+    async fn phases_stop_at_the_deadline() {
+        let checker = LinkChecker::builder(Url::parse("https://example.com/").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            checker.within_deadline("unlimited", async { 1 }).await,
+            Some(1)
+        );
+
+        let _ = checker
+            .deadline
+            .set(Instant::now() + Duration::from_millis(50));
+        assert_eq!(checker.within_deadline("quick", async { 2 }).await, Some(2));
+        let stopped = checker
+            .within_deadline("slow", tokio::time::sleep(Duration::from_secs(60)))
+            .await;
+        assert!(stopped.is_none());
+        // Phases starting after the deadline only run if they finish at once
+        let late = checker
+            .within_deadline("late", tokio::time::sleep(Duration::from_millis(1)))
+            .await;
+        assert!(late.is_none());
+        assert_eq!(*checker.cut_short.lock().unwrap(), ["slow", "late"]);
+    }
+}
@@ -0,0 +1,24 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use url::Url;
+
+/// Post a message to a Slack-compatible incoming webhook.
+pub(super) async fn send_webhook(
+    client: &reqwest::Client,
+    webhook: &Url,
+    text: &str,
+) -> Result<()> {
+    client
+        .post(webhook.as_str())
+        .json(&json!({ "text": text }))
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| {
+            format!(
+                "Failed to post to webhook {}",
+                webhook.host_str().unwrap_or_default()
+            )
+        })?;
+    Ok(())
+}
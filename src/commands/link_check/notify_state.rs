@@ -0,0 +1,132 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+/// What's known about notifications for a single broken link
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct NotifyEntry {
+    /// When owners were last notified the link is broken, in seconds since
+    /// the Unix epoch. Cleared once the link works again, so a new breakage
+    /// is notified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) notified_at: Option<u64>,
+    /// When a mute on the link expires, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) muted_until: Option<u64>,
+}
+
+/// Notifications sent for broken links, persisted between runs so each
+/// broken link is only notified once, unless muted.
+#[derive(Debug)]
+pub(crate) struct NotifyState {
+    path: PathBuf,
+    entries: BTreeMap<String, NotifyEntry>,
+}
+
+impl NotifyState {
+    /// Load the state from a file, dropping expired mutes. A missing file is
+    /// treated as empty.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let mut entries: BTreeMap<String, NotifyEntry> = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).with_context(|| {
+                format!("Failed to parse notification state {}", path.display())
+            })?,
+            Err(_) => BTreeMap::new(),
+        };
+        let now = now();
+        for entry in entries.values_mut() {
+            entry.muted_until = entry.muted_until.filter(|until| *until > now);
+        }
+        entries.retain(|_, entry| entry.notified_at.is_some() || entry.muted_until.is_some());
+        debug!(
+            "Loaded notification state for {} links from {}",
+            entries.len(),
+            path.display()
+        );
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Whether owners should be notified that a link is broken: it hasn't
+    /// been notified since it broke, and isn't muted.
+    pub(crate) fn should_notify(&self, url: &Url) -> bool {
+        self.entries
+            .get(&key(url))
+            .is_none_or(|entry| entry.notified_at.is_none() && entry.muted_until.is_none())
+    }
+
+    pub(crate) fn mark_notified(&mut self, url: &Url) {
+        self.entries.entry(key(url)).or_default().notified_at = Some(now());
+    }
+
+    /// Forget notifications for links that are no longer broken, so they're
+    /// notified again if they break again.
+    pub(crate) fn forget_recovered(&mut self, broken: &BTreeSet<Url>) {
+        let broken: BTreeSet<String> = broken.iter().map(key).collect();
+        for (url, entry) in self.entries.iter_mut() {
+            if !broken.contains(url) {
+                entry.notified_at = None;
+            }
+        }
+        self.entries
+            .retain(|_, entry| entry.notified_at.is_some() || entry.muted_until.is_some());
+    }
+
+    /// Stop notifying about a link for a while. Once the mute expires, the
+    /// link is notified again if it's still broken.
+    pub(crate) fn mute(&mut self, url: &Url, duration: Duration) {
+        let entry = self.entries.entry(key(url)).or_default();
+        entry.muted_until = Some(now() + duration.as_secs());
+        entry.notified_at = None;
+    }
+
+    /// Remove a link's mute, returning whether it was muted.
+    pub(crate) fn unmute(&mut self, url: &Url) -> bool {
+        let Some(entry) = self.entries.get_mut(&key(url)) else {
+            return false;
+        };
+        entry.muted_until.take().is_some()
+    }
+
+    /// Every link with notification state, keyed by URL.
+    pub(crate) fn entries(&self) -> &BTreeMap<String, NotifyEntry> {
+        &self.entries
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)
+            .with_context(|| format!("Failed to write notification state {}", self.path.display()))
+    }
+}
+
+/// Fragments don't affect whether a page loads, so they share an entry.
+fn key(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,318 @@
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use url::Url;
+
+use super::results::CheckRecord;
+
+/// How often metrics and the spans of finished checks are exported during
+/// the crawl
+const EXPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Span kind of a request made by the crawler
+const SPAN_KIND_CLIENT: u8 = 3;
+const SPAN_KIND_INTERNAL: u8 = 1;
+const STATUS_CODE_ERROR: u8 = 2;
+
+/// The counters metrics are computed from, shared with the crawler
+pub(super) struct CrawlCounters {
+    pub(super) successful_checks: Arc<AtomicUsize>,
+    pub(super) failed_checks: Arc<AtomicUsize>,
+    pub(super) pages_parsed: Arc<AtomicUsize>,
+}
+
+/// Exports crawl metrics (pages per second, error rate and queue depth) and
+/// a span for each check to an OpenTelemetry collector, over OTLP/HTTP with
+/// JSON encoding. The checks are children of one span for the whole crawl.
+pub(super) struct OtlpExporter {
+    client: reqwest::Client,
+    endpoint: Url,
+    /// Headers sent with every export, from `OTEL_EXPORTER_OTLP_HEADERS`
+    /// (e.g., for the collector's auth)
+    headers: HeaderMap,
+    base_url: Url,
+    counters: CrawlCounters,
+    queue_depth: AtomicUsize,
+    trace_id: String,
+    crawl_span_id: String,
+    started: SystemTime,
+    /// Spans of the checks finished since the last export
+    spans: Mutex<Vec<Value>>,
+    /// When metrics were last exported, with the number of pages parsed by
+    /// then, to compute the rate since
+    last_export: Mutex<(Instant, usize)>,
+    random: SystemRandom,
+}
+
+impl OtlpExporter {
+    pub(super) fn new(
+        client: reqwest::Client,
+        endpoint: &Url,
+        base_url: &Url,
+        counters: CrawlCounters,
+    ) -> Result<Self> {
+        // Paths are joined under the endpoint, as the collector's base URL
+        let mut endpoint = endpoint.clone();
+        if !endpoint.path().ends_with('/') {
+            endpoint.set_path(&format!("{}/", endpoint.path()));
+        }
+        let random = SystemRandom::new();
+        Ok(Self {
+            client,
+            endpoint,
+            headers: headers_from_env()?,
+            base_url: base_url.clone(),
+            counters,
+            queue_depth: AtomicUsize::new(0),
+            trace_id: random_id(&random, 16),
+            crawl_span_id: random_id(&random, 8),
+            started: SystemTime::now(),
+            spans: Mutex::new(Vec::new()),
+            last_export: Mutex::new((Instant::now(), 0)),
+            random,
+        })
+    }
+
+    pub(super) fn set_queue_depth(&self, depth: usize) {
+        self.queue_depth.store(depth, Ordering::Relaxed);
+    }
+
+    /// Record a span for a finished check, to be sent with the next export.
+    pub(super) fn add_check(&self, record: &CheckRecord) {
+        let end = SystemTime::now();
+        let start = end.checked_sub(record.elapsed).unwrap_or(end);
+        let mut attributes = vec![attribute("url.full", record.url.as_str())];
+        if let Some(referrer) = &record.referrer {
+            attributes.push(attribute("docs_tools.referrer", referrer.as_str()));
+        }
+        if let Some(status) = record.status {
+            attributes.push(json!({
+                "key": "http.response.status_code",
+                "value": { "intValue": status.to_string() },
+            }));
+        }
+        let mut span = json!({
+            "traceId": self.trace_id,
+            "spanId": random_id(&self.random, 8),
+            "parentSpanId": self.crawl_span_id,
+            "name": "check",
+            "kind": SPAN_KIND_CLIENT,
+            "startTimeUnixNano": unix_nanos(start),
+            "endTimeUnixNano": unix_nanos(end),
+            "attributes": attributes,
+        });
+        if let Some(error) = &record.error {
+            span["status"] = json!({ "code": STATUS_CODE_ERROR, "message": error });
+        }
+        self.spans.lock().unwrap().push(span);
+    }
+
+    /// Export metrics and spans every few seconds until the task is aborted.
+    pub(super) fn spawn_periodic(self: &Arc<Self>) -> JoinHandle<()> {
+        let exporter = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+            // The first tick completes immediately, before anything's checked
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                exporter.export(Vec::new()).await;
+            }
+        })
+    }
+
+    /// Export the final metrics, the remaining spans and the span for the
+    /// whole crawl.
+    pub(super) async fn finish(&self) {
+        let checks = self.checks();
+        let crawl_span = json!({
+            "traceId": self.trace_id,
+            "spanId": self.crawl_span_id,
+            "name": "crawl",
+            "kind": SPAN_KIND_INTERNAL,
+            "startTimeUnixNano": unix_nanos(self.started),
+            "endTimeUnixNano": unix_nanos(SystemTime::now()),
+            "attributes": [
+                attribute("docs_tools.base_url", self.base_url.as_str()),
+                json!({
+                    "key": "docs_tools.checks",
+                    "value": { "intValue": checks.to_string() },
+                }),
+            ],
+        });
+        self.export(vec![crawl_span]).await;
+    }
+
+    /// Send the current metrics and every span recorded since the last
+    /// export, along with `extra_spans`. Failures are logged rather than
+    /// failing the crawl.
+    async fn export(&self, extra_spans: Vec<Value>) {
+        if let Err(e) = self.post("v1/metrics", self.metrics()).await {
+            warn!("Failed to export metrics to OTLP: {e:#}");
+        }
+        let mut spans = std::mem::take(&mut *self.spans.lock().unwrap());
+        spans.extend(extra_spans);
+        if spans.is_empty() {
+            return;
+        }
+        let count = spans.len();
+        let traces = json!({
+            "resourceSpans": [{
+                "resource": self.resource(),
+                "scopeSpans": [{ "scope": scope(), "spans": spans }],
+            }],
+        });
+        match self.post("v1/traces", traces).await {
+            Ok(()) => debug!("Exported {count} spans to OTLP"),
+            Err(e) => warn!("Failed to export {count} spans to OTLP: {e:#}"),
+        }
+    }
+
+    fn metrics(&self) -> Value {
+        let now = SystemTime::now();
+        let pages = self.counters.pages_parsed.load(Ordering::Relaxed);
+        let pages_per_second = {
+            let mut last_export = self.last_export.lock().unwrap();
+            let (last_time, last_pages) = *last_export;
+            *last_export = (Instant::now(), pages);
+            let elapsed = last_time.elapsed().as_secs_f64();
+            match elapsed > 0.0 {
+                true => (pages - last_pages) as f64 / elapsed,
+                false => 0.0,
+            }
+        };
+        let failed = self.counters.failed_checks.load(Ordering::Relaxed);
+        let checks = self.checks();
+        let error_rate = match checks {
+            0 => 0.0,
+            checks => failed as f64 / checks as f64,
+        };
+        let time = unix_nanos(now);
+        let gauge = |name: &str, unit: &str, description: &str, point: Value| {
+            let mut point = point;
+            point["timeUnixNano"] = json!(time);
+            json!({
+                "name": name,
+                "unit": unit,
+                "description": description,
+                "gauge": { "dataPoints": [point] },
+            })
+        };
+        json!({
+            "resourceMetrics": [{
+                "resource": self.resource(),
+                "scopeMetrics": [{
+                    "scope": scope(),
+                    "metrics": [
+                        gauge(
+                            "docs_tools.pages_per_second",
+                            "{page}/s",
+                            "Pages parsed per second since the last export",
+                            json!({ "asDouble": pages_per_second }),
+                        ),
+                        gauge(
+                            "docs_tools.error_rate",
+                            "1",
+                            "Share of checks so far that failed",
+                            json!({ "asDouble": error_rate }),
+                        ),
+                        gauge(
+                            "docs_tools.queue_depth",
+                            "{url}",
+                            "URLs queued to be checked",
+                            json!({
+                                "asInt": self.queue_depth.load(Ordering::Relaxed).to_string(),
+                            }),
+                        ),
+                    ],
+                }],
+            }],
+        })
+    }
+
+    fn checks(&self) -> usize {
+        self.counters.successful_checks.load(Ordering::Relaxed)
+            + self.counters.failed_checks.load(Ordering::Relaxed)
+    }
+
+    fn resource(&self) -> Value {
+        json!({
+            "attributes": [
+                attribute("service.name", "docs-tools"),
+                attribute("service.version", env!("CARGO_PKG_VERSION")),
+                attribute("docs_tools.base_url", self.base_url.as_str()),
+            ],
+        })
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<()> {
+        let url = self.endpoint.join(path)?;
+        self.client
+            .post(url.clone())
+            .headers(self.headers.clone())
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("OTLP collector at {url} rejected the export"))?;
+        Ok(())
+    }
+}
+
+/// Headers from `OTEL_EXPORTER_OTLP_HEADERS`, as comma-separated
+/// `name=value` pairs, as other OpenTelemetry exporters read them.
+fn headers_from_env() -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    let Ok(value) = env::var("OTEL_EXPORTER_OTLP_HEADERS") else {
+        return Ok(headers);
+    };
+    for pair in value.split(',').filter(|pair| !pair.trim().is_empty()) {
+        let (name, value) = pair.split_once('=').with_context(|| {
+            format!("Expected name=value in OTEL_EXPORTER_OTLP_HEADERS, got {pair}")
+        })?;
+        let value = percent_encoding::percent_decode_str(value.trim()).decode_utf8_lossy();
+        headers.insert(
+            HeaderName::try_from(name.trim())?,
+            HeaderValue::try_from(value.as_ref())?,
+        );
+    }
+    Ok(headers)
+}
+
+fn scope() -> Value {
+    json!({ "name": "docs-tools", "version": env!("CARGO_PKG_VERSION") })
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Nanoseconds since the Unix epoch, as the string OTLP's JSON encoding
+/// expects for 64-bit integers.
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// A random trace or span ID of `len` bytes, hex-encoded.
+fn random_id(random: &SystemRandom, len: usize) -> String {
+    let mut bytes = vec![0; len];
+    // The system's random number generator doesn't fail in practice, and an
+    // all-zero ID only makes the span invalid
+    let _ = random.fill(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,67 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use url::Url;
+
+use super::utils::StartsWith;
+
+/// Owners of sections of the site, read from an OWNERS-style file of
+/// `PREFIX OWNER` lines (e.g., `/guides/ @docs-team`), so results can be
+/// split per owner.
+#[derive(Debug, Default)]
+pub(super) struct Owners {
+    /// Each section's root URL and owner, longest prefix first
+    sections: Vec<(Url, String)>,
+}
+
+impl Owners {
+    /// Read an owners file, resolving its path prefixes against the base URL.
+    pub(super) fn load(path: &Path, base_url: &Url) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read owners file {}", path.display()))?;
+        let mut sections = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (prefix, owner) = line.split_once(char::is_whitespace).with_context(|| {
+                format!(
+                    "Expected `PREFIX OWNER` on line {} of {}",
+                    number + 1,
+                    path.display()
+                )
+            })?;
+            let root = base_url
+                .join(prefix.trim_start_matches('/'))
+                .with_context(|| format!("Invalid prefix {prefix} in {}", path.display()))?;
+            sections.push((root, owner.trim().to_string()));
+        }
+        sections.sort_by_key(|(root, _)| std::cmp::Reverse(root.as_str().len()));
+        Ok(Self { sections })
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Every owner, sorted and deduplicated.
+    pub(super) fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .sections
+            .iter()
+            .map(|(_, owner)| owner.as_str())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The owner of the most specific section containing a URL, if any.
+    pub(super) fn owner_of(&self, url: &Url) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|(root, _)| url.starts_with(root))
+            .map(|(_, owner)| owner.as_str())
+    }
+}
@@ -0,0 +1,2191 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, ToSocketAddrs, UdpSocket},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
+use regex::{Regex, RegexBuilder};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::{debug, error};
+use url::Url;
+
+use super::resources::ResourceLimits;
+
+/// Calls of one function by another allowed before giving up, in case a
+/// script recurses forever
+const MAX_CALL_DEPTH: usize = 64;
+
+/// Statements run in one evaluation allowed before giving up, in case a
+/// script loops forever
+const MAX_STEPS: usize = 1_000_000;
+
+/// Proxy requests are sent through when the PAC file fails for their host.
+/// Its name can't resolve (`.invalid` is reserved), so the requests fail
+/// rather than silently connecting directly.
+const FAILED_PROXY: &str = "http://pac-file-failed.invalid/";
+
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Functions scripts may call without declaring them, as run by `builtin`
+const BUILTINS: &[&str] = &[
+    "isPlainHostName",
+    "dnsDomainIs",
+    "localHostOrDomainIs",
+    "dnsDomainLevels",
+    "shExpMatch",
+    "isResolvable",
+    "dnsResolve",
+    "myIpAddress",
+    "isInNet",
+    "convert_addr",
+    "weekdayRange",
+    "dateRange",
+    "timeRange",
+    "RegExp",
+    "Array",
+    "String",
+    "Number",
+    "parseInt",
+    "alert",
+];
+
+/// Methods and properties of strings, arrays and regular expressions that
+/// scripts may use, as run by `method`
+const METHODS: &[&str] = &[
+    "length",
+    "toString",
+    "toLowerCase",
+    "toUpperCase",
+    "trim",
+    "indexOf",
+    "lastIndexOf",
+    "includes",
+    "startsWith",
+    "endsWith",
+    "charAt",
+    "substring",
+    "slice",
+    "substr",
+    "split",
+    "replace",
+    "match",
+    "search",
+    "join",
+    "push",
+    "test",
+    "exec",
+    "source",
+];
+
+/// Words JavaScript reserves, which are rejected rather than read as
+/// variables where this interpreter doesn't support them
+const RESERVED: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// A proxy auto-config (PAC) file, choosing the proxy for each request with
+/// its `FindProxyForURL(url, host)` function.
+///
+/// PAC files are JavaScript, which is interpreted here only as far as PAC
+/// files use it: top-level functions and code, `var` (with `let` and
+/// `const` scoped like it), `if`/`else`, `switch`, `for`, `while` and `do`
+/// loops, strings, decimal numbers, arrays, regular expressions, the usual
+/// operators except bitwise ones, common string, array and `RegExp`
+/// methods, and every PAC helper. Variables are scoped as in JavaScript:
+/// those declared in a function are its own, and assigning any other
+/// variable sets a global one. Arrays are copied when assigned, rather than
+/// shared. Anything else, such as objects, `try` or function expressions,
+/// fails when the file is loaded rather than evaluating differently than a
+/// browser would.
+#[derive(Debug)]
+pub(super) struct PacScript {
+    functions: HashMap<String, Function>,
+    /// Global variables, set by the top-level code and by functions. Held
+    /// for a whole evaluation, so the script runs once at a time, as in
+    /// browsers.
+    globals: Mutex<HashMap<String, Value>>,
+    /// Proxy chosen for each scheme and host, or why the script failed, as
+    /// `FindProxyForURL` is only given those (like in browsers), so needn't
+    /// run for every request
+    cache: Mutex<HashMap<(String, String), PacResult>>,
+    /// Limits the script's DNS lookups along with the HTTP clients'
+    limits: Arc<ResourceLimits>,
+}
+
+/// The proxy a PAC file chose, or why it failed
+type PacResult = Result<Option<Url>, String>;
+
+#[derive(Debug)]
+struct Function {
+    params: Vec<String>,
+    /// Variables declared with `var` anywhere in the body, which are the
+    /// function's own from its start
+    locals: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+impl PacScript {
+    pub(super) fn load(path: &Path, limits: Arc<ResourceLimits>) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read PAC file {}", path.display()))?;
+        Self::parse(&source, limits).with_context(|| format!("Invalid PAC file {}", path.display()))
+    }
+
+    fn parse(source: &str, limits: Arc<ResourceLimits>) -> Result<Self> {
+        let tokens = tokenize(source).map_err(anyhow::Error::msg)?;
+        let mut parser = Parser::new(tokens);
+        let mut functions = HashMap::new();
+        let mut top_level = Vec::new();
+        while !parser.at_end() {
+            if parser.eat_keyword("function") {
+                let name = parser.ident()?;
+                parser.expect_punct("(")?;
+                let mut params = Vec::new();
+                while !parser.eat_punct(")") {
+                    params.push(parser.ident()?);
+                    parser.eat_punct(",");
+                }
+                let global_declarations = std::mem::take(&mut parser.declared);
+                let body = parser.block()?;
+                let locals = std::mem::replace(&mut parser.declared, global_declarations);
+                functions.insert(
+                    name,
+                    Function {
+                        params,
+                        locals,
+                        body,
+                    },
+                );
+            } else {
+                top_level.extend(parser.statement()?);
+            }
+        }
+        if !functions.contains_key("FindProxyForURL") {
+            anyhow::bail!("No FindProxyForURL function");
+        }
+        // Unsupported functions and methods are caught here rather than
+        // when they're first called
+        if let Some(name) = parser
+            .calls
+            .iter()
+            .find(|name| !functions.contains_key(*name) && !BUILTINS.contains(&name.as_str()))
+        {
+            anyhow::bail!("Unsupported function {name}");
+        }
+        if let Some(name) = parser
+            .methods
+            .iter()
+            .find(|name| !METHODS.contains(&name.as_str()))
+        {
+            anyhow::bail!("Unsupported method or property {name}");
+        }
+
+        // Declarations are hoisted, so top-level code may use any of them
+        let mut globals: HashMap<String, Value> = parser
+            .declared
+            .iter()
+            .map(|name| (name.clone(), Value::Undefined))
+            .collect();
+        let mut interpreter = Interpreter::new(&functions, &mut globals, &limits);
+        blocking(|| interpreter.run(&top_level, &mut HashMap::new(), 0))
+            .map_err(|e| anyhow::anyhow!("Top-level code failed: {e}"))?;
+        Ok(Self {
+            functions,
+            globals: Mutex::new(globals),
+            cache: Mutex::new(HashMap::new()),
+            limits,
+        })
+    }
+
+    /// Choose the proxy for a URL ahead of requesting it, off the async
+    /// runtime's workers as the script may look up host names.
+    ///
+    /// ## Returns
+    /// Fails if the script fails for the URL's host, so requests to it can
+    /// fail with the reason rather than going out directly.
+    pub(super) async fn prepare(self: &Arc<Self>, url: &Url) -> Result<()> {
+        let Some(key) = cache_key(url) else {
+            return Ok(());
+        };
+        let cached = self.cache.lock().unwrap().get(&key).cloned();
+        let result = match cached {
+            Some(result) => result,
+            None => {
+                let pac = Arc::clone(self);
+                let url = url.clone();
+                tokio::task::spawn_blocking(move || pac.evaluate_and_cache(&url)).await?
+            }
+        };
+        result
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("PAC file failed for {}: {e}", key.1))
+    }
+
+    /// The proxy to send a request for the URL through, or `None` to
+    /// connect directly. Only the first entry of the result is used, as a
+    /// request can't fall back to the next one here. If the script fails,
+    /// the request is sent to a proxy that can't be reached.
+    pub(super) fn proxy_for(&self, url: &Url) -> Option<Url> {
+        let key = cache_key(url)?;
+        let cached = self.cache.lock().unwrap().get(&key).cloned();
+        let result = cached.unwrap_or_else(|| blocking(|| self.evaluate_and_cache(url)));
+        match result {
+            Ok(proxy) => proxy,
+            Err(_) => Some(Url::parse(FAILED_PROXY).unwrap()),
+        }
+    }
+
+    fn evaluate_and_cache(&self, url: &Url) -> PacResult {
+        let Some(key) = cache_key(url) else {
+            return Ok(None);
+        };
+        let result = self.evaluate(url);
+        match &result {
+            Ok(proxy) => debug!(
+                "PAC file chose {} for {}",
+                proxy.as_ref().map_or("DIRECT", Url::as_str),
+                key.1
+            ),
+            Err(e) => error!("PAC file failed for {}, so requests to it fail: {e}", key.1),
+        }
+        self.cache.lock().unwrap().insert(key, result.clone());
+        result
+    }
+
+    /// Run `FindProxyForURL` for a URL. This may block to look up host names.
+    fn evaluate(&self, url: &Url) -> PacResult {
+        let host = url.host_str().unwrap_or_default().to_string();
+        let script_url = format!("{}://{}/", url.scheme(), url.authority());
+        let mut globals = self.globals.lock().unwrap();
+        let result = Interpreter::new(&self.functions, &mut globals, &self.limits).call(
+            "FindProxyForURL",
+            vec![Value::Str(script_url), Value::Str(host)],
+            0,
+        )?;
+        parse_result(&result.to_string())
+    }
+}
+
+/// Scripts are run once for each scheme and host
+fn cache_key(url: &Url) -> Option<(String, String)> {
+    Some((url.scheme().to_string(), url.host_str()?.to_string()))
+}
+
+/// Run blocking work, letting the async runtime move its other tasks off
+/// this worker if called from one.
+fn blocking<T>(work: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(work)
+        }
+        _ => work(),
+    }
+}
+
+/// Turn the first entry of a `FindProxyForURL` result (e.g.,
+/// `PROXY proxy:3128; DIRECT`) into a proxy URL.
+fn parse_result(result: &str) -> PacResult {
+    let entry = result.split(';').next().unwrap_or_default().trim();
+    let mut parts = entry.split_whitespace();
+    let kind = parts.next().unwrap_or("DIRECT").to_uppercase();
+    let scheme = match kind.as_str() {
+        "DIRECT" => return Ok(None),
+        "PROXY" | "HTTP" => "http",
+        "HTTPS" => "https",
+        "SOCKS" | "SOCKS5" => "socks5",
+        _ => return Err(format!("unsupported proxy type {kind}")),
+    };
+    let address = parts
+        .next()
+        .ok_or_else(|| format!("no address for {kind}"))?;
+    Url::parse(&format!("{scheme}://{address}"))
+        .map(Some)
+        .map_err(|e| format!("invalid proxy address {address}: {e}"))
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+    Regex(JsRegex),
+    Undefined,
+}
+
+#[derive(Debug, Clone)]
+struct JsRegex {
+    regex: Regex,
+    /// Whether the `g` flag was given, to match every occurrence
+    global: bool,
+}
+
+impl JsRegex {
+    fn new(pattern: &str, flags: &str) -> Result<Self, String> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(flags.contains('i'))
+            .multi_line(flags.contains('m'))
+            .dot_matches_new_line(flags.contains('s'))
+            .build()
+            .map_err(|e| format!("invalid regular expression /{pattern}/: {e}"))?;
+        Ok(Self {
+            regex,
+            global: flags.contains('g'),
+        })
+    }
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Str(s) => !s.is_empty(),
+            Value::Num(n) => *n != 0.0 && !n.is_nan(),
+            Value::Bool(b) => *b,
+            Value::Array(_) | Value::Regex(_) => true,
+            Value::Undefined => false,
+        }
+    }
+
+    fn to_number(&self) -> f64 {
+        match self {
+            Value::Str(s) if s.trim().is_empty() => 0.0,
+            Value::Str(s) => s.trim().parse().unwrap_or(f64::NAN),
+            Value::Num(n) => *n,
+            Value::Bool(b) => f64::from(u8::from(*b)),
+            Value::Array(_) | Value::Regex(_) | Value::Undefined => f64::NAN,
+        }
+    }
+
+    fn type_of(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "string",
+            Value::Num(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::Array(_) | Value::Regex(_) => "object",
+            Value::Undefined => "undefined",
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Str(s) => f.write_str(s),
+            Value::Num(n) => write!(f, "{n}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Array(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                f.write_str(&items.join(","))
+            }
+            Value::Regex(regex) => write!(f, "/{}/", regex.regex.as_str()),
+            Value::Undefined => f.write_str("undefined"),
+        }
+    }
+}
+
+fn binary(op: &str, left: &Value, right: &Value) -> Value {
+    let loosely_equal = || match (left, right) {
+        (Value::Str(a), Value::Str(b)) => a == b,
+        (Value::Undefined, Value::Undefined) => true,
+        (Value::Undefined, _) | (_, Value::Undefined) => false,
+        (Value::Array(_) | Value::Regex(_), _) | (_, Value::Array(_) | Value::Regex(_)) => false,
+        _ => left.to_number() == right.to_number(),
+    };
+    let strictly_equal =
+        || std::mem::discriminant(left) == std::mem::discriminant(right) && loosely_equal();
+    match op {
+        "+" => match (left, right) {
+            (Value::Num(_) | Value::Bool(_) | Value::Undefined, Value::Num(_) | Value::Bool(_))
+            | (Value::Num(_) | Value::Bool(_), Value::Undefined) => {
+                Value::Num(left.to_number() + right.to_number())
+            }
+            _ => Value::Str(format!("{left}{right}")),
+        },
+        "-" => Value::Num(left.to_number() - right.to_number()),
+        "*" => Value::Num(left.to_number() * right.to_number()),
+        "/" => Value::Num(left.to_number() / right.to_number()),
+        "%" => Value::Num(left.to_number() % right.to_number()),
+        "==" => Value::Bool(loosely_equal()),
+        "!=" => Value::Bool(!loosely_equal()),
+        "===" => Value::Bool(strictly_equal()),
+        "!==" => Value::Bool(!strictly_equal()),
+        _ => {
+            let ordering = match (left, right) {
+                (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+                _ => left.to_number().partial_cmp(&right.to_number()),
+            };
+            Value::Bool(ordering.is_some_and(|ordering| match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+    }
+}
+
+/// Call a method of a value, or read its `length`.
+fn method(target: &Value, name: &str, args: &[Value]) -> Result<Value, String> {
+    match target {
+        Value::Array(items) => array_method(items, name, args),
+        Value::Regex(regex) => regex_method(regex, name, args),
+        Value::Undefined => Err(format!("can't read {name} of undefined")),
+        target => string_method(&target.to_string(), name, args),
+    }
+}
+
+fn string_method(target: &str, name: &str, args: &[Value]) -> Result<Value, String> {
+    let arg = |index: usize| args.get(index).cloned().unwrap_or(Value::Undefined);
+    let chars: Vec<char> = target.chars().collect();
+    let char_index = |byte_index: usize| target[..byte_index].chars().count() as f64;
+    // Clamp an index to the string, counting negative ones from the end
+    // where `from_end` (as `slice` does)
+    let clamp = |value: Value, default: usize, from_end: bool| match value {
+        Value::Undefined => default,
+        value => {
+            let index = value.to_number();
+            let index = match index < 0.0 && from_end {
+                true => chars.len() as f64 + index,
+                false => index,
+            };
+            index.max(0.0).min(chars.len() as f64) as usize
+        }
+    };
+    Ok(match name {
+        "length" => Value::Num(chars.len() as f64),
+        "toString" => Value::Str(target.to_string()),
+        "toLowerCase" => Value::Str(target.to_lowercase()),
+        "toUpperCase" => Value::Str(target.to_uppercase()),
+        "trim" => Value::Str(target.trim().to_string()),
+        "indexOf" => Value::Num(target.find(&arg(0).to_string()).map_or(-1.0, char_index)),
+        "lastIndexOf" => Value::Num(target.rfind(&arg(0).to_string()).map_or(-1.0, char_index)),
+        "includes" => Value::Bool(target.contains(&arg(0).to_string())),
+        "startsWith" => Value::Bool(target.starts_with(&arg(0).to_string())),
+        "endsWith" => Value::Bool(target.ends_with(&arg(0).to_string())),
+        "charAt" => Value::Str(
+            chars
+                .get(clamp(arg(0), 0, false))
+                .map(char::to_string)
+                .unwrap_or_default(),
+        ),
+        "substring" => {
+            let (start, end) = (clamp(arg(0), 0, false), clamp(arg(1), chars.len(), false));
+            Value::Str(chars[start.min(end)..start.max(end)].iter().collect())
+        }
+        "slice" => {
+            let (start, end) = (clamp(arg(0), 0, true), clamp(arg(1), chars.len(), true));
+            Value::Str(chars[start..end.max(start)].iter().collect())
+        }
+        "substr" => {
+            let start = clamp(arg(0), 0, true);
+            let end = match arg(1) {
+                Value::Undefined => chars.len(),
+                length => (start + length.to_number().max(0.0) as usize).min(chars.len()),
+            };
+            Value::Str(chars[start..end].iter().collect())
+        }
+        "split" => {
+            let parts: Vec<Value> = match arg(0) {
+                Value::Undefined => vec![Value::Str(target.to_string())],
+                Value::Regex(regex) => regex
+                    .regex
+                    .split(target)
+                    .map(|part| Value::Str(part.to_string()))
+                    .collect(),
+                separator => match separator.to_string() {
+                    separator if separator.is_empty() => {
+                        chars.iter().map(|c| Value::Str(c.to_string())).collect()
+                    }
+                    separator => target
+                        .split(&separator)
+                        .map(|part| Value::Str(part.to_string()))
+                        .collect(),
+                },
+            };
+            Value::Array(parts)
+        }
+        "replace" => {
+            let replacement = arg(1).to_string();
+            Value::Str(match arg(0) {
+                Value::Regex(regex) if regex.global => regex
+                    .regex
+                    .replace_all(target, replacement.as_str())
+                    .into_owned(),
+                Value::Regex(regex) => regex
+                    .regex
+                    .replace(target, replacement.as_str())
+                    .into_owned(),
+                pattern => target.replacen(&pattern.to_string(), &replacement, 1),
+            })
+        }
+        "match" => {
+            let regex = match arg(0) {
+                Value::Regex(regex) => regex,
+                pattern => JsRegex::new(&regex::escape(&pattern.to_string()), "")?,
+            };
+            match regex.global {
+                true => {
+                    let matches: Vec<Value> = regex
+                        .regex
+                        .find_iter(target)
+                        .map(|m| Value::Str(m.as_str().to_string()))
+                        .collect();
+                    match matches.is_empty() {
+                        true => Value::Undefined,
+                        false => Value::Array(matches),
+                    }
+                }
+                false => regex_method(&regex, "exec", &[Value::Str(target.to_string())])?,
+            }
+        }
+        "search" => match arg(0) {
+            Value::Regex(regex) => Value::Num(
+                regex
+                    .regex
+                    .find(target)
+                    .map_or(-1.0, |m| char_index(m.start())),
+            ),
+            pattern => Value::Num(target.find(&pattern.to_string()).map_or(-1.0, char_index)),
+        },
+        _ => return Err(format!("unsupported string method {name}")),
+    })
+}
+
+fn array_method(items: &[Value], name: &str, args: &[Value]) -> Result<Value, String> {
+    let arg = |index: usize| args.get(index).cloned().unwrap_or(Value::Undefined);
+    let position = || {
+        let needle = arg(0);
+        items
+            .iter()
+            .position(|item| binary("===", item, &needle).is_truthy())
+    };
+    Ok(match name {
+        "length" => Value::Num(items.len() as f64),
+        "indexOf" => Value::Num(position().map_or(-1.0, |index| index as f64)),
+        "includes" => Value::Bool(position().is_some()),
+        "join" => {
+            let separator = match arg(0) {
+                Value::Undefined => ",".to_string(),
+                separator => separator.to_string(),
+            };
+            let items: Vec<String> = items.iter().map(Value::to_string).collect();
+            Value::Str(items.join(&separator))
+        }
+        "toString" => Value::Str(Value::Array(items.to_vec()).to_string()),
+        _ => return Err(format!("unsupported array method {name}")),
+    })
+}
+
+fn regex_method(regex: &JsRegex, name: &str, args: &[Value]) -> Result<Value, String> {
+    let subject = args.first().map(Value::to_string).unwrap_or_default();
+    Ok(match name {
+        "test" => Value::Bool(regex.regex.is_match(&subject)),
+        "exec" => match regex.regex.captures(&subject) {
+            Some(captures) => Value::Array(
+                captures
+                    .iter()
+                    .map(|group| {
+                        group.map_or(Value::Undefined, |group| {
+                            Value::Str(group.as_str().to_string())
+                        })
+                    })
+                    .collect(),
+            ),
+            None => Value::Undefined,
+        },
+        "source" => Value::Str(regex.regex.as_str().to_string()),
+        "toString" => Value::Str(Value::Regex(regex.clone()).to_string()),
+        _ => return Err(format!("unsupported RegExp method {name}")),
+    })
+}
+
+/// The functions PAC files may call, and the few global JavaScript ones
+/// they use.
+fn builtin(name: &str, args: &[Value], limits: &ResourceLimits) -> Result<Value, String> {
+    let arg = |index: usize| {
+        args.get(index)
+            .map(Value::to_string)
+            .unwrap_or_default()
+            .to_lowercase()
+    };
+    Ok(match name {
+        "isPlainHostName" => Value::Bool(!arg(0).contains('.')),
+        "dnsDomainIs" => Value::Bool(arg(0).ends_with(&arg(1))),
+        "localHostOrDomainIs" => {
+            let (host, domain) = (arg(0), arg(1));
+            Value::Bool(
+                host == domain || (!host.contains('.') && domain.starts_with(&format!("{host}."))),
+            )
+        }
+        "dnsDomainLevels" => Value::Num(arg(0).matches('.').count() as f64),
+        "shExpMatch" => Value::Bool(shell_match(
+            &args.first().map(Value::to_string).unwrap_or_default(),
+            &args.get(1).map(Value::to_string).unwrap_or_default(),
+        )),
+        "isResolvable" => Value::Bool(resolve(&arg(0), limits).is_some()),
+        "dnsResolve" => {
+            resolve(&arg(0), limits).map_or(Value::Undefined, |ip| Value::Str(ip.to_string()))
+        }
+        "myIpAddress" => Value::Str(my_ip_address().to_string()),
+        "isInNet" => {
+            let in_net = match (
+                resolve(&arg(0), limits),
+                arg(1).parse::<Ipv4Addr>(),
+                arg(2).parse::<Ipv4Addr>(),
+            ) {
+                (Some(ip), Ok(pattern), Ok(mask)) => {
+                    let mask = u32::from(mask);
+                    u32::from(ip) & mask == u32::from(pattern) & mask
+                }
+                _ => false,
+            };
+            Value::Bool(in_net)
+        }
+        "convert_addr" => Value::Num(
+            arg(0)
+                .parse::<Ipv4Addr>()
+                .map_or(f64::NAN, |ip| f64::from(u32::from(ip))),
+        ),
+        "weekdayRange" | "dateRange" | "timeRange" => {
+            let now = Local::now();
+            let in_range = match name {
+                "weekdayRange" => weekday_range(args, now.naive_local(), now.naive_utc()),
+                "dateRange" => date_range(args, now.naive_local(), now.naive_utc()),
+                _ => time_range(args, now.naive_local(), now.naive_utc())?,
+            };
+            Value::Bool(in_range)
+        }
+        "RegExp" => Value::Regex(JsRegex::new(&arg_string(args, 0), &arg_string(args, 1))?),
+        "Array" => Value::Array(args.to_vec()),
+        "String" => Value::Str(arg_string(args, 0)),
+        "Number" => Value::Num(args.first().map_or(0.0, Value::to_number)),
+        "parseInt" => {
+            let s = arg(0);
+            let s = s.trim();
+            let end = s
+                .char_indices()
+                .find(|(index, c)| !(c.is_ascii_digit() || (*index == 0 && *c == '-')))
+                .map_or(s.len(), |(index, _)| index);
+            Value::Num(s[..end].parse().unwrap_or(f64::NAN))
+        }
+        "alert" => Value::Undefined,
+        _ => return Err(format!("unsupported function {name}")),
+    })
+}
+
+/// An argument as a string, keeping its case, or empty if missing.
+fn arg_string(args: &[Value], index: usize) -> String {
+    match args.get(index) {
+        Some(Value::Undefined) | None => String::new(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// The arguments of a date or time helper without any trailing `"GMT"`,
+/// with the time they compare against: UTC after `"GMT"`, otherwise local.
+fn time_args(
+    args: &[Value],
+    local: NaiveDateTime,
+    utc: NaiveDateTime,
+) -> (&[Value], NaiveDateTime) {
+    match args.split_last() {
+        Some((Value::Str(last), rest)) if last.eq_ignore_ascii_case("GMT") => (rest, utc),
+        _ => (args, local),
+    }
+}
+
+/// Whether a value is in an inclusive range, which wraps around if `start`
+/// is after `end` (e.g., `FRI` to `MON`).
+fn in_wrapping_range<T: PartialOrd>(value: T, start: T, end: T) -> bool {
+    match start <= end {
+        true => start <= value && value <= end,
+        false => value >= start || value <= end,
+    }
+}
+
+/// `weekdayRange(wd1[, wd2][, "GMT"])`: whether today is `wd1`, or between
+/// `wd1` and `wd2`.
+fn weekday_range(args: &[Value], local: NaiveDateTime, utc: NaiveDateTime) -> bool {
+    let (args, now) = time_args(args, local, utc);
+    let day = |value: &Value| {
+        WEEKDAYS
+            .iter()
+            .position(|day| day.eq_ignore_ascii_case(&value.to_string()))
+    };
+    let today = now.weekday().num_days_from_sunday() as usize;
+    match args {
+        [start] => day(start) == Some(today),
+        [start, end] => match (day(start), day(end)) {
+            (Some(start), Some(end)) => in_wrapping_range(today, start, end),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// `dateRange(...)`: whether today is on a day of the month, in a month or
+/// year, or between two dates given as any of those, e.g.,
+/// `dateRange(1, "JUN", 15, "AUG")`.
+fn date_range(args: &[Value], local: NaiveDateTime, utc: NaiveDateTime) -> bool {
+    let (args, now) = time_args(args, local, utc);
+    // Day of the month (below 32), month name or year
+    enum Part {
+        Day(u32),
+        Month(u32),
+        Year(i32),
+    }
+    let part = |value: &Value| -> Option<Part> {
+        let s = value.to_string();
+        if let Some(month) = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(&s)) {
+            return Some(Part::Month(month as u32 + 1));
+        }
+        let n = value.to_number();
+        match n.fract() == 0.0 && n >= 1.0 {
+            true if n < 32.0 => Some(Part::Day(n as u32)),
+            true => Some(Part::Year(n as i32)),
+            false => None,
+        }
+    };
+    let Some(parts) = args.iter().map(part).collect::<Option<Vec<Part>>>() else {
+        return false;
+    };
+    let today = (now.year(), now.month(), now.day());
+    match parts.as_slice() {
+        [Part::Day(day)] => now.day() == *day,
+        [Part::Month(month)] => now.month() == *month,
+        [Part::Year(year)] => now.year() == *year,
+        parts if matches!(parts.len(), 2 | 4 | 6) => {
+            let (start, end) = parts.split_at(parts.len() / 2);
+            // Fields left out of a bound span the whole year, except with
+            // only days given, which are in the current month
+            let only_days = parts.len() == 2 && matches!(parts[0], Part::Day(_));
+            let bound = |parts: &[Part], is_end: bool| {
+                let mut date = match (only_days, is_end) {
+                    (true, _) => (now.year(), now.month(), 1),
+                    (false, false) => (now.year(), 1, 1),
+                    (false, true) => (now.year(), 12, 31),
+                };
+                for part in parts {
+                    match part {
+                        Part::Day(day) => date.2 = *day,
+                        Part::Month(month) => date.1 = *month,
+                        Part::Year(year) => date.0 = *year,
+                    }
+                }
+                // A month without a day spans the whole month
+                if !only_days && is_end && !parts.iter().any(|p| matches!(p, Part::Day(_))) {
+                    date.2 = 31;
+                }
+                date
+            };
+            in_wrapping_range(today, bound(start, false), bound(end, true))
+        }
+        _ => false,
+    }
+}
+
+/// `timeRange(...)`: whether it's now a given hour, or between two hours,
+/// two times in hours and minutes, or two in hours, minutes and seconds.
+fn time_range(args: &[Value], local: NaiveDateTime, utc: NaiveDateTime) -> Result<bool, String> {
+    let (args, now) = time_args(args, local, utc);
+    let n: Vec<u32> = args.iter().map(|arg| arg.to_number() as u32).collect();
+    let seconds = |h: u32, m: u32, s: u32| h * 3600 + m * 60 + s;
+    let current = seconds(now.hour(), now.minute(), now.second());
+    Ok(match n.as_slice() {
+        [hour] => now.hour() == *hour,
+        [h1, h2] => in_wrapping_range(now.hour(), *h1, *h2),
+        [h1, m1, h2, m2] => in_wrapping_range(current, seconds(*h1, *m1, 0), seconds(*h2, *m2, 59)),
+        [h1, m1, s1, h2, m2, s2] => {
+            in_wrapping_range(current, seconds(*h1, *m1, *s1), seconds(*h2, *m2, *s2))
+        }
+        _ => return Err(format!("timeRange given {} arguments", args.len())),
+    })
+}
+
+/// Whether a string matches a shell expression, where `*` matches any
+/// characters and `?` any one character. After a `*`, matching resumes from
+/// the latest one on a mismatch, so this takes at most `s.len()` times
+/// `pattern.len()` steps.
+fn shell_match(s: &str, pattern: &str) -> bool {
+    let (s, pattern): (Vec<char>, Vec<char>) = (s.chars().collect(), pattern.chars().collect());
+    let (mut i, mut p) = (0, 0);
+    // Where in the pattern the latest `*` is, and where in `s` it stopped
+    let mut star: Option<(usize, usize)> = None;
+    while i < s.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, i));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == s[i] => {
+                i += 1;
+                p += 1;
+            }
+            _ => match star {
+                // Let the `*` match one more character
+                Some((star_p, star_i)) => {
+                    star = Some((star_p, star_i + 1));
+                    p = star_p + 1;
+                    i = star_i + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The first IPv4 address of a host. This blocks while looking it up, and
+/// waiting for one of the lookups `limits` allow, so scripts only run on
+/// threads that may block.
+fn resolve(host: &str, limits: &ResourceLimits) -> Option<Ipv4Addr> {
+    if let Ok(ip) = host.parse::<Ipv4Addr>() {
+        return Some(ip);
+    }
+    limits
+        .blocking_lookup(|| (host, 0).to_socket_addrs())
+        .ok()?
+        .find_map(|address| match address.ip() {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+}
+
+/// The address of the interface used to reach the internet, found without
+/// sending anything by connecting a UDP socket.
+fn my_ip_address() -> Ipv4Addr {
+    UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| {
+            socket.connect("192.0.2.1:80")?;
+            socket.local_addr()
+        })
+        .ok()
+        .and_then(|address| match address.ip() {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+        .unwrap_or(Ipv4Addr::LOCALHOST)
+}
+
+/// How running statements ended
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// Runs a script's functions, with its global variables
+struct Interpreter<'a> {
+    functions: &'a HashMap<String, Function>,
+    globals: &'a mut HashMap<String, Value>,
+    limits: &'a ResourceLimits,
+    /// Statements run so far
+    steps: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(
+        functions: &'a HashMap<String, Function>,
+        globals: &'a mut HashMap<String, Value>,
+        limits: &'a ResourceLimits,
+    ) -> Self {
+        Self {
+            functions,
+            globals,
+            limits,
+            steps: 0,
+        }
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Value>, depth: usize) -> Result<Value, String> {
+        let functions = self.functions;
+        let Some(function) = functions.get(name) else {
+            return builtin(name, &args, self.limits);
+        };
+        if depth >= MAX_CALL_DEPTH {
+            return Err(format!("too much recursion in {name}"));
+        }
+        let mut scope: HashMap<String, Value> = function
+            .locals
+            .iter()
+            .map(|name| (name.clone(), Value::Undefined))
+            .collect();
+        scope.extend(
+            function
+                .params
+                .iter()
+                .cloned()
+                .zip(args.into_iter().chain(std::iter::repeat(Value::Undefined))),
+        );
+        match self.run(&function.body, &mut scope, depth)? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Value::Undefined),
+        }
+    }
+
+    /// Run statements, stopping at any `return`, `break` or `continue`.
+    /// `scope` holds the variables of the function being run, and is empty
+    /// for top-level code, whose variables are all global.
+    fn run(
+        &mut self,
+        stmts: &[Stmt],
+        scope: &mut HashMap<String, Value>,
+        depth: usize,
+    ) -> Result<Flow, String> {
+        for stmt in stmts {
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return Err("the script ran for too long".to_string());
+            }
+            let flow = match stmt {
+                Stmt::Assign(name, op, expr) => {
+                    let value = self.eval(expr, scope, depth)?;
+                    let value = match op {
+                        Some(op) => binary(op, &self.lookup(name, scope)?, &value),
+                        None => value,
+                    };
+                    self.assign(name, value, scope);
+                    Flow::Normal
+                }
+                Stmt::Expr(expr) => {
+                    self.eval(expr, scope, depth)?;
+                    Flow::Normal
+                }
+                Stmt::If(condition, then, otherwise) => {
+                    match self.eval(condition, scope, depth)?.is_truthy() {
+                        true => self.run(then, scope, depth)?,
+                        false => self.run(otherwise, scope, depth)?,
+                    }
+                }
+                Stmt::Loop {
+                    init,
+                    condition,
+                    update,
+                    body,
+                    check_first,
+                } => {
+                    self.run(init, scope, depth)?;
+                    let mut first = true;
+                    loop {
+                        let skip_check = first && !check_first;
+                        first = false;
+                        if !skip_check {
+                            if let Some(condition) = condition {
+                                if !self.eval(condition, scope, depth)?.is_truthy() {
+                                    break Flow::Normal;
+                                }
+                            }
+                        }
+                        self.steps += 1;
+                        if self.steps > MAX_STEPS {
+                            return Err("the script ran for too long".to_string());
+                        }
+                        match self.run(body, scope, depth)? {
+                            Flow::Break => break Flow::Normal,
+                            Flow::Return(value) => break Flow::Return(value),
+                            Flow::Normal | Flow::Continue => {}
+                        }
+                        self.run(update, scope, depth)?;
+                    }
+                }
+                Stmt::ForIn(name, target, body) => {
+                    let keys = match self.eval(target, scope, depth)? {
+                        Value::Array(items) => items.len(),
+                        Value::Str(s) => s.chars().count(),
+                        _ => 0,
+                    };
+                    let mut flow = Flow::Normal;
+                    for key in 0..keys {
+                        self.assign(name, Value::Str(key.to_string()), scope);
+                        match self.run(body, scope, depth)? {
+                            Flow::Break => break,
+                            Flow::Return(value) => {
+                                flow = Flow::Return(value);
+                                break;
+                            }
+                            Flow::Normal | Flow::Continue => {}
+                        }
+                    }
+                    flow
+                }
+                Stmt::Switch(target, cases) => {
+                    let target = self.eval(target, scope, depth)?;
+                    let mut matched = None;
+                    for (index, (case, _)) in cases.iter().enumerate() {
+                        if let Some(case) = case {
+                            if binary("===", &target, &self.eval(case, scope, depth)?).is_truthy() {
+                                matched = Some(index);
+                                break;
+                            }
+                        }
+                    }
+                    let matched =
+                        matched.or_else(|| cases.iter().position(|(case, _)| case.is_none()));
+                    let mut flow = Flow::Normal;
+                    // Cases fall through to the next until a `break`
+                    for (_, body) in cases.iter().skip(matched.unwrap_or(cases.len())) {
+                        match self.run(body, scope, depth)? {
+                            Flow::Normal => {}
+                            Flow::Break => break,
+                            other => {
+                                flow = other;
+                                break;
+                            }
+                        }
+                    }
+                    flow
+                }
+                Stmt::Break => Flow::Break,
+                Stmt::Continue => Flow::Continue,
+                Stmt::Return(expr) => Flow::Return(self.eval(expr, scope, depth)?),
+            };
+            if !matches!(flow, Flow::Normal) {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// The value of a variable, failing like JavaScript if it was never
+    /// declared or assigned.
+    fn lookup(&self, name: &str, scope: &HashMap<String, Value>) -> Result<Value, String> {
+        scope
+            .get(name)
+            .or_else(|| self.globals.get(name))
+            .cloned()
+            .ok_or_else(|| format!("{name} is not defined"))
+    }
+
+    /// Set the function's own variable, or else a global one.
+    fn assign(&mut self, name: &str, value: Value, scope: &mut HashMap<String, Value>) {
+        match scope.get_mut(name) {
+            Some(local) => *local = value,
+            None => {
+                self.globals.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    fn eval(
+        &mut self,
+        expr: &Expr,
+        scope: &mut HashMap<String, Value>,
+        depth: usize,
+    ) -> Result<Value, String> {
+        Ok(match expr {
+            Expr::Literal(value) => value.clone(),
+            Expr::Var(name) => self.lookup(name, scope)?,
+            Expr::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.eval(item, scope, depth))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::Index(target, index) => {
+                let target = self.eval(target, scope, depth)?;
+                let index = self.eval(index, scope, depth)?;
+                match (&target, index.to_number()) {
+                    (Value::Array(items), i) if i >= 0.0 => {
+                        items.get(i as usize).cloned().unwrap_or(Value::Undefined)
+                    }
+                    (Value::Str(s), i) if i >= 0.0 => s
+                        .chars()
+                        .nth(i as usize)
+                        .map_or(Value::Undefined, |c| Value::Str(c.to_string())),
+                    (Value::Undefined, _) => {
+                        return Err(format!("can't read {index} of undefined"));
+                    }
+                    _ => method(&target, &index.to_string(), &[]).unwrap_or(Value::Undefined),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg, scope, depth))
+                    .collect::<Result<_, _>>()?;
+                self.call(name, args, depth + 1)?
+            }
+            Expr::Method(target, name, args) => {
+                let args: Vec<Value> = args
+                    .iter()
+                    .map(|arg| self.eval(arg, scope, depth))
+                    .collect::<Result<_, _>>()?;
+                // Arrays are values here, so pushing updates the variable
+                if let (Expr::Var(var), "push") = (target.as_ref(), name.as_str()) {
+                    let Value::Array(mut items) = self.lookup(var, scope)? else {
+                        return Err(format!("{var} isn't an array"));
+                    };
+                    items.extend(args);
+                    let length = items.len();
+                    self.assign(var, Value::Array(items), scope);
+                    return Ok(Value::Num(length as f64));
+                }
+                let target = self.eval(target, scope, depth)?;
+                method(&target, name, &args)?
+            }
+            Expr::Update(name, delta, prefix) => {
+                let old = self.lookup(name, scope)?.to_number();
+                self.assign(name, Value::Num(old + delta), scope);
+                Value::Num(if *prefix { old + delta } else { old })
+            }
+            Expr::TypeOf(operand) => match operand.as_ref() {
+                Expr::Var(name) if self.functions.contains_key(name) => {
+                    Value::Str("function".into())
+                }
+                // Undeclared variables are `undefined` here rather than
+                // failing
+                Expr::Var(name)
+                    if !scope.contains_key(name) && !self.globals.contains_key(name) =>
+                {
+                    Value::Str("undefined".into())
+                }
+                operand => Value::Str(self.eval(operand, scope, depth)?.type_of().to_string()),
+            },
+            Expr::Not(operand) => Value::Bool(!self.eval(operand, scope, depth)?.is_truthy()),
+            Expr::Neg(operand) => Value::Num(-self.eval(operand, scope, depth)?.to_number()),
+            Expr::And(left, right) => {
+                let left = self.eval(left, scope, depth)?;
+                match left.is_truthy() {
+                    true => self.eval(right, scope, depth)?,
+                    false => left,
+                }
+            }
+            Expr::Or(left, right) => {
+                let left = self.eval(left, scope, depth)?;
+                match left.is_truthy() {
+                    true => left,
+                    false => self.eval(right, scope, depth)?,
+                }
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                match self.eval(condition, scope, depth)?.is_truthy() {
+                    true => self.eval(then, scope, depth)?,
+                    false => self.eval(otherwise, scope, depth)?,
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left, scope, depth)?;
+                let right = self.eval(right, scope, depth)?;
+                binary(op, &left, &right)
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Stmt {
+    /// `var name = value;`, `name = value;` or, with an operator,
+    /// `name += value;`. `var name;` only declares the variable, so gives
+    /// no statement.
+    Assign(String, Option<&'static str>, Expr),
+    Expr(Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    /// A `for`, `while` or `do` loop, the last not checking its condition
+    /// before the first run
+    Loop {
+        init: Vec<Stmt>,
+        condition: Option<Expr>,
+        update: Vec<Stmt>,
+        body: Vec<Stmt>,
+        check_first: bool,
+    },
+    /// `for (name in value)`, over the indexes of an array or string
+    ForIn(String, Expr, Vec<Stmt>),
+    /// The cases' values, `None` for `default`, and their statements
+    Switch(Expr, Vec<(Option<Expr>, Vec<Stmt>)>),
+    Break,
+    Continue,
+    Return(Expr),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    Var(String),
+    Array(Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// A method call, or a property like `length`
+    Method(Box<Expr>, String, Vec<Expr>),
+    /// `++name`, `name++`, `--name` or `name--`: the variable, the change and
+    /// whether the new value is the result
+    Update(String, f64, bool),
+    TypeOf(Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    /// A regular expression literal's pattern and flags
+    Regex(String, String),
+    Punct(&'static str),
+}
+
+/// Punctuation, longest first so that `===` isn't read as `==` and `=`
+const PUNCTUATION: &[&str] = &[
+    "===", "!==", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "+=", "-=", "(", ")", "{", "}",
+    "[", "]", ",", ";", ".", "!", "=", "+", "-", "*", "/", "%", "<", ">", "?", ":",
+];
+
+/// Keywords after which a `/` starts a regular expression rather than
+/// dividing
+const KEYWORDS_BEFORE_EXPRESSION: &[&str] = &[
+    "return", "typeof", "case", "in", "else", "new", "do", "void", "delete",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        // Whether a value was just read, so a `/` divides it
+        let after_value = match tokens.last() {
+            Some(Token::Ident(ident)) => !KEYWORDS_BEFORE_EXPRESSION.contains(&ident.as_str()),
+            Some(Token::Num(_) | Token::Str(_) | Token::Regex(..)) => true,
+            Some(Token::Punct(punct)) => matches!(*punct, ")" | "]"),
+            None => false,
+        };
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").ok_or("unterminated comment")?.1;
+        } else if c == '/' && !after_value {
+            let (pattern, flags, end) = regex_literal(rest)?;
+            tokens.push(Token::Regex(pattern, flags));
+            rest = &rest[end..];
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((index, next)) if next == c => break index + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, 'r')) => value.push('\r'),
+                        Some((_, 'b')) => value.push('\u{8}'),
+                        Some((_, 'f')) => value.push('\u{c}'),
+                        Some((_, 'v')) => value.push('\u{b}'),
+                        Some((_, '0')) => value.push('\0'),
+                        Some((_, kind @ ('x' | 'u'))) => {
+                            let digits: String = chars
+                                .by_ref()
+                                .take(if kind == 'x' { 2 } else { 4 })
+                                .map(|(_, c)| c)
+                                .collect();
+                            let escaped = u32::from_str_radix(&digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| format!("unsupported escape \\{kind}{digits}"))?;
+                            value.push(escaped);
+                        }
+                        Some((_, escaped)) => value.push(escaped),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some((_, next)) => value.push(next),
+                    None => return Err("unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::Str(value));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len());
+            // Hex, octal and exponent notation aren't supported, rather
+            // than read as a number followed by a name
+            if rest[end..].starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+                return Err(format!("unsupported number {}", &rest[..=end]));
+            }
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| format!("invalid number {}", &rest[..end]))?;
+            tokens.push(Token::Num(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            let punct = PUNCTUATION
+                .iter()
+                .find(|punct| rest.starts_with(**punct))
+                .ok_or_else(|| format!("unexpected character `{c}`"))?;
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        }
+    }
+    Ok(tokens)
+}
+
+/// Read a regular expression literal at the start of `s`, returning its
+/// pattern, its flags and where it ends. A `/` only ends it outside of a
+/// character class.
+fn regex_literal(s: &str) -> Result<(String, String, usize), String> {
+    let mut in_class = false;
+    let mut escaped = false;
+    for (index, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '\n' => break,
+            '/' if !in_class => {
+                let flags_end = s[index + 1..]
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .map_or(s.len(), |end| index + 1 + end);
+                return Ok((
+                    s[1..index].to_string(),
+                    s[index + 1..flags_end].to_string(),
+                    flags_end,
+                ));
+            }
+            _ => {}
+        }
+    }
+    Err("unterminated regular expression".to_string())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Variables declared with `var` in the function being parsed, or at
+    /// the top level outside functions
+    declared: Vec<String>,
+    /// Names of every function called, to check they all exist
+    calls: Vec<String>,
+    /// Names of every method called or property read, to check they're all
+    /// supported
+    methods: Vec<String>,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            declared: Vec::new(),
+            calls: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .context("Unexpected end of file")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<()> {
+        match self.next()? {
+            token if token == *expected => Ok(()),
+            token => anyhow::bail!("Expected {expected:?}, found {token:?}"),
+        }
+    }
+
+    fn expect_punct(&mut self, punct: &'static str) -> Result<()> {
+        self.expect(&Token::Punct(punct))
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Punct(p)) if *p == punct);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Ident(ident) if RESERVED.contains(&ident.as_str()) => {
+                anyhow::bail!("Unsupported `{ident}`")
+            }
+            Token::Ident(ident) => Ok(ident),
+            token => anyhow::bail!("Expected a name, found {token:?}"),
+        }
+    }
+
+    fn declare(&mut self, name: &str) {
+        if !self.declared.iter().any(|declared| declared == name) {
+            self.declared.push(name.to_string());
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        self.expect_punct("{")?;
+        let mut stmts = Vec::new();
+        while !self.eat_punct("}") {
+            stmts.extend(self.statement()?);
+        }
+        Ok(stmts)
+    }
+
+    /// A statement, or a block when braced. Empty statements give nothing.
+    fn statement(&mut self) -> Result<Vec<Stmt>> {
+        if matches!(self.peek(), Some(Token::Punct("{"))) {
+            return self.block();
+        }
+        if self.eat_punct(";") {
+            return Ok(Vec::new());
+        }
+        if self.eat_keyword("if") {
+            let condition = self.condition()?;
+            let then = self.statement()?;
+            let otherwise = match self.eat_keyword("else") {
+                true => self.statement()?,
+                false => Vec::new(),
+            };
+            return Ok(vec![Stmt::If(condition, then, otherwise)]);
+        }
+        if self.eat_keyword("while") {
+            let condition = self.condition()?;
+            return Ok(vec![Stmt::Loop {
+                init: Vec::new(),
+                condition: Some(condition),
+                update: Vec::new(),
+                body: self.statement()?,
+                check_first: true,
+            }]);
+        }
+        if self.eat_keyword("do") {
+            let body = self.statement()?;
+            if !self.eat_keyword("while") {
+                anyhow::bail!("Expected `while` after a `do` loop");
+            }
+            let condition = self.condition()?;
+            self.eat_punct(";");
+            return Ok(vec![Stmt::Loop {
+                init: Vec::new(),
+                condition: Some(condition),
+                update: Vec::new(),
+                body,
+                check_first: false,
+            }]);
+        }
+        if self.eat_keyword("for") {
+            return self.for_loop();
+        }
+        if self.eat_keyword("switch") {
+            return self.switch();
+        }
+        if self.eat_keyword("function") {
+            anyhow::bail!("Functions can only be declared at the top level");
+        }
+        let stmts = if self.eat_keyword("break") {
+            vec![Stmt::Break]
+        } else if self.eat_keyword("continue") {
+            vec![Stmt::Continue]
+        } else if self.eat_keyword("return") {
+            let value = match matches!(self.peek(), Some(Token::Punct(";" | "}")) | None) {
+                true => Expr::Literal(Value::Undefined),
+                false => self.expression()?,
+            };
+            vec![Stmt::Return(value)]
+        } else {
+            self.simple_statement()?
+        };
+        self.eat_punct(";");
+        Ok(stmts)
+    }
+
+    /// A declaration, assignment or expression, without its `;`, as in the
+    /// parts of a `for` loop.
+    fn simple_statement(&mut self) -> Result<Vec<Stmt>> {
+        if self.eat_keyword("var") || self.eat_keyword("let") || self.eat_keyword("const") {
+            let mut stmts = Vec::new();
+            loop {
+                let name = self.ident()?;
+                self.declare(&name);
+                if self.eat_punct("=") {
+                    stmts.push(Stmt::Assign(name, None, self.expression()?));
+                }
+                if !self.eat_punct(",") {
+                    return Ok(stmts);
+                }
+            }
+        }
+        let expr = self.expression()?;
+        let op = match () {
+            _ if self.eat_punct("=") => None,
+            _ if self.eat_punct("+=") => Some("+"),
+            _ if self.eat_punct("-=") => Some("-"),
+            _ => return Ok(vec![Stmt::Expr(expr)]),
+        };
+        match expr {
+            Expr::Var(name) => Ok(vec![Stmt::Assign(name, op, self.expression()?)]),
+            _ => anyhow::bail!("Can only assign to a variable"),
+        }
+    }
+
+    /// A parenthesized condition, as of `if` and `while`.
+    fn condition(&mut self) -> Result<Expr> {
+        self.expect_punct("(")?;
+        let condition = self.expression()?;
+        self.expect_punct(")")?;
+        Ok(condition)
+    }
+
+    fn for_loop(&mut self) -> Result<Vec<Stmt>> {
+        self.expect_punct("(")?;
+        // `for (var name in value)`
+        let start = self.position;
+        let declares =
+            self.eat_keyword("var") || self.eat_keyword("let") || self.eat_keyword("const");
+        if let Some(Token::Ident(name)) = self.peek().cloned() {
+            self.position += 1;
+            if self.eat_keyword("in") {
+                if declares {
+                    self.declare(&name);
+                }
+                let target = self.expression()?;
+                self.expect_punct(")")?;
+                return Ok(vec![Stmt::ForIn(name, target, self.statement()?)]);
+            }
+        }
+        self.position = start;
+
+        let init = match self.eat_punct(";") {
+            true => Vec::new(),
+            false => {
+                let init = self.simple_statement()?;
+                self.expect_punct(";")?;
+                init
+            }
+        };
+        let condition = match self.eat_punct(";") {
+            true => None,
+            false => {
+                let condition = self.expression()?;
+                self.expect_punct(";")?;
+                Some(condition)
+            }
+        };
+        let mut update = Vec::new();
+        while !self.eat_punct(")") {
+            update.extend(self.simple_statement()?);
+            self.eat_punct(",");
+        }
+        Ok(vec![Stmt::Loop {
+            init,
+            condition,
+            update,
+            body: self.statement()?,
+            check_first: true,
+        }])
+    }
+
+    fn switch(&mut self) -> Result<Vec<Stmt>> {
+        let target = self.condition()?;
+        self.expect_punct("{")?;
+        let mut cases: Vec<(Option<Expr>, Vec<Stmt>)> = Vec::new();
+        while !self.eat_punct("}") {
+            let case = if self.eat_keyword("case") {
+                Some(self.expression()?)
+            } else if self.eat_keyword("default") {
+                None
+            } else {
+                anyhow::bail!("Expected `case` or `default` in a switch");
+            };
+            self.expect_punct(":")?;
+            let mut body = Vec::new();
+            while !matches!(self.peek(), Some(Token::Punct("}")) | None)
+                && !matches!(self.peek(), Some(Token::Ident(k)) if k == "case" || k == "default")
+            {
+                body.extend(self.statement()?);
+            }
+            cases.push((case, body));
+        }
+        Ok(vec![Stmt::Switch(target, cases)])
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        let condition = self.or()?;
+        if !self.eat_punct("?") {
+            return Ok(condition);
+        }
+        let then = self.expression()?;
+        self.expect_punct(":")?;
+        let otherwise = self.expression()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.eat_punct("||") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.binary(0)?;
+        while self.eat_punct("&&") {
+            left = Expr::And(Box::new(left), Box::new(self.binary(0)?));
+        }
+        Ok(left)
+    }
+
+    /// Binary operators by increasing precedence: equality, comparison,
+    /// addition, then multiplication.
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: &[&[&str]] = &[
+            &["===", "!==", "==", "!="],
+            &["<=", ">=", "<", ">"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        let Some(operators) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = operators.iter().find(|op| self.eat_punct(op)) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.binary(level + 1)?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_punct("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat_punct("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat_keyword("typeof") {
+            return Ok(Expr::TypeOf(Box::new(self.unary()?)));
+        }
+        for (op, delta) in [("++", 1.0), ("--", -1.0)] {
+            if self.eat_punct(op) {
+                return Ok(Expr::Update(self.ident()?, delta, true));
+            }
+        }
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat_punct(".") {
+                let name = self.ident()?;
+                let args = match matches!(self.peek(), Some(Token::Punct("("))) {
+                    true => self.arguments()?,
+                    false => Vec::new(),
+                };
+                self.methods.push(name.clone());
+                expr = Expr::Method(Box::new(expr), name, args);
+            } else if self.eat_punct("[") {
+                let index = self.expression()?;
+                self.expect_punct("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                break;
+            }
+        }
+        if let Expr::Var(name) = &expr {
+            for (op, delta) in [("++", 1.0), ("--", -1.0)] {
+                if self.eat_punct(op) {
+                    return Ok(Expr::Update(name.clone(), delta, false));
+                }
+            }
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        Ok(match self.next()? {
+            Token::Str(s) => Expr::Literal(Value::Str(s)),
+            Token::Num(n) => Expr::Literal(Value::Num(n)),
+            Token::Regex(pattern, flags) => Expr::Literal(Value::Regex(
+                JsRegex::new(&pattern, &flags).map_err(anyhow::Error::msg)?,
+            )),
+            Token::Punct("(") => {
+                let expr = self.expression()?;
+                self.expect_punct(")")?;
+                expr
+            }
+            Token::Punct("[") => {
+                let mut items = Vec::new();
+                while !self.eat_punct("]") {
+                    items.push(self.expression()?);
+                    if !self.eat_punct(",") {
+                        self.expect_punct("]")?;
+                        break;
+                    }
+                }
+                Expr::Array(items)
+            }
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" | "undefined" => Expr::Literal(Value::Undefined),
+                // Only constructors of builtins are supported, so `new` is
+                // just a call
+                "new" => {
+                    let name = self.ident()?;
+                    let args = match matches!(self.peek(), Some(Token::Punct("("))) {
+                        true => self.arguments()?,
+                        false => Vec::new(),
+                    };
+                    self.calls.push(name.clone());
+                    Expr::Call(name, args)
+                }
+                _ if RESERVED.contains(&ident.as_str()) => {
+                    anyhow::bail!("Unsupported `{ident}`")
+                }
+                _ if matches!(self.peek(), Some(Token::Punct("("))) => {
+                    self.calls.push(ident.clone());
+                    Expr::Call(ident, self.arguments()?)
+                }
+                _ => Expr::Var(ident),
+            },
+            token => anyhow::bail!("Unexpected {token:?}"),
+        })
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expr>> {
+        self.expect_punct("(")?;
+        let mut args = Vec::new();
+        while !self.eat_punct(")") {
+            args.push(self.expression()?);
+            if !self.eat_punct(",") {
+                self.expect_punct(")")?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn parse(source: &str) -> Result<PacScript> {
+        PacScript::parse(
+            source,
+            Arc::new(ResourceLimits::new(
+                Some(1),
+                Some(1),
+                &Url::parse("http://localhost/").unwrap(),
+            )),
+        )
+    }
+
+    fn proxy(script: &PacScript, url: &str) -> Result<Option<String>, String> {
+        script
+            .evaluate(&Url::parse(url).unwrap())
+            .map(|proxy| proxy.map(|proxy| proxy.to_string()))
+    }
+
+    /// A Wednesday
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(hour, minute, 30)
+            .unwrap()
+    }
+
+    #[test]
+    fn typical_corporate_pac_file() {
+        let script = parse(
+            r#"
+            // Hosts reached without the proxy
+            var direct = ["intranet.example.com", ".corp.example.com"];
+            var proxy = "PROXY proxy.example.com:3128; DIRECT";
+
+            function isDirect(host) {
+                for (var i = 0; i < direct.length; i++) {
+                    if (dnsDomainIs(host, direct[i])) return true;
+                }
+                return false;
+            }
+
+            function FindProxyForURL(url, host) {
+                host = host.toLowerCase();
+                if (isPlainHostName(host) || isDirect(host)) {
+                    return "DIRECT";
+                }
+                if (isInNet(host, "10.0.0.0", "255.0.0.0")) return "DIRECT";
+                if (/^ftp:/.test(url)) return "SOCKS socks.example.com:1080";
+                if (shExpMatch(host, "*.cdn.example.net")) {
+                    return "HTTPS secure.example.com:443";
+                }
+                return proxy;
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(proxy(&script, "http://localhost/"), Ok(None));
+        assert_eq!(proxy(&script, "https://wiki.corp.example.com/a"), Ok(None));
+        assert_eq!(proxy(&script, "https://10.1.2.3/"), Ok(None));
+        assert_eq!(
+            proxy(&script, "ftp://files.example.org/"),
+            Ok(Some("socks5://socks.example.com:1080".to_string()))
+        );
+        assert_eq!(
+            proxy(&script, "https://static.cdn.example.net/"),
+            Ok(Some("https://secure.example.com/".to_string()))
+        );
+        assert_eq!(
+            proxy(&script, "https://docs.rs/"),
+            Ok(Some("http://proxy.example.com:3128/".to_string()))
+        );
+    }
+
+    #[test]
+    fn switch_while_and_string_methods() {
+        let script = parse(
+            r#"
+            function tld(host) {
+                var parts = host.split(".");
+                return parts[parts.length - 1];
+            }
+            function FindProxyForURL(url, host) {
+                var i = 0, count = 0;
+                while (i < host.length) {
+                    if (host.charAt(i) == ".") count += 1;
+                    i++;
+                }
+                if (count > 3) return "PROXY deep.example.com:80";
+                switch (tld(host)) {
+                    case "de":
+                    case "fr":
+                        return "PROXY eu.example.com:80";
+                    case "jp":
+                        return "PROXY " + "apac.example.com:" + (8000 + 80);
+                    default:
+                        break;
+                }
+                var m = host.match(/^([a-z]+)\.internal\./);
+                if (m && m[1] != "www") return "PROXY " + m[1] + ".example.com:80";
+                return "DIRECT";
+            }
+            "#,
+        )
+        .unwrap();
+
+        let proxy = |url| proxy(&script, url).unwrap();
+        assert_eq!(
+            proxy("http://a.b.c.d.e/"),
+            Some("http://deep.example.com/".into())
+        );
+        assert_eq!(
+            proxy("http://shop.de/"),
+            Some("http://eu.example.com/".into())
+        );
+        assert_eq!(
+            proxy("http://shop.fr/"),
+            Some("http://eu.example.com/".into())
+        );
+        assert_eq!(
+            proxy("http://shop.jp/"),
+            Some("http://apac.example.com:8080/".into())
+        );
+        assert_eq!(
+            proxy("http://git.internal.org/"),
+            Some("http://git.example.com/".into())
+        );
+        assert_eq!(proxy("http://www.internal.org/"), None);
+        assert_eq!(proxy("http://example.com/"), None);
+    }
+
+    #[test]
+    fn failures_are_errors_rather_than_direct() {
+        let script = parse(
+            r#"
+            function FindProxyForURL(url, host) {
+                if (host == "broken.example.com") return undeclaredVariable;
+                if (host == "loop.example.com") { while (true) {} }
+                if (host == "odd.example.com") return "TELNET odd:23";
+                return "DIRECT";
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert!(proxy(&script, "http://broken.example.com/").is_err());
+        assert!(proxy(&script, "http://loop.example.com/").is_err());
+        assert!(proxy(&script, "http://odd.example.com/").is_err());
+        assert_eq!(proxy(&script, "http://fine.example.com/"), Ok(None));
+
+        // Requests the script fails for go to a proxy that can't be reached
+        let broken = Url::parse("http://broken.example.com/").unwrap();
+        assert_eq!(
+            script.proxy_for(&broken).map(String::from),
+            Some(FAILED_PROXY.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_scripts() {
+        assert!(parse("function Other() { return 'DIRECT'; }").is_err());
+        assert!(parse("function FindProxyForURL(url, host) { return (; }").is_err());
+        assert!(parse(
+            "var x = missing(); function FindProxyForURL(url, host) { return 'DIRECT'; }"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn variables_are_scoped_like_javascript() {
+        let script = parse(
+            r#"
+            var calls = 0;
+            var last = "";
+            function remember(host) {
+                calls++;
+                last = host;
+                created = "by a function";
+                var own = "kept in the function";
+            }
+            function shadow() {
+                // Declared later in the function, but its own from the start
+                calls = 100;
+                var calls;
+                return calls;
+            }
+            function lastFound() {
+                for (var i = 0; i < 3; i++) {
+                    var found;
+                    if (i == 1) found = i;
+                }
+                return found;
+            }
+            function FindProxyForURL(url, host) {
+                remember(host);
+                if (last != host || calls < 1) return "PROXY last:1";
+                if (created != "by a function") return "PROXY created:1";
+                if (typeof own != "undefined") return "PROXY own:1";
+                if (shadow() != 100 || calls == 100) return "PROXY shadow:1";
+                if (lastFound() !== 1) return "PROXY found:1";
+                return "DIRECT";
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(proxy(&script, "http://example.com/"), Ok(None));
+        assert_eq!(proxy(&script, "http://example.org/"), Ok(None));
+    }
+
+    #[test]
+    fn rejects_unsupported_syntax_when_loaded() {
+        for body in [
+            "try { return 'DIRECT'; } catch (e) { return 'DIRECT'; }",
+            "throw 'no proxy';",
+            "var n = 0x10; return 'DIRECT';",
+            "var n = 1e3; return 'DIRECT';",
+            "var o = {direct: true}; return 'DIRECT';",
+            "var f = function () {}; return 'DIRECT';",
+            "return this.proxy;",
+            "if ('a' in host) return 'DIRECT';",
+            "return (1 & 2) ? 'DIRECT' : 'PROXY p:80';",
+            "if (new Date().getHours() > 9) return 'DIRECT';",
+            "return host.padStart(3);",
+            "return unknownHelper(host);",
+        ] {
+            let source = format!("function FindProxyForURL(url, host) {{ {body} }}");
+            assert!(parse(&source).is_err(), "{body}");
+        }
+    }
+
+    #[test]
+    fn string_escapes() {
+        let script = parse(
+            r#"
+            function FindProxyForURL(url, host) {
+                return "\x44IRECT" == "\u0044IRECT" && "a\tb".length == 3 ? "DIRECT" : "PROXY p:80";
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(proxy(&script, "http://example.com/"), Ok(None));
+    }
+
+    #[test]
+    fn shell_expressions() {
+        assert!(shell_match("www.example.com", "*.example.com"));
+        assert!(shell_match("example.com", "*example.com"));
+        assert!(!shell_match("example.com", "*.example.com"));
+        assert!(shell_match("a.b.c", "a.?.c"));
+        assert!(!shell_match("a.bb.c", "a.?.c"));
+        assert!(shell_match("http://x/a/b/c", "*/a/*/c"));
+        assert!(shell_match("aaab", "*a*b"));
+        assert!(!shell_match("aaac", "*a*b"));
+        assert!(shell_match("", "*"));
+        assert!(!shell_match("x", ""));
+        // Regex syntax has no meaning
+        assert!(!shell_match("exampleXcom", "example.com"));
+        assert!(shell_match("a+b", "a+b"));
+    }
+
+    #[test]
+    fn divides_rather_than_reading_a_regex() {
+        let script = parse(
+            r#"
+            var half = 10 / 2;
+            function FindProxyForURL(url, host) {
+                var n = (half * 4) / 5 % 3;
+                return n === 1 ? "DIRECT" : "PROXY wrong:80";
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(proxy(&script, "http://example.com/"), Ok(None));
+    }
+
+    #[test]
+    fn weekday_ranges() {
+        let wednesday = at(12, 0);
+        let check = |args: &[&str]| {
+            let args: Vec<Value> = args.iter().map(|a| Value::Str(a.to_string())).collect();
+            weekday_range(&args, wednesday, wednesday)
+        };
+        assert!(check(&["WED"]));
+        assert!(!check(&["THU"]));
+        assert!(check(&["MON", "FRI"]));
+        assert!(!check(&["THU", "SAT"]));
+        // Wrapping around the weekend
+        assert!(check(&["FRI", "WED"]));
+        assert!(!check(&["FRI", "TUE"]));
+        assert!(check(&["WED", "GMT"]));
+    }
+
+    #[test]
+    fn date_ranges() {
+        let today = at(12, 0);
+        let check = |args: &[Value]| date_range(args, today, today);
+        let s = |s: &str| Value::Str(s.to_string());
+        let n = |n: f64| Value::Num(n);
+        assert!(check(&[n(14.0)]));
+        assert!(!check(&[n(15.0)]));
+        assert!(check(&[s("OCT")]));
+        assert!(check(&[n(2026.0)]));
+        assert!(check(&[n(1.0), n(15.0)]));
+        assert!(!check(&[n(15.0), n(20.0)]));
+        assert!(check(&[s("SEP"), s("NOV")]));
+        assert!(!check(&[s("JAN"), s("MAR")]));
+        // Wrapping around the new year
+        assert!(check(&[s("OCT"), s("FEB")]));
+        assert!(check(&[n(1.0), s("OCT"), n(31.0), s("OCT")]));
+        assert!(!check(&[n(15.0), s("OCT"), n(31.0), s("DEC")]));
+        assert!(check(&[
+            n(1.0),
+            s("JAN"),
+            n(2026.0),
+            n(31.0),
+            s("DEC"),
+            n(2026.0),
+            s("GMT")
+        ]));
+        assert!(!check(&[s("OCT"), n(2025.0), s("DEC"), n(2025.0)]));
+    }
+
+    #[test]
+    fn time_ranges() {
+        let noon = at(12, 15);
+        let check = |args: &[f64]| {
+            let args: Vec<Value> = args.iter().map(|n| Value::Num(*n)).collect();
+            time_range(&args, noon, noon).unwrap()
+        };
+        assert!(check(&[12.0]));
+        assert!(!check(&[13.0]));
+        assert!(check(&[9.0, 17.0]));
+        assert!(!check(&[13.0, 17.0]));
+        // Overnight
+        assert!(check(&[22.0, 12.0]));
+        assert!(check(&[12.0, 0.0, 12.0, 15.0]));
+        assert!(!check(&[12.0, 16.0, 13.0, 0.0]));
+        assert!(check(&[12.0, 15.0, 30.0, 12.0, 15.0, 30.0]));
+        assert!(time_range(&[], noon, noon).is_err());
+    }
+
+    #[test]
+    fn parses_results() {
+        assert_eq!(parse_result("DIRECT"), Ok(None));
+        assert_eq!(
+            parse_result("PROXY a.example.com:8080; DIRECT"),
+            Ok(Some(Url::parse("http://a.example.com:8080").unwrap()))
+        );
+        assert_eq!(
+            parse_result("SOCKS5 s.example.com:1080"),
+            Ok(Some(Url::parse("socks5://s.example.com:1080").unwrap()))
+        );
+        assert!(parse_result("PROXY").is_err());
+    }
+}
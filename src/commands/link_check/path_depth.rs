@@ -0,0 +1,43 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use url::Url;
+
+/// How many path segments deep links matching a pattern may go, as
+/// configured under `[[path-depth]]`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct PathDepthConfig {
+    /// Regex of URLs the limit applies to (e.g., `/api/reference/` for a
+    /// deeply nested section)
+    url: String,
+    /// Number of path segments the links may have
+    max: usize,
+}
+
+/// A path depth limit for links matching a pattern, overriding
+/// `--max-path-depth`
+#[derive(Debug)]
+pub(super) struct PathDepthLimit {
+    pattern: Regex,
+    max: usize,
+}
+
+impl PathDepthLimit {
+    pub(super) fn new(config: &PathDepthConfig) -> Result<Self, String> {
+        Ok(Self {
+            pattern: Regex::new(&config.url).map_err(|e| e.to_string())?,
+            max: config.max,
+        })
+    }
+
+    /// The limit for a URL, if it matches.
+    pub(super) fn max_for(&self, url: &Url) -> Option<usize> {
+        self.pattern.is_match(url.as_str()).then_some(self.max)
+    }
+}
+
+/// Number of non-empty segments in a URL's path.
+pub(super) fn path_depth(url: &Url) -> usize {
+    url.path().split('/').filter(|s| !s.is_empty()).count()
+}
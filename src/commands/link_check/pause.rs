@@ -0,0 +1,59 @@
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Lets a running crawl be paused (e.g., while the docs host is being
+/// deployed) without losing its state. Requests already sent finish, but no
+/// new ones are sent until the crawl is resumed.
+#[derive(Debug)]
+pub(super) struct PauseControl {
+    paused: watch::Sender<bool>,
+}
+
+impl Default for PauseControl {
+    fn default() -> Self {
+        Self {
+            paused: watch::Sender::new(false),
+        }
+    }
+}
+
+impl PauseControl {
+    pub(super) fn pause(&self) {
+        if !self.paused.send_replace(true) {
+            warn!("Crawl paused, no new requests will be sent until it's resumed");
+        }
+    }
+
+    pub(super) fn resume(&self) {
+        if self.paused.send_replace(false) {
+            warn!("Crawl resumed");
+        }
+    }
+
+    /// Wait until the crawl isn't paused.
+    pub(super) async fn wait(&self) {
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| !paused).await;
+    }
+
+    /// Pause the crawl on `SIGUSR1` and resume it on `SIGUSR2`, for as long
+    /// as the process runs.
+    #[cfg(unix)]
+    pub(super) fn listen_for_signals(self: &std::sync::Arc<Self>) -> std::io::Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut pause_signals = signal(SignalKind::user_defined1())?;
+        let mut resume_signals = signal(SignalKind::user_defined2())?;
+        let control = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    Some(()) = pause_signals.recv() => control.pause(),
+                    Some(()) = resume_signals.recv() => control.resume(),
+                    else => break,
+                }
+            }
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{Context, Result};
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use url::Url;
+
+use super::{pac::PacScript, resources::ResourceLimits};
+
+/// Proxy and extra root certificates for requests, for crawling from behind
+/// a corporate proxy that intercepts TLS with a private CA. Without a proxy
+/// or PAC file, the system's `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+/// variables are used.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ProxySettings {
+    proxy: Option<Proxy>,
+    /// The PAC file choosing `proxy`, if any
+    pac: Option<Arc<PacScript>>,
+    ca_certs: Vec<Certificate>,
+}
+
+impl ProxySettings {
+    /// Send every request through `proxy` (HTTP, HTTPS or SOCKS5, with any
+    /// credentials in the URL), or the one the PAC file at `pac_file` chooses
+    /// for its host, except to the `no_proxy` hosts, and trust the
+    /// certificates in the PEM bundle at `ca_cert` alongside the system ones.
+    /// The PAC file's DNS lookups are held to `resource_limits`.
+    pub(super) fn new(
+        proxy: Option<&Url>,
+        pac_file: Option<&Path>,
+        no_proxy: &[String],
+        ca_cert: Option<&Path>,
+        resource_limits: &Arc<ResourceLimits>,
+    ) -> Result<Self> {
+        let pac = match (proxy, pac_file) {
+            (None, Some(path)) => Some(Arc::new(PacScript::load(
+                path,
+                Arc::clone(resource_limits),
+            )?)),
+            _ => None,
+        };
+        let proxy = match (proxy, &pac) {
+            (Some(url), _) => {
+                Some(Proxy::all(url.as_str()).with_context(|| format!("Invalid proxy {url}"))?)
+            }
+            (None, Some(pac)) => {
+                let pac = Arc::clone(pac);
+                Some(Proxy::custom(move |url| pac.proxy_for(url)))
+            }
+            (None, None) => None,
+        }
+        .map(|proxy| proxy.no_proxy(NoProxy::from_string(&no_proxy.join(","))));
+        let ca_certs = match ca_cert {
+            Some(path) => {
+                let pem = std::fs::read(path)
+                    .with_context(|| format!("Failed to read CA certificate {}", path.display()))?;
+                let certs = Certificate::from_pem_bundle(&pem).with_context(|| {
+                    format!("Failed to parse CA certificate {}", path.display())
+                })?;
+                if certs.is_empty() {
+                    anyhow::bail!("No certificates found in {}", path.display());
+                }
+                certs
+            }
+            None => Vec::new(),
+        };
+        Ok(Self {
+            proxy,
+            pac,
+            ca_certs,
+        })
+    }
+
+    /// Whether requests need a client with these settings, which the lychee
+    /// client can't be given.
+    pub(super) fn is_set(&self) -> bool {
+        self.proxy.is_some() || !self.ca_certs.is_empty()
+    }
+
+    /// Choose the proxy for a URL with the PAC file, if any, before
+    /// requesting it, failing if the PAC file fails for it.
+    pub(super) async fn prepare(&self, url: &Url) -> Result<()> {
+        match &self.pac {
+            Some(pac) => pac.prepare(url).await,
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn apply(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        for cert in &self.ca_certs {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        builder
+    }
+}
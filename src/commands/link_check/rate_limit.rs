@@ -0,0 +1,63 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use url::Url;
+
+/// Spaces out requests to each host, shared by all concurrent workers so the
+/// limit holds regardless of concurrency.
+#[derive(Debug, Default)]
+pub(super) struct RateLimiter {
+    /// Minimum time between requests to the same host, or `None` for no limit
+    interval: Option<Duration>,
+    /// Longer intervals required by specific hosts (e.g., by their
+    /// `robots.txt`)
+    host_intervals: Mutex<HashMap<String, Duration>>,
+    /// When the next request to each host may be sent
+    next_slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(requests_per_second: Option<f64>) -> Self {
+        Self {
+            interval: requests_per_second
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            host_intervals: Mutex::new(HashMap::new()),
+            next_slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Space out requests to a host by at least `interval`, even if the
+    /// global limit allows more.
+    pub(super) fn set_min_interval(&self, host: &str, interval: Duration) {
+        self.host_intervals
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), interval);
+    }
+
+    /// Wait until a request to the URL's host is allowed.
+    pub(super) async fn wait(&self, url: &Url) {
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        let host_interval = self.host_intervals.lock().unwrap().get(host).copied();
+        let Some(interval) = self.interval.max(host_interval) else {
+            return;
+        };
+        // Reserve the next slot while holding the lock, then sleep without it
+        let slot = {
+            let mut next_slots = self.next_slots.lock().unwrap();
+            let now = Instant::now();
+            let slot = next_slots
+                .get(host)
+                .map_or(now, |next_slot| (*next_slot).max(now));
+            next_slots.insert(host.to_string(), slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use reqwest::{
+    header::{HeaderMap, AUTHORIZATION, COOKIE, LOCATION, PROXY_AUTHORIZATION},
+    RequestBuilder, Response,
+};
+use url::Url;
+
+use super::{proxy::ProxySettings, rate_limit::RateLimiter, resources::ResourceLimits};
+
+/// A redirect followed while checking a link
+#[derive(Debug, Clone)]
+pub(super) struct Redirect {
+    pub(super) status: u16,
+    /// Where the response redirected to
+    pub(super) location: Url,
+}
+
+/// Send the request built by `request` for a URL, following redirects
+/// manually so that the chain can be reported.
+///
+/// Once a redirect leaves the URL's origin, `request` is passed `false` to
+/// build requests without the user's headers or signing, and any
+/// credentials left on them are removed, as reqwest's own redirect policy
+/// does, so they aren't sent to another site. Requests ask for their
+/// connections to be closed as `resource_limits` requires.
+///
+/// ## Returns
+/// Returns the final response and the redirects followed to reach it. Fails
+/// if the redirects loop or there are more than `max_redirects` of them, or
+/// the PAC file in `proxy_settings` fails for one of the URLs.
+pub(super) async fn follow(
+    url: &Url,
+    max_redirects: usize,
+    rate_limiter: &RateLimiter,
+    proxy_settings: &ProxySettings,
+    resource_limits: &ResourceLimits,
+    request: impl Fn(&Url, bool) -> RequestBuilder,
+) -> Result<(Response, Vec<Redirect>)> {
+    let mut redirects: Vec<Redirect> = Vec::new();
+    let mut current = url.clone();
+    let mut credentials = true;
+    loop {
+        // Credentials stay dropped even if a later redirect comes back
+        credentials &= current.origin() == url.origin();
+        rate_limiter.wait(&current).await;
+        proxy_settings.prepare(&current).await?;
+        let (client, request) = request(&current, credentials).build_split();
+        let mut request = request?;
+        if !credentials {
+            remove_credentials(request.headers_mut());
+        }
+        resource_limits.limit_idle(&current, request.headers_mut());
+        let response = client.execute(request).await?;
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|location| current.join(location).ok());
+        let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+            return Ok((response, redirects));
+        };
+
+        let is_loop = location == *url
+            || redirects
+                .iter()
+                .any(|redirect| redirect.location == location);
+        redirects.push(Redirect {
+            status: response.status().as_u16(),
+            location: location.clone(),
+        });
+        if is_loop {
+            anyhow::bail!("redirect loop: {}", describe_chain(url, &redirects));
+        }
+        if redirects.len() > max_redirects {
+            anyhow::bail!(
+                "more than {max_redirects} redirects: {}",
+                describe_chain(url, &redirects)
+            );
+        }
+        current = location;
+    }
+}
+
+fn remove_credentials(headers: &mut HeaderMap) {
+    for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+        headers.remove(name);
+    }
+}
+
+/// A redirect chain as `URL -> URL -> ...`, for logging.
+pub(super) fn describe_chain(url: &Url, redirects: &[Redirect]) -> String {
+    std::iter::once(url.as_str())
+        .chain(redirects.iter().map(|redirect| redirect.location.as_str()))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::*;
+
+    /// Serve one request with `response`, sending back the request's head.
+    async fn serve_once(response: String) -> (Url, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let (sender, receiver) = mpsc::channel(1);
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0; 1024];
+            while !head.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buf).await.unwrap();
+                head.extend_from_slice(&buf[..read]);
+            }
+            stream.write_all(response.as_bytes()).await.unwrap();
+            sender
+                .send(String::from_utf8_lossy(&head).to_lowercase())
+                .await
+                .unwrap();
+        });
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn credentials_are_dropped_on_cross_origin_redirects() {
+        let (other, mut other_request) =
+            serve_once("HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n".to_string()).await;
+        let (start, mut start_request) = serve_once(format!(
+            "HTTP/1.1 302 Found\r\nlocation: {other}landing\r\ncontent-length: 0\r\n\r\n"
+        ))
+        .await;
+
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let (response, redirects) = follow(
+            &start,
+            5,
+            &RateLimiter::default(),
+            &ProxySettings::default(),
+            &ResourceLimits::new(None, None, &start),
+            |url, credentials| {
+                let request = client
+                    .get(url.as_str())
+                    .header(AUTHORIZATION, "Bearer secret")
+                    .header(COOKIE, "session=secret");
+                match credentials {
+                    true => request.header("x-api-key", "secret"),
+                    false => request,
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(redirects.len(), 1);
+        let start_request = start_request.recv().await.unwrap();
+        for header in ["authorization:", "cookie:", "x-api-key:"] {
+            assert!(start_request.contains(header), "{header}");
+        }
+        let other_request = other_request.recv().await.unwrap();
+        assert!(other_request.starts_with("get /landing"));
+        assert!(!other_request.contains("secret"), "{other_request}");
+    }
+}
@@ -0,0 +1,209 @@
+use std::{env, fs::OpenOptions, io::Write};
+
+use anyhow::{Context, Result};
+
+use super::{BrokenLinkEntry, Report, ReportEntry};
+
+/// Write an `::error` workflow command for each failure and a `::warning` for
+/// each redirect, which GitHub Actions shows as annotations, and append a
+/// summary table to the job summary if running in Actions.
+pub(super) fn write(report: &Report, writer: &mut impl Write) -> Result<()> {
+    for broken in &report.broken_links {
+        writeln!(writer, "{}", broken_link_annotation(broken))?;
+    }
+    for entry in report
+        .results
+        .iter()
+        .filter(|entry| entry.success && !entry.redirects.is_empty())
+    {
+        writeln!(writer, "{}", redirect_annotation(entry))?;
+    }
+
+    if let Ok(summary_path) = env::var("GITHUB_STEP_SUMMARY") {
+        let mut summary = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&summary_path)
+            .with_context(|| format!("Failed to open job summary {summary_path}"))?;
+        write_summary(report, &mut summary)?;
+    }
+    Ok(())
+}
+
+/// An `::error` workflow command for a broken link, on the line linking to
+/// it when checking a local directory.
+fn broken_link_annotation(broken: &BrokenLinkEntry) -> String {
+    let page = broken
+        .referrers
+        .first()
+        .map(String::as_str)
+        .unwrap_or("start");
+    let mut message = format!("{} is broken", broken.url);
+    if let Some(status) = broken.status {
+        message.push_str(&format!(" ({status})"));
+    }
+    if let Some(error) = &broken.error {
+        message.push_str(&format!(": {error}"));
+    }
+    message.push_str(&format!(", linked from {}", linked_from(broken)));
+    workflow_command(
+        "error",
+        broken.referrer_file.as_deref(),
+        broken.referrer_line,
+        &format!("Broken link on {page}"),
+        &message,
+    )
+}
+
+/// A `::warning` workflow command for a link that redirected.
+fn redirect_annotation(entry: &ReportEntry) -> String {
+    let page = entry
+        .referrers
+        .first()
+        .map(String::as_str)
+        .unwrap_or("start");
+    let destination = entry
+        .redirects
+        .last()
+        .map(|redirect| redirect.location.as_str())
+        .unwrap_or_default();
+    workflow_command(
+        "warning",
+        entry.referrer_file.as_deref(),
+        entry.referrer_line,
+        &format!("Redirected link on {page}"),
+        &format!("{} redirects to {destination}", entry.url),
+    )
+}
+
+/// A workflow command annotating `file` (at `line`, if known), or the
+/// workflow run if there's no file.
+fn workflow_command(
+    command: &str,
+    file: Option<&str>,
+    line: Option<usize>,
+    title: &str,
+    message: &str,
+) -> String {
+    let mut properties = Vec::new();
+    if let Some(file) = file {
+        properties.push(format!("file={}", escape_property(file)));
+        if let Some(line) = line {
+            properties.push(format!("line={line}"));
+        }
+    }
+    properties.push(format!("title={}", escape_property(title)));
+    format!(
+        "::{command} {}::{}",
+        properties.join(","),
+        escape_data(message)
+    )
+}
+
+/// Write a Markdown job summary of the failures.
+fn write_summary(report: &Report, writer: &mut impl Write) -> Result<()> {
+    let summary = &report.summary;
+    writeln!(writer, "## Link check")?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "{} links checked, {} failed, {} broken anchors",
+        summary.total, summary.failed, summary.broken_anchors
+    )?;
+    writeln!(writer)?;
+
+    if report.broken_links.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "| Link | Status | Reason | Linked from |")?;
+    writeln!(writer, "| --- | --- | --- | --- |")?;
+    for broken in &report.broken_links {
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} |",
+            escape_cell(&broken.url),
+            broken
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_default(),
+            escape_cell(broken.error.as_deref().unwrap_or("")),
+            escape_cell(&linked_from(broken))
+        )?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// The pages linking to a broken link, with the number left out.
+fn linked_from(broken: &BrokenLinkEntry) -> String {
+    let pages = broken.referrers.join(", ");
+    match broken.referrers_omitted() {
+        0 if pages.is_empty() => "start".to_string(),
+        0 => pages,
+        omitted if pages.is_empty() => format!("{omitted} pages"),
+        omitted => format!("{pages} and {omitted} more"),
+    }
+}
+
+/// Escape a workflow command message.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn escape_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn broken_link(referrer_file: Option<&str>, referrer_line: Option<usize>) -> BrokenLinkEntry {
+        BrokenLinkEntry {
+            url: "https://example.com/missing".to_string(),
+            status: Some(404),
+            error: None,
+            category: None,
+            known: false,
+            referrer_file: referrer_file.map(str::to_string),
+            referrer_line,
+            referrer_count: 1,
+            referrers: vec!["https://docs.example.com/guides/a,b".to_string()],
+        }
+    }
+
+    #[test]
+    fn annotations_point_at_the_linking_file() {
+        assert_eq!(
+            broken_link_annotation(&broken_link(Some("guides/a,b/index.html"), Some(12))),
+            "::error file=guides/a%2Cb/index.html,line=12,\
+             title=Broken link on https%3A//docs.example.com/guides/a%2Cb::\
+             https://example.com/missing is broken (404), \
+             linked from https://docs.example.com/guides/a,b"
+        );
+        assert_eq!(
+            broken_link_annotation(&broken_link(Some("C:/site/index.html"), None)),
+            "::error file=C%3A/site/index.html,\
+             title=Broken link on https%3A//docs.example.com/guides/a%2Cb::\
+             https://example.com/missing is broken (404), \
+             linked from https://docs.example.com/guides/a,b"
+        );
+        assert!(broken_link_annotation(&broken_link(None, Some(12)))
+            .starts_with("::error title=Broken link on "));
+    }
+
+    #[test]
+    fn escapes_workflow_command_values() {
+        assert_eq!(
+            workflow_command("warning", None, None, "50%: a,b\r\n", "50%: a,b\r\n"),
+            "::warning title=50%25%3A a%2Cb%0D%0A::50%25: a,b%0D%0A"
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use std::{collections::BTreeMap, io::Write};
+
+use anyhow::Result;
+
+use super::{super::super::xml::escape, Report, ReportEntry};
+
+pub(super) const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; color: #1f2328; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.2rem; margin-top: 2rem; }
+h3 { font-size: 1rem; margin-top: 1.5rem; word-break: break-all; }
+.totals { display: flex; gap: 1rem; flex-wrap: wrap; }
+.total { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.5rem 1rem; }
+.total strong { display: block; font-size: 1.4rem; }
+.chart { display: grid; grid-template-columns: 8rem 1fr 4rem; gap: 0.25rem 0.5rem; align-items: center; }
+.bar { background: #2da44e; height: 1rem; border-radius: 3px; }
+.bar.failed { background: #cf222e; }
+.heat { text-align: right; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9rem; }
+th, td { border-bottom: 1px solid #d0d7de; padding: 0.4rem; text-align: left; vertical-align: top; }
+td:first-child { word-break: break-all; }
+th { cursor: pointer; user-select: none; background: #f6f8fa; }
+th::after { content: ' \\2195'; color: #8c959f; }
+";
+
+/// Sorts a table by the clicked column, toggling the direction on repeated
+/// clicks
+pub(super) const SCRIPT: &str = "
+document.querySelectorAll('th').forEach((th) => th.addEventListener('click', () => {
+  const table = th.closest('table');
+  const body = table.tBodies[0];
+  const index = Array.from(th.parentNode.children).indexOf(th);
+  const ascending = th.dataset.order !== 'asc';
+  table.querySelectorAll('th').forEach((other) => delete other.dataset.order);
+  th.dataset.order = ascending ? 'asc' : 'desc';
+  const rows = Array.from(body.rows).sort((a, b) => {
+    const x = a.cells[index].innerText;
+    const y = b.cells[index].innerText;
+    return (ascending ? 1 : -1) * x.localeCompare(y, undefined, { numeric: true });
+  });
+  rows.forEach((row) => body.appendChild(row));
+}));
+";
+
+/// Write the report as a self-contained HTML page, with a chart of the
+/// statuses seen and a sortable table of failures for each page they were
+/// found on, for readers who won't read JSON or logs.
+pub(super) fn write(report: &Report, writer: &mut impl Write) -> Result<()> {
+    let summary = &report.summary;
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, r#"<html lang="en">"#)?;
+    writeln!(writer, "<head>")?;
+    writeln!(writer, r#"<meta charset="utf-8">"#)?;
+    writeln!(writer, "<title>Link check report</title>")?;
+    writeln!(writer, "<style>{STYLE}</style>")?;
+    writeln!(writer, "</head>")?;
+    writeln!(writer, "<body>")?;
+    writeln!(writer, "<h1>Link check report</h1>")?;
+
+    writeln!(writer, r#"<div class="totals">"#)?;
+    let possible_false_skips = summary
+        .possible_false_skips
+        .map(|skips| ("Possibly skipped by --visited-bloom", skips));
+    for (label, value) in [
+        ("Links checked", summary.total),
+        ("Failed", summary.failed),
+        ("Broken anchors", summary.broken_anchors),
+        ("Redirected", summary.redirected),
+    ]
+    .into_iter()
+    .chain(possible_false_skips)
+    {
+        writeln!(
+            writer,
+            r#"<div class="total"><strong>{value}</strong>{label}</div>"#
+        )?;
+    }
+    writeln!(writer, "</div>")?;
+
+    write_status_chart(report, writer)?;
+    write_section_heatmap(report, writer)?;
+
+    write_failures(report, writer)?;
+
+    write_broken_assets(report, writer)?;
+
+    write_broken_forms(report, writer)?;
+
+    write_broken_comment_links(report, writer)?;
+
+    writeln!(writer, "<script>{SCRIPT}</script>")?;
+    writeln!(writer, "</body>")?;
+    writeln!(writer, "</html>")?;
+    Ok(())
+}
+
+/// Write a table of broken links, each once with the pages linking to it.
+fn write_failures(report: &Report, writer: &mut impl Write) -> Result<()> {
+    writeln!(writer, "<h2>Failures</h2>")?;
+    if report.broken_links.is_empty() {
+        writeln!(writer, "<p>No broken links found.</p>")?;
+        return Ok(());
+    }
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>Link</th><th>Status</th><th>Category</th><th>Reason</th><th>Last archived working</th><th>Linked from</th></tr></thead>"
+    )?;
+    writeln!(writer, "<tbody>")?;
+    for broken in &report.broken_links {
+        let entry = report
+            .results
+            .iter()
+            .find(|entry| !entry.success && entry.url == broken.url);
+        let mut pages: Vec<String> = broken.referrers.iter().map(|page| link(page)).collect();
+        if broken.referrers_omitted() > 0 {
+            pages.push(format!("and {} more", broken.referrers_omitted()));
+        }
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            link(&broken.url),
+            broken
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_default(),
+            broken
+                .category
+                .map(|category| category.to_string())
+                .unwrap_or_default(),
+            entry.map(reason).unwrap_or_default(),
+            escape(
+                entry
+                    .and_then(|entry| entry.last_alive.as_deref())
+                    .unwrap_or_default()
+            ),
+            pages.join("<br>")
+        )?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")?;
+    Ok(())
+}
+
+/// Write a bar chart of how many links returned each status, with failures
+/// without a status grouped as `error`.
+fn write_status_chart(report: &Report, writer: &mut impl Write) -> Result<()> {
+    let mut counts: BTreeMap<String, (usize, bool)> = BTreeMap::new();
+    for entry in &report.results {
+        let status = match entry.status {
+            Some(status) => status.to_string(),
+            None if entry.success => "ok".to_string(),
+            None => "error".to_string(),
+        };
+        let (count, failed) = counts.entry(status).or_default();
+        *count += 1;
+        *failed |= !entry.success;
+    }
+    let Some(max) = counts.values().map(|(count, _)| *count).max() else {
+        return Ok(());
+    };
+
+    writeln!(writer, "<h2>Statuses</h2>")?;
+    writeln!(writer, r#"<div class="chart">"#)?;
+    for (status, (count, failed)) in counts {
+        let class = if failed { "bar failed" } else { "bar" };
+        writeln!(
+            writer,
+            r#"<span>{status}</span><div class="{class}" style="width: {:.1}%"></div><span>{count}</span>"#,
+            count as f64 * 100.0 / max as f64
+        )?;
+    }
+    writeln!(writer, "</div>")?;
+    Ok(())
+}
+
+/// Write a sortable table of the failures in each section of the site,
+/// shaded by the share of its links that failed, so the sections rotting
+/// fastest stand out.
+fn write_section_heatmap(report: &Report, writer: &mut impl Write) -> Result<()> {
+    if report.sections.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "<h2>Sections</h2>")?;
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>Section</th><th>Links</th><th>Failed</th><th>Failed %</th><th>Pages with failures</th></tr></thead>"
+    )?;
+    writeln!(writer, "<tbody>")?;
+    for section in &report.sections {
+        let rate = section.failure_rate();
+        writeln!(
+            writer,
+            r#"<tr><td>{}</td><td>{}</td><td>{}</td><td class="heat" style="background: rgba(207, 34, 46, {rate:.2})">{:.1}</td><td>{}</td></tr>"#,
+            escape(&section.section),
+            section.total,
+            section.failed,
+            rate * 100.0,
+            section.failing_pages
+        )?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")?;
+    Ok(())
+}
+
+/// Write a sortable table of the assets that failed to load, if assets were
+/// checked.
+fn write_broken_assets(report: &Report, writer: &mut impl Write) -> Result<()> {
+    if report.assets.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "<h2>Assets</h2>")?;
+    let broken: Vec<_> = report
+        .assets
+        .iter()
+        .filter(|asset| !asset.success)
+        .collect();
+    if broken.is_empty() {
+        writeln!(writer, "<p>All {} assets loaded.</p>", report.assets.len())?;
+        return Ok(());
+    }
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>Asset</th><th>Kind</th><th>Status</th><th>Content type</th><th>Reason</th><th>Loaded by</th></tr></thead>"
+    )?;
+    writeln!(writer, "<tbody>")?;
+    for asset in broken {
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            link(&asset.url),
+            asset.kind.as_str(),
+            asset
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_default(),
+            escape(asset.content_type.as_deref().unwrap_or_default()),
+            escape(asset.error.as_deref().unwrap_or_default()),
+            asset
+                .pages
+                .iter()
+                .map(|page| link(page))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        )?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")?;
+    Ok(())
+}
+
+/// Write a table of the links in HTML comments that failed, if they were
+/// checked.
+fn write_broken_comment_links(report: &Report, writer: &mut impl Write) -> Result<()> {
+    if report.comment_links.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "<h2>Links in HTML comments</h2>")?;
+    let broken: Vec<_> = report
+        .comment_links
+        .iter()
+        .filter(|comment_link| !comment_link.success)
+        .collect();
+    if broken.is_empty() {
+        writeln!(
+            writer,
+            "<p>All {} links in HTML comments work.</p>",
+            report.comment_links.len()
+        )?;
+        return Ok(());
+    }
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>Link</th><th>Status</th><th>Reason</th><th>In comments on</th></tr></thead>"
+    )?;
+    writeln!(writer, "<tbody>")?;
+    for comment_link in broken {
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            link(&comment_link.url),
+            comment_link
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_default(),
+            escape(comment_link.error.as_deref().unwrap_or_default()),
+            comment_link
+                .pages
+                .iter()
+                .map(|page| link(page))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        )?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")?;
+    Ok(())
+}
+
+/// Write a table of form endpoints that don't exist, with the pages whose
+/// forms submit to them.
+fn write_broken_forms(report: &Report, writer: &mut impl Write) -> Result<()> {
+    if report.forms.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "<h2>Form endpoints</h2>")?;
+    let broken: Vec<_> = report.forms.iter().filter(|form| !form.success).collect();
+    if broken.is_empty() {
+        writeln!(
+            writer,
+            "<p>All {} form endpoints exist.</p>",
+            report.forms.len()
+        )?;
+        return Ok(());
+    }
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>Endpoint</th><th>Method</th><th>Status</th><th>Reason</th><th>Forms on</th></tr></thead>"
+    )?;
+    writeln!(writer, "<tbody>")?;
+    for form in broken {
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            link(&form.url),
+            escape(&form.method.to_ascii_uppercase()),
+            form.status
+                .map(|status| status.to_string())
+                .unwrap_or_default(),
+            escape(form.error.as_deref().unwrap_or_default()),
+            form.pages
+                .iter()
+                .map(|page| link(page))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        )?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")?;
+    Ok(())
+}
+
+/// Why a link failed, followed by any diagnosis of the connection failure.
+fn reason(entry: &ReportEntry) -> String {
+    let mut reason = escape(entry.error.as_deref().unwrap_or_default());
+    if let Some(diagnosis) = &entry.network_diagnosis {
+        reason.push_str(&format!("<br><em>{}</em>", escape(diagnosis)));
+    }
+    reason
+}
+
+/// A link opening the URL in a new tab, so it can be checked again by hand.
+fn link(url: &str) -> String {
+    let url = escape(url);
+    if url.starts_with("http://") || url.starts_with("https://") {
+        format!(r#"<a href="{url}" target="_blank" rel="noopener">{url}</a>"#)
+    } else {
+        url
+    }
+}
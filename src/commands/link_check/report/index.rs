@@ -0,0 +1,148 @@
+use std::{collections::BTreeMap, io::Write};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    super::super::xml::escape,
+    html::{SCRIPT, STYLE},
+    Category, Report,
+};
+
+/// Page listing every run's report, kept beside them
+pub(super) const INDEX_HTML: &str = "index.html";
+/// Results of every run, read back to update the index on the next one
+pub(super) const INDEX_JSON: &str = "index.json";
+
+/// A run listed in the index, with the summary of its report
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct IndexedRun {
+    /// When the report was written, in RFC 3339
+    time: String,
+    /// Path of the report, relative to the index
+    pub(super) path: String,
+    total: usize,
+    failed: usize,
+    /// Number of failures in the baseline, which don't fail the check
+    known: usize,
+    /// Number of links with each kind of problem
+    categories: BTreeMap<Category, usize>,
+    /// Whether the crawl stopped at `--max-duration`
+    partial: bool,
+}
+
+/// The runs whose reports are kept, newest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct ReportIndex {
+    runs: Vec<IndexedRun>,
+}
+
+impl ReportIndex {
+    pub(super) fn parse(json: &[u8], location: &str) -> Result<Self> {
+        serde_json::from_slice(json).with_context(|| format!("Invalid report index {location}"))
+    }
+
+    /// List a run's report, returning the oldest runs beyond the `keep`
+    /// newest, whose reports should be deleted.
+    pub(super) fn add(
+        &mut self,
+        report: &Report,
+        time: DateTime<Utc>,
+        path: String,
+        keep: Option<usize>,
+    ) -> Vec<IndexedRun> {
+        let summary = &report.summary;
+        self.runs.insert(
+            0,
+            IndexedRun {
+                time: time.to_rfc3339_opts(SecondsFormat::Secs, true),
+                path,
+                total: summary.total,
+                failed: summary.failed,
+                known: summary.known,
+                categories: summary.categories.clone(),
+                partial: summary.unchecked_at_deadline.is_some() || !summary.cut_short.is_empty(),
+            },
+        );
+        match keep {
+            Some(keep) if self.runs.len() > keep => self.runs.split_off(keep),
+            _ => Vec::new(),
+        }
+    }
+
+    pub(super) fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// The index as a self-contained HTML page, with the number of failures
+    /// in each run charted so trends stand out.
+    pub(super) fn to_html(&self) -> Result<Vec<u8>> {
+        let mut writer = Vec::new();
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, r#"<html lang="en">"#)?;
+        writeln!(writer, "<head>")?;
+        writeln!(writer, r#"<meta charset="utf-8">"#)?;
+        writeln!(writer, "<title>Link check reports</title>")?;
+        writeln!(writer, "<style>{STYLE}</style>")?;
+        writeln!(writer, "</head>")?;
+        writeln!(writer, "<body>")?;
+        writeln!(writer, "<h1>Link check reports</h1>")?;
+
+        let max = self.runs.iter().map(|run| run.failed).max().unwrap_or(0);
+        if max > 0 {
+            writeln!(writer, "<h2>Failures</h2>")?;
+            writeln!(writer, r#"<div class="chart">"#)?;
+            for run in &self.runs {
+                writeln!(
+                    writer,
+                    r#"<span>{}</span><div class="bar failed" style="width: {:.1}%"></div><span>{}</span>"#,
+                    escape(run.time.get(..10).unwrap_or(&run.time)),
+                    run.failed as f64 * 100.0 / max as f64,
+                    run.failed
+                )?;
+            }
+            writeln!(writer, "</div>")?;
+        }
+
+        writeln!(writer, "<h2>Runs</h2>")?;
+        writeln!(writer, "<table>")?;
+        writeln!(
+            writer,
+            "<thead><tr><th>Run</th><th>Links checked</th><th>Failed</th><th>Known</th><th>Problems</th></tr></thead>"
+        )?;
+        writeln!(writer, "<tbody>")?;
+        for run in &self.runs {
+            let mut problems = run
+                .categories
+                .iter()
+                .map(|(category, count)| format!("{category}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if run.partial {
+                problems.push_str(" <em>(partial, stopped at --max-duration)</em>");
+            }
+            writeln!(
+                writer,
+                r#"<tr><td><a href="{}">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{problems}</td></tr>"#,
+                escape(&run.path),
+                escape(&run.time),
+                run.total,
+                run.failed,
+                run.known
+            )?;
+        }
+        writeln!(writer, "</tbody>")?;
+        writeln!(writer, "</table>")?;
+
+        writeln!(writer, "<script>{SCRIPT}</script>")?;
+        writeln!(writer, "</body>")?;
+        writeln!(writer, "</html>")?;
+        Ok(writer)
+    }
+}
+
+/// Directory named after the time of a run, holding its report.
+pub(super) fn run_dir(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H-%M-%SZ").to_string()
+}
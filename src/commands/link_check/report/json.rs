@@ -0,0 +1,11 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use super::Report;
+
+pub(super) fn write(report: &Report, writer: &mut impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, report)?;
+    writeln!(writer)?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use super::{super::super::xml::escape, Report};
+
+/// Write the report as JUnit XML, with one test case per checked URL grouped
+/// under the page it was found on. Times are left out if the checks weren't
+/// timed.
+pub(super) fn write(report: &Report, writer: &mut impl Write) -> Result<()> {
+    let total_ms: Option<u64> = report.results.iter().map(|entry| entry.elapsed_ms).sum();
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<testsuites name="docs-tools" tests="{}" failures="{}"{}>"#,
+        report.results.len(),
+        report.results.iter().filter(|entry| !entry.success).count(),
+        time(total_ms)
+    )?;
+    writeln!(
+        writer,
+        r#"  <testsuite name="link-check" tests="{}" failures="{}"{}>"#,
+        report.results.len(),
+        report.results.iter().filter(|entry| !entry.success).count(),
+        time(total_ms)
+    )?;
+
+    for entry in &report.results {
+        let classname = entry
+            .referrers
+            .first()
+            .map(String::as_str)
+            .unwrap_or("start");
+        write!(
+            writer,
+            r#"    <testcase classname="{}" name="{}"{}"#,
+            escape(classname),
+            escape(&entry.url),
+            time(entry.elapsed_ms)
+        )?;
+        if entry.success {
+            writeln!(writer, "/>")?;
+            continue;
+        }
+        writeln!(writer, ">")?;
+
+        let reason = entry.error.as_deref().unwrap_or_default();
+        let mut details = Vec::new();
+        if let Some(status) = entry.status {
+            details.push(format!("Status: {status}"));
+        }
+        details.push(format!("Reason: {reason}"));
+        if !entry.referrers.is_empty() {
+            details.push(format!("Referrers: {}", entry.referrers.join(" <- ")));
+        }
+        if let Some(last_alive) = &entry.last_alive {
+            details.push(format!("Last archived working: {last_alive}"));
+        }
+        if let Some(diagnosis) = &entry.network_diagnosis {
+            details.push(format!("Diagnosis: {diagnosis}"));
+        }
+        writeln!(
+            writer,
+            r#"      <failure message="{}">{}</failure>"#,
+            escape(reason),
+            escape(&details.join("\n"))
+        )?;
+        writeln!(writer, "    </testcase>")?;
+    }
+
+    writeln!(writer, "  </testsuite>")?;
+    writeln!(writer, "</testsuites>")?;
+    Ok(())
+}
+
+/// A `time` attribute in seconds, or nothing if untimed.
+fn time(ms: Option<u64>) -> String {
+    ms.map(|ms| format!(r#" time="{:.3}""#, ms as f64 / 1000.0))
+        .unwrap_or_default()
+}
@@ -0,0 +1,546 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use tracing::{info, warn};
+use url::Url;
+
+use super::{
+    assets::AssetKind, category::Category, commit_status::CommitStatusTarget, issues::IssueTracker,
+    sections::SectionStats, skips::SkipReason, stats::Stats, upload::UploadTarget,
+};
+use index::ReportIndex;
+
+mod github;
+mod html;
+mod index;
+mod json;
+mod junit;
+mod ndjson;
+mod sarif;
+
+/// Format of the report written once the crawl finishes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable summary in the log output
+    #[default]
+    Text,
+    /// Structured report of every checked URL
+    Json,
+    /// JUnit XML, with a test case for every checked URL
+    Junit,
+    /// GitHub Actions annotations for each failure, plus a job summary
+    Github,
+    /// Self-contained HTML page, with the failures grouped by page
+    Html,
+    /// SARIF, for uploading to GitHub code scanning with
+    /// `github/codeql-action/upload-sarif`
+    Sarif,
+    /// Newline-delimited JSON events streamed as the crawl runs, for each
+    /// URL queued, started, succeeded or failed, then the summary
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// Extension of a file in this format, naming uploaded reports.
+    pub(super) fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Text | OutputFormat::Github => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Junit => "xml",
+            OutputFormat::Html => "html",
+            OutputFormat::Sarif => "sarif",
+            OutputFormat::Ndjson => "ndjson",
+        }
+    }
+
+    pub(super) fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Text | OutputFormat::Github => "text/plain; charset=utf-8",
+            OutputFormat::Json => "application/json",
+            OutputFormat::Junit => "application/xml",
+            OutputFormat::Html => "text/html; charset=utf-8",
+            OutputFormat::Sarif => "application/sarif+json",
+            OutputFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Options for the report written once a `link-check` crawl finishes. Kept
+/// separate from the crawl options so that other commands crawling a site can
+/// define their own outputs.
+#[derive(Args)]
+pub(crate) struct ReportArgs {
+    /// Format of the report written once the crawl finishes
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) format: OutputFormat,
+
+    /// Write the report to this file instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Number of pages listed as linking to each broken link in the summary
+    /// and report, counting the rest [default: all]
+    #[arg(long, value_name = "N")]
+    pub(crate) max_referrers_shown: Option<usize>,
+
+    /// Open an issue for each broken link, listing every page linking to it:
+    /// `github:OWNER/REPO` (authenticated with `GITHUB_TOKEN`) or
+    /// `jira:PROJECT` (with `JIRA_URL`, `JIRA_EMAIL` and `JIRA_API_TOKEN`).
+    /// Existing issues are updated rather than duplicated, and closed once
+    /// the link works again.
+    #[arg(long, value_name = "TRACKER")]
+    pub(crate) file_issues: Option<IssueTracker>,
+
+    /// Upload the report to S3 (`s3://BUCKET/KEY`, with the standard `AWS_*`
+    /// credentials) or Cloud Storage (`gs://BUCKET/KEY`, with
+    /// `GOOGLE_OAUTH_ACCESS_TOKEN`), linking to it from the summary,
+    /// notifications and commit status. A key ending in `/` keeps every
+    /// run's report, each under a directory named after its time, with an
+    /// index.html listing every run's results.
+    #[arg(long, value_name = "URL")]
+    pub(crate) upload: Option<UploadTarget>,
+
+    /// Keep each run's report under this directory, in a directory named
+    /// after its time, with an index.html listing every run's results
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    pub(crate) report_dir: Option<PathBuf>,
+
+    /// Number of runs whose reports are kept with --report-dir or an
+    /// --upload prefix, deleting older ones [default: all]
+    #[arg(long, value_name = "N")]
+    pub(crate) keep_reports: Option<usize>,
+
+    /// Post whether the check passed, with the number of failing links, as a
+    /// status on this GitHub commit (authenticated with `GITHUB_TOKEN`), for
+    /// checks run outside GitHub Actions
+    #[arg(long, value_name = "OWNER/REPO@SHA")]
+    pub(crate) commit_status: Option<CommitStatusTarget>,
+
+    /// URL of the published report, linked from the --commit-status status
+    /// unless the report is uploaded with --upload
+    #[arg(long, value_name = "URL", requires = "commit_status")]
+    pub(crate) report_url: Option<Url>,
+
+    /// Name of the --commit-status status, telling it apart from other
+    /// checks on the commit [default: docs-tools/link-check]
+    #[arg(long, value_name = "NAME", requires = "commit_status")]
+    pub(crate) status_context: Option<String>,
+}
+
+/// Results of a crawl, in a form suitable for machine-readable output
+#[derive(Debug, Serialize)]
+pub(super) struct Report {
+    pub(super) summary: Summary,
+    /// Checks by host and status, the slowest checks and bytes downloaded
+    pub(super) stats: Stats,
+    /// Checks by the top-level section of the site the linking page is in,
+    /// most failures first
+    pub(super) sections: Vec<SectionStats>,
+    /// Groups of URLs that served identical content
+    pub(super) duplicates: Vec<Vec<String>>,
+    /// Groups of distinct pages sharing the same `<title>`
+    pub(super) duplicate_titles: Vec<DuplicateText>,
+    /// Groups of distinct pages sharing the same `<h1>`
+    pub(super) duplicate_h1s: Vec<DuplicateText>,
+    /// Sitemap pages not linked from any page reachable from the start URL
+    pub(super) orphaned_pages: Vec<String>,
+    /// Crawled pages not linked from any other page, reachable only via the
+    /// sitemap or by direct URL
+    pub(super) unlinked_pages: Vec<String>,
+    /// Pages with more links than `--max-internal-links` or
+    /// `--max-external-links`
+    pub(super) over_link_budget: Vec<LinkBudgetEntry>,
+    /// Crawled pages not listed in the sitemap, other than those blocked
+    /// from indexing
+    pub(super) missing_from_sitemap: Vec<String>,
+    /// Crawled pages whose `X-Robots-Tag` header blocks indexing
+    pub(super) noindex_pages: Vec<String>,
+    /// Sitemap pages whose `X-Robots-Tag` header blocks indexing
+    pub(super) noindex_in_sitemap: Vec<String>,
+    /// Whether every sitemap `lastmod` is the day of the crawl
+    pub(super) lastmod_always_today: bool,
+    /// Sitemap pages whose `lastmod` is far from when they were last modified
+    pub(super) lastmod_mismatches: Vec<LastmodEntry>,
+    /// Baseline entries that are no longer failing
+    pub(super) stale_baseline: Vec<String>,
+    /// Assets loaded by crawled pages, with `--check-assets`
+    pub(super) assets: Vec<AssetEntry>,
+    /// Links in HTML comments, with `--check-comment-links`, which don't
+    /// fail the check
+    pub(super) comment_links: Vec<CommentLinkEntry>,
+    /// Endpoints forms submit to, with `--check-forms`
+    pub(super) forms: Vec<FormEntry>,
+    /// Results for each locale given with `--locale`, including the external
+    /// links its pages share with other locales
+    pub(super) locales: Vec<LocaleSection>,
+    /// Results for each owner in the `--owners` file, by the page each link
+    /// is on
+    pub(super) owners: Vec<OwnerSection>,
+    /// Responses of key pages to each `--accept-language`, alongside their
+    /// responses without one
+    pub(super) accept_language: Vec<LanguageEntry>,
+    /// Responses of the pages sampled with `--compare-mobile` on desktop and
+    /// mobile
+    pub(super) mobile: Vec<DeviceEntry>,
+    /// Each failing URL once, with the pages linking to it, most linked
+    /// first
+    pub(super) broken_links: Vec<BrokenLinkEntry>,
+    /// URLs found but not checked, with why and the first page linking to
+    /// them, so the crawl's scope can be checked against what was intended
+    pub(super) skipped: Vec<SkippedEntry>,
+    pub(super) results: Vec<ReportEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct Summary {
+    pub(super) total: usize,
+    pub(super) successful: usize,
+    pub(super) failed: usize,
+    pub(super) broken_anchors: usize,
+    pub(super) redirected: usize,
+    /// Number of links with each kind of problem
+    pub(super) categories: BTreeMap<Category, usize>,
+    /// Number of failures in the baseline, which don't fail the check
+    pub(super) known: usize,
+    /// Number of queued URLs left unchecked when the crawl stopped at
+    /// `--max-duration`, if it did, making the report partial
+    pub(super) unchecked_at_deadline: Option<usize>,
+    /// Checks after the crawl that `--max-duration` stopped, also making the
+    /// report partial
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(super) cut_short: Vec<&'static str>,
+    /// Number of URLs found but not checked, for each reason
+    pub(super) skipped: BTreeMap<SkipReason, usize>,
+    /// Estimated number of new URLs `--visited-bloom` mistook for visited
+    /// ones, so weren't checked, if it was used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) possible_false_skips: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct FormEntry {
+    pub(super) url: String,
+    /// The method the form submits with
+    pub(super) method: String,
+    pub(super) success: bool,
+    pub(super) status: Option<u16>,
+    pub(super) error: Option<String>,
+    /// Pages with a form submitting to the endpoint
+    pub(super) pages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct CommentLinkEntry {
+    pub(super) url: String,
+    pub(super) success: bool,
+    pub(super) status: Option<u16>,
+    pub(super) error: Option<String>,
+    /// Pages with the link in a comment
+    pub(super) pages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct AssetEntry {
+    pub(super) url: String,
+    pub(super) kind: AssetKind,
+    pub(super) success: bool,
+    pub(super) status: Option<u16>,
+    pub(super) content_type: Option<String>,
+    pub(super) error: Option<String>,
+    /// Pages loading the asset
+    pub(super) pages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct BrokenLinkEntry {
+    pub(super) url: String,
+    pub(super) status: Option<u16>,
+    pub(super) error: Option<String>,
+    pub(super) category: Option<Category>,
+    /// Whether the failure is in the baseline, so doesn't fail the check
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(super) known: bool,
+    /// Source file of the first page linking to the URL, when checking a
+    /// local directory
+    #[serde(skip)]
+    pub(super) referrer_file: Option<String>,
+    /// Line of `referrer_file` the link is on, if found
+    #[serde(skip)]
+    pub(super) referrer_line: Option<usize>,
+    /// Number of pages linking to the URL
+    pub(super) referrer_count: usize,
+    /// Pages linking to the URL, up to `--max-referrers-shown` of them
+    pub(super) referrers: Vec<String>,
+}
+
+impl BrokenLinkEntry {
+    /// Number of pages linking to the URL left out of `referrers`.
+    pub(super) fn referrers_omitted(&self) -> usize {
+        self.referrer_count - self.referrers.len()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct SkippedEntry {
+    pub(super) url: String,
+    pub(super) reason: SkipReason,
+    /// More about why, such as the limit the URL went beyond
+    pub(super) detail: Option<String>,
+    pub(super) referrer: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct LinkBudgetEntry {
+    pub(super) page: String,
+    /// Number of distinct links to other pages on the site
+    pub(super) internal: usize,
+    /// Number of distinct links to other sites
+    pub(super) external: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct DuplicateText {
+    pub(super) text: String,
+    pub(super) pages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct LastmodEntry {
+    pub(super) url: String,
+    pub(super) lastmod: String,
+    pub(super) last_modified: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct LanguageEntry {
+    pub(super) page: String,
+    /// The `Accept-Language` requested with, or `None` for the request
+    /// without one
+    pub(super) language: Option<String>,
+    pub(super) status: Option<u16>,
+    pub(super) final_url: Option<String>,
+    pub(super) canonical: Option<String>,
+    pub(super) error: Option<String>,
+    /// How the response differs from the one without `Accept-Language`
+    pub(super) problem: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct DeviceEntry {
+    pub(super) page: String,
+    pub(super) desktop: DeviceResponseEntry,
+    pub(super) mobile: DeviceResponseEntry,
+    /// How the mobile response differs from the desktop one
+    pub(super) problem: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct DeviceResponseEntry {
+    pub(super) status: Option<u16>,
+    /// Where each redirect followed went
+    pub(super) redirects: Vec<String>,
+    pub(super) canonical: Option<String>,
+    pub(super) error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct LocaleSection {
+    pub(super) locale: String,
+    pub(super) total: usize,
+    pub(super) failed: usize,
+    pub(super) results: Vec<ReportEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct OwnerSection {
+    pub(super) owner: String,
+    pub(super) total: usize,
+    pub(super) failed: usize,
+    pub(super) results: Vec<ReportEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ReportEntry {
+    pub(super) url: String,
+    pub(super) success: bool,
+    pub(super) status: Option<u16>,
+    /// Pages leading to this URL, from the page linking to it back to the
+    /// start URL
+    pub(super) referrers: Vec<String>,
+    /// Source file of the page linking to this URL, when checking a local
+    /// directory
+    #[serde(skip)]
+    pub(super) referrer_file: Option<String>,
+    /// Line of `referrer_file` the link is on, if found
+    #[serde(skip)]
+    pub(super) referrer_line: Option<usize>,
+    /// Redirects followed to reach the final response, if the link
+    /// redirected
+    pub(super) redirects: Vec<RedirectEntry>,
+    /// Time the check took, left out with `--deterministic` so that repeated
+    /// runs produce identical reports
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) elapsed_ms: Option<u64>,
+    pub(super) error: Option<String>,
+    /// The kind of problem with the link, if any
+    pub(super) category: Option<Category>,
+    /// Whether the failure is in the baseline, so doesn't fail the check
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(super) known: bool,
+    /// Date a broken link was last archived working, if looked up
+    pub(super) last_alive: Option<String>,
+    /// Why connections to the host failed, if diagnosed (e.g., an IPv6-only
+    /// failure)
+    pub(super) network_diagnosis: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct RedirectEntry {
+    pub(super) status: u16,
+    pub(super) location: String,
+}
+
+impl Report {
+    /// Write the report in the given format to a file, or to stdout if no
+    /// file is given. The text format is already covered by the log output,
+    /// and ndjson streamed during the crawl, so nothing is written for them.
+    pub(super) fn write(&self, args: &ReportArgs) -> Result<()> {
+        let format = args.format;
+        if matches!(format, OutputFormat::Text | OutputFormat::Ndjson) {
+            return Ok(());
+        }
+        let mut writer: Box<dyn Write> = match args.output.as_deref() {
+            Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| {
+                anyhow::anyhow!("Failed to create report file {}: {e}", path.display())
+            })?)),
+            None => Box::new(io::stdout().lock()),
+        };
+        self.write_to(format, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// The report in the given format, for uploading.
+    pub(super) fn render(&self, format: OutputFormat) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.write_to(format, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Write the report to a directory for this run under `dir`, listing it
+    /// in the index there and deleting the reports of runs beyond the `keep`
+    /// newest. Returns the path of the report.
+    pub(super) fn save_run(
+        &self,
+        format: OutputFormat,
+        dir: &Path,
+        keep: Option<usize>,
+    ) -> Result<PathBuf> {
+        let time = Utc::now();
+        let path = format!("{}/report.{}", index::run_dir(time), format.extension());
+        let report_path = dir.join(&path);
+        if let Some(parent) = report_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create report directory {}", parent.display())
+            })?;
+        }
+        fs::write(&report_path, self.render(format)?)
+            .with_context(|| format!("Failed to write report {}", report_path.display()))?;
+
+        let index_json = dir.join(index::INDEX_JSON);
+        let mut index = match fs::read(&index_json) {
+            Ok(json) => ReportIndex::parse(&json, &index_json.display().to_string())?,
+            Err(_) => ReportIndex::default(),
+        };
+        for run in index.add(self, time, path, keep) {
+            // Each report is alone in its run's directory
+            let Some(run_dir) = Path::new(&run.path).parent() else {
+                continue;
+            };
+            let run_dir = dir.join(run_dir);
+            match fs::remove_dir_all(&run_dir) {
+                Ok(()) => info!("Deleted old report {}", run_dir.display()),
+                Err(e) => warn!("Failed to delete old report {}: {e}", run_dir.display()),
+            }
+        }
+        fs::write(&index_json, index.to_json()?)?;
+        fs::write(dir.join(index::INDEX_HTML), index.to_html()?)?;
+        info!("Wrote report {}", report_path.display());
+        Ok(report_path)
+    }
+
+    /// Upload the report, named `file_name`, returning the URL it can be read
+    /// at. Under a prefix, it goes in a directory for this run, listed in the
+    /// index there, and the reports of runs beyond the `keep` newest are
+    /// deleted.
+    pub(super) async fn upload_run(
+        &self,
+        format: OutputFormat,
+        target: &UploadTarget,
+        client: &reqwest::Client,
+        file_name: &str,
+        keep: Option<usize>,
+    ) -> Result<Url> {
+        let body = self.render(format)?;
+        if target.prefix().is_none() {
+            let key = target.object_key(file_name);
+            return target.put(client, &key, format.content_type(), body).await;
+        }
+
+        let time = Utc::now();
+        let path = format!("{}/{file_name}", index::run_dir(time));
+        let url = target
+            .put(
+                client,
+                &target.object_key(&path),
+                format.content_type(),
+                body,
+            )
+            .await?;
+
+        let index_key = target.object_key(index::INDEX_JSON);
+        let mut index = match target.get(client, &index_key).await? {
+            Some(json) => ReportIndex::parse(&json, &index_key)?,
+            None => ReportIndex::default(),
+        };
+        for run in index.add(self, time, path, keep) {
+            if let Err(e) = target.delete(client, &target.object_key(&run.path)).await {
+                warn!("Failed to delete old report: {e:#}");
+            }
+        }
+        target
+            .put(client, &index_key, "application/json", index.to_json()?)
+            .await?;
+        let index_html = target.object_key(index::INDEX_HTML);
+        target
+            .put(
+                client,
+                &index_html,
+                OutputFormat::Html.content_type(),
+                index.to_html()?,
+            )
+            .await?;
+        Ok(url)
+    }
+
+    fn write_to(&self, format: OutputFormat, mut writer: impl Write) -> Result<()> {
+        match format {
+            OutputFormat::Text => {}
+            OutputFormat::Json => json::write(self, &mut writer)?,
+            OutputFormat::Junit => junit::write(self, &mut writer)?,
+            OutputFormat::Github => github::write(self, &mut writer)?,
+            OutputFormat::Html => html::write(self, &mut writer)?,
+            OutputFormat::Sarif => sarif::write(self, &mut writer)?,
+            OutputFormat::Ndjson => ndjson::write(self, &mut writer)?,
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use super::{super::events::Event, Report};
+
+/// Write the outcome of every check as a line of JSON, then the summary,
+/// matching the events streamed during the crawl.
+pub(super) fn write(report: &Report, writer: &mut impl Write) -> Result<()> {
+    for entry in &report.results {
+        let url = &entry.url;
+        let referrer = entry.referrers.first().map(String::as_str);
+        let event = match &entry.error {
+            None => Event::Succeeded {
+                url,
+                referrer,
+                status: entry.status,
+                elapsed_ms: entry.elapsed_ms,
+            },
+            Some(error) => Event::Failed {
+                url,
+                referrer,
+                status: entry.status,
+                elapsed_ms: entry.elapsed_ms,
+                error,
+                category: entry.category,
+            },
+        };
+        serde_json::to_writer(&mut *writer, &event)?;
+        writeln!(writer)?;
+    }
+    let summary = Event::Finished {
+        summary: &report.summary,
+    };
+    serde_json::to_writer(&mut *writer, &summary)?;
+    writeln!(writer)?;
+    Ok(())
+}
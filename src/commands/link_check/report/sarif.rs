@@ -0,0 +1,131 @@
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::{super::category::Category, Report, ReportEntry};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Write the report as SARIF 2.1.0, with a result for each failure and
+/// redirect located at the page linking to it, so it can be uploaded to
+/// GitHub code scanning with `github/codeql-action/upload-sarif`.
+pub(super) fn write(report: &Report, writer: &mut impl Write) -> Result<()> {
+    let entries: Vec<(&ReportEntry, Category)> = report
+        .results
+        .iter()
+        .filter_map(|entry| match entry.success {
+            true => entry.category.map(|category| (entry, category)),
+            false => Some((entry, entry.category.unwrap_or(Category::Broken))),
+        })
+        .collect();
+    let mut categories: Vec<Category> = entries.iter().map(|(_, category)| *category).collect();
+    categories.sort();
+    categories.dedup();
+
+    let rules: Vec<Value> = categories.iter().map(|&category| rule(category)).collect();
+    let results: Vec<Value> = entries
+        .iter()
+        .map(|(entry, category)| {
+            let rule_index = categories
+                .iter()
+                .position(|c| c == category)
+                .expect("every category has a rule");
+            result(entry, *category, rule_index)
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "docs-tools",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    });
+    serde_json::to_writer_pretty(&mut *writer, &sarif)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn rule(category: Category) -> Value {
+    let description = match category {
+        Category::Broken => "Broken link",
+        Category::Redirect => "Redirected link",
+        Category::Timeout => "Link timed out",
+        Category::Excluded => "Excluded link",
+        Category::RateLimited => "Link rate limited",
+        Category::Insecure => "Link with a TLS or certificate error",
+        Category::Shortened => "Link through a URL shortener",
+        Category::Affiliate => "Link with affiliate or referral parameters",
+        Category::Skipped => "Link skipped after its host's time budget ran out",
+        Category::PlainHttp => "Plain HTTP link or asset on an HTTPS page",
+    };
+    json!({
+        "id": category.to_string(),
+        "shortDescription": { "text": description },
+        "defaultConfiguration": { "level": level(category) },
+    })
+}
+
+fn result(entry: &ReportEntry, category: Category, rule_index: usize) -> Value {
+    let destination = entry
+        .redirects
+        .last()
+        .map(|redirect| redirect.location.as_str())
+        .unwrap_or_default();
+    let message = match category {
+        Category::Redirect => format!("{} redirects to {destination}", entry.url),
+        Category::Shortened => format!(
+            "{} goes through a URL shortener, replace with {destination}",
+            entry.url
+        ),
+        _ => {
+            let mut message = format!("{} is broken", entry.url);
+            if let Some(status) = entry.status {
+                message.push_str(&format!(" ({status})"));
+            }
+            if let Some(error) = &entry.error {
+                message.push_str(&format!(": {error}"));
+            }
+            message
+        }
+    };
+    // Prefer the page's source file, which code scanning can show inline
+    let location = entry
+        .referrer_file
+        .as_deref()
+        .or(entry.referrers.first().map(String::as_str))
+        .unwrap_or(entry.url.as_str());
+    let referrer = entry.referrers.first().map(String::as_str).unwrap_or("");
+    json!({
+        "ruleId": category.to_string(),
+        "ruleIndex": rule_index,
+        "level": level(category),
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": location },
+                "region": { "startLine": entry.referrer_line.unwrap_or(1) },
+            }
+        }],
+        // Identifies the same link on the same page across runs, so alerts
+        // are tracked rather than reopened
+        "partialFingerprints": { "linkOnPage/v1": format!("{referrer} -> {}", entry.url) },
+    })
+}
+
+fn level(category: Category) -> &'static str {
+    match category {
+        Category::Redirect | Category::Excluded | Category::Shortened | Category::Skipped => {
+            "warning"
+        }
+        _ => "error",
+    }
+}
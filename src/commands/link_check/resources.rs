@@ -0,0 +1,286 @@
+use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
+
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header::{HeaderMap, HeaderValue, CONNECTION},
+    ClientBuilder,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+use url::{Origin, Url};
+
+/// Without a limit on connections, checks may use one in this many of the
+/// files the process may open, leaving the rest for idle pooled connections,
+/// the cache database, reports and DNS lookups
+const FILE_LIMIT_PER_CONNECTION: usize = 2;
+
+/// Most keep-alive connections left open between requests, all to the
+/// origin kept alive
+const MAX_IDLE_CONNECTIONS: usize = 8;
+
+/// How long a keep-alive connection may stay idle before it's closed
+const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Ceilings on the connections and DNS lookups open at once during a run, so
+/// high concurrency queues work rather than running out of file descriptors
+/// (e.g., in CI containers with a low `ulimit -n`).
+///
+/// reqwest doesn't expose its connections, so they're limited by how they're
+/// used instead: work holding a connection sends its requests one after
+/// another, so has at most one connection in use, and connections are only
+/// kept alive afterwards to one origin (the checked site's), in a pool of
+/// idle connections set aside from the limit.
+#[derive(Debug)]
+pub(super) struct ResourceLimits {
+    /// Connections in use, beyond those set aside for idle connections
+    connections: Option<Arc<Semaphore>>,
+    dns_lookups: Option<Arc<Semaphore>>,
+    max_dns_lookups: Option<usize>,
+    /// Idle connections kept open to `kept_alive`, at most
+    max_idle_connections: usize,
+    kept_alive: Origin,
+}
+
+impl ResourceLimits {
+    /// Limits on connections and DNS lookups open at once, keeping
+    /// connections alive only to `kept_alive`'s origin. Without a limit on
+    /// connections, checks may use half of the process's limit on open
+    /// files, if it has one.
+    pub(super) fn new(
+        max_connections: Option<usize>,
+        max_dns_lookups: Option<usize>,
+        kept_alive: &Url,
+    ) -> Self {
+        let max_connections = max_connections.or_else(|| {
+            let max = file_descriptor_limit()? / FILE_LIMIT_PER_CONNECTION;
+            debug!("Limiting open connections to {max} from the limit on open files");
+            Some(max)
+        });
+        // Idle connections get at most half of the connections allowed
+        let max_idle_connections = max_connections
+            .map_or(MAX_IDLE_CONNECTIONS, |max| max / 2)
+            .min(MAX_IDLE_CONNECTIONS);
+        let semaphore = |max: usize| Arc::new(Semaphore::new(max.max(1)));
+        Self {
+            connections: max_connections.map(|max| semaphore(max - max_idle_connections)),
+            dns_lookups: max_dns_lookups.map(semaphore),
+            max_dns_lookups,
+            max_idle_connections,
+            kept_alive: kept_alive.origin(),
+        }
+    }
+
+    /// Run network work holding one of the connections allowed, waiting for
+    /// one to be released if they're all in use. The work must send its
+    /// requests one after another.
+    pub(super) async fn with_connection<F: Future>(&self, work: F) -> F::Output {
+        let _permit = acquire(self.connections.as_ref()).await;
+        work.await
+    }
+
+    /// Whether a client that can't be given the limited resolver (the lychee
+    /// client) stays within the limit on DNS lookups when checking
+    /// `concurrency` links at once, each making one lookup at a time.
+    pub(super) fn allow_unlimited_lookups(&self, concurrency: usize) -> bool {
+        self.max_dns_lookups.is_none_or(|max| concurrency <= max)
+    }
+
+    /// Ask for a request's connection to be closed once it's answered,
+    /// unless it's to the origin kept alive, so idle connections elsewhere
+    /// don't hold file descriptors.
+    pub(super) fn limit_idle(&self, url: &Url, headers: &mut HeaderMap) {
+        if url.origin() != self.kept_alive || self.max_idle_connections == 0 {
+            headers.insert(CONNECTION, HeaderValue::from_static("close"));
+        }
+    }
+
+    /// Run a blocking DNS lookup holding one of the lookups allowed, waiting
+    /// for one to be released if they're all in use. Only call this from
+    /// threads that may block.
+    pub(super) fn blocking_lookup<T>(&self, lookup: impl FnOnce() -> T) -> T {
+        let _permit = futures::executor::block_on(acquire(self.dns_lookups.as_ref()));
+        lookup()
+    }
+
+    /// Make an HTTP client queue DNS lookups beyond the limit, and close
+    /// connections rather than keep more idle than are set aside for them,
+    /// or keep them idle for long. Requests it sends must also go through
+    /// [`Self::limit_idle`].
+    pub(super) fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        builder
+            .dns_resolver(Arc::new(LimitedResolver {
+                lookups: self.dns_lookups.clone(),
+            }))
+            .pool_max_idle_per_host(self.max_idle_connections)
+            .pool_idle_timeout(IDLE_CONNECTION_TIMEOUT)
+    }
+}
+
+/// Resolves host names with the system resolver, at most as many at once as
+/// `--max-dns-lookups` allows
+struct LimitedResolver {
+    lookups: Option<Arc<Semaphore>>,
+}
+
+impl Resolve for LimitedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let lookups = self.lookups.clone();
+        Box::pin(async move {
+            let _permit = acquire(lookups.as_ref()).await;
+            // The port is replaced by the connector with the URL's
+            let addresses: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+async fn acquire(semaphore: Option<&Arc<Semaphore>>) -> Option<OwnedSemaphorePermit> {
+    // The semaphores are never closed, so acquiring only waits
+    semaphore?.clone().acquire_owned().await.ok()
+}
+
+/// The soft limit on the number of files the process may have open, or
+/// `None` if it's unlimited or unknown.
+#[cfg(unix)]
+fn file_descriptor_limit() -> Option<usize> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `getrlimit` only writes to the struct passed to it
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    match limit.rlim_cur {
+        libc::RLIM_INFINITY => None,
+        soft => usize::try_from(soft).ok(),
+    }
+}
+
+#[cfg(not(unix))]
+fn file_descriptor_limit() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    fn site() -> Url {
+        Url::parse("https://docs.example.com/").unwrap()
+    }
+
+    /// Counts work running at once, keeping the most seen
+    #[derive(Default)]
+    struct InFlight {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    impl InFlight {
+        fn start(&self) {
+            let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(current, Ordering::SeqCst);
+        }
+
+        fn finish(&self) {
+            self.current.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn connections_beyond_the_limit_queue_rather_than_fail() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut accepted = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                accepted.push(stream);
+            }
+        });
+
+        let limits = ResourceLimits::new(Some(6), Some(1), &site());
+        let in_flight = InFlight::default();
+        let connections = (0..50).map(|_| {
+            limits.with_connection(async {
+                in_flight.start();
+                let stream = TcpStream::connect(address).await;
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                in_flight.finish();
+                stream.map(drop)
+            })
+        });
+        let results = futures::future::join_all(connections).await;
+
+        // Half of the connections are set aside for idle ones
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(in_flight.max.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn lookups_beyond_the_limit_queue() {
+        let limits = Arc::new(ResourceLimits::new(None, Some(2), &site()));
+        let in_flight = Arc::new(InFlight::default());
+        let lookups: Vec<_> = (0..20)
+            .map(|_| {
+                let (limits, in_flight) = (limits.clone(), in_flight.clone());
+                tokio::task::spawn_blocking(move || {
+                    limits.blocking_lookup(|| {
+                        in_flight.start();
+                        std::thread::sleep(Duration::from_millis(5));
+                        in_flight.finish();
+                    })
+                })
+            })
+            .collect();
+        for lookup in lookups {
+            lookup.await.unwrap();
+        }
+
+        assert_eq!(in_flight.max.load(Ordering::SeqCst), 2);
+        assert!(limits.allow_unlimited_lookups(2));
+        assert!(!limits.allow_unlimited_lookups(3));
+    }
+
+    #[test]
+    fn only_the_site_keeps_connections_alive() {
+        let limits = ResourceLimits::new(Some(100), None, &site());
+        assert_eq!(limits.max_idle_connections, MAX_IDLE_CONNECTIONS);
+        let closes = |url: &str| {
+            let mut headers = HeaderMap::new();
+            limits.limit_idle(&Url::parse(url).unwrap(), &mut headers);
+            headers
+                .get(CONNECTION)
+                .is_some_and(|value| value == "close")
+        };
+        assert!(!closes("https://docs.example.com/guides/intro"));
+        assert!(closes("https://example.com/"));
+        assert!(closes("http://docs.example.com/"));
+
+        // With too few connections to set any aside, none are kept alive
+        let limits = ResourceLimits::new(Some(1), None, &site());
+        assert_eq!(limits.max_idle_connections, 0);
+        assert_eq!(limits.connections.unwrap().available_permits(), 1);
+    }
+
+    #[test]
+    fn connections_default_to_half_the_file_limit() {
+        let limits = ResourceLimits::new(None, None, &site());
+        let available = limits
+            .connections
+            .as_ref()
+            .map(|connections| connections.available_permits() + limits.max_idle_connections);
+        let expected = file_descriptor_limit().map(|max| max / FILE_LIMIT_PER_CONNECTION);
+        assert_eq!(available, expected);
+        assert!(limits.dns_lookups.is_none());
+    }
+}
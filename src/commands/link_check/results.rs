@@ -0,0 +1,159 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+    time::Duration,
+};
+
+use url::Url;
+
+use super::{category::Category, redirects::Redirect, utils::normalize_url};
+
+/// The outcome of checking a single link
+#[derive(Debug, Clone)]
+pub struct CheckRecord {
+    pub url: Url,
+    pub referrer: Option<Url>,
+    /// HTTP status code, if a response was received
+    pub status: Option<u16>,
+    pub elapsed: Duration,
+    /// Why the check failed, or `None` if it succeeded
+    pub error: Option<String>,
+    /// The kind of failure, if the check failed
+    pub category: Option<Category>,
+    /// Date the Wayback Machine last archived the URL working, for broken
+    /// links looked up there
+    pub last_alive: Option<String>,
+    /// Why connections to the host failed, for failures without a response
+    /// diagnosed after the crawl
+    pub network_diagnosis: Option<String>,
+}
+
+impl CheckRecord {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Every check made during a crawl, along with where each URL was first found
+/// so that the chain of referrers back to the start URL can be reconstructed.
+#[derive(Debug, Default)]
+pub(super) struct Results {
+    records: Mutex<Vec<CheckRecord>>,
+    referrers: Mutex<HashMap<Url, Url>>,
+    /// Redirects followed for each URL that redirected
+    redirects: Mutex<HashMap<Url, Vec<Redirect>>>,
+    /// URLs skipped by an exclude pattern
+    excluded: Mutex<BTreeSet<Url>>,
+}
+
+impl Results {
+    pub(super) fn record(&self, record: CheckRecord) {
+        if let Some(referrer) = &record.referrer {
+            self.add_referrer(&record.url, referrer);
+        }
+        self.records.lock().unwrap().push(record);
+    }
+
+    /// Record where a URL was found, unless it was already found elsewhere.
+    pub(super) fn add_referrer(&self, url: &Url, referrer: &Url) {
+        self.referrers
+            .lock()
+            .unwrap()
+            .entry(normalize_url(url))
+            .or_insert_with(|| referrer.clone());
+    }
+
+    /// URLs whose check failed, in the order they were checked.
+    pub(super) fn failed_urls(&self) -> Vec<Url> {
+        let mut urls: Vec<Url> = Vec::new();
+        for record in self.records.lock().unwrap().iter() {
+            if !record.is_success() && !urls.contains(&record.url) {
+                urls.push(record.url.clone());
+            }
+        }
+        urls
+    }
+
+    pub(super) fn set_last_alive(&self, url: &Url, date: &str) {
+        for record in self.records.lock().unwrap().iter_mut() {
+            if &record.url == url {
+                record.last_alive = Some(date.to_string());
+            }
+        }
+    }
+
+    pub(super) fn set_network_diagnosis(&self, url: &Url, diagnosis: &str) {
+        for record in self.records.lock().unwrap().iter_mut() {
+            if &record.url == url {
+                record.network_diagnosis = Some(diagnosis.to_string());
+            }
+        }
+    }
+
+    pub(super) fn add_redirects(&self, url: &Url, redirects: Vec<Redirect>) {
+        self.redirects
+            .lock()
+            .unwrap()
+            .insert(normalize_url(url), redirects);
+    }
+
+    /// Redirects followed when checking a URL, if it redirected.
+    pub(super) fn redirects(&self, url: &Url) -> Vec<Redirect> {
+        self.redirects
+            .lock()
+            .unwrap()
+            .get(&normalize_url(url))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Redirects followed for every URL that redirected.
+    pub(super) fn all_redirects(&self) -> Vec<(Url, Vec<Redirect>)> {
+        self.redirects
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(url, redirects)| (url.clone(), redirects.clone()))
+            .collect()
+    }
+
+    pub(super) fn add_excluded(&self, url: &Url) {
+        self.excluded.lock().unwrap().insert(normalize_url(url));
+    }
+
+    pub(super) fn excluded(&self) -> Vec<Url> {
+        self.excluded.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub(super) fn excluded_count(&self) -> usize {
+        self.excluded.lock().unwrap().len()
+    }
+
+    pub(super) fn redirected_count(&self) -> usize {
+        self.redirects.lock().unwrap().len()
+    }
+
+    /// All records, sorted by URL for stable output.
+    pub(super) fn records(&self) -> Vec<CheckRecord> {
+        let mut records = self.records.lock().unwrap().clone();
+        records.sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+        records
+    }
+
+    /// The pages leading to a URL, starting with its immediate referrer and
+    /// ending with the start URL.
+    pub(super) fn referrer_chain(&self, record: &CheckRecord) -> Vec<Url> {
+        let referrers = self.referrers.lock().unwrap();
+        let mut chain: Vec<Url> = Vec::new();
+        let mut next = record.referrer.clone();
+        while let Some(referrer) = next {
+            // Guard against cycles between redirect aliases
+            if chain.contains(&referrer) {
+                break;
+            }
+            next = referrers.get(&normalize_url(&referrer)).cloned();
+            chain.push(referrer);
+        }
+        chain
+    }
+}
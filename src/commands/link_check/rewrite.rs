@@ -0,0 +1,107 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{debug, warn};
+use url::Url;
+
+/// A rewrite of links found on pages before they're queued, as configured
+/// under `[[rewrite]]`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct RewriteConfig {
+    /// Regex of links to rewrite (e.g., `^https://example\.com/` for
+    /// absolute production URLs)
+    url: String,
+    /// What the first match of `url` is replaced with, referring to its
+    /// groups as `$1`, as `${NAME}` is read from the environment (e.g., for
+    /// the preview deployment's URL)
+    replace: Option<String>,
+    /// Query parameters removed from matching links (e.g., `ref`), with a
+    /// trailing `*` matching every parameter with the prefix (e.g., `utm_*`)
+    #[serde(default)]
+    strip_params: Vec<String>,
+}
+
+/// A rewrite of links matching a pattern, such as pointing absolute
+/// production URLs at a preview deployment or removing tracking parameters
+#[derive(Debug)]
+pub(super) struct RewriteRule {
+    pattern: Regex,
+    replace: Option<String>,
+    strip_params: Vec<String>,
+}
+
+impl RewriteRule {
+    pub(super) fn new(config: &RewriteConfig) -> Result<Self, String> {
+        if config.replace.is_none() && config.strip_params.is_empty() {
+            return Err(format!(
+                "rewrite of `{}` needs `replace` or `strip-params`",
+                config.url
+            ));
+        }
+        Ok(Self {
+            pattern: Regex::new(&config.url).map_err(|e| e.to_string())?,
+            replace: config.replace.clone(),
+            strip_params: config.strip_params.clone(),
+        })
+    }
+
+    /// The link rewritten, or `None` if the rule doesn't apply to it.
+    fn apply(&self, url: &Url) -> Option<Url> {
+        if !self.pattern.is_match(url.as_str()) {
+            return None;
+        }
+        let mut rewritten = match &self.replace {
+            Some(replace) => {
+                let replaced = self.pattern.replace(url.as_str(), replace.as_str());
+                match Url::parse(&replaced) {
+                    Ok(rewritten) => rewritten,
+                    Err(e) => {
+                        warn!(
+                            "Not rewriting {}, as it gives an invalid URL {replaced}: {e}",
+                            url.as_str()
+                        );
+                        return None;
+                    }
+                }
+            }
+            None => url.clone(),
+        };
+        self.strip_query(&mut rewritten);
+        Some(rewritten)
+    }
+
+    /// Remove the stripped parameters from a URL's query, leaving it as it
+    /// was if it has none of them.
+    fn strip_query(&self, url: &mut Url) {
+        let stripped = |name: &str| {
+            self.strip_params
+                .iter()
+                .any(|param| match param.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name == param,
+                })
+        };
+        let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        if !pairs.iter().any(|(name, _)| stripped(name)) {
+            return;
+        }
+        let kept: Vec<_> = pairs.iter().filter(|(name, _)| !stripped(name)).collect();
+        if kept.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+}
+
+/// A link rewritten by each rule applying to it in turn.
+pub(super) fn rewrite(rules: &[RewriteRule], url: Url) -> Url {
+    let rewritten = rules
+        .iter()
+        .fold(url.clone(), |url, rule| rule.apply(&url).unwrap_or(url));
+    if rewritten != url {
+        debug!("Rewrote {} to {}", url.as_str(), rewritten.as_str());
+    }
+    rewritten
+}
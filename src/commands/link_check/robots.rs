@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::sync::OnceCell;
+use tracing::debug;
+use url::Url;
+
+use super::rate_limit::RateLimiter;
+
+/// The rules in a `robots.txt` file that apply to this crawler
+#[derive(Debug, Default)]
+struct Rules {
+    /// `Allow` (true) and `Disallow` (false) path patterns
+    patterns: Vec<(String, bool)>,
+    crawl_delay: Option<Duration>,
+}
+
+/// `robots.txt` rules for each origin, fetched the first time a URL on the
+/// origin is checked
+#[derive(Debug)]
+pub(super) struct Robots {
+    user_agent: String,
+    origins: Mutex<HashMap<String, Arc<OnceCell<Rules>>>>,
+}
+
+impl Robots {
+    pub(super) fn new(user_agent: &str) -> Self {
+        Self {
+            user_agent: user_agent.to_lowercase(),
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the origin's `robots.txt` allows crawling a URL. The first
+    /// time an origin is seen, its `Crawl-delay` is applied to the rate
+    /// limiter.
+    pub(super) async fn allows(
+        &self,
+        client: &reqwest::Client,
+        rate_limiter: &RateLimiter,
+        url: &Url,
+    ) -> bool {
+        let origin = url.origin().ascii_serialization();
+        let cell = self
+            .origins
+            .lock()
+            .unwrap()
+            .entry(origin)
+            .or_default()
+            .clone();
+        let rules = cell
+            .get_or_init(|| async {
+                let rules = self.fetch(client, rate_limiter, url).await;
+                if let (Some(delay), Some(host)) = (rules.crawl_delay, url.host_str()) {
+                    debug!("Waiting {delay:?} between requests to {host} per robots.txt");
+                    rate_limiter.set_min_interval(host, delay);
+                }
+                rules
+            })
+            .await;
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+        rules.allows(&path)
+    }
+
+    /// Fetch and parse the `robots.txt` for a URL's origin. A missing or
+    /// unreadable file allows everything.
+    async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        rate_limiter: &RateLimiter,
+        url: &Url,
+    ) -> Rules {
+        let Ok(robots_url) = url.join("/robots.txt") else {
+            return Rules::default();
+        };
+        rate_limiter.wait(&robots_url).await;
+        let response = match client.get(robots_url.clone()).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                debug!("No robots.txt at {}: {}", robots_url, response.status());
+                return Rules::default();
+            }
+            Err(e) => {
+                debug!("Failed to fetch {}: {}", robots_url, e);
+                return Rules::default();
+            }
+        };
+        match response.text().await {
+            Ok(content) => Rules::parse(&content, &self.user_agent),
+            Err(e) => {
+                debug!("Failed to read {}: {}", robots_url, e);
+                Rules::default()
+            }
+        }
+    }
+}
+
+impl Rules {
+    /// Parse the rules from the most specific group matching the user agent,
+    /// falling back to the `*` group.
+    fn parse(content: &str, user_agent: &str) -> Self {
+        let mut specific: Option<Rules> = None;
+        let mut wildcard: Option<Rules> = None;
+        // User agents of the group being read, and whether its rules have
+        // started (a `User-agent` line after rules starts a new group)
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        let mut current = Rules::default();
+
+        let mut finish_group = |agents: &[String], rules: Rules| {
+            if agents
+                .iter()
+                .any(|agent| agent != "*" && user_agent.contains(agent.as_str()))
+            {
+                specific.get_or_insert_with(Rules::default).extend(rules);
+            } else if agents.iter().any(|agent| agent == "*") {
+                wildcard.get_or_insert_with(Rules::default).extend(rules);
+            }
+        };
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        finish_group(&agents, std::mem::take(&mut current));
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    agents.push(value.to_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty `Disallow` allows everything
+                    if !value.is_empty() {
+                        current.patterns.push((
+                            value.to_string(),
+                            field.trim().eq_ignore_ascii_case("allow"),
+                        ));
+                    }
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    current.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|delay| delay.is_finite() && *delay > 0.0)
+                        .map(Duration::from_secs_f64);
+                }
+                _ => {}
+            }
+        }
+        finish_group(&agents, current);
+        specific.or(wildcard).unwrap_or_default()
+    }
+
+    fn extend(&mut self, other: Rules) {
+        self.patterns.extend(other.patterns);
+        self.crawl_delay = self.crawl_delay.or(other.crawl_delay);
+    }
+
+    /// Whether a path (with its query) is allowed. The longest matching
+    /// pattern wins, with `Allow` winning ties.
+    fn allows(&self, path: &str) -> bool {
+        self.patterns
+            .iter()
+            .filter(|(pattern, _)| matches(pattern, path))
+            .max_by_key(|(pattern, allow)| (pattern.len(), *allow))
+            .is_none_or(|(_, allow)| *allow)
+    }
+}
+
+/// Whether a `robots.txt` path pattern matches a path. Patterns are prefixes,
+/// where `*` matches any characters and a trailing `$` anchors the end.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return true;
+    };
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part of an anchored pattern must match the end of the path
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "
+# Comments and unknown fields are ignored
+Sitemap: https://example.com/sitemap.xml
+
+User-agent: *
+Disallow: /private/
+Allow: /private/public-page
+Disallow: /*.pdf$
+Disallow: /search?
+Crawl-delay: 2
+
+User-agent: docs-tools
+User-agent: other-bot
+Disallow: /drafts
+Allow: /drafts/published/
+Crawl-delay: 0.5
+
+User-agent: unrelated
+Disallow: /
+";
+
+    #[test]
+    fn wildcard_group_applies_without_a_specific_one() {
+        let rules = Rules::parse(ROBOTS, "mozilla/5.0 (compatible; examplebot)");
+        assert!(rules.allows("/"));
+        assert!(rules.allows("/docs/guide"));
+        assert!(!rules.allows("/private/notes"));
+        // The longer `Allow` wins over the shorter `Disallow`
+        assert!(rules.allows("/private/public-page"));
+        assert!(!rules.allows("/files/manual.pdf"));
+        assert!(rules.allows("/files/manual.pdf.html"));
+        assert!(!rules.allows("/search?q=rust"));
+        assert!(rules.allows("/search"));
+        assert_eq!(rules.crawl_delay, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn specific_group_replaces_the_wildcard_one() {
+        let rules = Rules::parse(ROBOTS, "docs-tools/0.4");
+        assert!(!rules.allows("/drafts"));
+        assert!(!rules.allows("/drafts/unpublished"));
+        assert!(rules.allows("/drafts/published/post"));
+        // Only the matching group's rules apply
+        assert!(rules.allows("/private/notes"));
+        assert_eq!(rules.crawl_delay, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn missing_or_empty_rules_allow_everything() {
+        let rules = Rules::parse("", "docs-tools");
+        assert!(rules.allows("/anything"));
+        assert_eq!(rules.crawl_delay, None);
+
+        let rules = Rules::parse("User-agent: *\nDisallow:\nCrawl-delay: soon", "docs-tools");
+        assert!(rules.allows("/anything"));
+        assert_eq!(rules.crawl_delay, None);
+    }
+
+    #[test]
+    fn allow_wins_ties() {
+        let rules = Rules::parse("User-agent: *\nDisallow: /page\nAllow: /page", "docs-tools");
+        assert!(rules.allows("/page"));
+    }
+
+    #[test]
+    fn patterns_match_prefixes_wildcards_and_ends() {
+        assert!(matches("/docs", "/docs/guide"));
+        assert!(matches("/docs", "/docs-old"));
+        assert!(!matches("/docs", "/api/docs"));
+        assert!(matches("/*/edit", "/pages/edit"));
+        assert!(matches("/*/edit", "/pages/edit/history"));
+        assert!(!matches("/*/edit$", "/pages/edit/history"));
+        assert!(matches("/*.json$", "/api/v1/data.json"));
+        assert!(!matches("/*.json$", "/api/v1/data.json?x=1"));
+        assert!(matches("/a*b*c", "/a-1-b-2-c-3"));
+        assert!(!matches("/a*b*c", "/a-1-c-2-b"));
+        assert!(matches("/exact$", "/exact"));
+        assert!(!matches("/exact$", "/exactly"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::str::FromStr;
+
+use url::Url;
+
+use super::utils::StartsWith as _;
+
+/// Describes which URLs a scope rule applies to
+#[derive(Debug, Clone)]
+pub(crate) enum ScopeMatcher {
+    /// URLs sharing this origin and path prefix
+    Prefix(Url),
+    /// URLs on any subdomain of this host (given as `*.example.com`), over
+    /// either HTTP or HTTPS
+    Subdomains(String),
+    /// URLs on any in-scope origin whose path is this path or under it, so
+    /// `/docs` matches `/docs/api` but not `/docs-old`. Only used for
+    /// recursion exclusions, never to widen the scope.
+    Path(String),
+}
+
+impl ScopeMatcher {
+    fn matches(&self, url: &Url) -> bool {
+        match self {
+            ScopeMatcher::Prefix(prefix) => url.starts_with(prefix),
+            ScopeMatcher::Subdomains(domain) => url.host_str().is_some_and(|host| {
+                host.len() > domain.len()
+                    && host.ends_with(domain.as_str())
+                    && host[..host.len() - domain.len()].ends_with('.')
+            }),
+            ScopeMatcher::Path(path) => url
+                .path()
+                .strip_prefix(path.trim_end_matches('/'))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+        }
+    }
+
+    /// Parse a recursion exclusion, which may additionally be a bare path
+    /// prefix like `/reference/generated`.
+    pub(crate) fn parse_no_recurse(s: &str) -> Result<Self, String> {
+        if s.starts_with('/') {
+            return Ok(ScopeMatcher::Path(s.to_string()));
+        }
+        s.parse()
+    }
+
+    /// How specific the matcher is, used to pick between overlapping rules.
+    /// Prefixes always beat wildcards, and longer prefixes beat shorter ones.
+    fn specificity(&self) -> usize {
+        match self {
+            ScopeMatcher::Prefix(prefix) => prefix.path().len() + 1,
+            ScopeMatcher::Subdomains(_) | ScopeMatcher::Path(_) => 0,
+        }
+    }
+}
+
+impl FromStr for ScopeMatcher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(domain) = s.strip_prefix("*.") {
+            if domain.is_empty() || domain.contains(['/', '*', ':']) {
+                return Err(format!("Invalid wildcard host pattern: {s}"));
+            }
+            return Ok(ScopeMatcher::Subdomains(domain.to_lowercase()));
+        }
+        Url::parse(s)
+            .map(ScopeMatcher::Prefix)
+            .map_err(|e| format!("Expected a URL or a `*.example.com` pattern: {e}"))
+    }
+}
+
+/// A single rule describing a set of URLs that should be treated as internal
+#[derive(Debug, Clone)]
+pub(super) struct ScopeRule {
+    matcher: ScopeMatcher,
+    /// Whether internal HTML pages matching this rule should have their links
+    /// followed, or only be checked themselves
+    recurse: bool,
+}
+
+impl ScopeRule {
+    pub(super) fn new(matcher: ScopeMatcher, recurse: bool) -> Self {
+        Self { matcher, recurse }
+    }
+}
+
+/// The set of rules determining which URLs are internal to the crawl
+#[derive(Debug, Clone)]
+pub(super) struct Scope {
+    rules: Vec<ScopeRule>,
+    /// Matchers for internal URLs that are checked but never recursed into,
+    /// regardless of which rule they fall under
+    no_recurse: Vec<ScopeMatcher>,
+}
+
+impl Scope {
+    /// Create a scope whose primary rule is the base URL, which is always
+    /// recursed into.
+    pub(super) fn new(base_url: Url) -> Self {
+        Self {
+            rules: vec![ScopeRule::new(ScopeMatcher::Prefix(base_url), true)],
+            no_recurse: Vec::new(),
+        }
+    }
+
+    pub(super) fn add_rule(&mut self, rule: ScopeRule) {
+        self.rules.push(rule);
+    }
+
+    pub(super) fn add_no_recurse(&mut self, matcher: ScopeMatcher) {
+        self.no_recurse.push(matcher);
+    }
+
+    /// Whether the URL matches any scope rule.
+    pub(super) fn is_internal(&self, url: &Url) -> bool {
+        self.rules.iter().any(|rule| rule.matcher.matches(url))
+    }
+
+    /// Whether links found on the URL should be followed.
+    ///
+    /// Exclusions take precedence over every rule. Otherwise, when several
+    /// rules match, the most specific one wins.
+    pub(super) fn should_recurse(&self, url: &Url) -> bool {
+        if self.no_recurse.iter().any(|matcher| matcher.matches(url)) {
+            return false;
+        }
+        self.rules
+            .iter()
+            .filter(|rule| rule.matcher.matches(url))
+            .max_by_key(|rule| rule.matcher.specificity())
+            .is_some_and(|rule| rule.recurse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(matcher: &str, url: &str) -> bool {
+        ScopeMatcher::parse_no_recurse(matcher)
+            .unwrap()
+            .matches(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn paths_match_whole_segments() {
+        assert!(matches("/docs", "https://example.com/docs"));
+        assert!(matches("/docs", "https://example.com/docs/"));
+        assert!(matches("/docs", "https://example.com/docs/api/index.html"));
+        assert!(matches("/docs/", "https://example.com/docs/api"));
+        assert!(matches("/docs/", "https://example.com/docs"));
+        assert!(!matches("/docs", "https://example.com/docs-old"));
+        assert!(!matches("/docs", "https://example.com/docsearch/"));
+        assert!(!matches("/docs", "https://example.com/api/docs"));
+        assert!(matches("/", "https://example.com/anything"));
+    }
+
+    #[test]
+    fn subdomain_wildcards_need_a_subdomain() {
+        assert!(matches("*.example.com", "https://docs.example.com/"));
+        assert!(matches("*.example.com", "http://a.b.example.com/"));
+        assert!(!matches("*.example.com", "https://example.com/"));
+        assert!(!matches("*.example.com", "https://badexample.com/"));
+    }
+
+    #[test]
+    fn exclusions_take_precedence_over_rules() {
+        let mut scope = Scope::new(Url::parse("https://example.com/").unwrap());
+        scope.add_no_recurse(ScopeMatcher::parse_no_recurse("/reference/generated").unwrap());
+        let url = |path: &str| Url::parse(&format!("https://example.com{path}")).unwrap();
+        assert!(scope.should_recurse(&url("/reference/")));
+        assert!(!scope.should_recurse(&url("/reference/generated/a.html")));
+        assert!(scope.should_recurse(&url("/reference/generated-guide")));
+        assert!(scope.is_internal(&url("/reference/generated/a.html")));
+    }
+}
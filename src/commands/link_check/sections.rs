@@ -0,0 +1,96 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use tracing::info;
+use url::Url;
+
+use super::{utils::StartsWith, CheckRecord};
+
+/// Checks of the links on the pages of one top-level section of the site
+/// (e.g., `/guides`)
+#[derive(Debug, Serialize)]
+pub(super) struct SectionStats {
+    /// Top-level path segment relative to the base URL, or `/` for pages at
+    /// the top
+    pub(super) section: String,
+    pub(super) total: usize,
+    pub(super) failed: usize,
+    /// Number of pages in the section with at least one failing link
+    pub(super) failing_pages: usize,
+}
+
+impl SectionStats {
+    /// Share of the section's links that failed, from 0 to 1.
+    pub(super) fn failure_rate(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.failed as f64 / self.total as f64
+    }
+}
+
+/// Checks grouped by the section of the page each link is on, most failures
+/// first. Links on pages outside the base URL aren't counted.
+pub(super) fn by_section(records: &[CheckRecord], base_url: &Url) -> Vec<SectionStats> {
+    let mut sections: BTreeMap<String, (usize, usize, BTreeSet<&Url>)> = BTreeMap::new();
+    for record in records {
+        let page = record.referrer.as_ref().unwrap_or(&record.url);
+        let Some(section) = section_of(base_url, page) else {
+            continue;
+        };
+        let (total, failed, failing_pages) = sections.entry(section).or_default();
+        *total += 1;
+        if !record.is_success() {
+            *failed += 1;
+            failing_pages.insert(page);
+        }
+    }
+
+    let mut sections: Vec<SectionStats> = sections
+        .into_iter()
+        .map(|(section, (total, failed, failing_pages))| SectionStats {
+            section,
+            total,
+            failed,
+            failing_pages: failing_pages.len(),
+        })
+        .collect();
+    sections.sort_by(|a, b| {
+        b.failed
+            .cmp(&a.failed)
+            .then(b.failure_rate().total_cmp(&a.failure_rate()))
+            .then(a.section.cmp(&b.section))
+    });
+    sections
+}
+
+/// The top-level section of a page under the base URL.
+fn section_of(base_url: &Url, page: &Url) -> Option<String> {
+    if !page.starts_with(base_url) {
+        return None;
+    }
+    let relative = &page.path()[base_url.path().len()..];
+    Some(
+        match relative.split('/').find(|segment| !segment.is_empty()) {
+            Some(segment) => format!("/{segment}"),
+            None => "/".to_string(),
+        },
+    )
+}
+
+pub(super) fn log(sections: &[SectionStats]) {
+    if sections.iter().all(|section| section.failed == 0) {
+        return;
+    }
+    info!("Failures by section:");
+    for section in sections.iter().filter(|section| section.failed > 0) {
+        info!(
+            "  {}: {} of {} links failed ({:.1}%) on {} pages",
+            section.section,
+            section.failed,
+            section.total,
+            section.failure_rate() * 100.0,
+            section.failing_pages
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use url::Url;
+
+/// Hosts of well-known URL shorteners
+const SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "buff.ly",
+    "cutt.ly",
+    "goo.gl",
+    "is.gd",
+    "lnkd.in",
+    "ow.ly",
+    "rb.gy",
+    "rebrand.ly",
+    "shorturl.at",
+    "t.co",
+    "t.ly",
+    "tiny.cc",
+    "tinyurl.com",
+];
+
+/// URL shorteners, whose links are expanded so the destination is checked
+/// and can be suggested as a replacement
+#[derive(Debug)]
+pub(super) struct Shorteners {
+    hosts: HashSet<String>,
+}
+
+impl Shorteners {
+    /// The well-known shorteners plus any extra hosts.
+    pub(super) fn new(extra: &[String]) -> Self {
+        Self {
+            hosts: SHORTENERS
+                .iter()
+                .map(|host| host.to_string())
+                .chain(extra.iter().map(|host| host.to_lowercase()))
+                .collect(),
+        }
+    }
+
+    pub(super) fn contains(&self, url: &Url) -> bool {
+        url.host_str().is_some_and(|host| {
+            let host = host.strip_prefix("www.").unwrap_or(host);
+            self.hosts.contains(host)
+        })
+    }
+}
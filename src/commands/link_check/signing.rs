@@ -0,0 +1,561 @@
+use chrono::{DateTime, Utc};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+    Request, RequestBuilder,
+};
+use ring::{digest, hmac};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::debug;
+use url::Url;
+
+/// Characters left unencoded in SigV4's canonical request: letters, digits
+/// and `-_.~`
+const SIGV4_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+const DEFAULT_SIGNATURE_HEADER: &str = "X-Signature";
+const DEFAULT_TIMESTAMP_HEADER: &str = "X-Timestamp";
+
+/// How to sign requests for links matching a pattern, as configured under
+/// `[[sign]]`. Secrets are best given as `${NAME}` environment variables.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct SigningConfig {
+    /// Regex of URLs whose requests are signed
+    url: String,
+    /// Signing scheme: `aws-sigv4` or `hmac`
+    scheme: String,
+    /// AWS region of the endpoint (e.g., `us-east-1`), for `aws-sigv4`
+    region: Option<String>,
+    /// AWS service of the endpoint (e.g., `execute-api`), for `aws-sigv4`
+    service: Option<String>,
+    /// AWS access key ID, for `aws-sigv4`
+    access_key: Option<String>,
+    /// AWS secret access key, for `aws-sigv4`
+    secret_key: Option<String>,
+    /// AWS session token of temporary credentials, for `aws-sigv4`
+    session_token: Option<String>,
+    /// Shared secret, for `hmac`
+    secret: Option<String>,
+    /// Header carrying the signature, for `hmac` [default: X-Signature]
+    header: Option<String>,
+    /// Header carrying the Unix time the request was signed at, for `hmac`
+    /// [default: X-Timestamp]
+    timestamp_header: Option<String>,
+}
+
+/// How to sign a request
+#[derive(Debug)]
+pub(super) enum SigningScheme {
+    /// AWS Signature Version 4, for API Gateway and other AWS endpoints
+    AwsSigV4 {
+        region: String,
+        service: String,
+        access_key: String,
+        secret_key: String,
+        session_token: Option<String>,
+    },
+    /// A hex HMAC-SHA256 of the method, path and query, timestamp and body
+    /// hash, each on its own line, for gateways checking a shared secret
+    Hmac {
+        key: hmac::Key,
+        header: HeaderName,
+        timestamp_header: HeaderName,
+    },
+}
+
+/// Signing for requests to authenticated API gateways, so links to them can
+/// be checked rather than excluded
+#[derive(Debug)]
+pub(super) struct RequestSigner {
+    pattern: Regex,
+    scheme: SigningScheme,
+}
+
+impl RequestSigner {
+    pub(super) fn new(config: &SigningConfig) -> Result<Self, String> {
+        let required = |value: &Option<String>, key: &str| {
+            value
+                .clone()
+                .ok_or_else(|| format!("`{key}` is required for {} signing", config.scheme))
+        };
+        let header = |value: &Option<String>, default: &str| {
+            let name = value.as_deref().unwrap_or(default);
+            HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("invalid header name `{name}`"))
+        };
+        let scheme = match config.scheme.as_str() {
+            "aws-sigv4" => SigningScheme::AwsSigV4 {
+                region: required(&config.region, "region")?,
+                service: required(&config.service, "service")?,
+                access_key: required(&config.access_key, "access-key")?,
+                secret_key: required(&config.secret_key, "secret-key")?,
+                session_token: config
+                    .session_token
+                    .clone()
+                    .filter(|token| !token.is_empty()),
+            },
+            "hmac" => SigningScheme::Hmac {
+                key: hmac::Key::new(
+                    hmac::HMAC_SHA256,
+                    required(&config.secret, "secret")?.as_bytes(),
+                ),
+                header: header(&config.header, DEFAULT_SIGNATURE_HEADER)?,
+                timestamp_header: header(&config.timestamp_header, DEFAULT_TIMESTAMP_HEADER)?,
+            },
+            scheme => {
+                return Err(format!(
+                    "unknown signing scheme `{scheme}`, expected `aws-sigv4` or `hmac`"
+                ))
+            }
+        };
+        Ok(Self {
+            pattern: Regex::new(&config.url).map_err(|e| e.to_string())?,
+            scheme,
+        })
+    }
+
+    /// Whether requests for a URL are signed. Fragments aren't sent, so are
+    /// ignored.
+    pub(super) fn matches(&self, url: &Url) -> bool {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        self.pattern.is_match(url.as_str())
+    }
+
+    /// Sign a request for a matching URL, once everything else about it is
+    /// set.
+    pub(super) fn sign(&self, request: RequestBuilder) -> RequestBuilder {
+        self.scheme.sign(request)
+    }
+}
+
+impl SigningScheme {
+    /// Add the signature headers to a request, once everything else about
+    /// it is set.
+    pub(super) fn sign(&self, request: RequestBuilder) -> RequestBuilder {
+        let Some(built) = request.try_clone().and_then(|clone| clone.build().ok()) else {
+            // Streaming bodies can't be read up front, nor so signed
+            return request;
+        };
+        request.headers(self.signature_headers(&built))
+    }
+
+    fn signature_headers(&self, request: &Request) -> HeaderMap {
+        self.signature_headers_at(request, Utc::now())
+    }
+
+    /// The signature headers for a request signed at `now`.
+    fn signature_headers_at(&self, request: &Request, now: DateTime<Utc>) -> HeaderMap {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+        let body_hash = hex(digest::digest(&digest::SHA256, body).as_ref());
+        let mut headers = HeaderMap::new();
+        match self {
+            Self::AwsSigV4 {
+                region,
+                service,
+                access_key,
+                secret_key,
+                session_token,
+            } => {
+                let url = request.url();
+                let mut signed = vec![
+                    ("host".to_string(), host_header(url)),
+                    ("x-amz-content-sha256".to_string(), body_hash.clone()),
+                    (
+                        "x-amz-date".to_string(),
+                        now.format("%Y%m%dT%H%M%SZ").to_string(),
+                    ),
+                ];
+                if let Some(token) = session_token {
+                    signed.push(("x-amz-security-token".to_string(), token.clone()));
+                }
+                let signature = SigV4::new(
+                    request.method().as_str(),
+                    url,
+                    &mut signed,
+                    &body_hash,
+                    SigV4Scope {
+                        secret_key,
+                        region,
+                        service,
+                        now,
+                    },
+                );
+                // Gateways rejecting a signature say which canonical request
+                // they expected, to compare against
+                debug!(
+                    "Signed {url} with SigV4\nCanonical request:\n{}\nString to sign:\n{}",
+                    signature.canonical_request, signature.string_to_sign
+                );
+                let authorization = format!(
+                    "AWS4-HMAC-SHA256 Credential={access_key}/{}, SignedHeaders={}, Signature={}",
+                    signature.scope, signature.signed_headers, signature.signature
+                );
+                for (name, value) in signed.into_iter().filter(|(name, _)| name != "host") {
+                    insert(&mut headers, &name, &value);
+                }
+                insert(&mut headers, AUTHORIZATION.as_str(), &authorization);
+            }
+            Self::Hmac {
+                key,
+                header,
+                timestamp_header,
+            } => {
+                let timestamp = now.timestamp().to_string();
+                let url = request.url();
+                let path_and_query = match url.query() {
+                    Some(query) => format!("{}?{query}", url.path()),
+                    None => url.path().to_string(),
+                };
+                let message = format!(
+                    "{}\n{path_and_query}\n{timestamp}\n{body_hash}",
+                    request.method()
+                );
+                let signature = hex(hmac::sign(key, message.as_bytes()).as_ref());
+                insert(&mut headers, header.as_str(), &signature);
+                insert(&mut headers, timestamp_header.as_str(), &timestamp);
+            }
+        }
+        headers
+    }
+}
+
+/// Who signs a SigV4 request for what, and when
+struct SigV4Scope<'a> {
+    secret_key: &'a str,
+    region: &'a str,
+    service: &'a str,
+    now: DateTime<Utc>,
+}
+
+/// An AWS Signature Version 4 signature, with the steps to it
+struct SigV4 {
+    canonical_request: String,
+    string_to_sign: String,
+    /// The date, region and service signed for
+    scope: String,
+    /// Names of the signed headers, separated by `;`
+    signed_headers: String,
+    signature: String,
+}
+
+impl SigV4 {
+    /// Sign a request with the given headers, named in lowercase, which are
+    /// sorted as they're signed.
+    fn new(
+        method: &str,
+        url: &Url,
+        headers: &mut [(String, String)],
+        body_hash: &str,
+        scope: SigV4Scope,
+    ) -> Self {
+        let SigV4Scope {
+            secret_key,
+            region,
+            service,
+            now,
+        } = scope;
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        headers.sort();
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = [
+            method,
+            &canonical_path(url, service != "s3"),
+            &canonical_query(url),
+            &canonical_headers,
+            &signed_headers,
+            body_hash,
+        ]
+        .join("\n");
+        let scope = format!("{date}/{region}/{service}/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+        );
+        let signature = hex(&hmac_sha256(
+            &sigv4_signing_key(secret_key, &date, region, service),
+            string_to_sign.as_bytes(),
+        ));
+        Self {
+            canonical_request,
+            string_to_sign,
+            scope,
+            signed_headers,
+            signature,
+        }
+    }
+}
+
+fn sigv4_signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    [region, service, "aws4_request"].iter().fold(
+        hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date.as_bytes()),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    )
+}
+
+fn insert(headers: &mut HeaderMap, name: &str, value: &str) {
+    if let (Ok(name), Ok(value)) = (
+        HeaderName::from_bytes(name.as_bytes()),
+        HeaderValue::from_str(value),
+    ) {
+        headers.insert(name, value);
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The Host header sent for a URL, with the port only if it isn't the
+/// default.
+fn host_header(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    }
+}
+
+fn sigv4_encode(s: &str) -> String {
+    utf8_percent_encode(s, SIGV4_ENCODE).to_string()
+}
+
+/// The path with each segment encoded, twice for services other than S3.
+fn canonical_path(url: &Url, double_encode: bool) -> String {
+    url.path()
+        .split('/')
+        .map(|segment| {
+            let decoded = percent_decode_str(segment).decode_utf8_lossy();
+            let encoded = sigv4_encode(&decoded);
+            match double_encode {
+                true => sigv4_encode(&encoded),
+                false => encoded,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The query parameters encoded and sorted by name, then value.
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| (sigv4_encode(&name), sigv4_encode(&value)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Credentials used throughout AWS's SigV4 test suite
+    const ACCESS_KEY: &str = "AKIDEXAMPLE";
+    const SECRET_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+    const EMPTY_BODY_HASH: &str =
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    /// The time every request in AWS's SigV4 test suite is signed at
+    fn suite_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap()
+    }
+
+    /// Sign a bodiless request from AWS's SigV4 test suite, which signs only
+    /// `host` and `x-amz-date`, for `service` in `us-east-1`.
+    fn suite_signature(method: &str, url: &str) -> SigV4 {
+        let mut headers = vec![
+            ("host".to_string(), "example.amazonaws.com".to_string()),
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        SigV4::new(
+            method,
+            &Url::parse(url).unwrap(),
+            &mut headers,
+            EMPTY_BODY_HASH,
+            SigV4Scope {
+                secret_key: SECRET_KEY,
+                region: "us-east-1",
+                service: "service",
+                now: suite_time(),
+            },
+        )
+    }
+
+    #[test]
+    fn aws_suite_get_vanilla() {
+        let signature = suite_signature("GET", "https://example.amazonaws.com/");
+        assert_eq!(
+            signature.canonical_request,
+            format!(
+                "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\n\
+                 host;x-amz-date\n{EMPTY_BODY_HASH}"
+            )
+        );
+        assert_eq!(
+            signature.string_to_sign,
+            "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\n\
+             bb579772317eb040ac9ed261061d46c1f17a8133879d6129b6e1c25292927e63"
+        );
+        assert_eq!(
+            signature.signature,
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn aws_suite_post_vanilla() {
+        let signature = suite_signature("POST", "https://example.amazonaws.com/");
+        assert!(signature.canonical_request.starts_with("POST\n/\n\n"));
+        assert_eq!(
+            signature.string_to_sign,
+            "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\n\
+             553f88c9e4d10fc9e109e2aeb65f030801b70c2f6468faca261d401ae622fc87"
+        );
+        assert_eq!(
+            signature.signature,
+            "5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+        );
+    }
+
+    #[test]
+    fn aws_suite_get_vanilla_query_order_key_case() {
+        let signature = suite_signature(
+            "GET",
+            "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+        );
+        assert!(signature
+            .canonical_request
+            .starts_with("GET\n/\nParam1=value1&Param2=value2\n"));
+        assert_eq!(
+            signature.string_to_sign,
+            "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\n\
+             816cd5b414d056048ba4f7c5386d6e0533120fb1fcfa93762cf0fc39e2cf19e0"
+        );
+        assert_eq!(
+            signature.signature,
+            "b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    /// The `ListUsers` example from AWS's SigV4 documentation
+    #[test]
+    fn aws_iam_list_users_example() {
+        assert_eq!(
+            hex(&sigv4_signing_key(
+                SECRET_KEY,
+                "20150830",
+                "us-east-1",
+                "iam"
+            )),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+        let mut headers = vec![
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+            ("host".to_string(), "iam.amazonaws.com".to_string()),
+            (
+                "content-type".to_string(),
+                "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+            ),
+        ];
+        let signature = SigV4::new(
+            "GET",
+            &Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap(),
+            &mut headers,
+            EMPTY_BODY_HASH,
+            SigV4Scope {
+                secret_key: SECRET_KEY,
+                region: "us-east-1",
+                service: "iam",
+                now: suite_time(),
+            },
+        );
+        assert_eq!(signature.signed_headers, "content-type;host;x-amz-date");
+        assert!(signature
+            .string_to_sign
+            .ends_with("\nf536975d06c0309214f805bb90ccff089219ecd68b2577efef23edd43b7e1a59"));
+        assert_eq!(
+            signature.signature,
+            "5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn sigv4_signs_requests() {
+        let scheme = SigningScheme::AwsSigV4 {
+            region: "us-east-1".to_string(),
+            service: "service".to_string(),
+            access_key: ACCESS_KEY.to_string(),
+            secret_key: SECRET_KEY.to_string(),
+            session_token: None,
+        };
+        let request = reqwest::Client::new()
+            .get("https://example.amazonaws.com/")
+            .build()
+            .unwrap();
+        let headers = scheme.signature_headers_at(&request, suite_time());
+
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        assert_eq!(headers["x-amz-content-sha256"], EMPTY_BODY_HASH);
+        assert_eq!(
+            headers[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+             Signature=726c5c4879a6b4ccbbd3b24edbd6b8826d34f87450fbbf4e85546fc7ba9c1642"
+        );
+        assert!(!headers.contains_key("host"));
+    }
+
+    #[test]
+    fn hmac_signs_at_a_fixed_timestamp() {
+        let scheme = SigningScheme::Hmac {
+            key: hmac::Key::new(hmac::HMAC_SHA256, b"shared-secret"),
+            header: HeaderName::from_static("x-signature"),
+            timestamp_header: HeaderName::from_static("x-timestamp"),
+        };
+        let request = reqwest::Client::new()
+            .get("https://api.example.com/v1/items?id=1#section")
+            .build()
+            .unwrap();
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let headers = scheme.signature_headers_at(&request, now);
+
+        assert_eq!(headers["x-timestamp"], "1700000000");
+        // HMAC-SHA256 of "GET\n/v1/items?id=1\n1700000000\n" and the empty
+        // body's hash
+        assert_eq!(
+            headers["x-signature"],
+            "bb2592bdd6f4064db57772dd779e4b0f737ae15fd41ad413eb826b4e22a090a3"
+        );
+    }
+}
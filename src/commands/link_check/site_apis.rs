@@ -0,0 +1,135 @@
+use serde::{de::IgnoredAny, Deserialize};
+use url::Url;
+
+const STACK_EXCHANGE_API_URL: &str = "https://api.stackexchange.com/2.3";
+
+/// Stack Exchange sites not under `stackexchange.com`, by host, with the
+/// name the API knows them by
+const STACK_EXCHANGE_SITES: &[(&str, &str)] = &[
+    ("stackoverflow.com", "stackoverflow"),
+    ("serverfault.com", "serverfault"),
+    ("superuser.com", "superuser"),
+    ("askubuntu.com", "askubuntu"),
+    ("mathoverflow.net", "mathoverflow.net"),
+    ("stackapps.com", "stackapps"),
+];
+
+/// A link to a frequently linked site that is checked through the site's API
+/// rather than by fetching the page, avoiding bot protection and the
+/// localized redirects the page would get
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum SiteLookup {
+    /// A Stack Exchange question or answer, by ID
+    StackExchange { site: String, answer: bool, id: u64 },
+    /// An MDN document, by locale and slug
+    Mdn { locale: String, slug: String },
+}
+
+/// The outcome of looking up a link through a site's API
+#[derive(Debug)]
+pub(super) enum SiteCheck {
+    Found,
+    /// The API says the target doesn't exist
+    Missing(String),
+    /// The API couldn't answer (e.g., it was throttled), so the link needs a
+    /// regular check
+    Unavailable(String),
+}
+
+impl SiteLookup {
+    pub(super) fn from_url(url: &Url) -> Option<Self> {
+        let host = url.host_str()?;
+        let host = host.strip_prefix("www.").unwrap_or(host);
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        if host == "developer.mozilla.org" {
+            return Self::mdn(&segments);
+        }
+        let site = STACK_EXCHANGE_SITES
+            .iter()
+            .find(|(site_host, _)| *site_host == host)
+            .map(|(_, site)| site.to_string())
+            .or_else(|| {
+                host.strip_suffix(".stackexchange.com")
+                    .filter(|site| !site.contains('.') && *site != "meta")
+                    .map(str::to_string)
+            })?;
+        let (answer, id) = match segments.as_slice() {
+            ["questions", id, ..] | ["q", id, ..] => (false, id),
+            ["a", id, ..] => (true, id),
+            _ => return None,
+        };
+        Some(Self::StackExchange {
+            site,
+            answer,
+            id: id.parse().ok()?,
+        })
+    }
+
+    /// An MDN document from the segments of its path
+    /// (`/LOCALE/docs/SLUG`). Links without a locale are redirected to the
+    /// reader's, so are looked up in English.
+    fn mdn(segments: &[&str]) -> Option<Self> {
+        let (locale, slug) = match segments {
+            ["docs", slug @ ..] => ("en-US", slug),
+            [locale, "docs", slug @ ..] => (*locale, slug),
+            _ => return None,
+        };
+        if slug.is_empty() {
+            return None;
+        }
+        Some(Self::Mdn {
+            locale: locale.to_string(),
+            slug: slug.join("/"),
+        })
+    }
+
+    /// The API URL to look the link up with.
+    pub(super) fn api_url(&self) -> String {
+        match self {
+            SiteLookup::StackExchange { site, answer, id } => {
+                let kind = match answer {
+                    true => "answers",
+                    false => "questions",
+                };
+                format!("{STACK_EXCHANGE_API_URL}/{kind}/{id}?site={site}")
+            }
+            SiteLookup::Mdn { locale, slug } => {
+                format!("https://developer.mozilla.org/{locale}/docs/{slug}/index.json")
+            }
+        }
+    }
+
+    /// Interpret the API's response to the lookup.
+    pub(super) fn check(&self, status: u16, body: &str) -> SiteCheck {
+        match self {
+            SiteLookup::StackExchange { answer, .. } => {
+                #[derive(Deserialize)]
+                struct Items {
+                    items: Vec<IgnoredAny>,
+                }
+
+                if !(200..300).contains(&status) {
+                    return SiteCheck::Unavailable(format!("Stack Exchange API returned {status}"));
+                }
+                match serde_json::from_str::<Items>(body) {
+                    Ok(response) if response.items.is_empty() => {
+                        let kind = match answer {
+                            true => "answer",
+                            false => "question",
+                        };
+                        SiteCheck::Missing(format!("{kind} not found (deleted or never existed)"))
+                    }
+                    Ok(_) => SiteCheck::Found,
+                    Err(e) => SiteCheck::Unavailable(format!(
+                        "failed to parse Stack Exchange API response: {e}"
+                    )),
+                }
+            }
+            SiteLookup::Mdn { .. } => match status {
+                200..=299 => SiteCheck::Found,
+                404 | 410 => SiteCheck::Missing("document not found on MDN".to_string()),
+                _ => SiteCheck::Unavailable(format!("MDN document API returned {status}")),
+            },
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use url::Url;
+
+use super::{
+    super::xml::{self, Event},
+    lastmod::parse_lastmod,
+};
+
+const SITEMAP_NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+/// A parsed `sitemap.xml` file
+pub(super) enum Sitemap {
+    /// A sitemap index, listing the URLs of other sitemaps
+    Index(Vec<Url>),
+    /// A list of pages
+    UrlSet(Vec<SitemapEntry>),
+}
+
+/// A page listed in a sitemap
+pub(super) struct SitemapEntry {
+    pub(super) loc: Url,
+    pub(super) lastmod: Option<NaiveDate>,
+}
+
+/// Sitemap element whose text is currently being read
+enum Field {
+    Loc,
+    Lastmod,
+}
+
+impl Sitemap {
+    /// Parse a sitemap or sitemap index. Elements from other namespaces, such
+    /// as `<image:loc>`, are ignored.
+    ///
+    /// ## Returns
+    /// Fails if the sitemap isn't well-formed XML.
+    pub(super) fn parse(s: &str) -> Result<Self> {
+        let mut is_index = false;
+        let mut field: Option<Field> = None;
+        let mut text = String::new();
+        let mut loc: Option<Url> = None;
+        let mut lastmod: Option<NaiveDate> = None;
+        let mut entries = Vec::new();
+
+        for event in xml::read(s)? {
+            match event {
+                Event::Start { namespace, name } if is_sitemap(namespace.as_deref()) => {
+                    match name.as_str() {
+                        "sitemapindex" => is_index = true,
+                        "loc" => field = Some(Field::Loc),
+                        "lastmod" => field = Some(Field::Lastmod),
+                        _ => {}
+                    }
+                    text.clear();
+                }
+                Event::End { namespace, name } if is_sitemap(namespace.as_deref()) => {
+                    match (name.as_str(), field.take()) {
+                        ("loc", Some(Field::Loc)) => loc = Url::parse(text.trim()).ok(),
+                        ("lastmod", Some(Field::Lastmod)) => lastmod = parse_lastmod(text.trim()),
+                        ("url" | "sitemap", _) => {
+                            if let Some(loc) = loc.take() {
+                                entries.push(SitemapEntry {
+                                    loc,
+                                    lastmod: lastmod.take(),
+                                });
+                            }
+                            lastmod = None;
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Text(chunk) if field.is_some() => text.push_str(&chunk),
+                _ => {}
+            }
+        }
+
+        Ok(if is_index {
+            Sitemap::Index(entries.into_iter().map(|entry| entry.loc).collect())
+        } else {
+            Sitemap::UrlSet(entries)
+        })
+    }
+}
+
+/// Whether an element is in the sitemap namespace, or in none, as some
+/// sitemaps leave it out.
+fn is_sitemap(namespace: Option<&str>) -> bool {
+    namespace.is_none_or(|namespace| namespace == SITEMAP_NAMESPACE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(sitemap: &str) -> Vec<(String, Option<NaiveDate>)> {
+        match Sitemap::parse(sitemap).unwrap() {
+            Sitemap::UrlSet(entries) => entries
+                .into_iter()
+                .map(|entry| (entry.loc.to_string(), entry.lastmod))
+                .collect(),
+            Sitemap::Index(_) => panic!("Parsed as a sitemap index"),
+        }
+    }
+
+    #[test]
+    fn reads_pages_in_cdata_and_with_entities() {
+        let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+  <url>
+    <loc><![CDATA[https://example.com/search?q=a&b=c]]></loc>
+    <lastmod>2024-05-01</lastmod>
+    <image:image><image:loc>https://example.com/image.png</image:loc></image:image>
+  </url>
+  <url><loc>https://example.com/guide?x=1&amp;y=2</loc></url>
+  <!-- <url><loc>https://example.com/commented</loc></url> -->
+</urlset>"#;
+        assert_eq!(
+            pages(sitemap),
+            [
+                (
+                    "https://example.com/search?q=a&b=c".to_string(),
+                    NaiveDate::from_ymd_opt(2024, 5, 1)
+                ),
+                ("https://example.com/guide?x=1&y=2".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_sitemap_indexes() {
+        let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/a.xml</loc></sitemap>
+  <sitemap><loc>https://example.com/b.xml</loc><lastmod>2024-05-01</lastmod></sitemap>
+</sitemapindex>"#;
+        let Sitemap::Index(sitemaps) = Sitemap::parse(index).unwrap() else {
+            panic!("Parsed as a list of pages");
+        };
+        assert_eq!(
+            sitemaps.iter().map(Url::as_str).collect::<Vec<_>>(),
+            ["https://example.com/a.xml", "https://example.com/b.xml"]
+        );
+        assert!(Sitemap::parse("<urlset><url></urlset>").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use url::Url;
+
+/// Why a URL found during the crawl wasn't checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) enum SkipReason {
+    /// Not an http(s) URL (e.g., `mailto:` or `javascript:`)
+    Scheme,
+    /// Matches an `--exclude` pattern
+    Pattern,
+    /// On a domain skipped with `--skip-domain` or not allowed by
+    /// `--only-domains`
+    Domain,
+    /// Outside the site, with `--internal-only`
+    External,
+    /// Nested more path segments deep than the crawler follows, as pages
+    /// linking to themselves with ever longer paths are
+    PathDepth,
+    /// Disallowed by the site's robots.txt
+    Robots,
+    /// A sitemap listed in sitemap indexes beyond `--max-sitemaps`
+    SitemapLimit,
+    /// Opted out with the `data-docs-tools-skip` attribute, or nofollow with
+    /// `--skip-nofollow`
+    OptedOut,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Scheme => "not http(s)",
+            Self::Pattern => "excluded by a pattern",
+            Self::Domain => "on a skipped domain",
+            Self::External => "external with --internal-only",
+            Self::PathDepth => "nested too deep",
+            Self::Robots => "disallowed by robots.txt",
+            Self::SitemapLimit => "beyond the sitemap limit",
+            Self::OptedOut => "opted out on the page",
+        })
+    }
+}
+
+/// A URL that wasn't checked, with why and the first page it was found on
+#[derive(Debug, Clone)]
+pub(super) struct Skip {
+    pub(super) url: Url,
+    pub(super) reason: SkipReason,
+    /// More about why, such as the limit the URL went beyond
+    pub(super) detail: Option<String>,
+    pub(super) referrer: Option<Url>,
+}
+
+/// Every URL skipped during a crawl, so the crawl's scope can be checked
+/// against what was intended
+#[derive(Debug, Default)]
+pub(super) struct Skips {
+    skipped: Mutex<HashMap<Url, Skip>>,
+}
+
+impl Skips {
+    /// Record a skipped URL, unless it was already skipped elsewhere.
+    /// Returns whether it was new.
+    pub(super) fn add(
+        &self,
+        url: &Url,
+        referrer: Option<&Url>,
+        reason: SkipReason,
+        detail: Option<String>,
+    ) -> bool {
+        let mut skipped = self.skipped.lock().unwrap();
+        if skipped.contains_key(url) {
+            return false;
+        }
+        skipped.insert(
+            url.clone(),
+            Skip {
+                url: url.clone(),
+                reason,
+                detail,
+                referrer: referrer.cloned(),
+            },
+        );
+        true
+    }
+
+    /// Every skipped URL, sorted by reason, then URL.
+    pub(super) fn all(&self) -> Vec<Skip> {
+        let mut skips: Vec<Skip> = self.skipped.lock().unwrap().values().cloned().collect();
+        skips.sort_by(|a, b| (a.reason, &a.url).cmp(&(b.reason, &b.url)));
+        skips
+    }
+
+    /// Number of URLs skipped for each reason.
+    pub(super) fn counts(&self) -> BTreeMap<SkipReason, usize> {
+        let mut counts = BTreeMap::new();
+        for skip in self.skipped.lock().unwrap().values() {
+            *counts.entry(skip.reason).or_default() += 1;
+        }
+        counts
+    }
+}
@@ -0,0 +1,286 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use url::Url;
+
+use super::utils::StartsWith;
+
+/// How the site generates heading anchors on a locale's pages, as configured
+/// under `[[slug]]`, so links to headings in other scripts are matched the
+/// way the site's generator would
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct SlugConfig {
+    /// Locale path prefix of the pages the rules apply to (e.g., `de` or
+    /// `/ja/`), as given to `--locale`
+    locale: String,
+    /// How letters outside ASCII are slugged: `none` to keep them, `ascii` to
+    /// strip accents and transliterate Cyrillic and Greek, or `german` to
+    /// also spell umlauts out (`ä` as `ae`) [default: none]
+    transliterate: Option<String>,
+    /// How Chinese, Japanese and Korean characters are slugged: `keep`,
+    /// `strip` or `encode` (percent-encoded) [default: keep]
+    cjk: Option<String>,
+    /// Whether slugs are lowercased [default: true]
+    lowercase: Option<bool>,
+    /// What spaces are replaced with [default: -]
+    separator: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transliteration {
+    None,
+    Ascii,
+    German,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CjkHandling {
+    Keep,
+    Strip,
+    Encode,
+}
+
+/// Slug generation for the heading anchors of a locale's pages
+#[derive(Debug)]
+pub(super) struct SlugRules {
+    root: Url,
+    transliterate: Transliteration,
+    cjk: CjkHandling,
+    lowercase: bool,
+    separator: String,
+}
+
+impl SlugRules {
+    pub(super) fn new(base_url: &Url, config: &SlugConfig) -> Result<Self, String> {
+        let name = config.locale.trim_matches('/');
+        let root = base_url
+            .join(&format!("{name}/"))
+            .map_err(|e| format!("invalid locale `{}`: {e}", config.locale))?;
+        let transliterate = match config.transliterate.as_deref().unwrap_or("none") {
+            "none" => Transliteration::None,
+            "ascii" => Transliteration::Ascii,
+            "german" => Transliteration::German,
+            other => {
+                return Err(format!(
+                    "unknown transliteration `{other}`, expected `none`, `ascii` or `german`"
+                ))
+            }
+        };
+        let cjk = match config.cjk.as_deref().unwrap_or("keep") {
+            "keep" => CjkHandling::Keep,
+            "strip" => CjkHandling::Strip,
+            "encode" => CjkHandling::Encode,
+            other => {
+                return Err(format!(
+                    "unknown CJK handling `{other}`, expected `keep`, `strip` or `encode`"
+                ))
+            }
+        };
+        Ok(Self {
+            root,
+            transliterate,
+            cjk,
+            lowercase: config.lowercase.unwrap_or(true),
+            separator: config.separator.clone().unwrap_or_else(|| "-".to_string()),
+        })
+    }
+
+    /// Whether the rules apply to a page.
+    pub(super) fn applies_to(&self, page: &Url) -> bool {
+        page.starts_with(&self.root)
+    }
+
+    /// The anchor generated for a heading: transliterated, lowercased, with
+    /// punctuation removed and runs of spaces replaced by the separator.
+    pub(super) fn slug(&self, heading: &str) -> String {
+        let mut text = String::with_capacity(heading.len());
+        for c in heading.chars() {
+            match self.transliterate {
+                Transliteration::None => text.push(c),
+                Transliteration::Ascii => push_ascii(&mut text, c, false),
+                Transliteration::German => push_ascii(&mut text, c, true),
+            }
+        }
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+
+        let mut slug = String::with_capacity(text.len());
+        let mut pending_separator = false;
+        for c in text.chars() {
+            if c.is_whitespace() || (c == '-' && self.separator == "-") {
+                pending_separator = !slug.is_empty();
+                continue;
+            }
+            let kept = if is_cjk(c) {
+                match self.cjk {
+                    CjkHandling::Keep => Some(c.to_string()),
+                    CjkHandling::Strip => None,
+                    CjkHandling::Encode => Some(
+                        utf8_percent_encode(c.encode_utf8(&mut [0; 4]), NON_ALPHANUMERIC)
+                            .to_string(),
+                    ),
+                }
+            } else if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_string())
+            } else {
+                None
+            };
+            if let Some(kept) = kept {
+                if pending_separator {
+                    slug.push_str(&self.separator);
+                    pending_separator = false;
+                }
+                slug.push_str(&kept);
+            }
+        }
+        slug
+    }
+}
+
+/// Chinese, Japanese and Korean characters, including kana and hangul.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{2E80}'..='\u{2FDF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3100}'..='\u{312F}'
+        | '\u{3130}'..='\u{318F}'
+        | '\u{31F0}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF66}'..='\u{FF9F}'
+        | '\u{20000}'..='\u{2FA1F}')
+}
+
+/// Push a character spelled in ASCII, dropping letters with no spelling.
+/// CJK characters are left for the slug's CJK handling.
+fn push_ascii(text: &mut String, c: char, german: bool) {
+    if c.is_ascii() || is_cjk(c) {
+        text.push(c);
+        return;
+    }
+    if german {
+        let umlaut = match c {
+            'ä' => Some("ae"),
+            'ö' => Some("oe"),
+            'ü' => Some("ue"),
+            'Ä' => Some("Ae"),
+            'Ö' => Some("Oe"),
+            'Ü' => Some("Ue"),
+            _ => None,
+        };
+        if let Some(umlaut) = umlaut {
+            text.push_str(umlaut);
+            return;
+        }
+    }
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    let Some(ascii) = ascii_spelling(lower) else {
+        // Other scripts have no ASCII spelling, but are still word
+        // separators
+        if !c.is_alphanumeric() {
+            text.push(' ');
+        }
+        return;
+    };
+    if c.is_uppercase() {
+        let mut chars = ascii.chars();
+        text.extend(chars.next().map(|first| first.to_ascii_uppercase()));
+        text.push_str(chars.as_str());
+    } else {
+        text.push_str(ascii);
+    }
+}
+
+/// The ASCII spelling of a lowercase Latin letter with diacritics, or a
+/// Cyrillic or Greek letter.
+fn ascii_spelling(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'э' => "e",
+        'ё' => "yo",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'ю' => "yu",
+        'я' => "ya",
+        'є' => "ye",
+        'ї' => "yi",
+        'ґ' => "g",
+        'α' | 'ά' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' | 'έ' => "e",
+        'ζ' => "z",
+        'η' | 'ή' => "i",
+        'θ' => "th",
+        'ι' | 'ί' | 'ϊ' | 'ΐ' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ό' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' | 'ύ' | 'ϋ' | 'ΰ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' | 'ώ' => "o",
+        _ => return None,
+    };
+    Some(ascii)
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use tracing::info;
+
+use super::CheckRecord;
+
+/// Number of hosts listed in the log summary, most failures first. The JSON
+/// report lists every host.
+const HOSTS_LOGGED: usize = 10;
+
+/// Statistics of a crawl, for telling a flaky host apart from systemic
+/// breakage
+#[derive(Debug, Serialize)]
+pub(super) struct Stats {
+    /// Checks of external links by host, most failures first
+    pub(super) hosts: Vec<HostStats>,
+    /// Number of checks by status class (e.g., `4xx`), or `none` for checks
+    /// with no response
+    pub(super) status_classes: BTreeMap<String, usize>,
+    /// The slowest checks, slowest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(super) slowest: Vec<SlowCheck>,
+    /// Bytes of response bodies read during the crawl, not counting external
+    /// links whose bodies aren't read
+    pub(super) bytes_downloaded: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct HostStats {
+    pub(super) host: String,
+    pub(super) total: usize,
+    pub(super) failed: usize,
+    /// Mean time to check a link to the host
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) mean_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct SlowCheck {
+    pub(super) url: String,
+    pub(super) status: Option<u16>,
+    pub(super) elapsed_ms: u64,
+}
+
+impl Stats {
+    /// Compute statistics over the checks of a crawl, keeping the `slowest`
+    /// slowest. `is_external` tells which checks count towards the hosts.
+    pub(super) fn new(
+        records: &[CheckRecord],
+        is_external: impl Fn(&CheckRecord) -> bool,
+        slowest: usize,
+        bytes_downloaded: u64,
+    ) -> Self {
+        let mut hosts: HashMap<&str, (usize, usize, u128)> = HashMap::new();
+        let mut status_classes = BTreeMap::new();
+        for record in records {
+            let class = match record.status {
+                Some(status) => format!("{}xx", status / 100),
+                None => "none".to_string(),
+            };
+            *status_classes.entry(class).or_default() += 1;
+            if !is_external(record) {
+                continue;
+            }
+            let Some(host) = record.url.host_str() else {
+                continue;
+            };
+            let (total, failed, elapsed) = hosts.entry(host).or_default();
+            *total += 1;
+            *failed += usize::from(!record.is_success());
+            *elapsed += record.elapsed.as_millis();
+        }
+
+        let mut hosts: Vec<HostStats> = hosts
+            .into_iter()
+            .map(|(host, (total, failed, elapsed))| HostStats {
+                host: host.to_string(),
+                total,
+                failed,
+                mean_ms: Some((elapsed / total as u128) as u64),
+            })
+            .collect();
+        hosts.sort_by(|a, b| {
+            b.failed
+                .cmp(&a.failed)
+                .then(b.total.cmp(&a.total))
+                .then(a.host.cmp(&b.host))
+        });
+
+        let mut by_elapsed: Vec<&CheckRecord> = records.iter().collect();
+        by_elapsed.sort_by_key(|record| std::cmp::Reverse(record.elapsed));
+        let slowest = by_elapsed
+            .into_iter()
+            .take(slowest)
+            .map(|record| SlowCheck {
+                url: record.url.to_string(),
+                status: record.status,
+                elapsed_ms: record.elapsed.as_millis() as u64,
+            })
+            .collect();
+
+        Self {
+            hosts,
+            status_classes,
+            slowest,
+            bytes_downloaded,
+        }
+    }
+
+    /// The statistics without the times checks took, for reports that must
+    /// be identical across runs.
+    pub(super) fn untimed(mut self) -> Self {
+        for host in &mut self.hosts {
+            host.mean_ms = None;
+        }
+        self.slowest.clear();
+        self
+    }
+
+    pub(super) fn log(&self) {
+        if !self.hosts.is_empty() {
+            info!("External links by host:");
+            for host in self.hosts.iter().take(HOSTS_LOGGED) {
+                let mean = host
+                    .mean_ms
+                    .map(|mean| format!(", {mean} ms mean"))
+                    .unwrap_or_default();
+                info!(
+                    "  {}: {} checked, {} failed{mean}",
+                    host.host, host.total, host.failed
+                );
+            }
+            if self.hosts.len() > HOSTS_LOGGED {
+                info!("  ...and {} more hosts", self.hosts.len() - HOSTS_LOGGED);
+            }
+        }
+        let classes: Vec<String> = self
+            .status_classes
+            .iter()
+            .map(|(class, count)| format!("{class}: {count}"))
+            .collect();
+        if !classes.is_empty() {
+            info!("Checks by status: {}", classes.join(", "));
+        }
+        if !self.slowest.is_empty() {
+            info!("Slowest checks:");
+            for check in &self.slowest {
+                let status = check
+                    .status
+                    .map(|status| format!(" ({status})"))
+                    .unwrap_or_default();
+                info!("  {} ms: {}{status}", check.elapsed_ms, check.url);
+            }
+        }
+        info!("Bytes downloaded: {}", self.bytes_downloaded);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use clap::ValueEnum;
+use url::Url;
+
+use super::UrlWithReferrer;
+
+/// Order in which the crawl works through queued URLs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Strategy {
+    /// Breadth-first: every link on a page is checked before the links found
+    /// on those
+    #[default]
+    Bfs,
+    /// Depth-first: the links found most recently are checked first,
+    /// reaching deeply nested pages sooner
+    Dfs,
+    /// Internal links first, breadth-first, to discover the whole site
+    /// quickly, then external links grouped by host to reuse connections
+    Priority,
+}
+
+/// URLs waiting to be checked, taken in the order of a [`Strategy`]. With
+/// `Priority`, external links wait in a queue per host, so links queued at
+/// any point are taken in order without re-sorting.
+pub(super) struct CrawlQueue {
+    strategy: Strategy,
+    /// Every link, or only internal links with `Priority`
+    links: VecDeque<UrlWithReferrer>,
+    /// External links with `Priority`, by host
+    external_by_host: BTreeMap<String, VecDeque<UrlWithReferrer>>,
+    len: usize,
+}
+
+impl CrawlQueue {
+    pub(super) fn new(strategy: Strategy) -> Self {
+        Self {
+            strategy,
+            links: VecDeque::new(),
+            external_by_host: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Queue links, in order.
+    pub(super) fn extend(
+        &mut self,
+        targets: impl IntoIterator<Item = UrlWithReferrer>,
+        is_internal: impl Fn(&Url) -> bool,
+    ) {
+        for target in targets {
+            self.len += 1;
+            if self.strategy != Strategy::Priority || is_internal(&target.url) {
+                self.links.push_back(target);
+                continue;
+            }
+            let host = target.url.host_str().unwrap_or_default().to_string();
+            self.external_by_host
+                .entry(host)
+                .or_default()
+                .push_back(target);
+        }
+    }
+
+    /// Take the next URL to check. With `Priority`, internal links come
+    /// before external links, which are taken a host at a time, in order of
+    /// host.
+    pub(super) fn pop(&mut self) -> Option<UrlWithReferrer> {
+        let target = match self.strategy {
+            Strategy::Bfs => self.links.pop_front(),
+            Strategy::Dfs => self.links.pop_back(),
+            Strategy::Priority => self.links.pop_front().or_else(|| {
+                let mut host = self.external_by_host.first_entry()?;
+                let target = host.get_mut().pop_front();
+                if host.get().is_empty() {
+                    host.remove();
+                }
+                target
+            }),
+        }?;
+        self.len -= 1;
+        Some(target)
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every queued URL, in the order they'd be restored in.
+    pub(super) fn iter(&self) -> impl Iterator<Item = &UrlWithReferrer> {
+        self.links
+            .iter()
+            .chain(self.external_by_host.values().flatten())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(strategy: Strategy, urls: &[&str]) -> Vec<String> {
+        let mut queue = CrawlQueue::new(strategy);
+        let targets = urls.iter().map(|url| UrlWithReferrer {
+            url: Url::parse(url).unwrap(),
+            referrer: None,
+            depth: 0,
+        });
+        queue.extend(targets, |url| url.host_str() == Some("docs.example.com"));
+        assert_eq!(queue.len(), urls.len());
+        let drained = std::iter::from_fn(|| queue.pop())
+            .map(|target| target.url.to_string())
+            .collect();
+        assert!(queue.is_empty());
+        drained
+    }
+
+    #[test]
+    fn takes_links_in_strategy_order() {
+        let urls = [
+            "https://b.example.org/1",
+            "https://docs.example.com/a",
+            "https://a.example.org/1",
+            "https://b.example.org/2",
+            "https://docs.example.com/b",
+        ];
+        assert_eq!(drain(Strategy::Bfs, &urls), urls);
+        assert_eq!(
+            drain(Strategy::Dfs, &urls),
+            urls.iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            drain(Strategy::Priority, &urls),
+            [
+                "https://docs.example.com/a",
+                "https://docs.example.com/b",
+                "https://a.example.org/1",
+                "https://b.example.org/1",
+                "https://b.example.org/2",
+            ]
+        );
+    }
+
+    #[test]
+    fn internal_links_queued_later_go_first() {
+        let mut queue = CrawlQueue::new(Strategy::Priority);
+        let target = |url: &str| UrlWithReferrer {
+            url: Url::parse(url).unwrap(),
+            referrer: None,
+            depth: 0,
+        };
+        let is_internal = |url: &Url| url.host_str() == Some("docs.example.com");
+        queue.extend([target("https://example.org/")], is_internal);
+        queue.extend([target("https://docs.example.com/")], is_internal);
+        assert_eq!(
+            queue.pop().unwrap().url.as_str(),
+            "https://docs.example.com/"
+        );
+        assert_eq!(queue.iter().count(), 1);
+    }
+}
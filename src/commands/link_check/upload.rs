@@ -0,0 +1,208 @@
+use std::{env, str::FromStr};
+
+use anyhow::{Context, Result};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::{header::CONTENT_TYPE, Method, Response, StatusCode};
+use tracing::info;
+use url::Url;
+
+use super::signing::SigningScheme;
+
+const DEFAULT_AWS_REGION: &str = "us-east-1";
+const GCS_URL: &str = "https://storage.googleapis.com";
+
+/// Bucket to upload the report to once the crawl finishes, as
+/// `s3://BUCKET/KEY` or `gs://BUCKET/KEY`. A key ending in `/` is a prefix,
+/// under which each run's report goes in a directory named after the time it
+/// was uploaded, so earlier reports are kept.
+#[derive(Debug, Clone)]
+pub(crate) enum UploadTarget {
+    /// Amazon S3, or a compatible store given by `AWS_ENDPOINT_URL`
+    S3 { bucket: String, key: String },
+    /// Google Cloud Storage
+    Gcs { bucket: String, key: String },
+}
+
+impl FromStr for UploadTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, path) = s
+            .split_once("://")
+            .ok_or_else(|| format!("expected s3://BUCKET/KEY or gs://BUCKET/KEY, got {s}"))?;
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        if bucket.is_empty() {
+            return Err(format!("no bucket in {s}"));
+        }
+        let (bucket, key) = (bucket.to_string(), key.to_string());
+        match scheme {
+            "s3" => Ok(Self::S3 { bucket, key }),
+            "gs" => Ok(Self::Gcs { bucket, key }),
+            _ => Err(format!(
+                "unknown storage {scheme}://, expected s3://BUCKET/KEY or gs://BUCKET/KEY"
+            )),
+        }
+    }
+}
+
+impl UploadTarget {
+    fn bucket_and_key(&self) -> (&str, &str) {
+        let (Self::S3 { bucket, key } | Self::Gcs { bucket, key }) = self;
+        (bucket, key)
+    }
+
+    /// The prefix each run's report is kept under, unless the report goes to
+    /// a single key overwritten by each run.
+    pub(super) fn prefix(&self) -> Option<&str> {
+        let (_, key) = self.bucket_and_key();
+        (key.is_empty() || key.ends_with('/')).then_some(key)
+    }
+
+    /// The key of an object at a path under the prefix, or the exact key if
+    /// there's no prefix.
+    pub(super) fn object_key(&self, path: &str) -> String {
+        match self.prefix() {
+            Some(prefix) => format!("{prefix}{path}"),
+            None => self.bucket_and_key().1.to_string(),
+        }
+    }
+
+    /// Upload an object, returning the URL it can be read at.
+    pub(super) async fn put(
+        &self,
+        client: &reqwest::Client,
+        key: &str,
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> Result<Url> {
+        let url = match self {
+            Self::S3 { bucket, .. } => {
+                let url = s3_url(bucket, key)?;
+                let request = client
+                    .put(url.as_str())
+                    .header(CONTENT_TYPE, content_type)
+                    .body(body);
+                s3_signer()?
+                    .sign(request)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .with_context(|| format!("Failed to upload s3://{bucket}/{key}"))?;
+                url
+            }
+            Self::Gcs { bucket, .. } => {
+                let mut upload_url =
+                    Url::parse(&format!("{}/upload/storage/v1/b/{bucket}/o", gcs_api_url()))?;
+                upload_url
+                    .query_pairs_mut()
+                    .append_pair("uploadType", "media")
+                    .append_pair("name", key);
+                client
+                    .post(upload_url)
+                    .bearer_auth(gcs_token()?)
+                    .header(CONTENT_TYPE, content_type)
+                    .body(body)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .with_context(|| format!("Failed to upload gs://{bucket}/{key}"))?;
+                Url::parse(&format!("{GCS_URL}/{bucket}/{key}"))?
+            }
+        };
+        info!("Uploaded {}", url.as_str());
+        Ok(url)
+    }
+
+    /// Download an object, or `None` if there's none at the key.
+    pub(super) async fn get(&self, client: &reqwest::Client, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.send(client, Method::GET, key).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let (bucket, _) = self.bucket_and_key();
+        let response = response
+            .error_for_status()
+            .with_context(|| format!("Failed to download {key} from {bucket}"))?;
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    /// Delete an object, if there's one at the key.
+    pub(super) async fn delete(&self, client: &reqwest::Client, key: &str) -> Result<()> {
+        let response = self.send(client, Method::DELETE, key).await?;
+        let (bucket, _) = self.bucket_and_key();
+        if response.status() != StatusCode::NOT_FOUND {
+            response
+                .error_for_status()
+                .with_context(|| format!("Failed to delete {key} from {bucket}"))?;
+        }
+        info!("Deleted {key} from {bucket}");
+        Ok(())
+    }
+
+    /// Send a request without a body for an object.
+    async fn send(&self, client: &reqwest::Client, method: Method, key: &str) -> Result<Response> {
+        let request = match self {
+            Self::S3 { bucket, .. } => {
+                s3_signer()?.sign(client.request(method, s3_url(bucket, key)?.as_str()))
+            }
+            Self::Gcs { bucket, .. } => {
+                let mut url = Url::parse(&format!(
+                    "{}/storage/v1/b/{bucket}/o/{}",
+                    gcs_api_url(),
+                    utf8_percent_encode(key, NON_ALPHANUMERIC)
+                ))?;
+                if method == Method::GET {
+                    url.query_pairs_mut().append_pair("alt", "media");
+                }
+                client.request(method, url).bearer_auth(gcs_token()?)
+            }
+        };
+        Ok(request.send().await?)
+    }
+}
+
+fn s3_region() -> String {
+    env::var("AWS_REGION")
+        .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+        .unwrap_or_else(|_| DEFAULT_AWS_REGION.to_string())
+}
+
+/// The URL of an object in S3. Compatible stores (e.g., MinIO) are addressed
+/// by path, S3 itself by a virtual host per bucket.
+fn s3_url(bucket: &str, key: &str) -> Result<Url> {
+    let url = match env::var("AWS_ENDPOINT_URL_S3").or_else(|_| env::var("AWS_ENDPOINT_URL")) {
+        Ok(endpoint) => format!("{}/{bucket}/{key}", endpoint.trim_end_matches('/')),
+        Err(_) => format!("https://{bucket}.s3.{}.amazonaws.com/{key}", s3_region()),
+    };
+    Url::parse(&url).with_context(|| format!("Invalid S3 URL {url}"))
+}
+
+/// Signing for S3 with the credentials in `AWS_ACCESS_KEY_ID`,
+/// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` for `AWS_REGION`.
+fn s3_signer() -> Result<SigningScheme> {
+    Ok(SigningScheme::AwsSigV4 {
+        region: s3_region(),
+        service: "s3".to_string(),
+        access_key: env::var("AWS_ACCESS_KEY_ID")
+            .context("AWS_ACCESS_KEY_ID must be set to upload to S3")?,
+        secret_key: env::var("AWS_SECRET_ACCESS_KEY")
+            .context("AWS_SECRET_ACCESS_KEY must be set to upload to S3")?,
+        session_token: env::var("AWS_SESSION_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty()),
+    })
+}
+
+/// The Cloud Storage API, or the emulator in `STORAGE_EMULATOR_HOST`, which
+/// Google's client libraries also honor.
+fn gcs_api_url() -> String {
+    env::var("STORAGE_EMULATOR_HOST")
+        .map(|host| host.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| GCS_URL.to_string())
+}
+
+fn gcs_token() -> Result<String> {
+    env::var("GOOGLE_OAUTH_ACCESS_TOKEN").context(
+        "GOOGLE_OAUTH_ACCESS_TOKEN must be set to upload to Cloud Storage (e.g., from `gcloud auth print-access-token`)",
+    )
+}
@@ -1,35 +1,6 @@
 use url::Url;
 
-pub(super) fn is_html(url: &Url, content_type: Option<&str>) -> bool {
-    // Check URL extension
-    let path = url.path().to_lowercase();
-    let is_non_html_extension = path.ends_with(".svg")
-        || path.ends_with(".png")
-        || path.ends_with(".jpg")
-        || path.ends_with(".jpeg")
-        || path.ends_with(".gif")
-        || path.ends_with(".ico")
-        || path.ends_with(".css")
-        || path.ends_with(".js")
-        || path.ends_with(".json")
-        || path.ends_with(".woff")
-        || path.ends_with(".woff2")
-        || path.ends_with(".ttf")
-        || path.ends_with(".eot");
-    if is_non_html_extension {
-        return false;
-    }
-
-    // Check content type if available
-    if let Some(content_type) = content_type {
-        return content_type.contains("text/html");
-    }
-
-    // If no content type is available, assume it might be HTML
-    true
-}
-
-pub(super) fn normalize_url(url: &Url) -> Url {
+pub(crate) fn normalize_url(url: &Url) -> Url {
     let mut normalized = url.clone();
     normalized.set_fragment(None);
     normalized.set_query(None);
@@ -1,30 +1,242 @@
-use std::{collections::HashSet, sync::Mutex};
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Mutex,
+};
 
 use url::Url;
 
 use super::utils::normalize_url;
 
-#[derive(Debug, Default)]
+/// Number of independently locked shards, so concurrent checks rarely wait on
+/// each other
+const SHARDS: usize = 64;
+
+/// Bits per URL and hashes per lookup giving Bloom filters a false positive
+/// rate of about 1 in 10,000
+const BLOOM_BITS_PER_URL: f64 = 19.2;
+const BLOOM_HASHES: u64 = 13;
+
+/// The URLs visited during a crawl. Each is kept as a 64-bit hash of its
+/// normalized form in one of several shards, or as bits in a Bloom filter
+/// for crawls too large for even that, where a small share of new URLs are
+/// mistaken for visited ones. Those can't be told apart from URLs really
+/// visited, so only an estimate of how many there were is kept.
+#[derive(Debug)]
 pub(super) struct Visited {
-    visited: Mutex<HashSet<Url>>,
+    shards: Vec<Mutex<Shard>>,
+    /// Every visited URL, kept only when checkpoints need to list them
+    urls: Option<Mutex<Vec<Url>>>,
+}
+
+#[derive(Debug)]
+enum Shard {
+    Exact(HashSet<u64>),
+    Bloom(BloomFilter),
 }
 
 impl Visited {
+    /// A set of visited URLs, held in Bloom filters sized for `bloom_capacity`
+    /// URLs if given. With `keep_urls`, every URL is also kept so it can be
+    /// listed.
+    pub(super) fn new(bloom_capacity: Option<usize>, keep_urls: bool) -> Self {
+        let shards = (0..SHARDS)
+            .map(|_| {
+                Mutex::new(match bloom_capacity {
+                    Some(capacity) => Shard::Bloom(BloomFilter::new(capacity.div_ceil(SHARDS))),
+                    None => Shard::Exact(HashSet::new()),
+                })
+            })
+            .collect();
+        Self {
+            shards,
+            urls: keep_urls.then(|| Mutex::new(Vec::new())),
+        }
+    }
+
     /// Mark a URL as visited.
     ///
     /// ## Returns
-    /// Returns `true` if the URL was not already visited, `false` otherwise.
+    /// Returns `true` if the URL was already visited, `false` otherwise.
     pub(super) fn mark_visited(&self, url: &Url) -> bool {
         let normalized_url = normalize_url(url);
+        let (shard, hashes) = hash(&normalized_url);
 
-        {
-            let mut visited = self.visited.lock().unwrap();
-            if visited.contains(&normalized_url) {
-                return true;
-            }
-            visited.insert(normalized_url);
+        let inserted = match &mut *self.shards[shard].lock().unwrap() {
+            Shard::Exact(hashes_seen) => hashes_seen.insert(hashes.0),
+            Shard::Bloom(filter) => filter.insert(hashes),
+        };
+        if !inserted {
+            return true;
+        }
+        if let Some(urls) = &self.urls {
+            urls.lock().unwrap().push(normalized_url);
         }
 
         false
     }
+
+    /// Every visited URL, normalized. Only kept when the set was created to
+    /// keep them, and empty otherwise.
+    pub(super) fn urls(&self) -> Vec<Url> {
+        self.urls
+            .as_ref()
+            .map(|urls| urls.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    /// Estimated number of new URLs mistaken for visited ones, or `None`
+    /// when visited URLs are tracked exactly.
+    pub(super) fn possible_false_positives(&self) -> Option<usize> {
+        let mut estimate = None;
+        for shard in &self.shards {
+            if let Shard::Bloom(filter) = &*shard.lock().unwrap() {
+                *estimate.get_or_insert(0.0) += filter.possible_false_positives;
+            }
+        }
+        estimate.map(|estimate: f64| estimate.ceil() as usize)
+    }
+
+    pub(super) fn contains(&self, url: &Url) -> bool {
+        let (shard, hashes) = hash(&normalize_url(url));
+        match &*self.shards[shard].lock().unwrap() {
+            Shard::Exact(hashes_seen) => hashes_seen.contains(&hashes.0),
+            Shard::Bloom(filter) => filter.contains(hashes),
+        }
+    }
+}
+
+/// The shard a normalized URL belongs in, and two independent hashes of it.
+fn hash(url: &Url) -> (usize, (u64, u64)) {
+    let hash_with = |seed: u64| {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        url.as_str().hash(&mut hasher);
+        hasher.finish()
+    };
+    let hashes = (hash_with(0), hash_with(1));
+    // The high bits pick the shard, leaving the low bits for Bloom filters
+    ((hashes.0 >> 32) as usize % SHARDS, hashes)
+}
+
+/// A fixed-size Bloom filter, setting bits chosen by double hashing
+#[derive(Debug)]
+struct BloomFilter {
+    bits: Vec<u64>,
+    set_bits: usize,
+    /// Expected number of new URLs found in the filter so far
+    possible_false_positives: f64,
+}
+
+impl BloomFilter {
+    fn new(capacity: usize) -> Self {
+        let bits = ((capacity.max(1) as f64 * BLOOM_BITS_PER_URL) as usize).div_ceil(64);
+        Self {
+            bits: vec![0; bits],
+            set_bits: 0,
+            possible_false_positives: 0.0,
+        }
+    }
+
+    /// The chance of a new URL being found in the filter, as it is now.
+    fn false_positive_rate(&self) -> f64 {
+        let filled = self.set_bits as f64 / (self.bits.len() * 64) as f64;
+        filled.powi(BLOOM_HASHES as i32)
+    }
+
+    /// The word and bit within it of each of a URL's bits.
+    fn positions(&self, (h1, h2): (u64, u64)) -> impl Iterator<Item = (usize, u64)> {
+        let len = self.bits.len() as u64 * 64;
+        (0..BLOOM_HASHES).map(move |i| {
+            let position = h1.wrapping_add(i.wrapping_mul(h2)) % len;
+            ((position / 64) as usize, 1 << (position % 64))
+        })
+    }
+
+    /// Set the bits for a URL's hashes, returning whether any was unset, so
+    /// the URL wasn't in the filter.
+    ///
+    /// Each new URL inserted stands for about `1 / (1 - rate)` new URLs
+    /// looked up at the current false positive rate, the rest of which were
+    /// mistaken for visited ones, which is added to the estimate of them.
+    fn insert(&mut self, hashes: (u64, u64)) -> bool {
+        let rate = self.false_positive_rate();
+        let mut inserted = false;
+        for (word, bit) in self.positions(hashes) {
+            if self.bits[word] & bit == 0 {
+                inserted = true;
+                self.set_bits += 1;
+                self.bits[word] |= bit;
+            }
+        }
+        if inserted {
+            self.possible_false_positives += rate / (1.0 - rate);
+        }
+        inserted
+    }
+
+    fn contains(&self, hashes: (u64, u64)) -> bool {
+        self.positions(hashes)
+            .all(|(word, bit)| self.bits[word] & bit != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(i: usize) -> Url {
+        Url::parse(&format!("https://example.com/docs/page-{i}")).unwrap()
+    }
+
+    #[test]
+    fn exact_set_marks_each_normalized_url_once() {
+        let visited = Visited::new(None, true);
+        let url = Url::parse("https://example.com/docs/").unwrap();
+        assert!(!visited.contains(&url));
+        assert!(!visited.mark_visited(&url));
+        assert!(visited.mark_visited(&Url::parse("https://example.com/docs#intro").unwrap()));
+        assert!(visited.mark_visited(&Url::parse("https://example.com/docs?tab=2").unwrap()));
+        assert!(visited.contains(&url));
+
+        for i in 0..1000 {
+            assert!(!visited.mark_visited(&page(i)));
+        }
+        assert!((0..1000).all(|i| visited.contains(&page(i))));
+        assert!(!visited.contains(&page(1000)));
+        assert_eq!(visited.urls().len(), 1001);
+    }
+
+    #[test]
+    fn bloom_filters_have_no_false_negatives() {
+        let visited = Visited::new(Some(10_000), false);
+        let mut false_positives = 0;
+        for i in 0..10_000 {
+            false_positives += usize::from(visited.mark_visited(&page(i)));
+        }
+        assert!((0..10_000).all(|i| visited.contains(&page(i))));
+        assert!((0..10_000).all(|i| visited.mark_visited(&page(i))));
+        // About 1 in 10,000 is expected, so allow plenty of slack
+        assert!(false_positives < 10, "{false_positives} false positives");
+        assert!(visited.urls().is_empty());
+        assert!(visited
+            .possible_false_positives()
+            .is_some_and(|estimate| estimate < 10));
+        assert_eq!(Visited::new(None, false).possible_false_positives(), None);
+    }
+
+    #[test]
+    fn estimates_false_positives_of_overfilled_filters() {
+        let visited = Visited::new(Some(1_000), false);
+        let mut false_positives = 0;
+        for i in 0..10_000 {
+            false_positives += usize::from(visited.mark_visited(&page(i)));
+        }
+        let estimate = visited.possible_false_positives().unwrap();
+        assert!(false_positives > 100, "{false_positives} false positives");
+        assert!(
+            estimate.abs_diff(false_positives) < false_positives / 4,
+            "estimated {estimate}, but there were {false_positives}"
+        );
+    }
 }
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use serde_json::Value;
+use url::Url;
+
+use super::rate_limit::RateLimiter;
+
+const CDX_API: &str = "https://web.archive.org/cdx/search/cdx";
+
+/// Look up when the Wayback Machine last archived a URL returning 200, as an
+/// estimate of when a now-broken link stopped working.
+///
+/// ## Returns
+/// Returns the date of the last successful capture as `YYYY-MM-DD`, or `None`
+/// if the URL was never archived successfully.
+pub(super) async fn last_alive(
+    client: &reqwest::Client,
+    rate_limiter: &RateLimiter,
+    url: &Url,
+) -> Result<Option<String>> {
+    let mut cdx_url = Url::parse(CDX_API)?;
+    cdx_url
+        .query_pairs_mut()
+        .append_pair("url", url.as_str())
+        .append_pair("output", "json")
+        .append_pair("fl", "timestamp")
+        .append_pair("filter", "statuscode:200")
+        // A negative limit returns the most recent captures
+        .append_pair("limit", "-1");
+
+    rate_limiter.wait(&cdx_url).await;
+    let response = client.get(cdx_url).send().await?.error_for_status()?;
+    // The first row holds the field names, followed by one row per capture
+    let rows: Vec<Vec<Value>> = response.json().await?;
+    let timestamp = rows
+        .get(1)
+        .and_then(|row| row.first())
+        .and_then(Value::as_str);
+    Ok(timestamp.and_then(format_timestamp))
+}
+
+/// Format a CDX `YYYYMMDDhhmmss` timestamp as a date.
+fn format_timestamp(timestamp: &str) -> Option<String> {
+    let year = timestamp.get(0..4)?;
+    let month = timestamp.get(4..6)?;
+    let day = timestamp.get(6..8)?;
+    Some(format!("{year}-{month}-{day}"))
+}
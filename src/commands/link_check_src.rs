@@ -0,0 +1,422 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use futures::{stream, StreamExt};
+use lychee_lib::{extract::Extractor, FileType, InputContent};
+use tracing::{debug, error, info};
+use url::Url;
+
+use super::{
+    link_check::AffiliatePolicy,
+    paths::{normalize_path, percent_decode},
+};
+
+/// Extensions of the source files links are extracted from
+const SOURCE_EXTENSIONS: &[&str] = &["md", "mdx"];
+
+#[derive(Args)]
+pub(crate) struct LinkCheckSrcArgs {
+    /// Directory of Markdown and MDX source files (e.g., ./docs)
+    #[arg(long, value_name = "DIR")]
+    path: PathBuf,
+
+    /// Route a directory of sources is served at, as `DIR=ROUTE` with the
+    /// directory relative to --path (e.g., `reference=/docs/reference`).
+    /// Sources are otherwise served at their path relative to --path, without
+    /// the extension, and `index` files at their directory. Can be repeated.
+    #[arg(long = "route", value_name = "DIR=ROUTE", value_parser = parse_route)]
+    routes: Vec<(PathBuf, String)>,
+
+    /// Directory of static files served at the site root (e.g., ./public),
+    /// for links to images and downloads
+    #[arg(long = "static", value_name = "DIR")]
+    static_dir: Option<PathBuf>,
+
+    /// The base URL of the website (e.g., https://example.com), so that
+    /// absolute links to it are checked against the sources rather than over
+    /// HTTP
+    #[arg(short, long = "base")]
+    base_url: Option<Url>,
+
+    /// Only check links between source files, skipping external links
+    #[arg(long)]
+    internal_only: bool,
+
+    /// Maximum number of external links checked at once
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    concurrency: usize,
+
+    /// Fail on external links carrying affiliate or referral query
+    /// parameters (e.g., `ref` or `affiliate_id`)
+    #[arg(long)]
+    check_affiliate_params: bool,
+
+    /// Strip affiliate and referral query parameters from external links in
+    /// the source files, instead of failing on them
+    #[arg(long)]
+    fix_affiliate_params: bool,
+
+    /// Query parameter marking an affiliate link, in addition to the
+    /// defaults. A trailing `*` matches any parameter with that prefix. Can
+    /// be repeated.
+    #[arg(long = "affiliate-param", value_name = "NAME")]
+    affiliate_params: Vec<String>,
+}
+
+/// A link found in a source file, by where it points
+enum Target {
+    /// A route on the site, as a decoded path
+    Route(String),
+    /// Another source file, linked to by its relative path
+    Source(PathBuf),
+    External(Url),
+}
+
+/// The routes served by the source files
+struct Routes {
+    root: PathBuf,
+    /// Route prefixes for directories under the root, longest first
+    mapping: Vec<(PathBuf, String)>,
+    routes: BTreeSet<String>,
+}
+
+pub(crate) async fn run(args: LinkCheckSrcArgs) -> Result<()> {
+    let root = args
+        .path
+        .canonicalize()
+        .with_context(|| format!("Failed to read source directory {}", args.path.display()))
+        .inspect_err(|e| error!("{e:#}"))?;
+    let mut files = Vec::new();
+    collect_source_files(&root, &mut files)?;
+    files.sort();
+    info!("Found {} source files in {}", files.len(), root.display());
+
+    let mut mapping = args.routes.clone();
+    mapping.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+    let mut routes = Routes {
+        root: root.clone(),
+        mapping,
+        routes: BTreeSet::new(),
+    };
+    routes.routes = files.iter().map(|file| routes.route_of(file)).collect();
+
+    let affiliate_policy = (args.check_affiliate_params || args.fix_affiliate_params)
+        .then(|| AffiliatePolicy::new(&args.affiliate_params));
+    let extractor = Extractor::default();
+    let mut checked = 0;
+    let mut failed = 0;
+    let mut external: BTreeMap<Url, Vec<PathBuf>> = BTreeMap::new();
+    for file in &files {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))
+            .inspect_err(|e| error!("{e:#}"))?;
+        let relative = file.strip_prefix(&root).unwrap_or(file);
+        let links: BTreeSet<String> = extractor
+            .extract(&InputContent::from_string(&content, FileType::Markdown))
+            .into_iter()
+            .map(|raw_uri| raw_uri.text)
+            .collect();
+        let mut replacements = Vec::new();
+        for mut link in links {
+            if let Some(policy) = &affiliate_policy {
+                let violations = policy.violations(&link);
+                let is_external = matches!(
+                    routes.resolve(file, &link, args.base_url.as_ref()),
+                    Some(Target::External(_))
+                );
+                if is_external && !violations.is_empty() {
+                    let stripped = policy.strip(&link);
+                    if args.fix_affiliate_params {
+                        info!(
+                            "Stripped affiliate parameters {} from {link} in {}",
+                            violations.join(", "),
+                            relative.display()
+                        );
+                        replacements.push((link.clone(), stripped.clone()));
+                        link = stripped;
+                    } else {
+                        error!(
+                            "Affiliate link in {}: {link}: affiliate parameters {}, replace with {stripped}",
+                            relative.display(),
+                            violations.join(", ")
+                        );
+                        checked += 1;
+                        failed += 1;
+                        continue;
+                    }
+                }
+            }
+            let Some(target) = routes.resolve(file, &link, args.base_url.as_ref()) else {
+                debug!("Skipping {link} in {}", relative.display());
+                continue;
+            };
+            let result = match target {
+                Target::External(url) => {
+                    if !args.internal_only {
+                        external
+                            .entry(url)
+                            .or_default()
+                            .push(relative.to_path_buf());
+                    }
+                    continue;
+                }
+                Target::Source(path) if path.is_file() => Ok(()),
+                Target::Source(path) => Err(format!("{} does not exist", path.display())),
+                Target::Route(route) => routes.check(&route, args.static_dir.as_deref()),
+            };
+            checked += 1;
+            if let Err(reason) = result {
+                error!("Broken link in {}: {link}: {reason}", relative.display());
+                failed += 1;
+            }
+        }
+        if !replacements.is_empty() {
+            fs::write(file, replace_links(&content, &replacements))
+                .with_context(|| format!("Failed to write {}", file.display()))
+                .inspect_err(|e| error!("{e:#}"))?;
+        }
+    }
+
+    let client = lychee_lib::ClientBuilder::builder()
+        .user_agent("docs-tools".to_string())
+        .build()
+        .client()?;
+    let failures: Vec<(&Url, String)> = stream::iter(&external)
+        .map(|(url, _)| {
+            let client = &client;
+            async move {
+                match client.check(url.as_str()).await {
+                    Ok(response) if response.status().is_success() => None,
+                    Ok(response) => Some((url, response.status().to_string())),
+                    Err(e) => Some((url, e.to_string())),
+                }
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await;
+    checked += external.values().map(Vec::len).sum::<usize>();
+    for (url, reason) in failures {
+        for file in &external[url] {
+            error!("Broken link in {}: {url}: {reason}", file.display());
+            failed += 1;
+        }
+    }
+
+    info!("\nSource Link Check Summary:");
+    info!("Total links checked: {}", checked);
+    info!("Failed checks: {}", failed);
+    if failed > 0 {
+        error!("Some links in the sources are broken");
+        anyhow::bail!("Some links in the sources are broken");
+    }
+    Ok(())
+}
+
+impl Routes {
+    /// The route a source file is served at (e.g., `/guides/foo` for
+    /// `guides/foo.mdx`, or `/guides` for `guides/index.mdx`).
+    fn route_of(&self, file: &Path) -> String {
+        let relative = file.strip_prefix(&self.root).unwrap_or(file);
+        let (prefix, rest) = self
+            .mapping
+            .iter()
+            .find_map(|(dir, route)| Some((route.as_str(), relative.strip_prefix(dir).ok()?)))
+            .unwrap_or(("/", relative));
+        let mut segments: Vec<String> = rest
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if segments.last().is_some_and(|segment| segment == "index") {
+            segments.pop();
+        }
+        normalize_route(&format!("{}/{}", prefix, segments.join("/")))
+    }
+
+    /// Classify a link in a source file. Links to the same page, to
+    /// non-HTTP schemes (e.g., `mailto:`) and unparseable links are skipped.
+    fn resolve(&self, file: &Path, link: &str, base_url: Option<&Url>) -> Option<Target> {
+        if let Ok(url) = Url::parse(link) {
+            let internal = base_url.is_some_and(|base| base.origin() == url.origin());
+            return match url.scheme() {
+                "http" | "https" if internal => Some(Target::Route(percent_decode(url.path()))),
+                "http" | "https" => Some(Target::External(url)),
+                _ => None,
+            };
+        }
+        let path = link.split(['#', '?']).next().unwrap_or_default();
+        if path.is_empty() {
+            return None;
+        }
+        if path.starts_with('/') {
+            return Some(Target::Route(percent_decode(path)));
+        }
+        // Links to other sources are relative to the file, while links to
+        // routes are relative to the page the file is served at (or, for
+        // `index` files, the directory)
+        let decoded = percent_decode(path);
+        let is_source = Path::new(&decoded)
+            .extension()
+            .is_some_and(|ext| SOURCE_EXTENSIONS.iter().any(|source| ext == *source));
+        if is_source {
+            return Some(Target::Source(normalize_path(
+                &file.parent().unwrap_or(file).join(decoded),
+            )));
+        }
+        let mut route = self.route_of(file);
+        if file.file_stem().is_some_and(|stem| stem == "index") && route != "/" {
+            route.push('/');
+        }
+        let page = Url::parse("http://localhost").ok()?.join(&route).ok()?;
+        Some(Target::Route(percent_decode(page.join(path).ok()?.path())))
+    }
+
+    /// Check that a route is served by a source file or a static file.
+    fn check(&self, route: &str, static_dir: Option<&Path>) -> Result<(), String> {
+        if self.routes.contains(&normalize_route(route)) {
+            return Ok(());
+        }
+        if let Some(static_dir) = static_dir {
+            if static_dir.join(route.trim_start_matches('/')).is_file() {
+                return Ok(());
+            }
+        }
+        Err(format!("no source file for route {route}"))
+    }
+}
+
+fn parse_route(s: &str) -> Result<(PathBuf, String), String> {
+    let Some((dir, route)) = s.split_once('=') else {
+        return Err(format!("expected `DIR=ROUTE`, got `{s}`"));
+    };
+    if !route.starts_with('/') {
+        return Err(format!("route {route} must start with `/`"));
+    }
+    Ok((normalize_path(Path::new(dir)), route.to_string()))
+}
+
+pub(super) fn collect_source_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.') || name == "node_modules");
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_source_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| SOURCE_EXTENSIONS.iter().any(|source| ext == *source))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Collapse repeated slashes and drop any trailing slash, so that `/guides/`
+/// and `/guides` are the same route.
+fn normalize_route(route: &str) -> String {
+    let segments: Vec<&str> = route.split('/').filter(|s| !s.is_empty()).collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// Replace links in a file's content, only where each appears as a whole
+/// link, so one that's a prefix of another (`?a=1` of `?a=1&b=2`) or part of
+/// a longer URL is left alone.
+fn replace_links(content: &str, replacements: &[(String, String)]) -> String {
+    let mut ranges: Vec<(usize, usize, &str)> = replacements
+        .iter()
+        .flat_map(|(link, replacement)| {
+            content
+                .match_indices(link.as_str())
+                .map(|(start, _)| (start, start + link.len(), replacement.as_str()))
+                .filter(|(start, end, _)| is_whole_link(content, *start, *end))
+        })
+        .collect();
+    ranges.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+
+    let mut replaced = String::with_capacity(content.len());
+    let mut copied = 0;
+    for (start, end, replacement) in ranges {
+        // Overlaps another link already replaced
+        if start < copied {
+            continue;
+        }
+        replaced.push_str(&content[copied..start]);
+        replaced.push_str(replacement);
+        copied = end;
+    }
+    replaced.push_str(&content[copied..]);
+    replaced
+}
+
+/// Whether the text at `start..end` isn't joined to more of a URL on either
+/// side. Punctuation ending a sentence after a link isn't part of it.
+fn is_whole_link(content: &str, start: usize, end: usize) -> bool {
+    let is_url_char = |c: char| c.is_alphanumeric() || "-._~:/?#@!$&+,;=%".contains(c);
+    let before = content[..start].chars().next_back();
+    let mut after = content[end..].chars();
+    let continues = match after.next() {
+        Some(c @ ('.' | ',' | ';' | ':' | '!' | '?')) => {
+            after.next().is_some_and(|next| !next.is_whitespace()) && is_url_char(c)
+        }
+        Some(c) => is_url_char(c),
+        None => false,
+    };
+    !before.is_some_and(is_url_char) && !continues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_only_whole_links() {
+        let content = "\
+[a](https://shop.example.com/item?tag=aff)
+[b](https://shop.example.com/item?tag=aff&color=red)
+<https://shop.example.com/item?tag=aff>, and again https://shop.example.com/item?tag=aff.
+";
+        let replacements = [
+            (
+                "https://shop.example.com/item?tag=aff".to_string(),
+                "https://shop.example.com/item".to_string(),
+            ),
+            (
+                "https://shop.example.com/item?tag=aff&color=red".to_string(),
+                "https://shop.example.com/item?color=red".to_string(),
+            ),
+        ];
+        assert_eq!(
+            replace_links(content, &replacements),
+            "\
+[a](https://shop.example.com/item)
+[b](https://shop.example.com/item?color=red)
+<https://shop.example.com/item>, and again https://shop.example.com/item.
+"
+        );
+    }
+
+    #[test]
+    fn leaves_links_a_replaced_link_prefixes() {
+        let content = "See https://example.com/a?ref=x and https://example.com/a?ref=xyz";
+        let replacements = [(
+            "https://example.com/a?ref=x".to_string(),
+            "https://example.com/a".to_string(),
+        )];
+        assert_eq!(
+            replace_links(content, &replacements),
+            "See https://example.com/a and https://example.com/a?ref=xyz"
+        );
+    }
+}
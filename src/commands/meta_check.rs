@@ -0,0 +1,184 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::Write,
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+use tracing::{error, info};
+use url::Url;
+
+use super::link_check::{normalize_url, LinkCheckArgs, LinkChecker, PageMeta};
+
+#[derive(Args)]
+pub(crate) struct MetaCheckArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// Shortest meta description allowed, in characters
+    #[arg(long, value_name = "CHARS", default_value_t = 50)]
+    min_description: usize,
+
+    /// Longest meta description allowed, in characters, beyond which search
+    /// engines truncate it
+    #[arg(long, value_name = "CHARS", default_value_t = 160)]
+    max_description: usize,
+
+    /// File to write the problems found to as JSON (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// What's wrong with a page's tags
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ProblemKind {
+    /// No `<link rel=canonical>`, more than one, or one that doesn't load
+    Canonical,
+    /// No `<title>`
+    Title,
+    /// No meta description, more than one, or one outside the length bounds
+    Description,
+    /// Blocked from indexing despite being in the sitemap
+    Noindex,
+}
+
+#[derive(Serialize)]
+struct Problem {
+    page: String,
+    kind: ProblemKind,
+    message: String,
+}
+
+pub(crate) async fn run(mut args: MetaCheckArgs) -> Result<()> {
+    args.link_check.collect_meta = true;
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    let pages = checker.page_meta();
+    info!("Checking the meta tags of {} pages", pages.len());
+    let sitemap_pages: BTreeSet<Url> = checker.sitemap_pages().iter().map(normalize_url).collect();
+    let noindex_headers = checker.noindex_pages();
+    let mut loaded: HashMap<Url, bool> = checker
+        .results()
+        .into_iter()
+        .filter(|record| record.error.is_none())
+        .map(|record| (normalize_url(&record.url), true))
+        .collect();
+
+    let mut problems = Vec::new();
+    for (page, meta) in &pages {
+        let Ok(url) = Url::parse(page) else {
+            continue;
+        };
+        let mut problem = |kind, message: String| {
+            error!("{page}: {message}");
+            problems.push(Problem {
+                page: page.clone(),
+                kind,
+                message,
+            });
+        };
+
+        match meta.canonicals.as_slice() {
+            [] => problem(ProblemKind::Canonical, "no canonical link".to_string()),
+            [href] => match url.join(href) {
+                Ok(canonical) => {
+                    let key = normalize_url(&canonical);
+                    let loads = match loaded.get(&key) {
+                        Some(&loads) => loads,
+                        None => {
+                            let loads = pages.contains_key(key.as_str())
+                                || checker.resolves(&canonical).await.is_ok();
+                            loaded.insert(key, loads);
+                            loads
+                        }
+                    };
+                    if !loads {
+                        problem(
+                            ProblemKind::Canonical,
+                            format!("canonical link to {canonical} doesn't load"),
+                        );
+                    }
+                }
+                Err(e) => problem(
+                    ProblemKind::Canonical,
+                    format!("invalid canonical link {href}: {e}"),
+                ),
+            },
+            canonicals => problem(
+                ProblemKind::Canonical,
+                format!("{} canonical links", canonicals.len()),
+            ),
+        }
+
+        if meta.title.is_none() {
+            problem(ProblemKind::Title, "no title".to_string());
+        }
+
+        if let Some(message) = description_problem(meta, &args) {
+            problem(ProblemKind::Description, message);
+        }
+
+        let noindex = meta.noindex || noindex_headers.contains(&url);
+        if noindex && sitemap_pages.contains(&url) {
+            problem(
+                ProblemKind::Noindex,
+                "blocked from indexing but listed in the sitemap".to_string(),
+            );
+        }
+    }
+    info!(
+        "Found {} problems on {} pages",
+        problems.len(),
+        problems
+            .iter()
+            .map(|problem| &problem.page)
+            .collect::<BTreeSet<_>>()
+            .len()
+    );
+
+    match &args.output {
+        Some(path) => {
+            let mut file = File::create(path)?;
+            serde_json::to_writer_pretty(&mut file, &problems)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &problems)?;
+            writeln!(stdout)?;
+        }
+    }
+
+    if !problems.is_empty() {
+        error!("Some pages have invalid meta tags");
+        anyhow::bail!("Some pages have invalid meta tags");
+    }
+    Ok(())
+}
+
+fn description_problem(meta: &PageMeta, args: &MetaCheckArgs) -> Option<String> {
+    let description = match meta.descriptions.as_slice() {
+        [] => return Some("no meta description".to_string()),
+        [description] => description,
+        descriptions => return Some(format!("{} meta descriptions", descriptions.len())),
+    };
+    let length = description.chars().count();
+    if length < args.min_description {
+        Some(format!(
+            "meta description is {length} characters, under the minimum of {}",
+            args.min_description
+        ))
+    } else if length > args.max_description {
+        Some(format!(
+            "meta description is {length} characters, over the maximum of {}",
+            args.max_description
+        ))
+    } else {
+        None
+    }
+}
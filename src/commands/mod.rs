@@ -1 +1,20 @@
+pub(crate) mod anchors;
+pub(crate) mod check_redirects;
+pub(crate) mod completions;
+pub(crate) mod config_schema;
+pub(crate) mod docsearch_check;
+pub(crate) mod docset;
+pub(crate) mod frontmatter_lint;
+pub(crate) mod graph_diff;
+pub(crate) mod image_audit;
 pub(crate) mod link_check;
+pub(crate) mod link_check_src;
+pub(crate) mod meta_check;
+pub(crate) mod not_found_triage;
+pub(crate) mod notifications;
+pub(crate) mod paths;
+pub(crate) mod rustdoc_check;
+pub(crate) mod search_gaps;
+pub(crate) mod self_update;
+pub(crate) mod sitemap_gen;
+pub(crate) mod xml;
@@ -0,0 +1,245 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use regex::Regex;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use super::link_check::{LinkCheckArgs, LinkChecker};
+
+#[derive(Args)]
+pub(crate) struct NotFoundTriageArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// Server or CDN log to read 404s from, either in Common/Combined Log
+    /// Format or as a CSV file with a path column (and optionally status and
+    /// count columns)
+    #[arg(long, value_name = "FILE")]
+    log: PathBuf,
+
+    /// Only report the most frequently hit missing paths
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+
+    /// File to write the suggested redirects to as JSON (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Minimum similarity between a missing path and a crawled page for the page
+/// to be suggested as a redirect target
+const MIN_SIMILARITY: f64 = 0.6;
+
+/// A path that was requested but not found
+struct MissingPath {
+    path: String,
+    hits: u64,
+}
+
+/// A suggested redirect, in the `source`/`destination` shape used by most
+/// hosting platforms' redirect configs
+#[derive(Serialize)]
+struct Redirect {
+    source: String,
+    destination: String,
+    hits: u64,
+}
+
+pub(crate) async fn run(args: NotFoundTriageArgs) -> Result<()> {
+    let mut missing = read_log(&args.log)?;
+    info!(
+        "Found {} distinct missing paths in {}",
+        missing.len(),
+        args.log.display()
+    );
+    if let Some(top) = args.top {
+        missing.truncate(top);
+    }
+
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    // Crawled HTML pages are the candidate redirect targets
+    let pages: BTreeSet<String> = checker
+        .anchor_map()
+        .keys()
+        .filter_map(|url| Url::parse(url).ok())
+        .map(|url| url.path().to_string())
+        .collect();
+    info!("Matching against {} crawled pages", pages.len());
+
+    let mut redirects = Vec::new();
+    for MissingPath { path, hits } in missing {
+        if pages.iter().any(|page| trim_path(page) == trim_path(&path)) {
+            debug!("{path} now resolves, skipping");
+            continue;
+        }
+        match best_match(&path, &pages) {
+            Some(destination) => {
+                info!("{hits} hits: {path} -> {destination}");
+                redirects.push(Redirect {
+                    source: path,
+                    destination: destination.to_string(),
+                    hits,
+                });
+            }
+            None => warn!("{hits} hits: {path} has no likely target"),
+        }
+    }
+
+    match args.output {
+        Some(path) => {
+            let mut file = File::create(&path)?;
+            serde_json::to_writer_pretty(&mut file, &redirects)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &redirects)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Read the 404s from a log file, deduplicated and sorted by hit count.
+fn read_log(path: &Path) -> Result<Vec<MissingPath>> {
+    let mut hits: HashMap<String, u64> = HashMap::new();
+    if path.extension().is_some_and(|ext| ext == "csv") {
+        read_csv(path, &mut hits)?;
+    } else {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for line in content.lines() {
+            match parse_log_line(line) {
+                Some((path, 404)) => *hits.entry(strip_query(path)).or_default() += 1,
+                Some(_) => {}
+                None => debug!("Skipping unrecognized log line: {line}"),
+            }
+        }
+    }
+
+    let mut missing: Vec<MissingPath> = hits
+        .into_iter()
+        .map(|(path, hits)| MissingPath { path, hits })
+        .collect();
+    missing.sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.path.cmp(&b.path)));
+    Ok(missing)
+}
+
+fn read_csv(path: &Path, hits: &mut HashMap<String, u64>) -> Result<()> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let headers = reader.headers()?.clone();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.trim().to_ascii_lowercase().as_str()))
+    };
+    let path_column = column(&["path", "url", "uri", "request_uri", "cs-uri-stem"])
+        .with_context(|| format!("No path column found in {}", path.display()))?;
+    let status_column = column(&["status", "status_code", "sc-status"]);
+    let count_column = column(&["count", "hits", "requests"]);
+
+    for record in reader.records() {
+        let record = record?;
+        let status = status_column.and_then(|column| record.get(column));
+        if status.is_some_and(|status| status.trim() != "404") {
+            continue;
+        }
+        let Some(path) = record.get(path_column) else {
+            continue;
+        };
+        // Full URLs are accepted too, as some CDNs log them
+        let path = match Url::parse(path) {
+            Ok(url) => url.path().to_string(),
+            Err(_) => strip_query(path),
+        };
+        let count = count_column
+            .and_then(|column| record.get(column))
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(1);
+        *hits.entry(path).or_default() += count;
+    }
+    Ok(())
+}
+
+/// The request path and status code of a Common/Combined Log Format line.
+fn parse_log_line(line: &str) -> Option<(&str, u16)> {
+    static LOG_LINE: OnceLock<Regex> = OnceLock::new();
+    let log_line = LOG_LINE.get_or_init(|| {
+        Regex::new(r#""[A-Z]+ (\S+)(?: HTTP/[\d.]+)?" (\d{3}) "#).expect("Log regex is valid")
+    });
+    let captures = log_line.captures(line)?;
+    let path = captures.get(1)?.as_str();
+    let status = captures.get(2)?.as_str().parse().ok()?;
+    Some((path, status))
+}
+
+fn strip_query(path: &str) -> String {
+    path.split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// A path without its trailing slash or `.html` extension, for comparison.
+fn trim_path(path: &str) -> &str {
+    let path = path
+        .strip_suffix('/')
+        .filter(|p| !p.is_empty())
+        .unwrap_or(path);
+    path.strip_suffix(".html").unwrap_or(path)
+}
+
+/// The crawled page most likely intended by a missing path, if any is similar
+/// enough. A page with the same final segment (e.g., a page moved to another
+/// section) is preferred over one whose full path is merely similar.
+fn best_match<'a>(missing: &str, pages: &'a BTreeSet<String>) -> Option<&'a str> {
+    let missing = trim_path(missing);
+    let missing_slug = missing.rsplit('/').next().unwrap_or_default();
+
+    let mut best: Option<(&str, f64)> = None;
+    for page in pages {
+        let trimmed = trim_path(page);
+        let mut score = similarity(missing, trimmed);
+        if !missing_slug.is_empty() && trimmed.rsplit('/').next() == Some(missing_slug) {
+            score += 1.0;
+        }
+        if score >= MIN_SIMILARITY && best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((page, score));
+        }
+    }
+    best.map(|(page, _)| page)
+}
+
+/// Normalized Levenshtein similarity between two strings, from 0 to 1.
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
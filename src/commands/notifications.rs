@@ -0,0 +1,127 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use chrono::DateTime;
+use clap::{Args, Subcommand};
+use tracing::{error, info, warn};
+use url::Url;
+
+use super::link_check::NotifyState;
+
+#[derive(Subcommand)]
+pub(crate) enum NotificationsCommand {
+    /// Stop notifying owners that a link is broken for a while (e.g., during
+    /// a long outage of an external site)
+    Mute(MuteArgs),
+    /// Notify owners about a muted link again if it's still broken
+    Unmute(UnmuteArgs),
+    /// List the broken links owners were notified of, and muted links
+    List(StateArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct StateArgs {
+    /// Notification state file, as passed to `link-check --notify-state`
+    #[arg(long, value_name = "FILE")]
+    state: PathBuf,
+}
+
+#[derive(Args)]
+pub(crate) struct MuteArgs {
+    #[command(flatten)]
+    state: StateArgs,
+
+    /// The link to mute
+    url: Url,
+
+    /// How long to mute the link for, as a number followed by `m`, `h`, `d`
+    /// or `w` (e.g., `7d`)
+    #[arg(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+    duration: Duration,
+}
+
+#[derive(Args)]
+pub(crate) struct UnmuteArgs {
+    #[command(flatten)]
+    state: StateArgs,
+
+    /// The link to unmute
+    url: Url,
+}
+
+pub(crate) fn run(command: NotificationsCommand) -> Result<()> {
+    match command {
+        NotificationsCommand::Mute(args) => mute(args),
+        NotificationsCommand::Unmute(args) => unmute(args),
+        NotificationsCommand::List(args) => list(args),
+    }
+    .inspect_err(|e| error!("{e:#}"))
+}
+
+fn mute(args: MuteArgs) -> Result<()> {
+    let mut state = NotifyState::load(&args.state.state)?;
+    state.mute(&args.url, args.duration);
+    state.save()?;
+    info!(
+        "Muted notifications for {} for {}",
+        args.url.as_str(),
+        humanize(args.duration)
+    );
+    Ok(())
+}
+
+fn unmute(args: UnmuteArgs) -> Result<()> {
+    let mut state = NotifyState::load(&args.state.state)?;
+    if !state.unmute(&args.url) {
+        warn!("{} isn't muted", args.url.as_str());
+        return Ok(());
+    }
+    state.save()?;
+    info!("Unmuted notifications for {}", args.url.as_str());
+    Ok(())
+}
+
+fn list(args: StateArgs) -> Result<()> {
+    let state = NotifyState::load(&args.state)?;
+    for (url, entry) in state.entries() {
+        let mut line = url.clone();
+        if let Some(notified_at) = entry.notified_at.and_then(format_timestamp) {
+            line.push_str(&format!(" (notified {notified_at})"));
+        }
+        if let Some(muted_until) = entry.muted_until.and_then(format_timestamp) {
+            line.push_str(&format!(" (muted until {muted_until})"));
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (amount, unit) =
+        s.split_at(s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len());
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("expected a number followed by m, h, d or w, got `{s}`"))?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit `{unit}`, expected m, h, d or w")),
+    };
+    Ok(Duration::from_secs(amount * unit_secs))
+}
+
+fn humanize(duration: Duration) -> String {
+    let hours = duration.as_secs() / (60 * 60);
+    match hours {
+        0 => format!("{} minutes", duration.as_secs() / 60),
+        hours if hours % 24 == 0 => format!("{} days", hours / 24),
+        hours => format!("{hours} hours"),
+    }
+}
+
+fn format_timestamp(secs: u64) -> Option<String> {
+    let time = DateTime::from_timestamp(i64::try_from(secs).ok()?, 0)?;
+    Some(time.format("%Y-%m-%d %H:%M UTC").to_string())
+}
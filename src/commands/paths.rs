@@ -0,0 +1,67 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Resolve `.` and `..` components without touching the filesystem, so that
+/// missing targets can still be reported by path.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Decode a percent-encoded link path or fragment, replacing invalid UTF-8.
+pub(crate) fn percent_decode(s: &str) -> String {
+    percent_encoding::percent_decode_str(s)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Whether a decoded URL path segment names a single file or directory, so
+/// joining it to a directory can't climb out of it or replace it: not empty,
+/// `.` or `..`, and without separators, NULs or a drive prefix.
+pub(crate) fn is_plain_segment(segment: &str) -> bool {
+    let bytes = segment.as_bytes();
+    let has_drive_prefix = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    !matches!(segment, "" | "." | "..") && !segment.contains(['/', '\\', '\0']) && !has_drive_prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_dot_components() {
+        assert_eq!(
+            normalize_path(Path::new("docs/./guides/../api/index.md")),
+            PathBuf::from("docs/api/index.md")
+        );
+        assert_eq!(
+            normalize_path(Path::new("/site/a/../../b")),
+            PathBuf::from("/b")
+        );
+    }
+
+    #[test]
+    fn decodes_percent_encoding() {
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        assert_eq!(percent_decode("bad%FF"), "bad\u{FFFD}");
+    }
+
+    #[test]
+    fn plain_segments_stay_in_their_directory() {
+        assert!(is_plain_segment("guide.html"));
+        assert!(is_plain_segment("Special:Search"));
+        assert!(is_plain_segment("..."));
+        for segment in ["", ".", "..", "a/b", "/etc", "a\\b", "a\0b", "C:", "c:file"] {
+            assert!(!is_plain_segment(segment), "{segment:?}");
+        }
+    }
+}
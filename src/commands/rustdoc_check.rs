@@ -0,0 +1,243 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::Args;
+use html5gum::{Token, Tokenizer};
+use lychee_lib::{extract::Extractor, FileType, InputContent};
+use tracing::{debug, error, info};
+
+use super::{
+    link_check::extract_anchors,
+    paths::{normalize_path, percent_decode},
+};
+
+#[derive(Args)]
+pub(crate) struct RustdocCheckArgs {
+    /// Directory containing locally generated rustdoc output
+    #[arg(long, default_value = "target/doc")]
+    docs: PathBuf,
+}
+
+/// Local rustdoc output, indexed by file
+struct DocsTree {
+    /// Links found in each HTML file
+    links: HashMap<PathBuf, Vec<String>>,
+    /// Anchors found in each HTML file
+    anchors: HashMap<PathBuf, BTreeSet<String>>,
+    /// Targets of rustdoc's redirect stubs (generated for re-exports), which
+    /// use `<meta http-equiv="refresh">` rather than containing the item.
+    /// Both maps are filled lazily for files outside the docs directory.
+    redirects: HashMap<PathBuf, PathBuf>,
+}
+
+pub(crate) fn run(args: RustdocCheckArgs) -> Result<()> {
+    let root = args.docs.canonicalize().map_err(|e| {
+        anyhow::anyhow!("Failed to read docs directory {}: {e}", args.docs.display())
+    })?;
+
+    let mut html_files = Vec::new();
+    collect_html_files(&root, &mut html_files)?;
+    html_files.sort();
+    info!(
+        "Found {} HTML files in {}",
+        html_files.len(),
+        root.display()
+    );
+
+    let extractor = Extractor::default();
+    let mut tree = DocsTree {
+        links: HashMap::new(),
+        anchors: HashMap::new(),
+        redirects: HashMap::new(),
+    };
+    for file in &html_files {
+        let content = fs::read_to_string(file)?;
+        if let Some(target) = meta_refresh_target(&content) {
+            let target = normalize_path(&file.parent().unwrap_or(file).join(target));
+            tree.redirects.insert(file.clone(), target);
+            continue;
+        }
+        // Only consider links in attributes, not URL-like text in prose
+        let links = extractor
+            .extract(&InputContent::from_string(&content, FileType::Html))
+            .into_iter()
+            .filter(|raw_uri| raw_uri.attribute.is_some())
+            .map(|raw_uri| raw_uri.text)
+            .collect();
+        tree.links.insert(file.clone(), links);
+        tree.anchors.insert(file.clone(), extract_anchors(&content));
+    }
+
+    let mut checked = 0;
+    let mut failed = 0;
+    for file in &html_files {
+        let Some(links) = tree.links.get(file).cloned() else {
+            continue;
+        };
+        for link in links {
+            if link.contains(':') && !link.starts_with('#') {
+                debug!("Skipping non-local link {link} in {}", file.display());
+                continue;
+            }
+            checked += 1;
+            if let Err(reason) = tree.check_link(file, &link) {
+                error!("Broken link in {}: {link}: {reason}", file.display());
+                failed += 1;
+            }
+        }
+    }
+
+    info!("\nRustdoc Check Summary:");
+    info!("Total links checked: {}", checked);
+    info!("Failed checks: {}", failed);
+    if failed > 0 {
+        error!("Some intra-doc links are broken");
+        anyhow::bail!("Some intra-doc links are broken");
+    }
+    Ok(())
+}
+
+impl DocsTree {
+    /// Check that a relative link from `file` resolves to an existing file and,
+    /// if it has a fragment, to an existing anchor in that file.
+    fn check_link(&mut self, file: &Path, link: &str) -> Result<(), String> {
+        let (link, fragment) = match link.split_once('#') {
+            Some((link, fragment)) => (link, Some(fragment)),
+            None => (link, None),
+        };
+        let link = link.split('?').next().unwrap_or_default();
+
+        let target = if link.is_empty() {
+            file.to_path_buf()
+        } else {
+            let decoded = percent_decode(link);
+            let mut target = normalize_path(&file.parent().unwrap_or(file).join(decoded));
+            if target.is_dir() {
+                target.push("index.html");
+            }
+            target
+        };
+        if !target.is_file() {
+            return Err(format!("{} does not exist", target.display()));
+        }
+        let target = self.follow_redirects(target)?;
+
+        let Some(fragment) = fragment.filter(|fragment| !fragment.is_empty()) else {
+            return Ok(());
+        };
+        // Line anchors on source pages (e.g., `#10-20`) are handled by
+        // rustdoc's JavaScript rather than present as elements
+        if is_line_range(fragment) {
+            return Ok(());
+        }
+        if target.extension().is_none_or(|ext| ext != "html") {
+            return Ok(());
+        }
+
+        let Some(anchors) = self.anchors.get(&target) else {
+            return Ok(());
+        };
+        // Like browsers, accept the fragment either as written or decoded, as
+        // rustdoc ids may themselves contain percent-encoded characters
+        if !anchors.contains(fragment) && !anchors.contains(&percent_decode(fragment)) {
+            return Err(format!(
+                "anchor #{fragment} not found in {}",
+                target.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Index a page outside the docs directory (e.g., a dependency's docs)
+    /// the first time it is linked to.
+    fn load(&mut self, path: &Path) -> Result<(), String> {
+        if self.anchors.contains_key(path) || self.redirects.contains_key(path) {
+            return Ok(());
+        }
+        if path.extension().is_none_or(|ext| ext != "html") {
+            return Ok(());
+        }
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        match meta_refresh_target(&content) {
+            Some(next) => {
+                let next = normalize_path(&path.parent().unwrap_or(path).join(next));
+                self.redirects.insert(path.to_path_buf(), next);
+            }
+            None => {
+                self.anchors
+                    .insert(path.to_path_buf(), extract_anchors(&content));
+            }
+        }
+        Ok(())
+    }
+
+    fn follow_redirects(&mut self, mut target: PathBuf) -> Result<PathBuf, String> {
+        const MAX_REDIRECTS: usize = 10;
+        for _ in 0..MAX_REDIRECTS {
+            self.load(&target)?;
+            match self.redirects.get(&target) {
+                Some(next) if next.is_file() => target = next.clone(),
+                Some(next) => {
+                    return Err(format!(
+                        "{} redirects to {}, which does not exist",
+                        target.display(),
+                        next.display()
+                    ))
+                }
+                None => return Ok(target),
+            }
+        }
+        Err(format!("too many redirects from {}", target.display()))
+    }
+}
+
+/// The relative URL a `<meta http-equiv="refresh">` page redirects to.
+fn meta_refresh_target(s: &str) -> Option<String> {
+    for Ok(token) in Tokenizer::new(s) {
+        match token {
+            Token::StartTag(tag) if tag.name == b"meta" => {
+                let is_refresh = tag
+                    .attributes
+                    .get(b"http-equiv".as_slice())
+                    .is_some_and(|value| value.eq_ignore_ascii_case(b"refresh"));
+                if !is_refresh {
+                    continue;
+                }
+                let content = tag.attributes.get(b"content".as_slice())?;
+                let content = String::from_utf8_lossy(content);
+                let (_, url) = content.split_once(';')?;
+                let url = url.trim();
+                let url = url
+                    .strip_prefix("URL=")
+                    .or_else(|| url.strip_prefix("url="))
+                    .unwrap_or(url);
+                return Some(percent_decode(url));
+            }
+            // Redirect stubs declare the refresh in the head
+            Token::StartTag(tag) if tag.name == b"body" => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn collect_html_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_html_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_line_range(fragment: &str) -> bool {
+    let mut parts = fragment.splitn(2, '-');
+    parts.all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
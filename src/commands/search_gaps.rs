@@ -0,0 +1,156 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::link_check::{LinkCheckArgs, LinkChecker};
+
+#[derive(Args)]
+pub(crate) struct SearchGapsArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// CSV export of search queries, with a query column and optionally a
+    /// count column (e.g., an Algolia or DocSearch analytics export)
+    #[arg(long, value_name = "FILE")]
+    queries: PathBuf,
+
+    /// Only consider the most frequent queries
+    #[arg(long, value_name = "N", default_value_t = 100)]
+    top: usize,
+
+    /// File to write the gap report to as JSON (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Words ignored when matching queries against headings
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "can", "do", "does", "for", "from", "how", "i", "in", "is", "it",
+    "my", "of", "on", "or", "the", "to", "what", "when", "with",
+];
+
+/// A search query with no page whose title or headings cover it
+#[derive(Serialize)]
+struct Gap {
+    query: String,
+    count: u64,
+    /// The page whose headings matched the most words of the query, if any
+    closest_page: Option<String>,
+}
+
+pub(crate) async fn run(args: SearchGapsArgs) -> Result<()> {
+    let mut queries = read_queries(&args.queries)?;
+    info!(
+        "Found {} distinct queries in {}",
+        queries.len(),
+        args.queries.display()
+    );
+    queries.truncate(args.top);
+
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    let pages: Vec<(String, BTreeSet<String>)> = checker
+        .page_headings()
+        .into_iter()
+        .map(|(url, headings)| (url, tokenize(&headings.join(" "))))
+        .collect();
+    info!("Matching against the headings of {} pages", pages.len());
+
+    let mut gaps = Vec::new();
+    for (query, count) in queries {
+        let terms = tokenize(&query);
+        if terms.is_empty() {
+            continue;
+        }
+        let mut closest: Option<(&str, usize)> = None;
+        for (url, words) in &pages {
+            let matched = terms.intersection(words).count();
+            if matched > 0 && closest.is_none_or(|(_, best)| matched > best) {
+                closest = Some((url, matched));
+            }
+        }
+        if closest.is_some_and(|(_, matched)| matched == terms.len()) {
+            continue;
+        }
+        warn!("No page covers the query \"{query}\" ({count} searches)");
+        gaps.push(Gap {
+            query,
+            count,
+            closest_page: closest.map(|(url, _)| url.to_string()),
+        });
+    }
+    info!("Found {} queries with no matching content", gaps.len());
+
+    match args.output {
+        Some(path) => {
+            let mut file = File::create(&path)?;
+            serde_json::to_writer_pretty(&mut file, &gaps)?;
+            writeln!(file)?;
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &gaps)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Read queries from a CSV export, merged case-insensitively and sorted by
+/// count.
+fn read_queries(path: &Path) -> Result<Vec<(String, u64)>> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let headers = reader.headers()?.clone();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.trim().to_ascii_lowercase().as_str()))
+    };
+    let query_column = column(&["query", "search", "term", "keyword"])
+        .with_context(|| format!("No query column found in {}", path.display()))?;
+    let count_column = column(&["count", "searches", "hits"]);
+
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for record in reader.records() {
+        let record = record?;
+        let Some(query) = record.get(query_column) else {
+            continue;
+        };
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        if query.is_empty() {
+            continue;
+        }
+        let count = count_column
+            .and_then(|column| record.get(column))
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(1);
+        *counts.entry(query.to_lowercase()).or_default() += count;
+    }
+
+    let mut queries: Vec<(String, u64)> = counts.into_iter().collect();
+    queries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(queries)
+}
+
+/// Lowercase words of a string, without stop words and with plurals reduced
+/// to their singular so that "webhooks" matches "Webhook".
+fn tokenize(s: &str) -> BTreeSet<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
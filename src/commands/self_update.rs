@@ -0,0 +1,192 @@
+use std::{env, fs, path::Path};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use minisign_verify::{PublicKey, Signature};
+use semver::Version;
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+/// Minisign public key release binaries are signed with, set when building
+/// release binaries
+const BUILT_IN_PUBLIC_KEY: Option<&str> = option_env!("DOCS_TOOLS_UPDATE_PUBLIC_KEY");
+
+#[derive(Args)]
+pub(crate) struct SelfUpdateArgs {
+    /// GitHub repository to download releases from
+    #[arg(
+        long,
+        value_name = "OWNER/NAME",
+        default_value = "charislam/docs-tools"
+    )]
+    repo: String,
+
+    /// Release version to install (defaults to the latest release)
+    #[arg(long)]
+    version: Option<Version>,
+
+    /// Minisign public key to verify the release signature with (defaults to
+    /// the key the binary was built with)
+    #[arg(long, value_name = "KEY")]
+    public_key: Option<String>,
+
+    /// Install the release even if it isn't newer than the current version
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+pub(crate) async fn run(args: SelfUpdateArgs) -> Result<()> {
+    update(args).await.inspect_err(|e| error!("{e:#}"))
+}
+
+/// Replace the running binary with a release from GitHub, after verifying
+/// its minisign signature.
+async fn update(args: SelfUpdateArgs) -> Result<()> {
+    let Some(public_key) = args.public_key.as_deref().or(BUILT_IN_PUBLIC_KEY) else {
+        anyhow::bail!(
+            "This binary was built without an update signing key, pass one with --public-key"
+        );
+    };
+    let public_key = PublicKey::from_base64(public_key)
+        .map_err(|e| anyhow::anyhow!("Invalid public key: {e}"))?;
+
+    let client = reqwest::Client::builder()
+        .user_agent("docs-tools")
+        .build()?;
+    let release_url = match &args.version {
+        Some(version) => format!(
+            "https://api.github.com/repos/{}/releases/tags/v{version}",
+            args.repo
+        ),
+        None => format!("https://api.github.com/repos/{}/releases/latest", args.repo),
+    };
+    let release: Release = client
+        .get(&release_url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Failed to find release at {release_url}"))?
+        .json()
+        .await?;
+
+    let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let version = Version::parse(release.tag_name.trim_start_matches('v'))
+        .with_context(|| format!("Release tag {} is not a version", release.tag_name))?;
+    if version <= current && !args.force {
+        info!("Already up to date (current version {current}, release {version})");
+        return Ok(());
+    }
+
+    let asset_name = format!("docs-tools-{}-{}", env::consts::ARCH, env::consts::OS);
+    let find_asset = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .with_context(|| format!("Release {} has no asset {name}", release.tag_name))
+    };
+    let binary_asset = find_asset(&asset_name)?;
+    let signature_asset = find_asset(&format!("{asset_name}.minisig"))?;
+
+    let binary = download(&client, &binary_asset.browser_download_url).await?;
+    let signature =
+        String::from_utf8(download(&client, &signature_asset.browser_download_url).await?)?;
+    let signature =
+        Signature::decode(&signature).map_err(|e| anyhow::anyhow!("Invalid signature: {e}"))?;
+    public_key
+        .verify(&binary, &signature, false)
+        .map_err(|e| anyhow::anyhow!("Signature verification failed for {asset_name}: {e}"))?;
+
+    replace_current_exe(&binary)?;
+    if version < current {
+        warn!("Downgraded docs-tools from {current} to {version}");
+    } else {
+        info!("Updated docs-tools from {current} to {version}");
+    }
+    Ok(())
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    let response = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("Failed to download {url}"))?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Write the new binary next to the current one and move it into place, so
+/// the current binary is never left partially written.
+fn replace_current_exe(binary: &[u8]) -> Result<()> {
+    replace_exe(&env::current_exe()?, binary)
+}
+
+/// Replace an executable with a new binary. Windows won't replace a running
+/// executable but will rename it, so there the current one is moved aside to
+/// `.old` first, and removed by the next update once nothing runs it.
+fn replace_exe(exe: &Path, binary: &[u8]) -> Result<()> {
+    let new_exe = exe.with_extension("new");
+    let old_exe = exe.with_extension("old");
+    if old_exe.exists() {
+        let _ = fs::remove_file(&old_exe);
+    }
+    fs::write(&new_exe, binary)
+        .with_context(|| format!("Failed to write {}", new_exe.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new_exe, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    fs::rename(exe, &old_exe).with_context(|| format!("Failed to move {} aside", exe.display()))?;
+    if let Err(e) = fs::rename(&new_exe, exe) {
+        // Put the current binary back, so it's still installed
+        #[cfg(windows)]
+        let _ = fs::rename(&old_exe, exe);
+        let _ = fs::remove_file(&new_exe);
+        return Err(e).with_context(|| format!("Failed to replace {}", exe.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_executable() {
+        let dir = env::temp_dir().join(format!("docs-tools-self-update-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("docs-tools.exe");
+        fs::write(&exe, "current").unwrap();
+        fs::write(dir.join("docs-tools.old"), "left by the last update").unwrap();
+
+        let replaced = replace_exe(&exe, b"new").map_err(|e| e.to_string());
+        let contents = fs::read_to_string(&exe).unwrap();
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| {
+                name != "docs-tools.exe" && (cfg!(not(windows)) || name != "docs-tools.old")
+            })
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(replaced, Ok(()));
+        assert_eq!(contents, "new");
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fmt::Write as _, fs, io::Write, path::PathBuf};
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use regex::Regex;
+use tracing::{info, warn};
+use url::Url;
+
+use super::{
+    link_check::{normalize_url, LinkCheckArgs, LinkChecker},
+    xml,
+};
+
+/// Most URLs search engines read from one sitemap file
+const MAX_SITEMAP_URLS: usize = 50_000;
+
+#[derive(Args)]
+pub(crate) struct SitemapGenArgs {
+    #[command(flatten)]
+    link_check: LinkCheckArgs,
+
+    /// How often pages are expected to change, given as every page's
+    /// `<changefreq>`
+    #[arg(long, value_enum)]
+    changefreq: Option<ChangeFreq>,
+
+    /// Priority of pages relative to others on the site, from 0.0 to 1.0,
+    /// given as every page's `<priority>`
+    #[arg(long, value_parser = parse_priority)]
+    priority: Option<f32>,
+
+    /// Regex of crawled pages to leave out of the sitemap while still
+    /// following their links (e.g., `/search` or `\?`). Can be repeated.
+    #[arg(long = "omit", value_name = "REGEX")]
+    omits: Vec<Regex>,
+
+    /// File to write the sitemap to (defaults to stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// How often a page is expected to change, as a sitemap `<changefreq>`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ChangeFreq {
+    Always,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
+
+impl ChangeFreq {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Yearly => "yearly",
+            Self::Never => "never",
+        }
+    }
+}
+
+pub(crate) async fn run(mut args: SitemapGenArgs) -> Result<()> {
+    args.link_check.collect_meta = true;
+    let checker = LinkChecker::new(&args.link_check)?;
+    checker.crawl().await?;
+
+    let noindex_headers = checker.noindex_pages();
+    let last_modified = checker.last_modified();
+    let mut pages = Vec::new();
+    for (page, meta) in checker.page_meta() {
+        let Ok(url) = Url::parse(&page) else {
+            continue;
+        };
+        if args.omits.iter().any(|omit| omit.is_match(url.as_str())) {
+            info!("Omitting {page} from the sitemap");
+            continue;
+        }
+        if meta.noindex || noindex_headers.contains(&url) {
+            info!("Omitting {page}, which is blocked from indexing");
+            continue;
+        }
+        // Search engines index the canonical page in place of its
+        // duplicates, which is listed itself if it's crawled
+        let canonical = match meta.canonicals.as_slice() {
+            [href] => url
+                .join(href)
+                .ok()
+                .map(|canonical| normalize_url(&canonical)),
+            _ => None,
+        };
+        if let Some(canonical) = canonical.filter(|canonical| *canonical != url) {
+            info!("Omitting {page}, whose canonical URL is {canonical}");
+            continue;
+        }
+        let lastmod = last_modified.get(&url).copied();
+        pages.push((url, lastmod));
+    }
+    info!("Writing a sitemap of {} pages", pages.len());
+    if pages.len() > MAX_SITEMAP_URLS {
+        warn!(
+            "The sitemap lists {} pages, but search engines only read the first {MAX_SITEMAP_URLS}",
+            pages.len()
+        );
+    }
+
+    let mut sitemap = String::new();
+    writeln!(sitemap, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        sitemap,
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#
+    )?;
+    for (url, lastmod) in &pages {
+        writeln!(sitemap, "  <url>")?;
+        writeln!(sitemap, "    <loc>{}</loc>", xml::escape(url.as_str()))?;
+        if let Some(lastmod) = lastmod {
+            writeln!(
+                sitemap,
+                "    <lastmod>{}</lastmod>",
+                lastmod.format("%Y-%m-%d")
+            )?;
+        }
+        if let Some(changefreq) = args.changefreq {
+            writeln!(
+                sitemap,
+                "    <changefreq>{}</changefreq>",
+                changefreq.name()
+            )?;
+        }
+        if let Some(priority) = args.priority {
+            writeln!(sitemap, "    <priority>{priority}</priority>")?;
+        }
+        writeln!(sitemap, "  </url>")?;
+    }
+    writeln!(sitemap, "</urlset>")?;
+
+    match &args.output {
+        Some(path) => fs::write(path, sitemap)?,
+        None => std::io::stdout().lock().write_all(sitemap.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn parse_priority(s: &str) -> Result<f32, String> {
+    let priority: f32 = s
+        .parse()
+        .map_err(|_| format!("expected a number from 0.0 to 1.0, got `{s}`"))?;
+    if !(0.0..=1.0).contains(&priority) {
+        return Err(format!("expected a number from 0.0 to 1.0, got `{s}`"));
+    }
+    Ok(priority)
+}
@@ -0,0 +1,295 @@
+use anyhow::{bail, Context, Result};
+
+/// Escape text for XML or HTML, in element content or a quoted attribute.
+/// Newlines are escaped so that attribute values keep them.
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// An event read from an XML document. Names are split into the namespace
+/// their prefix (or the default namespace) is bound to and their local name.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    Start {
+        namespace: Option<String>,
+        name: String,
+    },
+    End {
+        namespace: Option<String>,
+        name: String,
+    },
+    /// Character data, with entities decoded, or the contents of a CDATA
+    /// section
+    Text(String),
+}
+
+/// An element left open while reading, with the namespaces it declares
+struct OpenElement<'a> {
+    qualified_name: &'a str,
+    /// Prefixes declared, as `(prefix, namespace)`, with `None` for the
+    /// default namespace
+    namespaces: Vec<(Option<&'a str>, String)>,
+}
+
+/// Read the elements and text of an XML document, skipping comments,
+/// processing instructions and the document type declaration. Only the
+/// predefined and numeric entities are known, as no DTD is read.
+///
+/// ## Returns
+/// Fails if the document isn't well-formed, or uses another entity.
+pub(crate) fn read(document: &str) -> Result<Vec<Event>> {
+    let mut events = Vec::new();
+    let mut open: Vec<OpenElement> = Vec::new();
+    let mut rest = document;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = skip_past(after, "-->").context("Unclosed comment")?;
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").context("Unclosed CDATA section")?;
+            events.push(Event::Text(after[..end].to_string()));
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            rest = skip_past(after, "?>").context("Unclosed processing instruction")?;
+        } else if let Some(after) = rest.strip_prefix("<!") {
+            rest = skip_declaration(after).context("Unclosed declaration")?;
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').context("Unclosed end tag")?;
+            let qualified_name = after[..end].trim();
+            let Some(element) = open.pop() else {
+                bail!("Unexpected end tag </{qualified_name}>");
+            };
+            if element.qualified_name != qualified_name {
+                bail!(
+                    "End tag </{qualified_name}> doesn't match <{}>",
+                    element.qualified_name
+                );
+            }
+            let (namespace, name) = resolve(&open, &element, qualified_name)?;
+            events.push(Event::End { namespace, name });
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (element, self_closing, after) = start_tag(after)?;
+            let (namespace, name) = resolve(&open, &element, element.qualified_name)?;
+            events.push(Event::Start {
+                namespace: namespace.clone(),
+                name: name.clone(),
+            });
+            if self_closing {
+                events.push(Event::End { namespace, name });
+            } else {
+                open.push(element);
+            }
+            rest = after;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            events.push(Event::Text(decode(&rest[..end])?));
+            rest = &rest[end..];
+        }
+    }
+    if let Some(element) = open.last() {
+        bail!("Unclosed element <{}>", element.qualified_name);
+    }
+    Ok(events)
+}
+
+/// The rest of a document after the next `end`.
+fn skip_past<'a>(s: &'a str, end: &str) -> Option<&'a str> {
+    s.find(end).map(|index| &s[index + end.len()..])
+}
+
+/// The rest of a document after a `<!DOCTYPE ...>` declaration, including
+/// any internal subset in brackets.
+fn skip_declaration(s: &str) -> Option<&str> {
+    let mut depth = 0;
+    for (index, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '>' if depth == 0 => return Some(&s[index + 1..]),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse a start tag after its `<`, returning the element, whether it
+/// closes itself (`<br/>`), and the rest of the document.
+fn start_tag(s: &str) -> Result<(OpenElement<'_>, bool, &str)> {
+    let name_end = s
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .context("Unclosed start tag")?;
+    let qualified_name = &s[..name_end];
+    if qualified_name.is_empty() {
+        bail!("Start tag without a name");
+    }
+    let mut element = OpenElement {
+        qualified_name,
+        namespaces: Vec::new(),
+    };
+    let mut rest = &s[name_end..];
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return Ok((element, true, after));
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return Ok((element, false, after));
+        }
+        let (name, after) = rest
+            .split_once('=')
+            .with_context(|| format!("Attribute without a value in <{qualified_name}>"))?;
+        let after = after.trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .with_context(|| format!("Unquoted attribute value in <{qualified_name}>"))?;
+        let value_end = after[1..]
+            .find(quote)
+            .with_context(|| format!("Unclosed attribute value in <{qualified_name}>"))?;
+        let value = decode(&after[1..1 + value_end])?;
+        match name.trim() {
+            "xmlns" => element.namespaces.push((None, value)),
+            name => {
+                if let Some(prefix) = name.strip_prefix("xmlns:") {
+                    element.namespaces.push((Some(prefix), value));
+                }
+            }
+        }
+        rest = &after[value_end + 2..];
+    }
+}
+
+/// The namespace and local name of an element, resolving its prefix with
+/// the declarations on it and the elements it's in.
+fn resolve(
+    open: &[OpenElement],
+    element: &OpenElement,
+    qualified_name: &str,
+) -> Result<(Option<String>, String)> {
+    let (prefix, name) = match qualified_name.split_once(':') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, qualified_name),
+    };
+    let namespace = std::iter::once(element)
+        .chain(open.iter().rev())
+        .flat_map(|element| element.namespaces.iter().rev())
+        .find(|(declared, _)| *declared == prefix)
+        .map(|(_, namespace)| namespace.clone())
+        // The default namespace can be undeclared with `xmlns=""`
+        .filter(|namespace| !namespace.is_empty());
+    if prefix.is_some_and(|prefix| prefix != "xml") && namespace.is_none() {
+        bail!("Undeclared namespace prefix in <{qualified_name}>");
+    }
+    Ok((namespace, name.to_string()))
+}
+
+/// Decode the predefined and numeric entities in text or an attribute value.
+fn decode(s: &str) -> Result<String> {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .with_context(|| format!("Unterminated entity in {s:?}"))?;
+        let entity = &rest[start + 1..start + end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(|code| char::from_u32(code.ok()?))
+                .with_context(|| format!("Unknown entity &{entity};"))?,
+        };
+        decoded.push(c);
+        rest = &rest[start + end + 1..];
+    }
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup_and_quotes() {
+        assert_eq!(
+            escape(r#"<a href="/?q=1&r='2'">"#),
+            "&lt;a href=&quot;/?q=1&amp;r=&#39;2&#39;&quot;&gt;"
+        );
+        assert_eq!(escape("line\nbreak"), "line&#10;break");
+        assert_eq!(escape("plain café"), "plain café");
+    }
+
+    #[test]
+    fn reads_elements_text_and_cdata() {
+        let events = read(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE root [<!ENTITY x "y">]>
+<!-- comment -->
+<root xmlns="urn:default" xmlns:p="urn:prefixed"><p:a href='/?q=1&amp;r=2'>A &lt;&#233;&#xE9;&gt;</p:a><b/><![CDATA[<raw> & text]]></root>"#,
+        )
+        .unwrap();
+        let start = |namespace: &str, name: &str| Event::Start {
+            namespace: Some(namespace.to_string()),
+            name: name.to_string(),
+        };
+        let end = |namespace: &str, name: &str| Event::End {
+            namespace: Some(namespace.to_string()),
+            name: name.to_string(),
+        };
+        let text = |text: &str| Event::Text(text.to_string());
+        assert_eq!(
+            events
+                .into_iter()
+                .filter(|event| *event != text("\n"))
+                .collect::<Vec<_>>(),
+            [
+                start("urn:default", "root"),
+                start("urn:prefixed", "a"),
+                text("A <éé>"),
+                end("urn:prefixed", "a"),
+                start("urn:default", "b"),
+                end("urn:default", "b"),
+                text("<raw> & text"),
+                end("urn:default", "root"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for document in [
+            "<a><b></a></b>",
+            "<a>",
+            "</a>",
+            "<a>&nbsp;</a>",
+            "<a>&amp</a>",
+            "<p:a></p:a>",
+            "<a href=x></a>",
+            "<a><![CDATA[x</a>",
+            "<a><!-- x</a>",
+        ] {
+            assert!(read(document).is_err(), "{document}");
+        }
+    }
+}
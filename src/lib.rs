@@ -0,0 +1,143 @@
+//! Tools for checking and maintaining documentation sites.
+//!
+//! The `docs-tools` binary is a thin wrapper around [`run`]. The link checker
+//! can also be embedded directly with [`LinkChecker::builder`], subscribing to
+//! each result as it's checked instead of parsing logs:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! let checker = docs_tools::LinkChecker::builder("https://example.com".parse()?)
+//!     .internal_only(true)
+//!     .on_result(|record| {
+//!         if let Some(error) = &record.error {
+//!             eprintln!("{} is broken: {error}", record.url);
+//!         }
+//!     })
+//!     .build()?;
+//! checker.crawl().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use clap::{CommandFactory, Parser, Subcommand};
+use tracing::info;
+
+mod commands;
+mod logging;
+
+pub use commands::link_check::{
+    Category, CheckRecord, DeadlineReached, LinkChecker, LinkCheckerBuilder,
+};
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Enable trace level logging
+    #[arg(long)]
+    trace: bool,
+
+    /// Format of log lines. Each line includes the spans it was logged in,
+    /// such as the link being checked and the page it was linked from.
+    #[arg(long, value_enum, default_value_t)]
+    log_format: logging::LogFormat,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+// Parsed once per run, so the size of the largest variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+enum Commands {
+    /// Check links recursively starting from a given URL
+    LinkCheck {
+        #[command(flatten)]
+        args: commands::link_check::LinkCheckArgs,
+        #[command(flatten)]
+        report: commands::link_check::ReportArgs,
+    },
+    /// Check links in Markdown and MDX sources before the site is built
+    LinkCheckSrc(commands::link_check_src::LinkCheckSrcArgs),
+    /// Build and export an index of the anchors on each page of a site
+    Anchors {
+        #[command(subcommand)]
+        command: commands::anchors::AnchorsCommand,
+    },
+    /// Verify that each rule in a redirects file redirects to a working page
+    CheckRedirects(commands::check_redirects::CheckRedirectsArgs),
+    /// Generate a shell completion script
+    Completions(commands::completions::CompletionsArgs),
+    /// Print the JSON Schema for the docs-tools.toml config file
+    ConfigSchema(commands::config_schema::ConfigSchemaArgs),
+    /// Check that an Algolia DocSearch config's selectors match live pages
+    DocsearchCheck(commands::docsearch_check::DocsearchCheckArgs),
+    /// Crawl a site and package it as a Dash/Zeal docset
+    Docset(commands::docset::DocsetArgs),
+    /// Mute or list notifications sent to owners about broken links
+    Notifications {
+        #[command(subcommand)]
+        command: commands::notifications::NotificationsCommand,
+    },
+    /// Rank the missing paths in a 404 log and suggest redirects to crawled pages
+    #[command(name = "404-triage")]
+    NotFoundTriage(commands::not_found_triage::NotFoundTriageArgs),
+    /// Validate intra-doc links and anchors in locally generated rustdoc output
+    RustdocCheck(commands::rustdoc_check::RustdocCheckArgs),
+    /// Update docs-tools to the latest signed release
+    SelfUpdate(commands::self_update::SelfUpdateArgs),
+    /// Report frequent search queries that no page title or heading covers
+    SearchGaps(commands::search_gaps::SearchGapsArgs),
+    /// Report images missing alt text, oversized images and images in legacy
+    /// formats
+    ImageAudit(commands::image_audit::ImageAuditArgs),
+    /// Compare the link graphs of two crawls saved with `link-check
+    /// --save-graph`, reporting removed sections, pages that lost their
+    /// inbound links and new hub pages
+    GraphDiff(commands::graph_diff::GraphDiffArgs),
+    /// Validate the YAML frontmatter of Markdown and MDX sources against a
+    /// schema of required fields, allowed values, date formats and unique
+    /// values
+    FrontmatterLint(commands::frontmatter_lint::FrontmatterLintArgs),
+    /// Check that each crawled page has exactly one canonical link that
+    /// loads, a title and a meta description of a reasonable length, and
+    /// isn't blocked from indexing while listed in the sitemap
+    MetaCheck(commands::meta_check::MetaCheckArgs),
+    /// Crawl a site and write a sitemap.xml of its indexable pages, including
+    /// those only linked dynamically that a static site generator misses
+    SitemapGen(commands::sitemap_gen::SitemapGenArgs),
+}
+
+/// Parse the command line and run the requested command.
+pub async fn run() -> Result<()> {
+    let cli = Cli::parse();
+
+    logging::init(cli.trace, cli.log_format);
+
+    info!("Starting docs-tools");
+
+    match cli.command {
+        Commands::LinkCheck { args, report } => {
+            commands::link_check::LinkChecker::new(&args)?
+                .check(&report)
+                .await
+        }
+        Commands::LinkCheckSrc(args) => commands::link_check_src::run(args).await,
+        Commands::Anchors { command } => commands::anchors::run(command).await,
+        Commands::CheckRedirects(args) => commands::check_redirects::run(args).await,
+        Commands::Completions(args) => commands::completions::run(args, Cli::command()),
+        Commands::ConfigSchema(args) => commands::config_schema::run(args),
+        Commands::DocsearchCheck(args) => commands::docsearch_check::run(args).await,
+        Commands::Docset(args) => commands::docset::run(args).await,
+        Commands::Notifications { command } => commands::notifications::run(command),
+        Commands::NotFoundTriage(args) => commands::not_found_triage::run(args).await,
+        Commands::RustdocCheck(args) => commands::rustdoc_check::run(args),
+        Commands::SelfUpdate(args) => commands::self_update::run(args).await,
+        Commands::SearchGaps(args) => commands::search_gaps::run(args).await,
+        Commands::ImageAudit(args) => commands::image_audit::run(args).await,
+        Commands::GraphDiff(args) => commands::graph_diff::run(args),
+        Commands::FrontmatterLint(args) => commands::frontmatter_lint::run(args),
+        Commands::MetaCheck(args) => commands::meta_check::run(args).await,
+        Commands::SitemapGen(args) => commands::sitemap_gen::run(args).await,
+    }
+}